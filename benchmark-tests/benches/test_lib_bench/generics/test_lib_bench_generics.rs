@@ -29,9 +29,16 @@ fn bench_format<I: std::fmt::Debug>(input: I) -> usize {
     black_box(run_format(input))
 }
 
+#[library_benchmark]
+#[bench::short("  abc  ")]
+#[bench::long("  abcdefghijklmnopqrstuvwxyz  ")]
+fn bench_trim<'a>(input: &'a str) -> &'a str {
+    black_box(input.trim())
+}
+
 library_benchmark_group!(
     name = bench_format_group;
-    benchmarks = bench_format
+    benchmarks = bench_format, bench_trim
 );
 
 main!(library_benchmark_groups = bench_format_group);
@@ -204,6 +204,18 @@ impl Bench {
         }
     }
 
+    /// Render the function which calls the calibration function with the arguments of this bench
+    fn render_calibrate_as_code(&self) -> TokenStream {
+        LibraryBenchmark::render_calibrate(
+            &self.calibrate_ident(),
+            &self.setup.render_as_code(&self.args),
+        )
+    }
+
+    fn calibrate_ident(&self) -> Ident {
+        format_ident("__calibrate", Some(&self.id))
+    }
+
     fn render_as_member(&self) -> TokenStream {
         let id = &self.id;
         let id_display = self.id.to_string();
@@ -346,6 +358,9 @@ impl LibraryBenchmark {
         let config = self.config.render_as_code();
 
         let inner = self.setup.render_as_code(&Args::default());
+        let calibration = Self::render_calibration(item_fn);
+        let calibrate_ident = format_ident("__calibrate", None);
+        let calibrate = Self::render_calibrate(&calibrate_ident, &inner);
         let call = quote! { std::hint::black_box(__iai_callgrind_wrapper_mod::#ident(#inner)) };
 
         let call = self.teardown.render_as_code(call);
@@ -358,6 +373,8 @@ impl LibraryBenchmark {
 
                     #[inline(never)]
                     #new_item_fn
+
+                    #calibration
                 }
 
                 pub const __BENCHES: &[iai_callgrind::internal::InternalMacroLibBench]= &[
@@ -375,6 +392,41 @@ impl LibraryBenchmark {
                 pub fn wrapper() {
                     let _ = #call;
                 }
+
+                pub const __CALIBRATIONS: &[fn()] = &[#calibrate_ident];
+
+                #calibrate
+            }
+        }
+    }
+
+    /// Render the calibration function of the benchmark function
+    ///
+    /// The calibration function has the same generics, argument types and where clause as the
+    /// benchmark function but an empty body. It is placed in the `__iai_callgrind_wrapper_mod`, so
+    /// running it measures the overhead of the harness for the benchmark function.
+    fn render_calibration(item_fn: &ItemFn) -> TokenStream {
+        let generics = &item_fn.sig.generics;
+        let where_clause = &generics.where_clause;
+        let types = item_fn.sig.inputs.iter().filter_map(|input| match input {
+            syn::FnArg::Typed(pat_type) => Some(&pat_type.ty),
+            syn::FnArg::Receiver(_) => None,
+        });
+        quote! {
+            #[inline(never)]
+            pub(super) fn __iai_callgrind_calibration #generics (#(_: #types),*) #where_clause {}
+        }
+    }
+
+    /// Render the function `ident` which calls the calibration function with the (already
+    /// rendered) arguments `inner`
+    fn render_calibrate(ident: &Ident, inner: &TokenStream) -> TokenStream {
+        quote! {
+            #[inline(never)]
+            pub fn #ident() {
+                let _ = std::hint::black_box(
+                    __iai_callgrind_wrapper_mod::__iai_callgrind_calibration(#inner)
+                );
             }
         }
     }
@@ -438,9 +490,15 @@ impl LibraryBenchmark {
         let callee = &item_fn.sig.ident;
         let mut funcs = TokenStream::new();
         let mut lib_benches = vec![];
+        let mut calibrate_idents = vec![];
+        let mut calibrates = TokenStream::new();
+        let calibration = Self::render_calibration(item_fn);
         for bench in self.benches {
             funcs.append_all(bench.render_as_code(callee));
             lib_benches.push(bench.render_as_member());
+            // Each bench is calibrated with its own arguments
+            calibrates.append_all(bench.render_calibrate_as_code());
+            calibrate_idents.push(bench.calibrate_ident());
         }
 
         let config = self.config.render_as_code();
@@ -453,6 +511,8 @@ impl LibraryBenchmark {
 
                     #[inline(never)]
                     #new_item_fn
+
+                    #calibration
                 }
 
                 pub const __BENCHES: &[iai_callgrind::internal::InternalMacroLibBench] = &[
@@ -462,6 +522,10 @@ impl LibraryBenchmark {
                 #config
 
                 #funcs
+
+                pub const __CALIBRATIONS: &[fn()] = &[#(#calibrate_idents),*];
+
+                #calibrates
             }
         }
     }
@@ -646,6 +710,34 @@ mod tests {
                 }
             ));
         }
+        let generics = &func.sig.generics;
+        let where_clause = &generics.where_clause;
+        let types = func.sig.inputs.iter().map(|input| match input {
+            syn::FnArg::Typed(pat_type) => &pat_type.ty,
+            syn::FnArg::Receiver(_) => unreachable!(),
+        });
+        let calibrate_idents = bench
+            .iter()
+            .map(|(ident, _)| {
+                if ident == "wrapper" {
+                    format_ident!("__calibrate")
+                } else {
+                    format_ident!("__calibrate_{ident}")
+                }
+            })
+            .collect::<Vec<_>>();
+        let calibrates = bench.iter().zip(&calibrate_idents).map(|((_, args), ident)| {
+            quote!(
+                #[inline(never)]
+                pub fn #ident() {
+                    let _ = std::hint::black_box(
+                        __iai_callgrind_wrapper_mod::__iai_callgrind_calibration(
+                            #(std::hint::black_box(#args)),*
+                        )
+                    );
+                }
+            )
+        });
         parse_quote!(
             mod #callee {
                 use super::*;
@@ -655,6 +747,10 @@ mod tests {
 
                     #[inline(never)]
                     #new_item_fn
+
+                    #[inline(never)]
+                    pub(super) fn __iai_callgrind_calibration #generics (#(_: #types),*)
+                    #where_clause {}
                 }
 
                 pub const __BENCHES: &[iai_callgrind::internal::InternalMacroLibBench]= &[
@@ -664,6 +760,10 @@ mod tests {
                 #rendered_get_config
 
                 #(#rendered_benches)*
+
+                pub const __CALIBRATIONS: &[fn()] = &[#(#calibrate_idents),*];
+
+                #(#calibrates)*
             }
         )
     }
@@ -1152,4 +1252,80 @@ mod tests {
         .unwrap();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_bench_when_generic_func() {
+        let input = quote!(
+            #[bench::first(1u8)]
+            #[bench::second(2u16)]
+            fn some<T>(var: T) -> u64
+            where
+                T: Into<u64>,
+            {
+                var.into() + 2
+            }
+        );
+
+        let expected = expected_model(
+            &parse_quote!(
+                fn some<T>(var: T) -> u64
+                where
+                    T: Into<u64>,
+                {
+                    var.into() + 2
+                }
+            ),
+            &[
+                parse_quote!(iai_callgrind::internal::InternalMacroLibBench {
+                    id_display: Some("first"),
+                    args_display: Some("1u8"),
+                    func: first,
+                    config: None
+                }),
+                parse_quote!(iai_callgrind::internal::InternalMacroLibBench {
+                    id_display: Some("second"),
+                    args_display: Some("2u16"),
+                    func: second,
+                    config: None
+                }),
+            ],
+            &None,
+            &[],
+            &[
+                (parse_quote!(first), vec![parse_quote!(1u8)]),
+                (parse_quote!(second), vec![parse_quote!(2u16)]),
+            ],
+        );
+        let actual: Model = parse2(render(quote!(), input).unwrap()).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_bench_when_func_with_lifetime() {
+        let input = quote!(
+            #[bench::first("abc")]
+            fn some<'a>(var: &'a str) -> &'a str {
+                var.trim()
+            }
+        );
+
+        let expected = expected_model(
+            &parse_quote!(
+                fn some<'a>(var: &'a str) -> &'a str {
+                    var.trim()
+                }
+            ),
+            &[parse_quote!(iai_callgrind::internal::InternalMacroLibBench {
+                id_display: Some("first"),
+                args_display: Some("\"abc\""),
+                func: first,
+                config: None
+            })],
+            &None,
+            &[],
+            &[(parse_quote!(first), vec![parse_quote!("abc")])],
+        );
+        let actual: Model = parse2(render(quote!(), input).unwrap()).unwrap();
+        assert_eq!(actual, expected);
+    }
 }
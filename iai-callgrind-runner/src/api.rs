@@ -11,6 +11,17 @@ use std::process::{Child, Command as StdCommand, Stdio as StdStdio};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// The environment variable which tells the benchmark harness of library benchmarks to start and
+/// stop the instrumentation of cachegrind around the benchmark function
+pub const CACHEGRIND_INSTRUMENTATION_ENV: &str = "IAI_CALLGRIND_CACHEGRIND_INSTRUMENTATION";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct AnnotateConfig {
+    pub top: Option<usize>,
+    pub event_kinds: Option<Vec<EventKind>>,
+    pub context: Option<usize>,
+}
+
 /// The model for the `#[binary_benchmark]` attribute or the equivalent from the low level api
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BinaryBenchmark {
@@ -37,8 +48,18 @@ pub struct BinaryBenchmarkConfig {
     pub entry_point: Option<String>,
     pub exit_with: Option<ExitWith>,
     pub raw_callgrind_args: RawArgs,
+    pub raw_cachegrind_args: RawArgs,
+    pub default_tool: Option<ValgrindTool>,
     pub envs: Vec<(OsString, Option<OsString>)>,
     pub flamegraph_config: Option<FlamegraphConfig>,
+    pub function_costs_config: Option<FunctionCostsConfig>,
+    pub annotate_config: Option<AnnotateConfig>,
+    pub call_graph_config: Option<CallGraphConfig>,
+    pub pprof_config: Option<PprofConfig>,
+    pub cost_model: Option<CostModel>,
+    pub cache_config: Option<CacheConfig>,
+    pub cache_matrix: Option<Vec<(String, CacheConfig)>>,
+    pub collect_config: Option<CollectConfig>,
     pub regression_config: Option<RegressionConfig>,
     pub tools: Tools,
     pub tools_override: Option<Tools>,
@@ -68,6 +89,75 @@ pub struct BinaryBenchmarkGroups {
     pub has_teardown: bool,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheConfig {
+    pub i1: Option<CacheGeometry>,
+    pub d1: Option<CacheGeometry>,
+    pub ll: Option<CacheGeometry>,
+}
+
+/// The geometry of a single cache level of the cache simulation of callgrind
+///
+/// This is the typed counterpart of the value of the `--I1`, `--D1` and `--LL` arguments in the
+/// format `<size>,<associativity>,<line size>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CacheGeometry {
+    /// The size of the cache in bytes
+    pub size: u64,
+    /// The associativity of the cache
+    pub associativity: u64,
+    /// The size of a cache line in bytes
+    pub line_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct CallGraphConfig {
+    pub event_kind: Option<EventKind>,
+    pub threshold: Option<f64>,
+    pub differential: Option<bool>,
+}
+
+/// The functions of which the costs of a benchmark are calculated and the functions which are
+/// excluded
+///
+/// The functions are glob patterns. They are not passed to callgrind as `--toggle-collect`
+/// arguments but applied to the inclusive costs of the functions after the callgrind run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectConfig {
+    pub collect_only: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostModel {
+    pub preset: Option<CostModelPreset>,
+    pub l1_hits: Option<u64>,
+    pub ll_hits: Option<u64>,
+    pub ram_hits: Option<u64>,
+    pub branch_cond_misses: Option<u64>,
+    pub branch_indirect_misses: Option<u64>,
+}
+
+/// The presets of the weights of the cost model of the [`EventKind::EstimatedCycles`]
+///
+/// The weights are the estimated cycles of a hit in the L1 cache, the LL cache and the RAM and of
+/// a mispredicted conditional or indirect branch. The branch mispredictions are only available if
+/// callgrind runs with `--branch-sim=yes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostModelPreset {
+    /// Itamar Turner-Trauring's formula from
+    /// <https://pythonspeed.com/articles/consistent-benchmarking-in-ci/> without the branch
+    /// mispredictions: L1 hit = `1`, LL hit = `5`, RAM hit = `35`. This is the default.
+    Classic,
+    /// A modern desktop or server cpu with a high memory latency: L1 hit = `1`, LL hit = `12`,
+    /// RAM hit = `100`, branch misprediction = `15`
+    Modern,
+    /// A small embedded cpu with a short pipeline and a low memory latency: L1 hit = `1`, LL hit =
+    /// `4`, RAM hit = `20`, branch misprediction = `4`
+    Embedded,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Command {
     pub path: PathBuf,
@@ -78,6 +168,18 @@ pub struct Command {
     pub config: BinaryBenchmarkConfig,
 }
 
+/// The metrics of DHAT by which the stacks of a DHAT flamegraph can be weighted
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum DhatMetric {
+    /// The total number of allocated bytes. This is the default.
+    TotalBytes,
+    /// The total number of allocated blocks
+    TotalBlocks,
+    /// The number of bytes alive at the time of the global heap maximum (t-gmax)
+    AtTGmaxBytes,
+}
+
 /// The `Direction` in which the flamegraph should grow.
 ///
 /// The default is `TopToBottom`.
@@ -176,10 +278,40 @@ pub struct FlamegraphConfig {
     pub negate_differential: Option<bool>,
     pub normalize_differential: Option<bool>,
     pub event_kinds: Option<Vec<EventKind>>,
+    pub dhat_metrics: Option<Vec<DhatMetric>>,
     pub direction: Option<Direction>,
     pub title: Option<String>,
     pub subtitle: Option<String>,
     pub min_width: Option<f64>,
+    pub speedscope: Option<bool>,
+    pub include_frames: Option<Vec<String>>,
+    pub exclude_frames: Option<Vec<String>>,
+    pub focus: Option<String>,
+    pub collapse_frames: Option<Vec<String>>,
+    pub folded: Option<bool>,
+    pub granularity: Option<FlamegraphGranularity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct FunctionCostsConfig {
+    pub top: Option<usize>,
+    pub event_kinds: Option<Vec<EventKind>>,
+}
+
+/// The granularity of the innermost frames of the callgrind flamegraphs
+///
+/// The default is `Function`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlamegraphGranularity {
+    /// The innermost frames are functions. The code of inlined functions from other source files
+    /// is shown as frame of its own with the name of the function it was inlined into.
+    #[default]
+    Function,
+    /// The code inlined from other source files is shown as innermost frame of the function it was
+    /// inlined into
+    Inline,
+    /// The lines of the source files (`file:line`) are shown as innermost frames of the functions
+    Line,
 }
 
 /// The kind of `Flamegraph` which is going to be constructed
@@ -216,8 +348,19 @@ pub struct LibraryBenchmarkBench {
 pub struct LibraryBenchmarkConfig {
     pub env_clear: Option<bool>,
     pub raw_callgrind_args: RawArgs,
+    pub raw_cachegrind_args: RawArgs,
+    pub default_tool: Option<ValgrindTool>,
     pub envs: Vec<(OsString, Option<OsString>)>,
     pub flamegraph_config: Option<FlamegraphConfig>,
+    pub function_costs_config: Option<FunctionCostsConfig>,
+    pub annotate_config: Option<AnnotateConfig>,
+    pub call_graph_config: Option<CallGraphConfig>,
+    pub pprof_config: Option<PprofConfig>,
+    pub cost_model: Option<CostModel>,
+    pub cache_config: Option<CacheConfig>,
+    pub cache_matrix: Option<Vec<(String, CacheConfig)>>,
+    pub collect_config: Option<CollectConfig>,
+    pub harness_calibration: Option<bool>,
     pub regression_config: Option<RegressionConfig>,
     pub tools: Tools,
     pub tools_override: Option<Tools>,
@@ -246,12 +389,20 @@ pub struct LibraryBenchmarkGroups {
     pub has_teardown: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct PprofConfig {
+    pub event_kinds: Option<Vec<EventKind>>,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawArgs(pub Vec<String>);
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct RegressionConfig {
     pub limits: Vec<(EventKind, f64)>,
+    pub function_limits: Vec<(String, EventKind, f64)>,
+    pub thread_limits: Vec<(usize, EventKind, f64)>,
+    pub child_filters: Vec<String>,
     pub fail_fast: Option<bool>,
 }
 
@@ -321,21 +472,34 @@ pub enum Stdin {
     Pipe,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tool {
     pub kind: ValgrindTool,
     pub enable: Option<bool>,
     pub raw_args: RawArgs,
     pub outfile_modifier: Option<String>,
     pub show_log: Option<bool>,
+    pub regression_config: Option<ToolRegressionConfig>,
+    pub fail_on_errors: Option<bool>,
+    pub flamegraph_config: Option<FlamegraphConfig>,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolRegressionConfig {
+    pub limits: Vec<(String, f64)>,
+    pub fail_fast: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Tools(pub Vec<Tool>);
 
 /// An enum with all possible valgrind tools
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ValgrindTool {
+    /// [Callgrind: a call-graph generating cache and branch prediction profiler](https://valgrind.org/docs/manual/cl-manual.html)
+    Callgrind,
+    /// [Cachegrind: a high-precision tracing profiler](https://valgrind.org/docs/manual/cg-manual.html)
+    Cachegrind,
     /// [Memcheck: a memory error detector](https://valgrind.org/docs/manual/mc-manual.html)
     Memcheck,
     /// [Helgrind: a thread error detector](https://valgrind.org/docs/manual/hg-manual.html)
@@ -348,6 +512,12 @@ pub enum ValgrindTool {
     DHAT,
     /// [BBV: an experimental basic block vector generation tool](https://valgrind.org/docs/manual/bbv-manual.html)
     BBV,
+    /// Any other valgrind tool by the name passed to `valgrind --tool`
+    ///
+    /// This allows running tools like [Lackey](https://valgrind.org/docs/manual/lk-manual.html)
+    /// or the experimental `exp-*` tools shipped with some valgrind builds. Only the log files of
+    /// custom tools are parsed.
+    Custom(String),
 }
 
 impl BinaryBenchmarkConfig {
@@ -363,10 +533,23 @@ impl BinaryBenchmarkConfig {
 
             self.raw_callgrind_args
                 .extend_ignore_flag(other.raw_callgrind_args.0.iter());
+            self.raw_cachegrind_args
+                .extend_ignore_flag(other.raw_cachegrind_args.0.iter());
+            self.default_tool = update_option(&self.default_tool, &other.default_tool);
 
             self.envs.extend_from_slice(&other.envs);
             self.flamegraph_config =
                 update_option(&self.flamegraph_config, &other.flamegraph_config);
+            self.function_costs_config =
+                update_option(&self.function_costs_config, &other.function_costs_config);
+            self.annotate_config = update_option(&self.annotate_config, &other.annotate_config);
+            self.call_graph_config =
+                update_option(&self.call_graph_config, &other.call_graph_config);
+            self.pprof_config = update_option(&self.pprof_config, &other.pprof_config);
+            self.cost_model = update_option(&self.cost_model, &other.cost_model);
+            self.cache_config = update_option(&self.cache_config, &other.cache_config);
+            self.cache_matrix = update_option(&self.cache_matrix, &other.cache_matrix);
+            self.collect_config = update_option(&self.collect_config, &other.collect_config);
             self.regression_config =
                 update_option(&self.regression_config, &other.regression_config);
             if let Some(other_tools) = &other.tools_override {
@@ -401,6 +584,40 @@ impl BinaryBenchmarkConfig {
     }
 }
 
+impl Display for CacheGeometry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.size, self.associativity, self.line_size)
+    }
+}
+
+impl CostModelPreset {
+    /// Return the `CostModelPreset` with the name `value` matched case insensitive
+    pub fn from_str_ignore_case(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "classic" => Some(Self::Classic),
+            "modern" => Some(Self::Modern),
+            "embedded" => Some(Self::Embedded),
+            _ => None,
+        }
+    }
+}
+
+impl DhatMetric {
+    pub fn to_name(&self) -> String {
+        format!("{:?}", *self)
+    }
+}
+
+impl Display for DhatMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DhatMetric::TotalBytes => f.write_str("Total bytes"),
+            DhatMetric::TotalBlocks => f.write_str("Total blocks"),
+            DhatMetric::AtTGmaxBytes => f.write_str("At t-gmax bytes"),
+        }
+    }
+}
+
 impl Default for Direction {
     fn default() -> Self {
         Self::BottomToTop
@@ -532,9 +749,24 @@ impl LibraryBenchmarkConfig {
             self.env_clear = update_option(&self.env_clear, &other.env_clear);
             self.raw_callgrind_args
                 .extend_ignore_flag(other.raw_callgrind_args.0.iter());
+            self.raw_cachegrind_args
+                .extend_ignore_flag(other.raw_cachegrind_args.0.iter());
+            self.default_tool = update_option(&self.default_tool, &other.default_tool);
             self.envs.extend_from_slice(&other.envs);
             self.flamegraph_config =
                 update_option(&self.flamegraph_config, &other.flamegraph_config);
+            self.function_costs_config =
+                update_option(&self.function_costs_config, &other.function_costs_config);
+            self.annotate_config = update_option(&self.annotate_config, &other.annotate_config);
+            self.call_graph_config =
+                update_option(&self.call_graph_config, &other.call_graph_config);
+            self.pprof_config = update_option(&self.pprof_config, &other.pprof_config);
+            self.cost_model = update_option(&self.cost_model, &other.cost_model);
+            self.cache_config = update_option(&self.cache_config, &other.cache_config);
+            self.cache_matrix = update_option(&self.cache_matrix, &other.cache_matrix);
+            self.collect_config = update_option(&self.collect_config, &other.collect_config);
+            self.harness_calibration =
+                update_option(&self.harness_calibration, &other.harness_calibration);
             self.regression_config =
                 update_option(&self.regression_config, &other.regression_config);
             if let Some(other_tools) = &other.tools_override {
@@ -815,8 +1047,19 @@ mod tests {
         let other = LibraryBenchmarkConfig {
             env_clear: Some(true),
             raw_callgrind_args: RawArgs(vec!["--just-testing=yes".to_owned()]),
+            raw_cachegrind_args: RawArgs(vec!["--just-testing=no".to_owned()]),
+            default_tool: Some(ValgrindTool::Cachegrind),
             envs: vec![(OsString::from("MY_ENV"), Some(OsString::from("value")))],
             flamegraph_config: Some(FlamegraphConfig::default()),
+            function_costs_config: Some(FunctionCostsConfig::default()),
+            annotate_config: Some(AnnotateConfig::default()),
+            call_graph_config: Some(CallGraphConfig::default()),
+            pprof_config: Some(PprofConfig::default()),
+            cost_model: Some(CostModel::default()),
+            cache_config: Some(CacheConfig::default()),
+            cache_matrix: Some(vec![("small".to_owned(), CacheConfig::default())]),
+            collect_config: Some(CollectConfig::default()),
+            harness_calibration: Some(true),
             regression_config: Some(RegressionConfig::default()),
             tools: Tools(vec![Tool {
                 kind: ValgrindTool::DHAT,
@@ -824,6 +1067,9 @@ mod tests {
                 raw_args: RawArgs(vec![]),
                 outfile_modifier: None,
                 show_log: None,
+                regression_config: None,
+                fail_on_errors: None,
+                flamegraph_config: None,
             }]),
             tools_override: None,
             truncate_description: None,
@@ -838,8 +1084,19 @@ mod tests {
         let other = LibraryBenchmarkConfig {
             env_clear: Some(true),
             raw_callgrind_args: RawArgs(vec!["--just-testing=yes".to_owned()]),
+            raw_cachegrind_args: RawArgs(vec!["--just-testing=no".to_owned()]),
+            default_tool: Some(ValgrindTool::Cachegrind),
             envs: vec![(OsString::from("MY_ENV"), Some(OsString::from("value")))],
             flamegraph_config: Some(FlamegraphConfig::default()),
+            function_costs_config: Some(FunctionCostsConfig::default()),
+            annotate_config: Some(AnnotateConfig::default()),
+            call_graph_config: Some(CallGraphConfig::default()),
+            pprof_config: Some(PprofConfig::default()),
+            cost_model: Some(CostModel::default()),
+            cache_config: Some(CacheConfig::default()),
+            cache_matrix: Some(vec![("small".to_owned(), CacheConfig::default())]),
+            collect_config: Some(CollectConfig::default()),
+            harness_calibration: Some(true),
             regression_config: Some(RegressionConfig::default()),
             tools: Tools(vec![Tool {
                 kind: ValgrindTool::DHAT,
@@ -847,6 +1104,9 @@ mod tests {
                 raw_args: RawArgs(vec![]),
                 outfile_modifier: None,
                 show_log: None,
+                regression_config: None,
+                fail_on_errors: None,
+                flamegraph_config: None,
             }]),
             tools_override: Some(Tools(vec![])),
             truncate_description: None,
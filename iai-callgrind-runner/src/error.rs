@@ -18,11 +18,18 @@ pub enum Error {
     /// (`process_name`, [`Output`], [`ExitStatus`], [`ToolOutputPath`])
     ProcessError((String, Option<Output>, ExitStatus, Option<ToolOutputPath>)),
     InvalidCallgrindBoolArgument((String, String)),
+    InvalidCachegrindBoolArgument((String, String)),
+    /// (`module_path`, `message`)
+    InvalidCacheConfig(ModulePath, String),
+    /// (`module_path`, `message`)
+    InvalidCollectConfig(ModulePath, String),
     ParseError((PathBuf, String)),
     RegressionError(bool),
     EnvironmentVariableError((String, String)),
     SandboxError(String),
     BenchmarkError(ValgrindTool, ModulePath, String),
+    /// (`tool`, `module_path`, `errors`, `contexts`)
+    ToolError(ValgrindTool, ModulePath, u64, u64),
 }
 
 impl std::error::Error for Error {}
@@ -94,6 +101,19 @@ impl Display for Error {
                      or 'no'"
                 )
             }
+            Self::InvalidCachegrindBoolArgument((option, value)) => {
+                write!(
+                    f,
+                    "Invalid cachegrind argument for {option}: '{value}'. Valid values are 'yes' \
+                     or 'no'"
+                )
+            }
+            Self::InvalidCacheConfig(module_path, message) => {
+                write!(f, "Invalid cache configuration in benchmark {module_path}: {message}")
+            }
+            Self::InvalidCollectConfig(module_path, message) => {
+                write!(f, "Invalid collect configuration in benchmark {module_path}: {message}")
+            }
             Self::ParseError((path, message)) => {
                 write!(f, "Error parsing file '{}': {message}", path.display())
             }
@@ -113,6 +133,13 @@ impl Display for Error {
             Self::BenchmarkError(tool, module_path, message) => {
                 write!(f, "Error in {tool} benchmark {module_path}: {message}")
             }
+            Self::ToolError(tool, module_path, errors, contexts) => {
+                write!(
+                    f,
+                    "{tool} reported {errors} errors from {contexts} contexts in benchmark \
+                     {module_path}. Aborting ..."
+                )
+            }
         }
     }
 }
@@ -3,11 +3,14 @@ use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 use clap::builder::BoolishValueParser;
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, ArgGroup, Parser};
 
 use super::format::OutputFormat;
 use super::summary::{BaselineName, SummaryFormat};
-use crate::api::{EventKind, RawArgs, RegressionConfig};
+use super::tool::ValgrindTool;
+use crate::api::{
+    CostModel, CostModelPreset, EventKind, RawArgs, RegressionConfig, ToolRegressionConfig,
+};
 
 /// A filter for benchmarks
 ///
@@ -76,7 +79,8 @@ instead of `true` and one of `n`, `no`, `f`, `false`, `off`, and `0` instead of
 `false`",
     long_about = None,
     no_binary_name = true,
-    override_usage= "cargo bench ... [BENCHNAME] -- [OPTIONS]"
+    override_usage= "cargo bench ... [BENCHNAME] -- [OPTIONS]",
+    group = ArgGroup::new("regressions").multiple(true).args(["regression", "tool_regression"])
 )]
 pub struct CommandLineArgs {
     /// `--bench` usually shows up as last argument set by cargo and not by us.
@@ -108,6 +112,37 @@ pub struct CommandLineArgs {
     )]
     pub callgrind_args: Option<RawArgs>,
 
+    /// The raw arguments to pass through to Cachegrind
+    ///
+    /// This is a space separated list of command-line-arguments specified as if they were
+    /// passed directly to valgrind. These arguments are only used if cachegrind is the default
+    /// tool.
+    ///
+    /// Examples:
+    ///   * --cachegrind-args=--branch-sim=yes
+    ///   * --cachegrind-args='--branch-sim=yes --instr-at-start=no'
+    #[arg(
+        long = "cachegrind-args",
+        value_parser = parse_args,
+        num_args = 1,
+        verbatim_doc_comment,
+        env = "IAI_CALLGRIND_CACHEGRIND_ARGS"
+    )]
+    pub cachegrind_args: Option<RawArgs>,
+
+    /// The default tool used to run the benchmarks
+    ///
+    /// The default tool collects the metrics shown in the main benchmark output and is used for
+    /// the performance regression checks. Possible values are `callgrind` (the default) and
+    /// `cachegrind`. This option overrides the default tool of the benchmark configurations.
+    #[arg(
+        long = "default-tool",
+        num_args = 1,
+        value_parser = parse_default_tool,
+        env = "IAI_CALLGRIND_DEFAULT_TOOL"
+    )]
+    pub default_tool: Option<ValgrindTool>,
+
     /// Save a machine-readable summary of each benchmark run in json format next to the usual
     /// benchmark output
     #[arg(
@@ -154,12 +189,35 @@ pub struct CommandLineArgs {
     )]
     pub regression: Option<RegressionConfig>,
 
+    /// Set performance regression limits for the metrics of tools other than callgrind
+    ///
+    /// This is a `;` separated list of `TOOL:METRIC=LIMIT` entries. Multiple METRIC=LIMIT
+    /// (key=value) pairs of the same tool are separated by `,`. The limit is a positive or negative
+    /// percentage like in `--regression`. The metric is matched case insensitive with all
+    /// non-alphanumeric characters (like whitespace or `-`) treated as `_`. For example, the metric
+    /// `At t-gmax bytes` of DHAT is matched by `at_t_gmax_bytes`. These limits replace the limits
+    /// configured in the benchmark for the same tool. Limits for a tool which is not configured in
+    /// the benchmark are ignored with a warning.
+    ///
+    /// Examples: --tool-regression='dhat:total_bytes=5' or
+    /// --tool-regression='dhat:total_bytes=5,total_blocks=0;massif:peak_heap_bytes=10'
+    #[allow(clippy::doc_markdown)]
+    #[arg(
+        long = "tool-regression",
+        num_args = 1,
+        value_delimiter = ';',
+        value_parser = parse_tool_regression_config,
+        env = "IAI_CALLGRIND_TOOL_REGRESSION",
+    )]
+    pub tool_regression: Vec<(ValgrindTool, ToolRegressionConfig)>,
+
     /// If true, the first failed performance regression check fails the whole benchmark run
     ///
-    /// This option requires `--regression=...` or `IAI_CALLGRIND_REGRESSION=...` to be present.
+    /// This option requires `--regression=...` (`IAI_CALLGRIND_REGRESSION=...`) or
+    /// `--tool-regression=...` (`IAI_CALLGRIND_TOOL_REGRESSION=...`) to be present.
     #[arg(
         long = "regression-fail-fast",
-        requires = "regression",
+        requires = "regressions",
         default_missing_value = "true",
         num_args = 0..=1,
         require_equals = true,
@@ -168,6 +226,39 @@ pub struct CommandLineArgs {
     )]
     pub regression_fail_fast: Option<bool>,
 
+    /// If true, fail the benchmark if a tool like Memcheck, Helgrind or DRD reports errors
+    ///
+    /// Suppressed errors are ignored. This option overrides the `fail_on_errors` setting of all
+    /// tools configured in the benchmarks.
+    #[arg(
+        long = "tools-fail-on-error",
+        default_missing_value = "true",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = BoolishValueParser::new(),
+        env = "IAI_CALLGRIND_TOOLS_FAIL_ON_ERROR",
+    )]
+    pub tools_fail_on_error: Option<bool>,
+
+    /// The weights of the cost model with which the `EstimatedCycles` are calculated
+    ///
+    /// This is a `,` separated list of an optional preset and weight=value (key=value) pairs. The
+    /// presets are `classic` (the default), `modern` and `embedded`. The weights are `l1` (L1
+    /// hits), `ll` (LL hits), `ram` (RAM hits), `bcm` (mispredicted conditional branches) and
+    /// `bim` (mispredicted indirect branches) and replace the weights of the preset. The branch
+    /// mispredictions are only available with `--branch-sim=yes`. This option replaces the cost
+    /// model configured in the benchmarks.
+    ///
+    /// Examples: --cost-model=modern or --cost-model='l1=1,ll=10,ram=80' or
+    /// --cost-model='modern,ram=200'
+    #[arg(
+        long = "cost-model",
+        num_args = 1,
+        value_parser = parse_cost_model,
+        env = "IAI_CALLGRIND_COST_MODEL",
+    )]
+    pub cost_model: Option<CostModel>,
+
     /// Compare against this baseline if present and then overwrite it
     #[arg(
         long = "save-baseline",
@@ -295,6 +386,16 @@ fn parse_args(value: &str) -> Result<RawArgs, String> {
         .map(RawArgs::new)
 }
 
+fn parse_default_tool(value: &str) -> Result<ValgrindTool, String> {
+    match ValgrindTool::try_from(value.trim().to_lowercase().as_str()) {
+        Ok(tool) if tool.is_default_tool() => Ok(tool),
+        Ok(tool) => Err(format!(
+            "Invalid default tool: '{tool}'. Valid values are 'callgrind' or 'cachegrind'"
+        )),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
 fn parse_regression_config(value: &str) -> Result<RegressionConfig, String> {
     let value = value.trim();
     if value.is_empty() {
@@ -332,6 +433,98 @@ fn parse_regression_config(value: &str) -> Result<RegressionConfig, String> {
     Ok(regression_config)
 }
 
+fn parse_cost_model(value: &str) -> Result<CostModel, String> {
+    let mut cost_model = CostModel::default();
+    for split in value.split(',') {
+        let split = split.trim();
+        if split.is_empty() {
+            continue;
+        }
+
+        if let Some((key, value)) = split.split_once('=') {
+            let (key, value) = (key.trim(), value.trim());
+            let weight = value
+                .parse::<u64>()
+                .map_err(|error| format!("Invalid weight for '{key}': {error}"))?;
+            let field = match key.to_lowercase().as_str() {
+                "l1" => &mut cost_model.l1_hits,
+                "ll" => &mut cost_model.ll_hits,
+                "ram" => &mut cost_model.ram_hits,
+                "bcm" => &mut cost_model.branch_cond_misses,
+                "bim" => &mut cost_model.branch_indirect_misses,
+                _ => return Err(format!("Unknown weight: '{key}'")),
+            };
+            *field = Some(weight);
+        } else {
+            let preset = CostModelPreset::from_str_ignore_case(split)
+                .ok_or_else(|| format!("Unknown cost model preset: '{split}'"))?;
+            cost_model.preset = Some(preset);
+        }
+    }
+
+    if cost_model == CostModel::default() {
+        return Err(
+            "No cost model found: At least a preset or one weight must be specified".to_owned(),
+        );
+    }
+    Ok(cost_model)
+}
+
+fn parse_tool_regression_config(
+    value: &str,
+) -> Result<(ValgrindTool, ToolRegressionConfig), String> {
+    let value = value.trim();
+    let Some((tool, limits)) = value.split_once(':') else {
+        return Err(format!("Invalid format of tool regression: '{value}'"));
+    };
+
+    let tool = ValgrindTool::try_from(tool.trim().to_lowercase().as_str())
+        .map_err(|error| error.to_string())?;
+    if tool.is_default_tool() {
+        return Err(format!(
+            "Invalid tool: '{tool}'. Use --regression to set the limits of the default tool"
+        ));
+    }
+
+    let mut config = ToolRegressionConfig::default();
+    for split in limits.split(',') {
+        let split = split.trim();
+        if split.is_empty() {
+            continue;
+        }
+
+        if let Some((key, value)) = split.split_once('=') {
+            let (key, value) = (key.trim(), value.trim());
+            let pct = value.parse::<f64>().map_err(|error| -> String {
+                format!("Invalid percentage for '{key}': {error}")
+            })?;
+            config.limits.push((key.to_owned(), pct));
+        } else {
+            return Err(format!("Invalid format of key/value pair: '{split}'"));
+        }
+    }
+
+    if config.limits.is_empty() {
+        return Err(format!("No limits found for '{tool}': At least one limit must be specified"));
+    }
+
+    Ok((tool, config))
+}
+
+impl From<&CommandLineArgs> for Vec<(ValgrindTool, ToolRegressionConfig)> {
+    fn from(value: &CommandLineArgs) -> Self {
+        value
+            .tool_regression
+            .iter()
+            .cloned()
+            .map(|(tool, mut config)| {
+                config.fail_fast = value.regression_fail_fast;
+                (tool, config)
+            })
+            .collect()
+    }
+}
+
 impl From<&CommandLineArgs> for Option<RegressionConfig> {
     fn from(value: &CommandLineArgs) -> Self {
         let mut config = value.regression.clone();
@@ -395,6 +588,9 @@ mod tests {
     ) {
         let expected = RegressionConfig {
             limits: expected_limits,
+            function_limits: vec![],
+            thread_limits: vec![],
+            child_filters: vec![],
             fail_fast: None,
         };
 
@@ -423,6 +619,122 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case::single("dhat:total_bytes=5", ValgrindTool::DHAT, vec![("total_bytes", 5f64)])]
+    #[case::case_insensitive_tool("DHAT:total_bytes=5", ValgrindTool::DHAT, vec![("total_bytes", 5f64)])]
+    #[case::multiple(
+        "dhat:total_bytes=5, total_blocks = -10",
+        ValgrindTool::DHAT,
+        vec![("total_bytes", 5f64), ("total_blocks", -10f64)]
+    )]
+    #[case::other_tool("massif:peak_heap_bytes=0", ValgrindTool::Massif, vec![("peak_heap_bytes", 0f64)])]
+    fn test_parse_tool_regression_config(
+        #[case] value: &str,
+        #[case] expected_tool: ValgrindTool,
+        #[case] expected_limits: Vec<(&str, f64)>,
+    ) {
+        let expected = ToolRegressionConfig {
+            limits: expected_limits
+                .into_iter()
+                .map(|(m, l)| (m.to_owned(), l))
+                .collect(),
+            fail_fast: None,
+        };
+
+        let actual = parse_tool_regression_config(value).unwrap();
+        assert_eq!(actual, (expected_tool, expected));
+    }
+
+    #[rstest]
+    #[case::no_tool("total_bytes=5", "Invalid format of tool regression: 'total_bytes=5'")]
+    #[case::unknown_tool("unknown:total_bytes=5", "Unknown tool 'unknown'")]
+    #[case::default_tool(
+        "callgrind:ir=5",
+        "Invalid tool: 'callgrind'. Use --regression to set the limits of the default tool"
+    )]
+    #[case::wrong_format("dhat:total_bytes:5", "Invalid format of key/value pair: 'total_bytes:5'")]
+    #[case::invalid_percentage(
+        "dhat:total_bytes=5.0.0",
+        "Invalid percentage for 'total_bytes': invalid float literal"
+    )]
+    #[case::empty_limits("dhat:", "No limits found for 'dhat': At least one limit must be specified")]
+    fn test_parse_tool_regression_config_then_error(
+        #[case] value: &str,
+        #[case] expected_reason: &str,
+    ) {
+        assert_eq!(
+            &parse_tool_regression_config(value).unwrap_err(),
+            expected_reason
+        );
+    }
+
+    #[rstest]
+    #[case::preset("modern", Some(CostModelPreset::Modern), [None; 5])]
+    #[case::preset_case_insensitive("Embedded", Some(CostModelPreset::Embedded), [None; 5])]
+    #[case::weights(
+        "l1=1, ll = 10,ram=80,bcm=15,BIM=20",
+        None,
+        [Some(1), Some(10), Some(80), Some(15), Some(20)]
+    )]
+    #[case::preset_and_weight(
+        "modern,ram=200",
+        Some(CostModelPreset::Modern),
+        [None, None, Some(200), None, None]
+    )]
+    fn test_parse_cost_model(
+        #[case] value: &str,
+        #[case] preset: Option<CostModelPreset>,
+        #[case] weights: [Option<u64>; 5],
+    ) {
+        let expected = CostModel {
+            preset,
+            l1_hits: weights[0],
+            ll_hits: weights[1],
+            ram_hits: weights[2],
+            branch_cond_misses: weights[3],
+            branch_indirect_misses: weights[4],
+        };
+
+        assert_eq!(parse_cost_model(value).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::unknown_preset("fast", "Unknown cost model preset: 'fast'")]
+    #[case::unknown_weight("l2=3", "Unknown weight: 'l2'")]
+    #[case::invalid_weight("ram=1.5", "Invalid weight for 'ram': invalid digit found in string")]
+    #[case::empty("", "No cost model found: At least a preset or one weight must be specified")]
+    fn test_parse_cost_model_then_error(#[case] value: &str, #[case] expected_reason: &str) {
+        assert_eq!(&parse_cost_model(value).unwrap_err(), expected_reason);
+    }
+
+    #[test]
+    fn test_tool_regression_cli_multiple_tools_with_fail_fast() {
+        let result = CommandLineArgs::parse_from([
+            "--tool-regression=dhat:total_bytes=5;massif:peak_heap_bytes=10",
+            "--regression-fail-fast",
+        ]);
+        let expected = vec![
+            (
+                ValgrindTool::DHAT,
+                ToolRegressionConfig {
+                    limits: vec![("total_bytes".to_owned(), 5f64)],
+                    fail_fast: Some(true),
+                },
+            ),
+            (
+                ValgrindTool::Massif,
+                ToolRegressionConfig {
+                    limits: vec![("peak_heap_bytes".to_owned(), 10f64)],
+                    fail_fast: Some(true),
+                },
+            ),
+        ];
+        assert_eq!(
+            Into::<Vec<(ValgrindTool, ToolRegressionConfig)>>::into(&result),
+            expected
+        );
+    }
+
     #[test]
     #[serial_test::serial]
     fn test_callgrind_args_env() {
@@ -458,6 +770,48 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case::callgrind("callgrind", ValgrindTool::Callgrind)]
+    #[case::cachegrind("cachegrind", ValgrindTool::Cachegrind)]
+    #[case::case_insensitive("CacheGrind", ValgrindTool::Cachegrind)]
+    fn test_default_tool_cli(#[case] value: &str, #[case] expected: ValgrindTool) {
+        let result = CommandLineArgs::parse_from([format!("--default-tool={value}")]);
+        assert_eq!(result.default_tool, Some(expected));
+    }
+
+    #[rstest]
+    #[case::unknown("unknown", "Unknown tool 'unknown'")]
+    #[case::no_default_tool(
+        "dhat",
+        "Invalid default tool: 'dhat'. Valid values are 'callgrind' or 'cachegrind'"
+    )]
+    fn test_parse_default_tool_then_error(#[case] value: &str, #[case] expected_reason: &str) {
+        assert_eq!(&parse_default_tool(value).unwrap_err(), expected_reason);
+    }
+
+    #[test]
+    fn test_cachegrind_args_cli() {
+        let test_arg = "--branch-sim=yes";
+        let result = CommandLineArgs::parse_from([format!("--cachegrind-args={test_arg}")]);
+        assert_eq!(
+            result.cachegrind_args,
+            Some(RawArgs::new(vec![test_arg.to_owned()]))
+        );
+    }
+
+    #[rstest]
+    #[case::default("", true)]
+    #[case::yes("yes", true)]
+    #[case::no("no", false)]
+    fn test_tools_fail_on_error_cli(#[case] value: &str, #[case] expected: bool) {
+        let result = if value.is_empty() {
+            CommandLineArgs::parse_from(["--tools-fail-on-error".to_owned()])
+        } else {
+            CommandLineArgs::parse_from([format!("--tools-fail-on-error={value}")])
+        };
+        assert_eq!(result.tools_fail_on_error, Some(expected));
+    }
+
     #[test]
     #[serial_test::serial]
     fn test_save_summary_env() {
@@ -0,0 +1,293 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+
+use crate::error::Error;
+use crate::runner::costs::Costs;
+use crate::runner::summary::{BasicBlockSummary, BasicBlockVectorSummary};
+
+pub const INTERVALS: &str = "Intervals";
+pub const BASIC_BLOCKS: &str = "Basic blocks";
+pub const INSTRUCTIONS: &str = "Instructions";
+
+/// The number of basic blocks shown in the terminal output and stored in the summary
+pub const TOP_BASIC_BLOCKS: usize = 10;
+
+/// The location of a basic block as recorded in the `pc` out file of BBV
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BasicBlockLocation {
+    pub address: String,
+    pub function: Option<String>,
+}
+
+/// The basic block vector of a `bb` out file of BBV
+///
+/// The vectors of all intervals are summed up into a single vector of the whole program run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BasicBlockVector {
+    /// The number of intervals
+    pub intervals: u64,
+    /// The number of executed instructions of each basic block by id in order of appearance
+    pub counts: IndexMap<u64, u64>,
+    /// The locations of the basic blocks by id if the `pc` out file was present
+    pub locations: HashMap<u64, BasicBlockLocation>,
+}
+
+impl BasicBlockVector {
+    /// The metrics of the basic block vector which are compared with the old run
+    pub fn to_costs(&self) -> Costs<String> {
+        Costs::with_event_kinds([
+            (INTERVALS.to_owned(), self.intervals),
+            (BASIC_BLOCKS.to_owned(), self.counts.len() as u64),
+            (INSTRUCTIONS.to_owned(), self.counts.values().sum()),
+        ])
+    }
+
+    /// The `top` basic blocks with the most executed instructions
+    pub fn hottest_blocks(&self, top: usize) -> Vec<BasicBlockSummary> {
+        let mut blocks = self
+            .counts
+            .iter()
+            .map(|(id, instructions)| {
+                let location = self.locations.get(id);
+                BasicBlockSummary {
+                    id: *id,
+                    address: location.map(|l| l.address.clone()),
+                    function: location.and_then(|l| l.function.clone()),
+                    instructions: *instructions,
+                }
+            })
+            .collect::<Vec<BasicBlockSummary>>();
+        // The sort is stable, so blocks with the same count stay in order of appearance
+        blocks.sort_by_key(|block| Reverse(block.instructions));
+        blocks.truncate(top);
+        blocks
+    }
+
+    /// The normalized manhattan distance between this and the `other` basic block vector
+    ///
+    /// Both vectors are normalized to a total of `1`, so the distance is in the range of `0`
+    /// (identical execution profile) to `1` (no common basic blocks). If the locations of the
+    /// `pc` out file are present in both vectors, the basic blocks are matched by their address.
+    /// The ids are assigned by BBV in the order of the first execution of a basic block, so they
+    /// can differ between two runs for the same basic block and are only used as a fallback.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn distance(&self, other: &Self) -> f64 {
+        let by_address = !self.locations.is_empty() && !other.locations.is_empty();
+        let counts = self.counts_by_key(by_address);
+        let other_counts = other.counts_by_key(by_address);
+
+        let total = counts.values().sum::<u64>() as f64;
+        let other_total = other_counts.values().sum::<u64>() as f64;
+        let normalize = |count: Option<&u64>, total: f64| {
+            count.map_or(0f64, |c| if total > 0f64 { *c as f64 / total } else { 0f64 })
+        };
+
+        let sum = counts
+            .keys()
+            .chain(other_counts.keys().filter(|key| !counts.contains_key(*key)))
+            .map(|key| {
+                (normalize(counts.get(key), total)
+                    - normalize(other_counts.get(key), other_total))
+                .abs()
+            })
+            .sum::<f64>();
+        sum / 2f64
+    }
+
+    /// The executed instructions of the basic blocks by address or by id
+    ///
+    /// Basic blocks without a location fall back to their id.
+    fn counts_by_key(&self, by_address: bool) -> HashMap<BlockKey<'_>, u64> {
+        let mut counts = HashMap::new();
+        for (id, count) in &self.counts {
+            let key = match self.locations.get(id) {
+                Some(location) if by_address => BlockKey::Address(&location.address),
+                _ => BlockKey::Id(*id),
+            };
+            *counts.entry(key).or_insert(0) += count;
+        }
+        counts
+    }
+}
+
+/// The key to match the basic blocks of two basic block vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BlockKey<'a> {
+    Address(&'a str),
+    Id(u64),
+}
+
+fn parse_number(path: &Path, value: &str) -> Result<u64> {
+    value.parse::<u64>().map_err(|error| -> anyhow::Error {
+        Error::ParseError((path.to_owned(), format!("Invalid number '{value}': {error}"))).into()
+    })
+}
+
+/// Parse the intervals of a `bb` out file of BBV
+///
+/// Each interval is a line starting with `T` followed by `:<block id>:<instructions>` entries,
+/// for example `T:45:1024   :189:99343`.
+pub fn parse_bb_out(path: &Path) -> Result<BasicBlockVector> {
+    let file = File::open(path)
+        .with_context(|| format!("Error opening bbv out file '{}'", path.display()))?;
+
+    let mut vector = BasicBlockVector::default();
+    for line in BufReader::new(file)
+        .lines()
+        .map(std::result::Result::unwrap)
+    {
+        let Some(rest) = line.trim().strip_prefix('T') else {
+            continue;
+        };
+        vector.intervals += 1;
+        for entry in rest.split_whitespace() {
+            let (id, count) = entry
+                .strip_prefix(':')
+                .and_then(|entry| entry.split_once(':'))
+                .ok_or_else(|| -> anyhow::Error {
+                    Error::ParseError((path.to_owned(), format!("Invalid entry '{entry}'"))).into()
+                })?;
+            *vector.counts.entry(parse_number(path, id)?).or_insert(0) +=
+                parse_number(path, count)?;
+        }
+    }
+
+    Ok(vector)
+}
+
+/// Parse the locations of the basic blocks of a `pc` out file of BBV
+///
+/// Each line looks like `F:<block id>:<address>:<function>`. Lines which don't start with `F` are
+/// ignored.
+pub fn parse_pc_out(path: &Path) -> Result<HashMap<u64, BasicBlockLocation>> {
+    let file = File::open(path)
+        .with_context(|| format!("Error opening bbv pc file '{}'", path.display()))?;
+
+    let mut locations = HashMap::new();
+    for line in BufReader::new(file)
+        .lines()
+        .map(std::result::Result::unwrap)
+    {
+        let Some(rest) = line.trim().strip_prefix("F:") else {
+            continue;
+        };
+        let mut split = rest.splitn(3, ':');
+        let (Some(id), Some(address)) = (split.next(), split.next()) else {
+            return Err(
+                Error::ParseError((path.to_owned(), format!("Invalid line '{line}'"))).into(),
+            );
+        };
+        let address = address.trim_start_matches("0x");
+        locations.insert(
+            parse_number(path, id)?,
+            BasicBlockLocation {
+                address: format!("0x{address}"),
+                function: split
+                    .next()
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .map(ToOwned::to_owned),
+            },
+        );
+    }
+
+    Ok(locations)
+}
+
+/// Summarize the `new` basic block vector and compare it with the `old` one
+///
+/// The hottest basic blocks are taken from the `new` vector if present, otherwise from the `old`
+/// vector. The distance is only present if both vectors are present.
+pub fn summarize_basic_block_vectors(
+    new: Option<&BasicBlockVector>,
+    old: Option<&BasicBlockVector>,
+) -> Option<BasicBlockVectorSummary> {
+    let hottest_blocks = new.or(old)?.hottest_blocks(TOP_BASIC_BLOCKS);
+    Some(BasicBlockVectorSummary {
+        hottest_blocks,
+        distance: new.zip(old).map(|(new, old)| new.distance(old)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn vector<T>(counts: T) -> BasicBlockVector
+    where
+        T: IntoIterator<Item = (u64, u64)>,
+    {
+        BasicBlockVector {
+            intervals: 1,
+            counts: counts.into_iter().collect(),
+            locations: HashMap::new(),
+        }
+    }
+
+    #[rstest]
+    #[case::both_empty(vec![], vec![], 0f64)]
+    #[case::identical(vec![(1, 10), (2, 30)], vec![(1, 10), (2, 30)], 0f64)]
+    #[case::scaled(vec![(1, 10), (2, 30)], vec![(1, 20), (2, 60)], 0f64)]
+    #[case::disjoint(vec![(1, 10)], vec![(2, 10)], 1f64)]
+    #[case::one_empty(vec![(1, 10)], vec![], 0.5f64)]
+    #[case::partial(vec![(1, 50), (2, 50)], vec![(1, 100)], 0.5f64)]
+    fn test_basic_block_vector_distance(
+        #[case] new: Vec<(u64, u64)>,
+        #[case] old: Vec<(u64, u64)>,
+        #[case] expected: f64,
+    ) {
+        let actual = vector(new.clone()).distance(&vector(old.clone()));
+        assert!((actual - expected).abs() < f64::EPSILON, "{actual} != {expected}");
+        let actual = vector(old).distance(&vector(new));
+        assert!((actual - expected).abs() < f64::EPSILON, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_basic_block_vector_distance_when_locations_then_match_by_address() {
+        let with_locations = |counts: [(u64, u64); 2], addresses: [(u64, &str); 2]| {
+            BasicBlockVector {
+                locations: addresses
+                    .into_iter()
+                    .map(|(id, address)| {
+                        (
+                            id,
+                            BasicBlockLocation {
+                                address: address.to_owned(),
+                                function: Some("bench::my_bench".to_owned()),
+                            },
+                        )
+                    })
+                    .collect(),
+                ..vector(counts)
+            }
+        };
+        // The same basic blocks were executed first in a different order, so the ids are swapped
+        let new = with_locations([(1, 10), (2, 30)], [(1, "0x1000"), (2, "0x2000")]);
+        let old = with_locations([(1, 30), (2, 10)], [(1, "0x2000"), (2, "0x1000")]);
+
+        assert!(new.distance(&old).abs() < f64::EPSILON);
+        // Without the locations of the `pc` out file the ids are compared
+        let actual = vector([(1, 10), (2, 30)]).distance(&old);
+        assert!((actual - 0.5f64).abs() < f64::EPSILON, "{actual} != 0.5");
+    }
+
+    #[test]
+    fn test_basic_block_vector_hottest_blocks() {
+        let vector = vector([(1, 10), (2, 30), (3, 10), (4, 20)]);
+        let ids = vector
+            .hottest_blocks(3)
+            .iter()
+            .map(|b| b.id)
+            .collect::<Vec<u64>>();
+        assert_eq!(ids, vec![2, 4, 1]);
+    }
+}
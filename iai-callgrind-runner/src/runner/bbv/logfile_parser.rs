@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::bb_parser::{parse_bb_out, parse_pc_out};
+use crate::runner::dhat::logfile_parser::merge_by_command;
+use crate::runner::summary::ToolRunSummary;
+use crate::runner::tool::logfile_parser::{
+    parse_with_out_files_with_extension, LogfileParser, LogfileSummary, ToolLogfileParser,
+};
+use crate::runner::tool::ToolOutputPath;
+
+/// The parser for the log files and the `bb` and `pc` out files of BBV
+///
+/// The log file provides the command, pids and details. The basic block vector is extracted from
+/// the accompanying `bb` out file and the locations of the basic blocks from the `pc` out file.
+pub struct BbvLogfileParser {
+    pub root_dir: PathBuf,
+}
+
+impl LogfileParser for BbvLogfileParser {
+    /// Parse the BBV log file
+    ///
+    /// The basic block vector is not part of the log file and is added in
+    /// [`BbvLogfileParser::parse`]
+    fn parse_single(&self, path: PathBuf) -> Result<LogfileSummary> {
+        ToolLogfileParser {
+            root_dir: self.root_dir.clone(),
+        }
+        .parse_single(path)
+    }
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Vec<LogfileSummary>> {
+        parse_with_out_files_with_extension(self, output_path, "out.bb", |summary, bb_file| {
+            let mut vector = parse_bb_out(bb_file)?;
+            // The pc file has the same name as the bb file just with `pc` instead of `bb`
+            let pc_file = bb_file
+                .file_name()
+                .map(|name| name.to_string_lossy().replacen(".out.bb", ".out.pc", 1))
+                .map(|name| bb_file.with_file_name(name))
+                .filter(|path| path.exists());
+            if let Some(pc_file) = pc_file {
+                vector.locations = parse_pc_out(&pc_file)?;
+            }
+
+            summary.costs = Some(vector.to_costs());
+            summary.basic_block_vector = Some(vector);
+            Ok(())
+        })
+    }
+
+    fn merge_logfile_summaries(
+        &self,
+        old: Vec<LogfileSummary>,
+        new: Vec<LogfileSummary>,
+    ) -> Vec<ToolRunSummary> {
+        merge_by_command(old, new)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod bb_parser;
+pub mod logfile_parser;
@@ -1,24 +1,41 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::io::stderr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
+use log::warn;
 
 use super::args::NoCapture;
+use super::cachegrind::args::Args as CachegrindArgs;
+use super::cachegrind::summary_parser::SummaryParser as CachegrindSummaryParser;
+use super::callgrind::annotate::{self, AnnotateConfig, LineCostsMap};
 use super::callgrind::args::Args;
+use super::callgrind::cache::{self, CacheRun};
+use super::callgrind::call_graph::{self, CallGraphConfig};
+use super::callgrind::collect::{CollectFilter, CollectParser};
+use super::callgrind::dumps::{self, Dump, DumpCostsSummary};
 use super::callgrind::flamegraph::{
     BaselineFlamegraphGenerator, Config as FlamegraphConfig, Flamegraph, FlamegraphGenerator,
     LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
 };
+use super::callgrind::function_costs::{self, FunctionCostsConfig};
+use super::callgrind::hashmap_parser::CallgrindMap;
+use super::callgrind::model::Costs;
+use super::callgrind::pprof::PprofConfig;
+use super::callgrind::processes::{self, ProcessDumps};
 use super::callgrind::summary_parser::SummaryParser;
-use super::callgrind::RegressionConfig;
+use super::callgrind::{CostModel, RegressionConfig};
 use super::common::{Assistant, AssistantKind, Config, ModulePath, Sandbox};
-use super::format::{BinaryBenchmarkHeader, OutputFormat, VerticalFormat};
+use super::format::{
+    print_dump_headline, print_function_costs, print_process_headline, BinaryBenchmarkHeader,
+    OutputFormat, VerticalFormat,
+};
 use super::meta::Metadata;
 use super::summary::{
-    BaselineKind, BaselineName, BenchmarkKind, BenchmarkSummary, CallgrindSummary, CostsSummary,
-    SummaryOutput,
+    AnnotatedSourceSummary, BaselineKind, BaselineName, BenchmarkKind, BenchmarkSummary,
+    CachegrindSummary, CallGraphSummary, CallgrindSummary, CallgrindThreadSummary, CostsSummary,
+    FlamegraphSummary, FunctionCostsSummary, PprofSummary, SummaryOutput,
 };
 use super::tool::{
     Parser, RunOptions, ToolCommand, ToolConfig, ToolConfigs, ToolOutputPath, ToolOutputPathKind,
@@ -44,7 +61,7 @@ struct BaselineBenchmark {
     baseline_kind: BaselineKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BinBench {
     pub id: Option<String>,
     pub args: Option<String>,
@@ -52,7 +69,16 @@ pub struct BinBench {
     pub command: Command,
     pub run_options: RunOptions,
     pub callgrind_args: Args,
+    pub cache_name: Option<String>,
+    pub cachegrind_args: CachegrindArgs,
+    pub default_tool: ValgrindTool,
     pub flamegraph_config: Option<FlamegraphConfig>,
+    pub function_costs_config: Option<FunctionCostsConfig>,
+    pub annotate_config: Option<AnnotateConfig>,
+    pub call_graph_config: Option<CallGraphConfig>,
+    pub pprof_config: Option<PprofConfig>,
+    pub collect_filter: Option<CollectFilter>,
+    pub cost_model: CostModel,
     pub regression_config: Option<RegressionConfig>,
     pub tools: ToolConfigs,
     pub setup: Option<Assistant>,
@@ -122,7 +148,7 @@ impl Benchmark for BaselineBenchmark {
     fn output_path(&self, bin_bench: &BinBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Out,
-            ValgrindTool::Callgrind,
+            bin_bench.default_tool.clone(),
             &self.baseline_kind,
             &config.meta.target_dir,
             &group.module_path,
@@ -146,18 +172,13 @@ impl Benchmark for BaselineBenchmark {
         let header = BinaryBenchmarkHeader::new(&config.meta, bin_bench);
         header.print();
 
-        let callgrind_command = ToolCommand::new(
-            ValgrindTool::Callgrind,
+        let command = ToolCommand::new(
+            bin_bench.default_tool.clone(),
             &config.meta,
             config.meta.args.nocapture,
         );
 
-        let tool_config = ToolConfig::new(
-            ValgrindTool::Callgrind,
-            true,
-            bin_bench.callgrind_args.clone(),
-            None,
-        );
+        let tool_config = bin_bench.default_tool_config();
 
         let out_path = self.output_path(bin_bench, config, group);
         out_path.init()?;
@@ -193,7 +214,7 @@ impl Benchmark for BaselineBenchmark {
             .as_ref()
             .map_or(Ok(None), |setup| setup.run(config, &bin_bench.module_path))?;
 
-        let output = callgrind_command.run(
+        let output = command.run(
             tool_config,
             &bin_bench.command.path,
             &bin_bench.command.args,
@@ -215,45 +236,61 @@ impl Benchmark for BaselineBenchmark {
             sandbox.reset()?;
         }
 
-        let new_costs = SummaryParser.parse(&out_path)?;
-
-        let old_costs = old_path
-            .exists()
-            .then(|| SummaryParser.parse(&old_path))
-            .transpose()?;
+        let new_processes = bin_bench.parse_processes(&config.meta, &out_path)?;
+        let old_processes = if old_path.exists() {
+            bin_bench.parse_processes(&config.meta, &old_path)?
+        } else {
+            vec![]
+        };
+        let old_lines = bin_bench.parse_line_costs(&config.meta, &old_path, &old_processes)?;
+        let old_calls = bin_bench.parse_call_graph(&config.meta, &old_path, &old_processes)?;
 
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
-        VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
+        let dumps = bin_bench.summarize_processes(
+            &config.meta,
+            &self.baselines(),
+            &new_processes,
+            &old_processes,
+        )?;
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = bin_bench.check_and_print_regressions(&costs_summary);
-
-        let callgrind_summary = benchmark_summary
-            .callgrind_summary
-            .insert(CallgrindSummary::new(
-                log_path.real_paths()?,
-                out_path.real_paths()?,
-            ));
-
-        callgrind_summary.add_summary(
-            &bin_bench.command.path,
-            &bin_bench.command.args,
-            &old_path,
-            costs_summary,
-            regressions,
-        );
-
-        if let Some(flamegraph_config) = bin_bench.flamegraph_config.clone() {
-            callgrind_summary.flamegraphs = BaselineFlamegraphGenerator {
-                baseline_kind: self.baseline_kind.clone(),
+        if let Some(callgrind_summary) = bin_bench.add_default_tool_summary(
+            &mut benchmark_summary,
+            &log_path,
+            &out_path,
+            dumps,
+        )? {
+            if let Some(flamegraph_config) = bin_bench.flamegraph_config.clone() {
+                callgrind_summary.flamegraphs = BinBench::create_flamegraphs(
+                    &BaselineFlamegraphGenerator {
+                        baseline_kind: self.baseline_kind.clone(),
+                    },
+                    &Flamegraph::new(header.to_title(), flamegraph_config),
+                    &out_path,
+                    (&new_processes, &old_processes),
+                    &config.meta.project_root,
+                )?;
             }
-            .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+
+            callgrind_summary.annotated_source = bin_bench.annotate(
+                &header.to_title(),
+                &config.meta,
                 &out_path,
-                None,
-                &config.meta.project_root,
+                &new_processes,
+                old_lines.as_ref(),
+            )?;
+            callgrind_summary.call_graph = bin_bench.create_call_graph(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                &new_processes,
+                old_calls.as_ref(),
+            )?;
+            callgrind_summary.pprof = bin_bench.export_pprof(
+                &config.meta,
+                (&out_path, &new_processes),
+                Some((&old_path, &old_processes)),
             )?;
         }
 
@@ -275,7 +312,10 @@ impl Benchmark for BaselineBenchmark {
 }
 
 impl BinBench {
-    fn new(
+    /// Create the `BinBench`es of a single benchmark, one for each run of the cache matrix
+    ///
+    /// Without a cache matrix, there is only a single `BinBench`.
+    fn new_all(
         meta: &Metadata,
         group: &Group,
         config: BinaryBenchmarkConfig,
@@ -283,7 +323,7 @@ impl BinBench {
         bench_index: usize,
         raw_args: &api::RawArgs,
         binary_benchmark_bench: BinaryBenchmarkBench,
-    ) -> Result<Self> {
+    ) -> Result<Vec<Self>> {
         let module_path = group
             .module_path
             .join(&binary_benchmark_bench.function_name);
@@ -299,7 +339,11 @@ impl BinBench {
 
         let command = Command::new(&module_path, path, args)?;
 
-        let callgrind_args = Args::from_raw_args(&[&config.raw_callgrind_args, raw_args])?;
+        let cachegrind_args = CachegrindArgs::from_raw_args(&[
+            &config.raw_cachegrind_args,
+            &meta.args.cachegrind_args.clone().unwrap_or_default(),
+        ])?;
+        let default_tool = ValgrindTool::resolve_default_tool(meta, config.default_tool.clone())?;
 
         let mut assistant_envs = config.collect_envs();
         assistant_envs.push((
@@ -308,20 +352,79 @@ impl BinBench {
         ));
 
         let command_envs = config.resolve_envs();
-        let flamegraph_config = config.flamegraph_config.map(Into::into);
+        let cache_runs = match (&default_tool, &config.cache_config, &config.cache_matrix) {
+            (ValgrindTool::Cachegrind, None, None) => vec![CacheRun::default()],
+            (ValgrindTool::Cachegrind, ..) => {
+                warn!(
+                    "{module_path}: Ignoring cache configuration: The typed cache configuration \
+                     is only available if callgrind is the default tool"
+                );
+                vec![CacheRun::default()]
+            }
+            (_, cache_config, cache_matrix) => cache::resolve(
+                &module_path,
+                cache_config.as_ref(),
+                cache_matrix.as_deref(),
+            )?,
+        };
+        let cost_model = api::update_option(&config.cost_model, &meta.cost_model)
+            .map(Into::into)
+            .unwrap_or_default();
+        let flamegraph_config = default_tool
+            .callgrind_only(
+                &module_path,
+                "flamegraph configuration",
+                config.flamegraph_config,
+            )
+            .map(|config| FlamegraphConfig {
+                cost_model,
+                ..config.into()
+            });
+        let function_costs_config = default_tool
+            .callgrind_only(
+                &module_path,
+                "function costs configuration",
+                config.function_costs_config,
+            )
+            .map(Into::into);
+        let annotate_config = default_tool
+            .callgrind_only(&module_path, "annotate configuration", config.annotate_config)
+            .map(Into::into);
+        let call_graph_config = default_tool
+            .callgrind_only(&module_path, "call graph configuration", config.call_graph_config)
+            .map(Into::into);
+        let pprof_config = default_tool
+            .callgrind_only(&module_path, "pprof configuration", config.pprof_config)
+            .map(|config| PprofConfig {
+                cost_model,
+                ..config.into()
+            });
+        let collect_filter = default_tool
+            .callgrind_only(&module_path, "collect configuration", config.collect_config)
+            .map(|config| CollectFilter::new(&config, &module_path))
+            .transpose()?;
 
-        Ok(Self {
+        let bin_bench = Self {
             id: binary_benchmark_bench.id,
             args: binary_benchmark_bench.args,
             function_name: binary_benchmark_bench.function_name,
-            callgrind_args,
+            callgrind_args: Args::default(),
+            cache_name: None,
+            cachegrind_args,
+            default_tool: default_tool.clone(),
             flamegraph_config,
+            function_costs_config,
+            annotate_config,
+            call_graph_config,
+            pprof_config,
+            collect_filter,
+            cost_model,
             regression_config: api::update_option(
                 &config.regression_config,
                 &meta.regression_config,
             )
             .map(Into::into),
-            tools: ToolConfigs(config.tools.0.into_iter().map(Into::into).collect()),
+            tools: ToolConfigs::new(config.tools, &default_tool, meta),
             setup: binary_benchmark_bench
                 .has_setup
                 .then_some(Assistant::new_bench_assistant(
@@ -361,14 +464,41 @@ impl BinBench {
             truncate_description: config
                 .truncate_description
                 .unwrap_or(defaults::TRUNCATE_LENGTH),
-        })
+        };
+
+        cache_runs
+            .into_iter()
+            .map(|cache_run| {
+                let mut callgrind_args = Args::from_raw_args_and_cache(
+                    &config.raw_callgrind_args,
+                    &cache_run.config,
+                    raw_args,
+                )?;
+                if bin_bench.annotate_config.is_some() {
+                    callgrind_args.set_annotate();
+                }
+                if let Some(flamegraph_config) = &bin_bench.flamegraph_config {
+                    callgrind_args.set_flamegraph_granularity(flamegraph_config.granularity);
+                }
+                Ok(Self {
+                    callgrind_args,
+                    cache_name: cache_run.name,
+                    ..bin_bench.clone()
+                })
+            })
+            .collect()
     }
 
     fn name(&self) -> String {
-        if let Some(bench_id) = &self.id {
+        let name = if let Some(bench_id) = &self.id {
             format!("{}.{}", self.function_name, bench_id)
         } else {
             self.function_name.clone()
+        };
+        if let Some(cache_name) = &self.cache_name {
+            format!("{name}.cache@{cache_name}")
+        } else {
+            name
         }
     }
 
@@ -380,6 +510,395 @@ impl BinBench {
         );
     }
 
+    /// The [`ToolConfig`] of the default tool
+    fn default_tool_config(&self) -> ToolConfig {
+        if self.default_tool == ValgrindTool::Cachegrind {
+            ToolConfig::new(
+                ValgrindTool::Cachegrind,
+                true,
+                self.cachegrind_args.clone(),
+                None,
+            )
+        } else {
+            ToolConfig::new(
+                ValgrindTool::Callgrind,
+                true,
+                self.callgrind_args.clone(),
+                self.is_trace_children().then(|| processes::PID_MODIFIER.to_owned()),
+            )
+        }
+    }
+
+    /// Return true if callgrind traces the children of the benchmarked command
+    fn is_trace_children(&self) -> bool {
+        self.default_tool != ValgrindTool::Cachegrind && self.callgrind_args.is_trace_children()
+    }
+
+    /// Parse the total [`Costs`] from the output file of the default tool
+    ///
+    /// With a [`CollectFilter`], the costs are calculated with the [`CollectParser`] instead.
+    fn parse_costs(&self, output_path: &ToolOutputPath) -> Result<Costs> {
+        let mut costs = if self.default_tool == ValgrindTool::Cachegrind {
+            CachegrindSummaryParser.parse(output_path)
+        } else if let Some(filter) = &self.collect_filter {
+            CollectParser::new(filter, None).parse(output_path)
+        } else {
+            SummaryParser.parse(output_path)
+        }?;
+        self.cost_model.apply(&mut costs)?;
+        Ok(costs)
+    }
+
+    /// Parse the per-function costs if needed by the [`FunctionCostsConfig`] or the function
+    /// limits of the [`RegressionConfig`]
+    ///
+    /// Returns `None` if the per-function costs are not needed or the `output_path` doesn't exist.
+    fn parse_function_costs(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+    ) -> Result<Option<CallgrindMap>> {
+        let is_needed = self.function_costs_config.is_some()
+            || self
+                .regression_config
+                .as_ref()
+                .map_or(false, RegressionConfig::has_function_limits);
+        if self.default_tool != ValgrindTool::Cachegrind && is_needed && output_path.exists() {
+            let mut map = function_costs::parse(output_path, &meta.project_root)?;
+            map.make_summary(&self.cost_model)?;
+            Ok(Some(map))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse the costs of all dumps of the output of the default tool at `output_path`
+    ///
+    /// Only callgrind can split its output into multiple dumps and threads. All other outputs
+    /// consist of a single dump without part number and trigger.
+    fn parse_dumps(&self, meta: &Metadata, output_path: &ToolOutputPath) -> Result<Vec<Dump>> {
+        if self.default_tool == ValgrindTool::Cachegrind {
+            Ok(vec![Dump {
+                costs: self.parse_costs(output_path)?,
+                functions: None,
+                threads: vec![],
+                path: output_path.clone(),
+                part: None,
+                trigger: None,
+            }])
+        } else {
+            dumps::parse(
+                output_path,
+                |path| self.parse_costs(path),
+                |path| self.parse_function_costs(meta, path),
+            )
+        }
+    }
+
+    /// Parse the dumps of all processes of the output of the default tool at `output_path`
+    ///
+    /// Only if callgrind traces the children, the output is split into one output per process.
+    fn parse_processes(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+    ) -> Result<Vec<ProcessDumps>> {
+        if self.is_trace_children() {
+            processes::find(output_path, &meta.project_root)?
+                .into_iter()
+                .map(|process| {
+                    Ok(ProcessDumps {
+                        dumps: self.parse_dumps(meta, &process.path)?,
+                        process: Some(process),
+                    })
+                })
+                .collect()
+        } else {
+            Ok(vec![ProcessDumps {
+                process: None,
+                dumps: self.parse_dumps(meta, output_path)?,
+            }])
+        }
+    }
+
+    /// Summarize and print the costs of the dumps of all `new` processes
+    ///
+    /// Each process is compared with the matching `old` process. The root process is always
+    /// checked for regressions, the child processes only if they match the child filters of the
+    /// [`RegressionConfig`].
+    fn summarize_processes(
+        &self,
+        meta: &Metadata,
+        baselines: &(Option<String>, Option<String>),
+        new: &[ProcessDumps],
+        old: &[ProcessDumps],
+    ) -> Result<Vec<DumpCostsSummary>> {
+        let mut summaries = vec![];
+        for (index, new_process) in new.iter().enumerate() {
+            let old_process = ProcessDumps::find_old(new, index, old);
+            let is_checked = new_process.process.as_ref().map_or(true, |process| {
+                !process.is_child
+                    || self
+                        .regression_config
+                        .as_ref()
+                        .map_or(true, |config| config.is_checked_child(&process.command))
+            });
+
+            let process = new_process.to_summary(old_process, is_checked);
+            if let Some(process) = &process {
+                print_process_headline(meta, process);
+            }
+
+            let old_dumps = old_process.map_or(&[][..], |old| &old.dumps);
+            for mut summary in
+                self.summarize_dumps(meta, baselines, &new_process.dumps, old_dumps, is_checked)?
+            {
+                summary.process.clone_from(&process);
+                summaries.push(summary);
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Summarize and print the costs of all `new` dumps and check them for regressions
+    ///
+    /// Each dump is compared with the `old` dump of the same part. Only if there are multiple
+    /// dumps, the part number and trigger are printed and recorded in the summary. The regressions
+    /// are only checked if `is_checked` is true.
+    fn summarize_dumps(
+        &self,
+        meta: &Metadata,
+        baselines: &(Option<String>, Option<String>),
+        new: &[Dump],
+        old: &[Dump],
+        is_checked: bool,
+    ) -> Result<Vec<DumpCostsSummary>> {
+        let mut summaries = vec![];
+        for new_dump in new {
+            let old_dump = new_dump.find_old(old);
+            let new_functions = new_dump.functions.as_ref();
+            let old_functions = old_dump.and_then(|dump| dump.functions.as_ref());
+
+            let dump = if new.len() > 1 {
+                new_dump.to_summary()
+            } else {
+                None
+            };
+            if let Some(dump) = &dump {
+                print_dump_headline(meta, dump);
+            }
+
+            let costs_summary =
+                CostsSummary::new(&new_dump.costs, old_dump.map(|dump| &dump.costs));
+            let functions = self.summarize_function_costs(new_functions, old_functions);
+
+            VerticalFormat::default().print(meta, baselines.clone(), &costs_summary)?;
+            print_function_costs(meta, &functions)?;
+            let threads = dumps::summarize_threads(meta, baselines, new_dump, old_dump)?;
+
+            let regressions = if is_checked {
+                self.check_and_print_regressions(
+                    &costs_summary,
+                    new_functions,
+                    old_functions,
+                    &threads,
+                )
+            } else {
+                vec![]
+            };
+
+            summaries.push(DumpCostsSummary {
+                path: new_dump.main_path().clone(),
+                dump,
+                costs_summary,
+                raw_costs_summary: None,
+                regressions,
+                functions,
+                threads,
+                process: None,
+            });
+        }
+        Ok(summaries)
+    }
+
+    /// Parse the line costs if needed by the [`AnnotateConfig`]
+    ///
+    /// Like the flamegraphs, the line costs are parsed from the output of the root process if the
+    /// children are traced. Returns `None` if the line costs are not needed or the output doesn't
+    /// exist.
+    fn parse_line_costs(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        processes: &[ProcessDumps],
+    ) -> Result<Option<LineCostsMap>> {
+        let path = Self::root_output_path(output_path, processes);
+        if self.annotate_config.is_some() && path.exists() {
+            let mut map = annotate::parse(path, &meta.project_root)?;
+            map.make_summary(&self.cost_model)?;
+            Ok(Some(map))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Create the annotated source report if configured with an [`AnnotateConfig`]
+    fn annotate(
+        &self,
+        title: &str,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        new: &[ProcessDumps],
+        old: Option<&LineCostsMap>,
+    ) -> Result<Option<AnnotatedSourceSummary>> {
+        match (&self.annotate_config, self.parse_line_costs(meta, output_path, new)?) {
+            (Some(config), Some(new)) => config
+                .create(title, &new, old, output_path, &meta.project_root)
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse the function costs and calls if needed by the [`CallGraphConfig`]
+    ///
+    /// Like the flamegraphs, the call graph is created from the output of the root process if the
+    /// children are traced. Returns `None` if the call graph is not created or the output doesn't
+    /// exist.
+    fn parse_call_graph(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        processes: &[ProcessDumps],
+    ) -> Result<Option<CallgrindMap>> {
+        let path = Self::root_output_path(output_path, processes);
+        if self.call_graph_config.is_some() && path.exists() {
+            let mut map = call_graph::parse(path, &meta.project_root)?;
+            map.make_summary(&self.cost_model)?;
+            Ok(Some(map))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Create the call graph if configured with a [`CallGraphConfig`]
+    fn create_call_graph(
+        &self,
+        title: &str,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        new: &[ProcessDumps],
+        old: Option<&CallgrindMap>,
+    ) -> Result<Option<CallGraphSummary>> {
+        match (&self.call_graph_config, self.parse_call_graph(meta, output_path, new)?) {
+            (Some(config), Some(new)) => config.create(title, &new, old, output_path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Export the callgrind output to pprof profiles if configured with a [`PprofConfig`]
+    ///
+    /// Like the flamegraphs, the profiles are created from the output of the root process if the
+    /// children are traced. The profile of the old output is created only if it exists.
+    fn export_pprof(
+        &self,
+        meta: &Metadata,
+        (output_path, new): (&ToolOutputPath, &[ProcessDumps]),
+        old: Option<(&ToolOutputPath, &[ProcessDumps])>,
+    ) -> Result<Option<PprofSummary>> {
+        self.pprof_config
+            .as_ref()
+            .map(|config| {
+                config.create(
+                    Self::root_output_path(output_path, new),
+                    old.map(|(old_path, old)| Self::root_output_path(old_path, old)),
+                    None,
+                    &meta.project_root,
+                )
+            })
+            .transpose()
+    }
+
+    /// Return the output path of the root process if the children are traced or else the
+    /// `output_path`
+    fn root_output_path<'a>(
+        output_path: &'a ToolOutputPath,
+        processes: &'a [ProcessDumps],
+    ) -> &'a ToolOutputPath {
+        processes::find_root(processes)
+            .and_then(|dumps| dumps.process.as_ref())
+            .map_or(output_path, |process| &process.path)
+    }
+
+    /// Create the flamegraphs of the callgrind run
+    ///
+    /// If the children are traced, only the flamegraphs of the root process are created. The
+    /// flamegraph files are named like the ones of a run without traced children.
+    fn create_flamegraphs<G>(
+        generator: &G,
+        flamegraph: &Flamegraph,
+        output_path: &ToolOutputPath,
+        (new, old): (&[ProcessDumps], &[ProcessDumps]),
+        project_root: &Path,
+    ) -> Result<Vec<FlamegraphSummary>>
+    where
+        G: FlamegraphGenerator,
+    {
+        let Some(root) = processes::find_root(new).and_then(|dumps| dumps.process.as_ref()) else {
+            return generator.create_all(flamegraph, output_path, None, project_root);
+        };
+
+        let old_root = processes::find_root(old)
+            .and_then(|dumps| dumps.process.as_ref())
+            .filter(|old_root| old_root.command == root.command);
+        generator.create_from(
+            flamegraph,
+            output_path,
+            &flamegraph.config.event_kinds,
+            |no_differential| {
+                flamegraph.parse_paths(
+                    &root.path,
+                    old_root.map(|old_root| &old_root.path),
+                    None,
+                    project_root,
+                    no_differential,
+                )
+            },
+        )
+    }
+
+    /// Summarize the costs of the top functions if configured with a [`FunctionCostsConfig`]
+    fn summarize_function_costs(
+        &self,
+        new: Option<&CallgrindMap>,
+        old: Option<&CallgrindMap>,
+    ) -> Vec<FunctionCostsSummary> {
+        match (&self.function_costs_config, new) {
+            (Some(config), Some(new)) => config.summarize(new, old),
+            _ => vec![],
+        }
+    }
+
+    /// Create the initial [`CallgrindSummary`] of the callgrind output files
+    ///
+    /// The effective cache geometry and the toggles in effect are recorded for all kinds of
+    /// baselines, also if the costs are loaded from a baseline.
+    fn create_callgrind_summary(
+        &self,
+        log_path: &ToolOutputPath,
+        out_path: &ToolOutputPath,
+    ) -> Result<CallgrindSummary> {
+        let mut callgrind_summary =
+            CallgrindSummary::new(log_path.real_paths()?, out_path.real_paths()?);
+        callgrind_summary.cache_geometry = self
+            .callgrind_args
+            .cache_geometry(self.cache_name.clone());
+        callgrind_summary.collect = Some(
+            self.callgrind_args
+                .collect_summary(self.collect_filter.as_ref()),
+        );
+        Ok(callgrind_summary)
+    }
+
     fn create_benchmark_summary(
         &self,
         config: &Config,
@@ -412,13 +931,70 @@ impl BinBench {
     fn check_and_print_regressions(
         &self,
         costs_summary: &CostsSummary,
+        new_functions: Option<&CallgrindMap>,
+        old_functions: Option<&CallgrindMap>,
+        threads: &[CallgrindThreadSummary],
     ) -> Vec<super::summary::CallgrindRegressionSummary> {
         if let Some(regression_config) = &self.regression_config {
-            regression_config.check_and_print(costs_summary)
+            regression_config.check_and_print(
+                costs_summary,
+                new_functions.zip(old_functions),
+                threads,
+            )
         } else {
             vec![]
         }
     }
+
+    /// Add the summaries of all `dumps` to the summary of the default tool
+    ///
+    /// Returns the [`CallgrindSummary`] if callgrind is the default tool, so the callgrind specific
+    /// reports can be added.
+    fn add_default_tool_summary<'a>(
+        &self,
+        benchmark_summary: &'a mut BenchmarkSummary,
+        log_path: &ToolOutputPath,
+        out_path: &ToolOutputPath,
+        dumps: Vec<DumpCostsSummary>,
+    ) -> Result<Option<&'a mut CallgrindSummary>> {
+        if self.default_tool == ValgrindTool::Cachegrind {
+            let cachegrind_summary = benchmark_summary
+                .cachegrind_summary
+                .insert(CachegrindSummary::new(
+                    log_path.real_paths()?,
+                    out_path.real_paths()?,
+                ));
+            for dump in dumps {
+                cachegrind_summary.add_summary(
+                    &self.command.path,
+                    &self.command.args,
+                    &dump.path.to_base_path(),
+                    dump.costs_summary,
+                    dump.regressions,
+                );
+            }
+            Ok(None)
+        } else {
+            let callgrind_summary = benchmark_summary
+                .callgrind_summary
+                .insert(self.create_callgrind_summary(log_path, out_path)?);
+            for dump in dumps {
+                callgrind_summary.add_summary(
+                    &self.command.path,
+                    &self.command.args,
+                    &dump.path.to_base_path(),
+                    dump.costs_summary,
+                    dump.raw_costs_summary,
+                    dump.regressions,
+                    dump.functions,
+                    dump.dump,
+                    dump.threads,
+                    dump.process,
+                );
+            }
+            Ok(Some(callgrind_summary))
+        }
+    }
 }
 
 impl Command {
@@ -448,7 +1024,8 @@ impl Group {
 
             let summary = benchmark.run(bench, config, self)?;
             summary.print_and_save(&config.meta.args.output_format)?;
-            summary.check_regression(is_regressed, fail_fast)?;
+            summary.check_regression(is_regressed, fail_fast, &bench.tools)?;
+            summary.check_tool_errors(&bench.tools)?;
 
             if self.compare_by_id && config.meta.args.output_format == OutputFormat::Default {
                 if let Some(id) = &summary.id {
@@ -525,7 +1102,7 @@ impl Groups {
                         Some(&binary_benchmark_bench.command.config),
                     ]);
 
-                    let bin_bench = BinBench::new(
+                    let bin_benches = BinBench::new_all(
                         meta,
                         &group,
                         config,
@@ -534,7 +1111,7 @@ impl Groups {
                         &meta_callgrind_args,
                         binary_benchmark_bench,
                     )?;
-                    group.benches.push(bin_bench);
+                    group.benches.extend(bin_benches);
                 }
             }
 
@@ -576,7 +1153,7 @@ impl Benchmark for LoadBaselineBenchmark {
     fn output_path(&self, bin_bench: &BinBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.loaded_baseline.to_string()),
-            ValgrindTool::Callgrind,
+            bin_bench.default_tool.clone(),
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
             &group.module_path,
@@ -611,45 +1188,61 @@ impl Benchmark for LoadBaselineBenchmark {
             header.description(),
         )?;
 
-        let new_costs = SummaryParser.parse(&out_path)?;
-        let old_costs = Some(SummaryParser.parse(&old_path)?);
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
+        let new_processes = bin_bench.parse_processes(&config.meta, &out_path)?;
+        let old_processes = bin_bench.parse_processes(&config.meta, &old_path)?;
+        let old_lines = bin_bench.parse_line_costs(&config.meta, &old_path, &old_processes)?;
+        let old_calls = bin_bench.parse_call_graph(&config.meta, &old_path, &old_processes)?;
 
-        VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
-
-        let regressions = bin_bench.check_and_print_regressions(&costs_summary);
-
-        let callgrind_summary = benchmark_summary
-            .callgrind_summary
-            .insert(CallgrindSummary::new(
-                log_path.real_paths()?,
-                out_path.real_paths()?,
-            ));
-
-        callgrind_summary.add_summary(
-            &bin_bench.command.path,
-            &bin_bench.command.args,
-            &old_path,
-            costs_summary,
-            regressions,
-        );
+        let dumps = bin_bench.summarize_processes(
+            &config.meta,
+            &self.baselines(),
+            &new_processes,
+            &old_processes,
+        )?;
 
-        if let Some(flamegraph_config) = bin_bench.flamegraph_config.clone() {
-            callgrind_summary.flamegraphs = LoadBaselineFlamegraphGenerator {
-                loaded_baseline: self.loaded_baseline.clone(),
-                baseline: self.baseline.clone(),
+        if let Some(callgrind_summary) = bin_bench.add_default_tool_summary(
+            &mut benchmark_summary,
+            &log_path,
+            &out_path,
+            dumps,
+        )? {
+            if let Some(flamegraph_config) = bin_bench.flamegraph_config.clone() {
+                callgrind_summary.flamegraphs = BinBench::create_flamegraphs(
+                    &LoadBaselineFlamegraphGenerator {
+                        loaded_baseline: self.loaded_baseline.clone(),
+                        baseline: self.baseline.clone(),
+                    },
+                    &Flamegraph::new(header.to_title(), flamegraph_config),
+                    &out_path,
+                    (&new_processes, &old_processes),
+                    &config.meta.project_root,
+                )?;
             }
-            .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+
+            callgrind_summary.annotated_source = bin_bench.annotate(
+                &header.to_title(),
+                &config.meta,
                 &out_path,
-                None,
-                &config.meta.project_root,
+                &new_processes,
+                old_lines.as_ref(),
+            )?;
+            callgrind_summary.call_graph = bin_bench.create_call_graph(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                &new_processes,
+                old_calls.as_ref(),
+            )?;
+            callgrind_summary.pprof = bin_bench.export_pprof(
+                &config.meta,
+                (&out_path, &new_processes),
+                Some((&old_path, &old_processes)),
             )?;
         }
 
         benchmark_summary.tool_summaries = bin_bench
             .tools
-            .run_loaded_vs_base(&config.meta, &out_path)?;
+            .run_loaded_vs_base(&config.meta, &out_path, &bin_bench.module_path)?;
 
         Ok(benchmark_summary)
     }
@@ -727,7 +1320,7 @@ impl Benchmark for SaveBaselineBenchmark {
     fn output_path(&self, bin_bench: &BinBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.baseline.to_string()),
-            ValgrindTool::Callgrind,
+            bin_bench.default_tool.clone(),
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
             &group.module_path,
@@ -751,30 +1344,27 @@ impl Benchmark for SaveBaselineBenchmark {
         let header = BinaryBenchmarkHeader::new(&config.meta, bin_bench);
         header.print();
 
-        let callgrind_command = ToolCommand::new(
-            ValgrindTool::Callgrind,
+        let command = ToolCommand::new(
+            bin_bench.default_tool.clone(),
             &config.meta,
             config.meta.args.nocapture,
         );
 
-        let tool_config = ToolConfig::new(
-            ValgrindTool::Callgrind,
-            true,
-            bin_bench.callgrind_args.clone(),
-            None,
-        );
+        let tool_config = bin_bench.default_tool_config();
 
         let out_path = self.output_path(bin_bench, config, group);
         out_path.init()?;
 
-        let old_costs = out_path
-            .exists()
-            .then(|| {
-                SummaryParser
-                    .parse(&out_path)
-                    .and_then(|costs| out_path.clear().map(|()| costs))
-            })
-            .transpose()?;
+        // The old dumps need to be parsed before the old output files are cleared
+        let (old_processes, old_lines, old_calls) = if out_path.exists() {
+            let old_processes = bin_bench.parse_processes(&config.meta, &out_path)?;
+            let old_lines = bin_bench.parse_line_costs(&config.meta, &out_path, &old_processes)?;
+            let old_calls = bin_bench.parse_call_graph(&config.meta, &out_path, &old_processes)?;
+            out_path.clear()?;
+            (old_processes, old_lines, old_calls)
+        } else {
+            (vec![], None, None)
+        };
 
         let log_path = out_path.to_log_output();
         log_path.clear()?;
@@ -797,7 +1387,7 @@ impl Benchmark for SaveBaselineBenchmark {
             .as_ref()
             .map_or(Ok(None), |setup| setup.run(config, &bin_bench.module_path))?;
 
-        let output = callgrind_command.run(
+        let output = command.run(
             tool_config,
             &bin_bench.command.path,
             &bin_bench.command.args,
@@ -817,39 +1407,53 @@ impl Benchmark for SaveBaselineBenchmark {
             sandbox.reset()?;
         }
 
-        let new_costs = SummaryParser.parse(&out_path)?;
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
-        VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
+        let new_processes = bin_bench.parse_processes(&config.meta, &out_path)?;
+        let dumps = bin_bench.summarize_processes(
+            &config.meta,
+            &self.baselines(),
+            &new_processes,
+            &old_processes,
+        )?;
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = bin_bench.check_and_print_regressions(&costs_summary);
-
-        let callgrind_summary = benchmark_summary
-            .callgrind_summary
-            .insert(CallgrindSummary::new(
-                log_path.real_paths()?,
-                out_path.real_paths()?,
-            ));
-
-        callgrind_summary.add_summary(
-            &bin_bench.command.path,
-            &bin_bench.command.args,
+        if let Some(callgrind_summary) = bin_bench.add_default_tool_summary(
+            &mut benchmark_summary,
+            &log_path,
             &out_path,
-            costs_summary,
-            regressions,
-        );
-
-        if let Some(flamegraph_config) = bin_bench.flamegraph_config.clone() {
-            callgrind_summary.flamegraphs = SaveBaselineFlamegraphGenerator {
-                baseline: self.baseline.clone(),
+            dumps,
+        )? {
+            if let Some(flamegraph_config) = bin_bench.flamegraph_config.clone() {
+                callgrind_summary.flamegraphs = BinBench::create_flamegraphs(
+                    &SaveBaselineFlamegraphGenerator {
+                        baseline: self.baseline.clone(),
+                    },
+                    &Flamegraph::new(header.to_title(), flamegraph_config),
+                    &out_path,
+                    (&new_processes, &old_processes),
+                    &config.meta.project_root,
+                )?;
             }
-            .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+
+            callgrind_summary.annotated_source = bin_bench.annotate(
+                &header.to_title(),
+                &config.meta,
                 &out_path,
+                &new_processes,
+                old_lines.as_ref(),
+            )?;
+            callgrind_summary.call_graph = bin_bench.create_call_graph(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                &new_processes,
+                old_calls.as_ref(),
+            )?;
+            callgrind_summary.pprof = bin_bench.export_pprof(
+                &config.meta,
+                (&out_path, &new_processes),
                 None,
-                &config.meta.project_root,
             )?;
         }
 
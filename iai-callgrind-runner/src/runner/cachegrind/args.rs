@@ -0,0 +1,229 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use log::{log_enabled, warn};
+
+use crate::api::RawArgs;
+use crate::error::Error;
+use crate::runner::tool;
+use crate::util::{bool_to_yesno, yesno_to_bool};
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    i1: String,
+    d1: String,
+    ll: String,
+    cache_sim: bool,
+    instr_at_start: Option<bool>,
+    other: Vec<String>,
+    verbose: bool,
+    cachegrind_out_file: Option<PathBuf>,
+    log_arg: Option<OsString>,
+}
+
+impl Args {
+    pub fn from_raw_args(args: &[&RawArgs]) -> Result<Self> {
+        let mut default = Self::default();
+        default.update(args.iter().flat_map(|s| &s.0))?;
+        Ok(default)
+    }
+
+    pub fn update<'a, T: Iterator<Item = &'a String>>(&mut self, args: T) -> Result<()> {
+        for arg in args {
+            match arg
+                .trim()
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+            {
+                Some(("--I1", value)) => value.clone_into(&mut self.i1),
+                Some(("--D1", value)) => value.clone_into(&mut self.d1),
+                Some(("--LL", value)) => value.clone_into(&mut self.ll),
+                Some((key @ "--cache-sim", value)) => {
+                    self.cache_sim = yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCachegrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?;
+                }
+                Some((key @ "--instr-at-start", value)) => {
+                    self.instr_at_start = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCachegrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
+                Some((
+                    key @ ("--cachegrind-out-file"
+                    | "--log-file"
+                    | "--log-fd"
+                    | "--log-socket"
+                    | "--xml"
+                    | "--xml-file"
+                    | "--xml-fd"
+                    | "--xml-socket"
+                    | "--xml-user-comment"
+                    | "--tool"),
+                    value,
+                )) => {
+                    warn!("Ignoring cachegrind argument: '{}={}'", key, value);
+                }
+                Some(_) => self.other.push(arg.clone()),
+                None if arg == "-v" || arg == "--verbose" => self.verbose = true,
+                None if matches!(
+                    arg.trim(),
+                    "-h" | "--help"
+                        | "--help-dyn-options"
+                        | "--help-debug"
+                        | "--version"
+                        | "-q"
+                        | "--quiet"
+                ) =>
+                {
+                    warn!("Ignoring cachegrind argument: '{arg}'");
+                }
+                None if arg.starts_with('-') => self.other.push(arg.clone()),
+                // ignore positional arguments for now. It might be a filtering argument for cargo
+                // bench
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `--instr-at-start` to `value` if it wasn't set by the user
+    pub fn set_default_instr_at_start(&mut self, value: bool) {
+        self.instr_at_start.get_or_insert(value);
+    }
+
+    /// Return true if cachegrind starts the instrumentation at the start of the program
+    pub fn is_instr_at_start(&self) -> bool {
+        self.instr_at_start.unwrap_or(true)
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            // Use the same cache sizes as for callgrind, so the results of both tools are
+            // comparable. Since valgrind 3.21, the cache simulation of cachegrind is switched off
+            // per default, but we want the same event kinds as in callgrind runs.
+            i1: String::from("32768,8,64"),
+            d1: String::from("32768,8,64"),
+            ll: String::from("8388608,16,64"),
+            cache_sim: true,
+            instr_at_start: Option::default(),
+            verbose: log_enabled!(log::Level::Debug),
+            cachegrind_out_file: Option::default(),
+            log_arg: Option::default(),
+            other: Vec::default(),
+        }
+    }
+}
+
+impl From<Args> for tool::args::ToolArgs {
+    fn from(mut value: Args) -> Self {
+        let mut other = if value.cache_sim {
+            vec![
+                format!("--I1={}", &value.i1),
+                format!("--D1={}", &value.d1),
+                format!("--LL={}", &value.ll),
+            ]
+        } else {
+            vec![]
+        };
+        other.push(format!("--cache-sim={}", bool_to_yesno(value.cache_sim)));
+        if let Some(instr_at_start) = value.instr_at_start {
+            other.push(format!("--instr-at-start={}", bool_to_yesno(instr_at_start)));
+        }
+        other.append(&mut value.other);
+
+        Self {
+            tool: tool::ValgrindTool::Cachegrind,
+            output_paths: value
+                .cachegrind_out_file
+                .map_or_else(Vec::new, |o| vec![o.into()]),
+            log_path: value.log_arg,
+            error_exitcode: "0".to_owned(),
+            verbose: value.verbose,
+            other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::tool::args::ToolArgs;
+
+    #[test]
+    fn test_cachegrind_args_default() {
+        let actual: ToolArgs = Args::from_raw_args(&[]).unwrap().into();
+        assert_eq!(
+            actual.other,
+            vec![
+                "--I1=32768,8,64".to_owned(),
+                "--D1=32768,8,64".to_owned(),
+                "--LL=8388608,16,64".to_owned(),
+                "--cache-sim=yes".to_owned(),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::cache_sim_no(&["--cache-sim=no"], &["--cache-sim=no"])]
+    #[case::ignored_out_file(
+        &["--cachegrind-out-file=some", "--cache-sim=no"],
+        &["--cache-sim=no"]
+    )]
+    #[case::instr_at_start(
+        &["--instr-at-start=no", "--cache-sim=no"],
+        &["--cache-sim=no", "--instr-at-start=no"]
+    )]
+    #[case::other(
+        &["--cache-sim=no", "--branch-sim=yes"],
+        &["--cache-sim=no", "--branch-sim=yes"]
+    )]
+    #[case::cache_sizes(
+        &["--I1=1,2,3", "--branch-sim=yes"],
+        &[
+            "--I1=1,2,3",
+            "--D1=32768,8,64",
+            "--LL=8388608,16,64",
+            "--cache-sim=yes",
+            "--branch-sim=yes"
+        ]
+    )]
+    fn test_cachegrind_args_from_raw_args(#[case] args: &[&str], #[case] expected: &[&str]) {
+        let raw_args = RawArgs::from_iter(args);
+        let actual: ToolArgs = Args::from_raw_args(&[&raw_args]).unwrap().into();
+        assert_eq!(actual.other, expected);
+    }
+
+    #[rstest]
+    #[case::cache_sim("--cache-sim=maybe")]
+    #[case::instr_at_start("--instr-at-start=maybe")]
+    fn test_cachegrind_args_when_invalid_bool_then_error(#[case] arg: &str) {
+        let raw_args = RawArgs::from_iter([arg]);
+        assert!(Args::from_raw_args(&[&raw_args]).is_err());
+    }
+
+    #[rstest]
+    #[case::default(&[], false)]
+    #[case::yes(&["--instr-at-start=yes"], true)]
+    #[case::no(&["--instr-at-start=no"], false)]
+    fn test_cachegrind_args_set_default_instr_at_start(
+        #[case] args: &[&str],
+        #[case] expected: bool,
+    ) {
+        let raw_args = RawArgs::from_iter(args);
+        let mut args = Args::from_raw_args(&[&raw_args]).unwrap();
+        args.set_default_instr_at_start(false);
+
+        assert_eq!(args.is_instr_at_start(), expected);
+        let actual: ToolArgs = args.into();
+        assert_eq!(
+            actual.other.last().unwrap(),
+            &format!("--instr-at-start={}", bool_to_yesno(expected))
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod args;
+pub mod parser;
+pub mod summary_parser;
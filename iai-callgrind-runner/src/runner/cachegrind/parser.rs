@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use log::trace;
+
+use crate::runner::callgrind::model::Costs;
+
+#[derive(Debug, Default)]
+pub struct CachegrindProperties {
+    pub costs_prototype: Costs,
+    pub desc: Vec<String>,
+    pub cmd: Option<String>,
+}
+
+/// Parse the header of a cachegrind output file
+///
+/// The header of a cachegrind output file consists of the `desc:`, `cmd:` and `events:` lines.
+/// Unlike callgrind output files, there is no file format specifier and no `version` or `positions`
+/// field. The `events:` line is mandatory and the last line of the header.
+pub fn parse_header(iter: &mut impl Iterator<Item = String>) -> Result<CachegrindProperties> {
+    let mut desc = vec![];
+    let mut cmd = None;
+    let mut costs_prototype: Option<Costs> = None;
+
+    for line in iter {
+        if line.is_empty() || line.starts_with('#') {
+            // skip empty lines or comments
+            continue;
+        }
+        match line.split_once(':').map(|(k, v)| (k.trim(), v.trim())) {
+            Some(("desc", value)) => desc.push(value.to_owned()),
+            Some(("cmd", value)) => cmd = Some(value.to_owned()),
+            Some(("events", events)) => {
+                trace!("Using events from line: '{line}'");
+                costs_prototype = Some(events.split_ascii_whitespace().collect());
+                break;
+            }
+            // None is actually a malformed header line we just ignore here
+            None | Some(_) => {}
+        }
+    }
+
+    Ok(CachegrindProperties {
+        costs_prototype: costs_prototype
+            .ok_or_else(|| anyhow!("Header field 'events' must be present"))?,
+        desc,
+        cmd,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::api::EventKind;
+
+    #[test]
+    fn test_parse_header() {
+        let header = [
+            "desc: I1 cache:         32768 B, 64 B, 8-way associative",
+            "desc: D1 cache:         32768 B, 64 B, 8-way associative",
+            "desc: LL cache:         8388608 B, 64 B, 16-way associative",
+            "cmd: target/release/deps/bench-123 --iai-run",
+            "events: Ir I1mr ILmr",
+            "fl=file.rs",
+        ];
+
+        let actual = parse_header(&mut header.iter().map(|s| (*s).to_owned())).unwrap();
+
+        assert_eq!(actual.desc.len(), 3);
+        assert_eq!(
+            actual.cmd.as_deref(),
+            Some("target/release/deps/bench-123 --iai-run")
+        );
+        assert_eq!(
+            actual.costs_prototype,
+            Costs::with_event_kinds([
+                (EventKind::Ir, 0),
+                (EventKind::I1mr, 0),
+                (EventKind::ILmr, 0)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_header_when_no_events_then_error() {
+        let header = ["desc: I1 cache: 32768 B, 64 B, 8-way associative", "cmd: some"];
+
+        assert_eq!(
+            parse_header(&mut header.iter().map(|s| (*s).to_owned()))
+                .unwrap_err()
+                .to_string(),
+            "Header field 'events' must be present"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use log::{debug, trace};
+
+use super::parser::parse_header;
+use crate::error::Error;
+use crate::runner::callgrind::model::Costs;
+use crate::runner::tool::{Parser, ToolOutputPath};
+
+/// Parse the `summary:` line of a cachegrind output file
+///
+/// In contrast to callgrind, cachegrind always writes a `summary:` line with the total costs of
+/// the instrumented code.
+pub struct SummaryParser;
+
+impl Parser for SummaryParser {
+    type Output = Costs;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output>
+    where
+        Self: std::marker::Sized,
+    {
+        debug!("Parsing cachegrind output file '{}' for a summary", output_path);
+
+        let mut iter = output_path.lines()?;
+        let config = parse_header(&mut iter)
+            .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
+
+        let mut costs = config.costs_prototype;
+        for line in iter {
+            if let Some(stripped) = line.strip_prefix("summary:") {
+                trace!("Found line with summary: '{}'", line);
+                costs.add_iter_str(stripped.split_ascii_whitespace());
+                trace!("Updated counters to '{:?}'", &costs);
+                return Ok(costs);
+            }
+        }
+
+        Err(Error::ParseError((output_path.to_path(), "No summary line found".to_owned())).into())
+    }
+}
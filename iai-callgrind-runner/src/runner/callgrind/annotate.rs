@@ -0,0 +1,660 @@
+//! The annotated source report of callgrind runs
+//!
+//! The report is similar to the output of `callgrind_annotate` with the source files. It shows the
+//! exclusive costs of every line of the source files with the highest costs and the difference to
+//! the costs of the old or baseline run. The report is written as html file next to the callgrind
+//! output files.
+
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::debug;
+
+use super::function_costs::cost_of;
+use super::hashmap_parser::{make_path, SourcePath};
+use super::model::{Costs, PositionType};
+use super::parser::{parse_header, parse_position};
+use super::CostModel;
+use crate::api::{self, EventKind};
+use crate::error::Error;
+use crate::runner::costs::Summarize;
+use crate::runner::summary::AnnotatedSourceSummary;
+use crate::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind};
+use crate::util::{percentage_diff, to_string_signed_short};
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; font-family: monospace; margin-bottom: 2em; }
+th, td { padding: 0 0.5em; text-align: right; white-space: pre; }
+th { border-bottom: 1px solid #999; }
+td.source { text-align: left; }
+tr.gap td { color: #999; text-align: center; }
+tr.hot td.source { background-color: #fff3cd; }
+.increase { color: #c00; }
+.decrease { color: #080; }";
+
+/// The default number of source files to annotate
+const DEFAULT_TOP: usize = 10;
+
+/// The default number of context lines like the `--context` option of `callgrind_annotate`
+const DEFAULT_CONTEXT: usize = 8;
+
+/// The configuration of the annotated source report of callgrind runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotateConfig {
+    /// The number of source files with the highest costs to annotate
+    pub top: usize,
+    /// The [`EventKind`]s to show. The source files are sorted by the first one.
+    pub event_kinds: Vec<EventKind>,
+    /// The number of lines without costs shown around the lines with costs
+    pub context: usize,
+}
+
+/// The exclusive costs of the lines of all source files of a callgrind run
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineCostsMap(pub HashMap<SourcePath, BTreeMap<u64, Costs>>);
+
+/// Parse the exclusive costs of every source line from callgrind output files
+///
+/// Like the flamegraphs, the line costs are the costs of all dumps and threads of a callgrind run.
+/// The costs of the calls to other functions are not added to the lines of the calls.
+#[derive(Debug, Default)]
+pub struct LineCostsParser {
+    pub project_root: PathBuf,
+}
+
+impl AnnotateConfig {
+    /// Create the annotated source report of the `new` line costs compared to the `old` line
+    /// costs
+    ///
+    /// The report is written next to the callgrind output file at `output_path`.
+    pub fn create(
+        &self,
+        title: &str,
+        new: &LineCostsMap,
+        old: Option<&LineCostsMap>,
+        output_path: &ToolOutputPath,
+        project_root: &Path,
+    ) -> Result<AnnotatedSourceSummary> {
+        let path = report_path(output_path);
+        debug!("Creating annotated source report '{}'", path.display());
+
+        let totals = new.total();
+        let mut totals = Cow::Borrowed(&totals);
+        EventKind::summarize(&mut totals);
+        let event_kinds = self
+            .event_kinds
+            .iter()
+            .filter(|kind| totals.cost_by_kind(kind).is_some())
+            .copied()
+            .collect::<Vec<EventKind>>();
+        let files = self.top_files(new);
+
+        let mut html = String::new();
+        writeln!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</\
+             title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>{0}</h1>",
+            escape(title)
+        )?;
+        write_files_table(&mut html, &files, &event_kinds, new, old)?;
+        for (index, file) in files.iter().enumerate() {
+            writeln!(
+                html,
+                "<h2 id=\"file-{}\">{}</h2>",
+                index + 1,
+                escape(&file_name(file))
+            )?;
+            let source = read_source(file, project_root);
+            write_source_table(
+                &mut html,
+                &event_kinds,
+                new.0.get(*file),
+                old.and_then(|old| old.0.get(*file)),
+                old.is_some(),
+                source.as_deref(),
+                self.context,
+            )?;
+        }
+        html.push_str("</body>\n</html>\n");
+
+        std::fs::write(&path, html).with_context(|| {
+            format!(
+                "Failed creating annotated source file '{}'",
+                path.display()
+            )
+        })?;
+
+        Ok(AnnotatedSourceSummary {
+            path,
+            files: files
+                .iter()
+                .filter_map(|file| file.as_path().map(PathBuf::from))
+                .collect(),
+        })
+    }
+
+    /// Return the `top` source files sorted descending by the costs of the first [`EventKind`]
+    ///
+    /// Source files with the same costs are sorted by path. Lines of unknown source files can't be
+    /// annotated and are ignored.
+    fn top_files<'a>(&self, map: &'a LineCostsMap) -> Vec<&'a SourcePath> {
+        let sort_kind = self.event_kinds.first().copied().unwrap_or(EventKind::Ir);
+        let mut files = map
+            .0
+            .iter()
+            .filter(|(file, _)| **file != SourcePath::Unknown)
+            .map(|(file, lines)| (Reverse(cost_of(&sum(lines.values()), sort_kind)), file))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+            .into_iter()
+            .take(self.top)
+            .map(|(_, file)| file)
+            .collect()
+    }
+}
+
+impl Default for AnnotateConfig {
+    fn default() -> Self {
+        Self {
+            top: DEFAULT_TOP,
+            event_kinds: vec![EventKind::Ir],
+            context: DEFAULT_CONTEXT,
+        }
+    }
+}
+
+impl From<api::AnnotateConfig> for AnnotateConfig {
+    fn from(value: api::AnnotateConfig) -> Self {
+        let api::AnnotateConfig {
+            top,
+            event_kinds,
+            context,
+        } = value;
+        Self {
+            top: top.unwrap_or(DEFAULT_TOP),
+            event_kinds: event_kinds
+                .filter(|e| !e.is_empty())
+                .unwrap_or_else(|| vec![EventKind::Ir]),
+            context: context.unwrap_or(DEFAULT_CONTEXT),
+        }
+    }
+}
+
+impl LineCostsMap {
+    /// Add the costs of all lines of the `other` map to the costs of this map
+    pub fn add(&mut self, other: LineCostsMap) {
+        for (file, lines) in other.0 {
+            let entry = self.0.entry(file).or_default();
+            for (line, costs) in lines {
+                entry
+                    .entry(line)
+                    .and_modify(|c| c.add(&costs))
+                    .or_insert(costs);
+            }
+        }
+    }
+
+    /// Calculate the derived events of the costs of all lines with the `cost_model`
+    ///
+    /// # Errors
+    ///
+    /// If the derived events could not be calculated
+    pub fn make_summary(&mut self, cost_model: &CostModel) -> Result<()> {
+        for costs in self.0.values_mut().flat_map(BTreeMap::values_mut) {
+            cost_model.apply(costs)?;
+        }
+        Ok(())
+    }
+
+    /// Return the total costs of all lines
+    pub fn total(&self) -> Costs {
+        sum(self.0.values().flat_map(BTreeMap::values))
+    }
+}
+
+impl LineCostsParser {
+    /// Parse the line costs of a single callgrind output file
+    #[allow(clippy::similar_names)]
+    fn parse_single(&self, output_path: &ToolOutputPath) -> Result<LineCostsMap> {
+        let mut iter = output_path.lines()?;
+        let config = parse_header(&mut iter)
+            .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
+        let line_index = config
+            .positions_prototype
+            .index_of(PositionType::Line)
+            .ok_or_else(|| {
+                Error::ParseError((
+                    output_path.to_path(),
+                    "Line positions are required to annotate the source files. Please run \
+                     callgrind with `--dump-line=yes`"
+                        .to_owned(),
+                ))
+            })?;
+
+        let mut map = LineCostsMap::default();
+        let mut fn_file = SourcePath::Unknown;
+        let mut file = SourcePath::Unknown;
+        let mut last_line = 0;
+        let mut is_call = false;
+        for line in iter {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some(("fl", value)) => {
+                    fn_file = make_path(&self.project_root, value);
+                    file = fn_file.clone();
+                }
+                // All cost lines of a function without `fi` or `fe` belong to the file of `fl`
+                Some(("fn", _)) => file = fn_file.clone(),
+                Some(("fi" | "fe", value)) => file = make_path(&self.project_root, value),
+                Some(("calls", _)) => is_call = true,
+                None if line.starts_with(|c: char| c.is_ascii_digit() || "+-*".contains(c)) => {
+                    let mut split = line.split_ascii_whitespace();
+                    let positions = split
+                        .by_ref()
+                        .take(config.positions_prototype.len())
+                        .collect::<Vec<&str>>();
+                    last_line = positions
+                        .get(line_index)
+                        .map_or(last_line, |pos| parse_position(pos, last_line));
+
+                    // The costs of calls are the inclusive costs of the called function
+                    if is_call {
+                        is_call = false;
+                        continue;
+                    }
+
+                    let mut costs = config.costs_prototype.clone();
+                    costs.add_iter_str(split);
+                    map.0
+                        .entry(file.clone())
+                        .or_default()
+                        .entry(last_line)
+                        .and_modify(|c| c.add(&costs))
+                        .or_insert(costs);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+impl Parser for LineCostsParser {
+    type Output = LineCostsMap;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output> {
+        debug!("Parsing line costs from file '{}'", output_path);
+
+        let mut map = LineCostsMap::default();
+        for part in output_path.to_parts() {
+            let threads = part.to_threads();
+            if threads.is_empty() {
+                map.add(self.parse_single(&part)?);
+            } else {
+                for thread in threads {
+                    map.add(self.parse_single(&thread)?);
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Parse the line costs of the callgrind output files at `output_path`
+pub fn parse(output_path: &ToolOutputPath, project_root: &Path) -> Result<LineCostsMap> {
+    LineCostsParser {
+        project_root: project_root.to_owned(),
+    }
+    .parse(output_path)
+}
+
+/// Return the path to the html file of the annotated source report
+///
+/// The report of a baseline is stored with the name of the baseline like the callgrind output
+/// files, for example `callgrind.bench_name.annotate.base@foo.html`.
+pub fn report_path(output_path: &ToolOutputPath) -> PathBuf {
+    let extension = match &output_path.kind {
+        ToolOutputPathKind::Base(name) | ToolOutputPathKind::BaseLog(name) => {
+            format!("annotate.base@{name}.html")
+        }
+        _ => "annotate.html".to_owned(),
+    };
+    output_path.dir.join(format!(
+        "{}.{}.{extension}",
+        output_path.tool.id(),
+        output_path.name
+    ))
+}
+
+/// Sum up the `costs` which need to have the same event kinds in the same order
+fn sum<'a>(mut costs: impl Iterator<Item = &'a Costs>) -> Costs {
+    let Some(first) = costs.next() else {
+        return Costs::empty();
+    };
+    costs.fold(first.clone(), |mut sum, costs| {
+        sum.add(costs);
+        sum
+    })
+}
+
+/// Return the path of the source `file` as string
+fn file_name(file: &SourcePath) -> String {
+    file.as_path()
+        .map_or_else(|| "???".to_owned(), |path| path.display().to_string())
+}
+
+/// Read the lines of the source `file`
+///
+/// The sources of the rust standard library are not available since their paths are shortened.
+fn read_source(file: &SourcePath, project_root: &Path) -> Option<Vec<String>> {
+    let path = match file {
+        SourcePath::Relative(path) => project_root.join(path),
+        SourcePath::Absolute(path) => path.clone(),
+        SourcePath::Rust(_) | SourcePath::Unknown => return None,
+    };
+    std::fs::read(path).ok().map(|bytes| {
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(ToOwned::to_owned)
+            .collect()
+    })
+}
+
+/// Escape the characters with special meaning in html
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write the header cells of the costs of all `event_kinds`
+fn write_header_cells(
+    html: &mut String,
+    event_kinds: &[EventKind],
+    is_compared: bool,
+) -> Result<()> {
+    for kind in event_kinds {
+        write!(html, "<th>{kind}</th>")?;
+        if is_compared {
+            write!(html, "<th>{kind} (old)</th><th>Diff</th>")?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the cells of the `new` and `old` costs of all `event_kinds`
+///
+/// The `old` costs and the difference are only written if `is_compared` is true.
+fn write_cost_cells(
+    html: &mut String,
+    event_kinds: &[EventKind],
+    new: Option<&Costs>,
+    old: Option<&Costs>,
+    is_compared: bool,
+) -> Result<()> {
+    for kind in event_kinds {
+        let new_cost = new.map(|costs| cost_of(costs, *kind));
+        write!(
+            html,
+            "<td>{}</td>",
+            new_cost.map_or_else(String::new, |c| c.to_string())
+        )?;
+        if is_compared {
+            let old_cost = old.map(|costs| cost_of(costs, *kind));
+            let (class, diff) = match (new_cost, old_cost) {
+                (Some(new), Some(old)) if new == old => ("", String::new()),
+                (Some(new), Some(old)) => (
+                    if new > old {
+                        " class=\"increase\""
+                    } else {
+                        " class=\"decrease\""
+                    },
+                    format!("{}%", to_string_signed_short(percentage_diff(new, old))),
+                ),
+                (Some(_), None) => (" class=\"increase\"", "new".to_owned()),
+                (None, Some(_)) => (" class=\"decrease\"", "removed".to_owned()),
+                (None, None) => ("", String::new()),
+            };
+            write!(
+                html,
+                "<td>{}</td><td{class}>{diff}</td>",
+                old_cost.map_or_else(String::new, |c| c.to_string())
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the table of the annotated source `files` with their total costs
+fn write_files_table(
+    html: &mut String,
+    files: &[&SourcePath],
+    event_kinds: &[EventKind],
+    new: &LineCostsMap,
+    old: Option<&LineCostsMap>,
+) -> Result<()> {
+    html.push_str("<table class=\"files\">\n<thead><tr>");
+    write_header_cells(html, event_kinds, old.is_some())?;
+    html.push_str("<th>File</th></tr></thead>\n<tbody>\n");
+    for (index, file) in files.iter().enumerate() {
+        html.push_str("<tr>");
+        let new_total = new.0.get(*file).map(|lines| sum(lines.values()));
+        let old_total = old.and_then(|old| old.0.get(*file).map(|lines| sum(lines.values())));
+        write_cost_cells(
+            html,
+            event_kinds,
+            new_total.as_ref(),
+            old_total.as_ref(),
+            old.is_some(),
+        )?;
+        writeln!(
+            html,
+            "<td class=\"source\"><a href=\"#file-{}\">{}</a></td></tr>",
+            index + 1,
+            escape(&file_name(file))
+        )?;
+    }
+    html.push_str("</tbody>\n</table>\n");
+    Ok(())
+}
+
+/// Write the table of a single source file with the costs of every line
+///
+/// Only the lines with costs and `context` lines around them are shown. If the `source` is not
+/// available, only the line numbers and costs are shown.
+fn write_source_table(
+    html: &mut String,
+    event_kinds: &[EventKind],
+    new: Option<&BTreeMap<u64, Costs>>,
+    old: Option<&BTreeMap<u64, Costs>>,
+    is_compared: bool,
+    source: Option<&[String]>,
+    context: usize,
+) -> Result<()> {
+    let cost_lines = new
+        .into_iter()
+        .chain(old)
+        .flat_map(BTreeMap::keys)
+        .copied()
+        .collect::<BTreeSet<u64>>();
+    let mut visible = BTreeSet::new();
+    for line in &cost_lines {
+        visible.insert(*line);
+        // Callgrind uses line `0` if the line number is unknown
+        if let (Some(source), true) = (source, *line > 0) {
+            let context = context as u64;
+            let last = (source.len() as u64).min(line.saturating_add(context));
+            visible.extend(line.saturating_sub(context).max(1)..=last);
+        }
+    }
+
+    html.push_str("<table class=\"source\">\n<thead><tr>");
+    write_header_cells(html, event_kinds, is_compared)?;
+    html.push_str("<th>Line</th><th></th></tr></thead>\n<tbody>\n");
+    let columns = event_kinds.len() * if is_compared { 3 } else { 1 } + 2;
+    let mut previous = None;
+    for line in visible {
+        if previous.map_or(line > 1, |previous| line > previous + 1) {
+            writeln!(
+                html,
+                "<tr class=\"gap\"><td colspan=\"{columns}\">...</td></tr>"
+            )?;
+        }
+        previous = Some(line);
+
+        let class = if cost_lines.contains(&line) {
+            " class=\"hot\""
+        } else {
+            ""
+        };
+        write!(html, "<tr{class}>")?;
+        write_cost_cells(
+            html,
+            event_kinds,
+            new.and_then(|lines| lines.get(&line)),
+            old.and_then(|lines| lines.get(&line)),
+            is_compared,
+        )?;
+        let code = usize::try_from(line)
+            .ok()
+            .and_then(|line| line.checked_sub(1))
+            .and_then(|index| source.and_then(|source| source.get(index)))
+            .map_or_else(String::new, |code| escape(code));
+        writeln!(html, "<td>{line}</td><td class=\"source\">{code}</td></tr>")?;
+    }
+    html.push_str("</tbody>\n</table>\n");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::summary::BaselineKind;
+    use crate::runner::tool::ValgrindTool;
+
+    fn lines<T>(costs: T) -> BTreeMap<u64, Costs>
+    where
+        T: IntoIterator<Item = (u64, u64)>,
+    {
+        costs
+            .into_iter()
+            .map(|(line, cost)| (line, Costs::with_event_kinds([(EventKind::Ir, cost)])))
+            .collect()
+    }
+
+    #[rstest]
+    #[case::absolute("12", 5, 12)]
+    #[case::plus("+2", 5, 7)]
+    #[case::minus("-2", 5, 3)]
+    #[case::minus_saturates("-6", 5, 0)]
+    #[case::same("*", 5, 5)]
+    #[case::invalid("abc", 5, 0)]
+    fn test_parse_position(#[case] position: &str, #[case] last: u64, #[case] expected: u64) {
+        assert_eq!(parse_position(position, last), expected);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"if a < b && c > "d" { 'e' }"#),
+            "if a &lt; b &amp;&amp; c &gt; &quot;d&quot; { &#39;e&#39; }"
+        );
+    }
+
+    #[rstest]
+    #[case::out(ToolOutputPathKind::Out, "callgrind.bench.annotate.html")]
+    #[case::old_out(ToolOutputPathKind::OldOut, "callgrind.bench.annotate.html")]
+    #[case::base(
+        ToolOutputPathKind::Base("foo".to_owned()),
+        "callgrind.bench.annotate.base@foo.html"
+    )]
+    fn test_report_path(#[case] kind: ToolOutputPathKind, #[case] expected: &str) {
+        let output_path = ToolOutputPath {
+            kind,
+            tool: ValgrindTool::Callgrind,
+            baseline_kind: BaselineKind::Old,
+            dir: PathBuf::from("/tmp"),
+            name: "bench".to_owned(),
+            modifiers: vec![],
+            part: None,
+            thread: None,
+        };
+
+        assert_eq!(report_path(&output_path), Path::new("/tmp").join(expected));
+    }
+
+    #[test]
+    fn test_line_costs_map_add() {
+        let file = SourcePath::Relative(PathBuf::from("src/lib.rs"));
+        let mut map = LineCostsMap(HashMap::from([(file.clone(), lines([(1, 10), (2, 5)]))]));
+
+        map.add(LineCostsMap(HashMap::from([(file.clone(), lines([(2, 3), (4, 1)]))])));
+
+        assert_eq!(
+            map,
+            LineCostsMap(HashMap::from([(file, lines([(1, 10), (2, 8), (4, 1)]))]))
+        );
+        assert_eq!(map.total(), Costs::with_event_kinds([(EventKind::Ir, 19)]));
+    }
+
+    #[test]
+    fn test_write_source_table_with_context_and_gaps() {
+        let source = (1_i32..=20_i32).map(|n| format!("line {n}")).collect::<Vec<String>>();
+        let new = lines([(5, 10)]);
+        let old = lines([(15, 2)]);
+        let mut html = String::new();
+
+        write_source_table(
+            &mut html,
+            &[EventKind::Ir],
+            Some(&new),
+            Some(&old),
+            true,
+            Some(&source),
+            1,
+        )
+        .unwrap();
+
+        let rows = html
+            .lines()
+            .filter(|line| line.starts_with("<tr") && !line.contains("<th>"))
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            rows,
+            vec![
+                "<tr class=\"gap\"><td colspan=\"5\">...</td></tr>",
+                "<tr><td></td><td></td><td></td><td>4</td><td class=\"source\">line 4</td></tr>",
+                "<tr class=\"hot\"><td>10</td><td></td><td class=\"increase\">new</td><td>5</td>\
+                 <td class=\"source\">line 5</td></tr>",
+                "<tr><td></td><td></td><td></td><td>6</td><td class=\"source\">line 6</td></tr>",
+                "<tr class=\"gap\"><td colspan=\"5\">...</td></tr>",
+                "<tr><td></td><td></td><td></td><td>14</td><td class=\"source\">line 14</td></tr>",
+                "<tr class=\"hot\"><td></td><td>2</td><td class=\"decrease\">removed</td>\
+                 <td>15</td><td class=\"source\">line 15</td></tr>",
+                "<tr><td></td><td></td><td></td><td>16</td><td class=\"source\">line 16</td></tr>",
+            ]
+        );
+    }
+}
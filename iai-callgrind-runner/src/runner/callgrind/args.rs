@@ -3,10 +3,14 @@ use std::ffi::OsString;
 use std::path::PathBuf;
 
 use anyhow::Result;
+use glob::Pattern;
 use log::{log_enabled, warn};
 
-use crate::api::RawArgs;
+use super::cache::{self, DEFAULT_D1, DEFAULT_I1, DEFAULT_LL};
+use super::collect::CollectFilter;
+use crate::api::{CacheConfig, FlamegraphGranularity, RawArgs};
 use crate::error::Error;
+use crate::runner::summary::{CacheGeometrySummary, CollectSummary};
 use crate::runner::tool;
 use crate::util::{bool_to_yesno, yesno_to_bool};
 
@@ -19,12 +23,16 @@ pub struct Args {
     cache_sim: bool,
     other: Vec<String>,
     toggle_collect: VecDeque<String>,
+    collect_atstart: Option<bool>,
     compress_strings: bool,
     compress_pos: bool,
     verbose: bool,
     dump_instr: bool,
     dump_line: bool,
+    read_inline_info: Option<bool>,
     combine_dumps: bool,
+    separate_threads: bool,
+    trace_children: bool,
     callgrind_out_file: Option<PathBuf>,
     log_arg: Option<OsString>,
 }
@@ -36,6 +44,30 @@ impl Args {
         Ok(default)
     }
 
+    /// Create the `Args` with the geometries of the `cache_config`
+    ///
+    /// The geometries of the `cache_config` replace the `--I1`, `--D1` and `--LL` arguments of the
+    /// `raw_args` of the benchmark configuration but not the ones of the `meta_args` from the
+    /// command-line.
+    pub fn from_raw_args_and_cache(
+        raw_args: &RawArgs,
+        cache_config: &CacheConfig,
+        meta_args: &RawArgs,
+    ) -> Result<Self> {
+        let mut args = Self::from_raw_args(&[raw_args])?;
+        if let Some(i1) = cache_config.i1 {
+            args.i1 = i1.to_string();
+        }
+        if let Some(d1) = cache_config.d1 {
+            args.d1 = d1.to_string();
+        }
+        if let Some(ll) = cache_config.ll {
+            args.ll = ll.to_string();
+        }
+        args.update(meta_args.0.iter())?;
+        Ok(args)
+    }
+
     pub fn update<'a, T: Iterator<Item = &'a String>>(&mut self, args: T) -> Result<()> {
         for arg in args {
             match arg
@@ -46,25 +78,53 @@ impl Args {
                 Some(("--I1", value)) => value.clone_into(&mut self.i1),
                 Some(("--D1", value)) => value.clone_into(&mut self.d1),
                 Some(("--LL", value)) => value.clone_into(&mut self.ll),
+                Some((key @ "--cache-sim", value)) => {
+                    self.cache_sim = yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?;
+                }
                 Some((key @ "--dump-instr", value)) => {
                     self.dump_instr = yesno_to_bool(value).ok_or_else(|| {
                         Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
                     })?;
                 }
+                Some((key @ "--combine-dumps", value)) => {
+                    self.combine_dumps = yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?;
+                }
+                Some((key @ "--separate-threads", value)) => {
+                    self.separate_threads = yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?;
+                }
+                Some((key @ "--trace-children", value)) => {
+                    self.trace_children = yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?;
+                }
                 Some((key @ "--dump-line", value)) => {
                     self.dump_line = yesno_to_bool(value).ok_or_else(|| {
                         Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
                     })?;
                 }
+                Some((key @ "--read-inline-info", value)) => {
+                    self.read_inline_info = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
                 Some(("--toggle-collect", value)) => {
                     self.toggle_collect.push_back(value.to_owned());
                 }
+                Some((key @ "--collect-atstart", value)) => {
+                    self.collect_atstart = Some(yesno_to_bool(value).ok_or_else(|| {
+                        Error::InvalidCallgrindBoolArgument((key.to_owned(), value.to_owned()))
+                    })?);
+                }
                 Some((
-                    key @ ("--separate-threads"
-                    | "--callgrind-out-file"
+                    key @ ("--callgrind-out-file"
                     | "--compress-strings"
                     | "--compress-pos"
-                    | "--combine-dumps"
                     | "--log-file"
                     | "--log-fd"
                     | "--log-socket"
@@ -101,6 +161,86 @@ impl Args {
         Ok(())
     }
 
+    /// Adjust the arguments to the annotated source report
+    ///
+    /// The annotated source report needs the line positions in the callgrind output files, so
+    /// `--dump-line=no` is overridden.
+    pub fn set_annotate(&mut self) {
+        if !self.dump_line {
+            warn!(
+                "Overriding callgrind argument '--dump-line=no': The annotated source report \
+                 requires the line positions"
+            );
+            self.dump_line = true;
+        }
+    }
+
+    /// Adjust the arguments to the [`FlamegraphGranularity`] of the flamegraphs
+    ///
+    /// Callgrind records the code inlined from other source files only with
+    /// `--read-inline-info=yes`, so this argument is added for [`FlamegraphGranularity::Inline`]
+    /// and [`FlamegraphGranularity::Line`]. Flamegraphs with [`FlamegraphGranularity::Line`]
+    /// additionally need the line positions, so `--dump-line=no` is overridden.
+    pub fn set_flamegraph_granularity(&mut self, granularity: FlamegraphGranularity) {
+        if granularity == FlamegraphGranularity::Function {
+            return;
+        }
+
+        if self.read_inline_info == Some(false) {
+            warn!(
+                "Overriding callgrind argument '--read-inline-info=no': Flamegraphs with inline \
+                 or line granularity require the inline information"
+            );
+        }
+        self.read_inline_info = Some(true);
+
+        if granularity == FlamegraphGranularity::Line && !self.dump_line {
+            warn!(
+                "Overriding callgrind argument '--dump-line=no': Flamegraphs with line granularity \
+                 require the line positions"
+            );
+            self.dump_line = true;
+        }
+    }
+
+    /// Return the [`CollectSummary`] with the toggles in effect and the patterns of the `filter`
+    ///
+    /// Callgrind collects events from the start only if there are no toggles or
+    /// `--collect-atstart=yes` was given.
+    pub fn collect_summary(&self, filter: Option<&CollectFilter>) -> CollectSummary {
+        let to_strings =
+            |patterns: &[Pattern]| patterns.iter().map(|p| p.as_str().to_owned()).collect();
+        CollectSummary {
+            collect_atstart: self
+                .collect_atstart
+                .unwrap_or(self.toggle_collect.is_empty()),
+            toggle_collect: self.toggle_collect.iter().cloned().collect(),
+            collect_only: filter.map_or_else(Vec::new, |f| to_strings(&f.collect_only)),
+            exclude: filter.map_or_else(Vec::new, |f| to_strings(&f.exclude)),
+        }
+    }
+
+    /// Return true if callgrind traces into the child processes with `--trace-children=yes`
+    pub fn is_trace_children(&self) -> bool {
+        self.trace_children
+    }
+
+    /// Return the effective cache geometries if the cache simulation is enabled
+    ///
+    /// The `name` is the name of the entry of the cache matrix if present.
+    pub fn cache_geometry(&self, name: Option<String>) -> Option<CacheGeometrySummary> {
+        if !self.cache_sim {
+            return None;
+        }
+
+        Some(CacheGeometrySummary {
+            name,
+            i1: cache::parse(&self.i1)?,
+            d1: cache::parse(&self.d1)?,
+            ll: cache::parse(&self.ll)?,
+        })
+    }
+
     // Insert the --toggle-collect argument at the start
     //
     // This is pure cosmetics, since callgrind doesn't prioritize the toggles by any order
@@ -115,17 +255,21 @@ impl Default for Args {
             // Set some reasonable cache sizes. The exact sizes matter less than having fixed sizes,
             // since otherwise callgrind would take them from the CPU and make benchmark runs
             // even more incomparable between machines.
-            i1: String::from("32768,8,64"),
-            d1: String::from("32768,8,64"),
-            ll: String::from("8388608,16,64"),
+            i1: DEFAULT_I1.to_string(),
+            d1: DEFAULT_D1.to_string(),
+            ll: DEFAULT_LL.to_string(),
             cache_sim: true,
             compress_pos: false,
             compress_strings: false,
-            combine_dumps: true,
+            combine_dumps: false,
+            separate_threads: false,
+            trace_children: false,
             verbose: log_enabled!(log::Level::Debug),
             dump_line: true,
+            read_inline_info: None,
             dump_instr: false,
             toggle_collect: VecDeque::default(),
+            collect_atstart: Option::default(),
             callgrind_out_file: Option::default(),
             log_arg: Option::default(),
             other: Vec::default(),
@@ -148,7 +292,18 @@ impl From<Args> for tool::args::ToolArgs {
             format!("--dump-line={}", bool_to_yesno(value.dump_line)),
             format!("--dump-instr={}", bool_to_yesno(value.dump_instr)),
             format!("--combine-dumps={}", bool_to_yesno(value.combine_dumps)),
+            format!(
+                "--separate-threads={}",
+                bool_to_yesno(value.separate_threads)
+            ),
+            format!("--trace-children={}", bool_to_yesno(value.trace_children)),
         ];
+        if let Some(read_inline_info) = value.read_inline_info {
+            other.push(format!(
+                "--read-inline-info={}",
+                bool_to_yesno(read_inline_info)
+            ));
+        }
         other.append(
             &mut value
                 .toggle_collect
@@ -156,6 +311,12 @@ impl From<Args> for tool::args::ToolArgs {
                 .map(|s| format!("--toggle-collect={s}"))
                 .collect::<Vec<String>>(),
         );
+        if let Some(collect_atstart) = value.collect_atstart {
+            other.push(format!(
+                "--collect-atstart={}",
+                bool_to_yesno(collect_atstart)
+            ));
+        }
         other.append(&mut value.other);
 
         Self {
@@ -170,3 +331,167 @@ impl From<Args> for tool::args::ToolArgs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::api::{CacheGeometry, CollectConfig};
+    use crate::runner::common::ModulePath;
+
+    #[rstest]
+    #[case::default(&[], None, &[], ["32768,8,64", "32768,8,64", "8388608,16,64"])]
+    #[case::cache_config(
+        &["--D1=16384,4,64", "--LL=1048576,8,64"],
+        Some("4194304,16,64"),
+        &[],
+        ["32768,8,64", "16384,4,64", "4194304,16,64"]
+    )]
+    #[case::meta_args(
+        &[],
+        Some("4194304,16,64"),
+        &["--LL=2097152,8,64"],
+        ["32768,8,64", "32768,8,64", "2097152,8,64"]
+    )]
+    fn test_from_raw_args_and_cache(
+        #[case] raw_args: &[&str],
+        #[case] ll: Option<&str>,
+        #[case] meta_args: &[&str],
+        #[case] expected: [&str; 3],
+    ) {
+        let cache_config = CacheConfig {
+            ll: ll.map(|ll| cache::parse(ll).unwrap()),
+            ..Default::default()
+        };
+
+        let args = Args::from_raw_args_and_cache(
+            &RawArgs::from_iter(raw_args),
+            &cache_config,
+            &RawArgs::from_iter(meta_args),
+        )
+        .unwrap();
+
+        assert_eq!([args.i1, args.d1, args.ll], expected);
+    }
+
+    #[test]
+    fn test_cache_geometry() {
+        let args = Args::from_raw_args(&[&RawArgs::new(vec!["--LL=4194304,16,64".to_owned()])])
+            .unwrap();
+        let expected = CacheGeometrySummary {
+            name: Some("small".to_owned()),
+            i1: DEFAULT_I1,
+            d1: DEFAULT_D1,
+            ll: CacheGeometry {
+                size: 4_194_304,
+                associativity: 16,
+                line_size: 64,
+            },
+        };
+
+        assert_eq!(
+            args.cache_geometry(Some("small".to_owned())),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case::cache_sim_off("--cache-sim=no")]
+    #[case::unknown_geometry("--I1=32768,8")]
+    fn test_cache_geometry_when_none(#[case] arg: &str) {
+        let args = Args::from_raw_args(&[&RawArgs::new(vec![arg.to_owned()])]).unwrap();
+
+        assert_eq!(args.cache_geometry(None), None);
+    }
+
+    #[rstest]
+    #[case::overrides_dump_line("--dump-line=no")]
+    #[case::with_dump_line("--dump-line=yes")]
+    fn test_set_annotate(#[case] arg: &str) {
+        let mut args = Args::from_raw_args(&[&RawArgs::new(vec![arg.to_owned()])]).unwrap();
+
+        args.set_annotate();
+
+        assert!(args.dump_line);
+    }
+
+    #[rstest]
+    #[case::function(FlamegraphGranularity::Function, "--dump-line=no", false, None)]
+    #[case::inline(FlamegraphGranularity::Inline, "--dump-line=no", false, Some(true))]
+    #[case::line(FlamegraphGranularity::Line, "--dump-line=no", true, Some(true))]
+    #[case::line_with_dump_line(FlamegraphGranularity::Line, "--dump-line=yes", true, Some(true))]
+    #[case::inline_overrides_read_inline_info(
+        FlamegraphGranularity::Inline,
+        "--read-inline-info=no",
+        true,
+        Some(true)
+    )]
+    fn test_set_flamegraph_granularity(
+        #[case] granularity: FlamegraphGranularity,
+        #[case] arg: &str,
+        #[case] expected_dump_line: bool,
+        #[case] expected_read_inline_info: Option<bool>,
+    ) {
+        let mut args = Args::from_raw_args(&[&RawArgs::new(vec![arg.to_owned()])]).unwrap();
+
+        args.set_flamegraph_granularity(granularity);
+
+        assert_eq!(args.dump_line, expected_dump_line);
+        assert_eq!(args.read_inline_info, expected_read_inline_info);
+    }
+
+    #[rstest]
+    #[case::no_toggles(&[], &[], &[], true, &[])]
+    #[case::toggle(&["--toggle-collect=entry"], &[], &[], false, &["entry"])]
+    #[case::collect_atstart(
+        &["--toggle-collect=entry", "--collect-atstart=yes"],
+        &[],
+        &[],
+        true,
+        &["entry"]
+    )]
+    #[case::filter(&["--toggle-collect=entry"], &["decode"], &["log::*"], false, &["entry"])]
+    fn test_collect_summary(
+        #[case] raw_args: &[&str],
+        #[case] collect_only: &[&str],
+        #[case] exclude: &[&str],
+        #[case] expected_collect_atstart: bool,
+        #[case] expected_toggles: &[&str],
+    ) {
+        let args = Args::from_raw_args(&[&RawArgs::from_iter(raw_args)]).unwrap();
+        let to_strings = |s: &[&str]| s.iter().map(|s| (*s).to_owned()).collect::<Vec<String>>();
+        let filter = CollectFilter::new(
+            &CollectConfig {
+                collect_only: to_strings(collect_only),
+                exclude: to_strings(exclude),
+            },
+            &ModulePath::new("file::group::bench"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            args.collect_summary(Some(&filter)),
+            CollectSummary {
+                collect_atstart: expected_collect_atstart,
+                toggle_collect: to_strings(expected_toggles),
+                collect_only: to_strings(collect_only),
+                exclude: to_strings(exclude),
+            }
+        );
+    }
+
+    #[test]
+    fn test_collect_summary_without_filter() {
+        assert_eq!(
+            Args::default().collect_summary(None),
+            CollectSummary {
+                collect_atstart: true,
+                toggle_collect: vec![],
+                collect_only: vec![],
+                exclude: vec![],
+            }
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! The typed cache geometry of the cache simulation of callgrind
+//!
+//! The geometry of the I1, D1 and LL cache can be configured with a [`CacheConfig`] instead of the
+//! raw `--I1`, `--D1` and `--LL` arguments. With a cache matrix, a benchmark is run once for each
+//! named [`CacheConfig`] of the matrix.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use crate::api::{CacheConfig, CacheGeometry};
+use crate::error::Error;
+use crate::runner::common::ModulePath;
+
+/// The default geometry of the I1 cache
+pub const DEFAULT_I1: CacheGeometry = CacheGeometry {
+    size: 32768,
+    associativity: 8,
+    line_size: 64,
+};
+
+/// The default geometry of the D1 cache
+pub const DEFAULT_D1: CacheGeometry = CacheGeometry {
+    size: 32768,
+    associativity: 8,
+    line_size: 64,
+};
+
+/// The default geometry of the LL cache
+pub const DEFAULT_LL: CacheGeometry = CacheGeometry {
+    size: 8_388_608,
+    associativity: 16,
+    line_size: 64,
+};
+
+/// The minimum line size accepted by callgrind
+const MIN_LINE_SIZE: u64 = 16;
+
+/// A single run of a benchmark with the [`CacheConfig`] of an entry of the cache matrix
+///
+/// The `name` is only present if the run belongs to a cache matrix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheRun {
+    pub name: Option<String>,
+    pub config: CacheConfig,
+}
+
+/// Return the [`CacheGeometry`] of the value of the `--I1`, `--D1` or `--LL` argument
+///
+/// The format of the value is `<size>,<associativity>,<line size>`.
+pub fn parse(value: &str) -> Option<CacheGeometry> {
+    let mut split = value.split(',').map(|v| v.trim().parse::<u64>());
+    match (split.next(), split.next(), split.next(), split.next()) {
+        (Some(Ok(size)), Some(Ok(associativity)), Some(Ok(line_size)), None) => {
+            Some(CacheGeometry {
+                size,
+                associativity,
+                line_size,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Check that callgrind accepts the `geometry`
+///
+/// These are the same checks callgrind applies to the values of the `--I1`, `--D1` and `--LL`
+/// arguments.
+///
+/// # Errors
+///
+/// Returns the reason why the `geometry` is invalid
+pub fn validate(geometry: &CacheGeometry) -> std::result::Result<(), String> {
+    let CacheGeometry {
+        size,
+        associativity,
+        line_size,
+    } = *geometry;
+
+    if size == 0 || associativity == 0 || line_size == 0 {
+        return Err("The size, associativity and line size have to be greater than 0".to_owned());
+    }
+    if [size, associativity, line_size]
+        .iter()
+        .any(|value| i32::try_from(*value).is_err())
+    {
+        return Err(format!(
+            "The size, associativity and line size have to be less than or equal to {}",
+            i32::MAX
+        ));
+    }
+    if !line_size.is_power_of_two() {
+        return Err("The line size has to be a power of two".to_owned());
+    }
+    if line_size < MIN_LINE_SIZE {
+        return Err(format!("The line size has to be at least {MIN_LINE_SIZE} bytes"));
+    }
+    if size <= line_size {
+        return Err("The size has to be greater than the line size".to_owned());
+    }
+    if associativity > size / line_size {
+        return Err(
+            "The associativity has to be less than or equal to the number of lines".to_owned(),
+        );
+    }
+    if size % (line_size * associativity) != 0
+        || !(size / line_size / associativity).is_power_of_two()
+    {
+        return Err("The number of sets has to be a power of two".to_owned());
+    }
+
+    Ok(())
+}
+
+/// Return the [`CacheRun`]s of the `config` and the cache `matrix`
+///
+/// Without a `matrix`, there is a single run with the `config`. Otherwise, there is a run for each
+/// entry of the `matrix` with the geometries of the entry falling back to the geometries of the
+/// `config`.
+///
+/// # Errors
+///
+/// Returns an [`Error::InvalidCacheConfig`] if a cache geometry is invalid or if the name of an
+/// entry of the `matrix` is not unique or doesn't consist of alphanumeric characters, `-` and `_`
+/// only.
+pub fn resolve(
+    module_path: &ModulePath,
+    config: Option<&CacheConfig>,
+    matrix: Option<&[(String, CacheConfig)]>,
+) -> Result<Vec<CacheRun>> {
+    let base = config.map_or_else(CacheConfig::default, Clone::clone);
+    validate_config(module_path, None, &base)?;
+
+    let mut names = HashSet::new();
+    let runs = matrix
+        .unwrap_or_default()
+        .iter()
+        .map(|(name, config)| {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(Error::InvalidCacheConfig(
+                    module_path.clone(),
+                    format!(
+                        "The name of the cache matrix entry '{name}' may only consist of \
+                         alphanumeric characters, '-' and '_'"
+                    ),
+                )
+                .into());
+            }
+            if !names.insert(name) {
+                return Err(Error::InvalidCacheConfig(
+                    module_path.clone(),
+                    format!("The name of the cache matrix entry '{name}' is not unique"),
+                )
+                .into());
+            }
+
+            validate_config(module_path, Some(name), config)?;
+            Ok(CacheRun {
+                name: Some(name.clone()),
+                config: CacheConfig {
+                    i1: config.i1.or(base.i1),
+                    d1: config.d1.or(base.d1),
+                    ll: config.ll.or(base.ll),
+                },
+            })
+        })
+        .collect::<Result<Vec<CacheRun>>>()?;
+
+    if runs.is_empty() {
+        Ok(vec![CacheRun {
+            name: None,
+            config: base,
+        }])
+    } else {
+        Ok(runs)
+    }
+}
+
+/// Check the cache geometries of the `config` of the cache matrix entry with the `name`
+fn validate_config(
+    module_path: &ModulePath,
+    name: Option<&str>,
+    config: &CacheConfig,
+) -> Result<()> {
+    for (level, geometry) in [("I1", config.i1), ("D1", config.d1), ("LL", config.ll)] {
+        if let Some(geometry) = geometry {
+            validate(&geometry).map_err(|reason| {
+                let entry = name.map_or_else(String::new, |name| format!(" of '{name}'"));
+                Error::InvalidCacheConfig(
+                    module_path.clone(),
+                    format!("Invalid {level} cache geometry{entry} '{geometry}': {reason}"),
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn geometry(size: u64, associativity: u64, line_size: u64) -> CacheGeometry {
+        CacheGeometry {
+            size,
+            associativity,
+            line_size,
+        }
+    }
+
+    #[rstest]
+    #[case::default("32768,8,64", Some(geometry(32768, 8, 64)))]
+    #[case::whitespace(" 32768, 8 ,64 ", Some(geometry(32768, 8, 64)))]
+    #[case::too_few("32768,8", None)]
+    #[case::too_many("32768,8,64,1", None)]
+    #[case::not_a_number("32768,eight,64", None)]
+    #[case::empty("", None)]
+    fn test_parse(#[case] value: &str, #[case] expected: Option<CacheGeometry>) {
+        assert_eq!(parse(value), expected);
+    }
+
+    #[rstest]
+    #[case::default_i1(DEFAULT_I1)]
+    #[case::default_ll(DEFAULT_LL)]
+    #[case::direct_mapped(geometry(16384, 1, 32))]
+    #[case::fully_associative(geometry(1024, 16, 64))]
+    #[case::min_line_size(geometry(4096, 4, 16))]
+    fn test_validate(#[case] geometry: CacheGeometry) {
+        assert_eq!(validate(&geometry), Ok(()));
+    }
+
+    #[rstest]
+    #[case::zero_size(geometry(0, 8, 64))]
+    #[case::zero_associativity(geometry(32768, 0, 64))]
+    #[case::zero_line_size(geometry(32768, 8, 0))]
+    #[case::too_large(geometry(1 << 32, 8, 64))]
+    #[case::line_size_not_power_of_two(geometry(32768, 8, 48))]
+    #[case::line_size_too_small(geometry(32768, 8, 8))]
+    #[case::size_not_greater_than_line_size(geometry(64, 1, 64))]
+    #[case::associativity_too_high(geometry(1024, 32, 64))]
+    #[case::sets_not_power_of_two(geometry(24576, 8, 64))]
+    fn test_validate_then_error(#[case] geometry: CacheGeometry) {
+        assert!(validate(&geometry).is_err());
+    }
+
+    #[test]
+    fn test_resolve_without_matrix() {
+        let config = CacheConfig {
+            i1: Some(geometry(16384, 4, 64)),
+            d1: None,
+            ll: None,
+        };
+        let expected = vec![CacheRun {
+            name: None,
+            config: config.clone(),
+        }];
+
+        assert_eq!(
+            resolve(&ModulePath::new("bench"), Some(&config), None).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_resolve_with_matrix() {
+        let config = CacheConfig {
+            i1: Some(geometry(16384, 4, 64)),
+            d1: None,
+            ll: Some(geometry(4_194_304, 16, 64)),
+        };
+        let matrix = [
+            (
+                "small".to_owned(),
+                CacheConfig {
+                    ll: Some(geometry(1_048_576, 8, 64)),
+                    ..Default::default()
+                },
+            ),
+            ("default".to_owned(), CacheConfig::default()),
+        ];
+        let expected = vec![
+            CacheRun {
+                name: Some("small".to_owned()),
+                config: CacheConfig {
+                    i1: Some(geometry(16384, 4, 64)),
+                    d1: None,
+                    ll: Some(geometry(1_048_576, 8, 64)),
+                },
+            },
+            CacheRun {
+                name: Some("default".to_owned()),
+                config: config.clone(),
+            },
+        ];
+
+        assert_eq!(
+            resolve(&ModulePath::new("bench"), Some(&config), Some(&matrix)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_resolve_when_empty_matrix() {
+        assert_eq!(
+            resolve(&ModulePath::new("bench"), None, Some(&[])).unwrap(),
+            vec![CacheRun::default()]
+        );
+    }
+
+    #[rstest]
+    #[case::invalid_config(
+        Some(CacheConfig { d1: Some(geometry(24576, 8, 64)), ..Default::default() }),
+        vec![],
+        "Invalid D1 cache geometry '24576,8,64': The number of sets has to be a power of two"
+    )]
+    #[case::invalid_matrix_entry(
+        None,
+        vec![("small", CacheConfig { ll: Some(geometry(0, 0, 0)), ..Default::default() })],
+        "Invalid LL cache geometry of 'small' '0,0,0': The size, associativity and line size have \
+         to be greater than 0"
+    )]
+    #[case::empty_name(
+        None,
+        vec![("", CacheConfig::default())],
+        "The name of the cache matrix entry '' may only consist of alphanumeric characters, '-' \
+         and '_'"
+    )]
+    #[case::invalid_name(
+        None,
+        vec![("in valid", CacheConfig::default())],
+        "The name of the cache matrix entry 'in valid' may only consist of alphanumeric \
+         characters, '-' and '_'"
+    )]
+    #[case::duplicate_name(
+        None,
+        vec![("small", CacheConfig::default()), ("small", CacheConfig::default())],
+        "The name of the cache matrix entry 'small' is not unique"
+    )]
+    fn test_resolve_then_error(
+        #[case] config: Option<CacheConfig>,
+        #[case] matrix: Vec<(&str, CacheConfig)>,
+        #[case] expected: &str,
+    ) {
+        let matrix = matrix
+            .into_iter()
+            .map(|(name, config)| (name.to_owned(), config))
+            .collect::<Vec<(String, CacheConfig)>>();
+
+        let error =
+            resolve(&ModulePath::new("bench"), config.as_ref(), Some(&matrix)).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<Error>(),
+            Some(&Error::InvalidCacheConfig(
+                ModulePath::new("bench"),
+                expected.to_owned()
+            ))
+        );
+    }
+}
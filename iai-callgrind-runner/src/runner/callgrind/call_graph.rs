@@ -0,0 +1,360 @@
+//! The call graph of callgrind runs exported in the DOT format of Graphviz
+//!
+//! Other than the flamegraphs, the call graph shows how often a function was called by another
+//! function. The call graph is pruned to the functions and calls with an inclusive cost above a
+//! threshold. If there is an old or baseline run to compare with, the nodes are coloured by the
+//! change of their inclusive cost.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+
+use super::function_costs::{self, cost_of};
+use super::hashmap_parser::{CallgrindMap, Id};
+use crate::api::{self, EventKind};
+use crate::runner::summary::CallGraphSummary;
+use crate::runner::tool::{ToolOutputPath, ToolOutputPathKind};
+use crate::util::{percentage_diff, to_string_signed_short};
+
+/// The default threshold in percent of the total cost
+const DEFAULT_THRESHOLD: f64 = 0.5;
+
+/// The configuration of the call graph of callgrind runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallGraphConfig {
+    /// The [`EventKind`] of the costs of the nodes and edges
+    pub event_kind: EventKind,
+    /// The minimum inclusive cost in percent of the total cost of the functions and calls shown
+    pub threshold: f64,
+    /// If true, colour the nodes by the change of their costs if there is an old run
+    pub differential: bool,
+}
+
+/// A function of the pruned call graph
+#[derive(Debug)]
+struct Node<'a> {
+    id: &'a Id,
+    inclusive: u64,
+    exclusive: u64,
+    calls: u64,
+}
+
+impl CallGraphConfig {
+    /// Create the call graph of the `new` map and compare it with the `old` map if present
+    ///
+    /// The DOT file is written next to the callgrind output file at `output_path`.
+    pub fn create(
+        &self,
+        title: &str,
+        new: &CallgrindMap,
+        old: Option<&CallgrindMap>,
+        output_path: &ToolOutputPath,
+    ) -> Result<CallGraphSummary> {
+        let path = graph_path(output_path);
+        debug!("Creating call graph '{}'", path.display());
+
+        let old = old.filter(|_| self.differential);
+        let total = new
+            .iter()
+            .map(|(_, value)| cost_of(&value.exclusive_costs, self.event_kind))
+            .sum::<u64>();
+        let is_shown = |cost: u64| total > 0 && share(cost, total) >= self.threshold;
+
+        let mut incoming = HashMap::<&Id, u64>::new();
+        for ((_, callee), call) in &new.calls {
+            *incoming.entry(callee).or_default() += call.count;
+        }
+        let mut nodes = new
+            .iter()
+            .map(|(id, value)| Node {
+                id,
+                inclusive: cost_of(&value.costs, self.event_kind),
+                exclusive: cost_of(&value.exclusive_costs, self.event_kind),
+                calls: incoming.get(id).copied().unwrap_or_default(),
+            })
+            .filter(|node| is_shown(node.inclusive))
+            .collect::<Vec<Node>>();
+        nodes.sort_by_cached_key(|node| {
+            (
+                Reverse(node.inclusive),
+                node.id.func.clone(),
+                node.id.file.clone(),
+                node.id.obj.clone(),
+            )
+        });
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id, index))
+            .collect::<HashMap<&Id, usize>>();
+
+        let mut edges = new
+            .calls
+            .iter()
+            .filter_map(|(key, call)| {
+                let cost = cost_of(&call.costs, self.event_kind);
+                match (indices.get(&key.0), indices.get(&key.1)) {
+                    (Some(from), Some(to)) if is_shown(cost) => Some((*from, *to, cost, key, call)),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|(from, to, ..)| (*from, *to));
+
+        let mut dot = String::new();
+        writeln!(
+            dot,
+            "digraph \"call graph\" {{\nlabel=\"{}\";\nlabelloc=t;\nnode [shape=box, \
+             style=filled, fillcolor=\"#ffffff\", fontname=monospace];\nedge \
+             [fontname=monospace];",
+            escape(title)
+        )?;
+        for (index, node) in nodes.iter().enumerate() {
+            let mut label = escape(&node.id.func);
+            if let Some(file) = node.id.file.as_ref().and_then(|f| f.as_path()) {
+                write!(label, "\\n{}", escape(&file.display().to_string()))?;
+            }
+            write!(
+                label,
+                "\\n{}: {} ({:.2}%)\\nself: {}\\ncalls: {}",
+                self.event_kind,
+                node.inclusive,
+                share(node.inclusive, total),
+                node.exclusive,
+                node.calls
+            )?;
+            let mut fill = String::new();
+            if let Some(old) = old {
+                let old_cost = old
+                    .get_key_value(node.id)
+                    .map(|(_, value)| cost_of(&value.costs, self.event_kind));
+                let diff = if let Some(old_cost) = old_cost {
+                    let diff = percentage_diff(node.inclusive, old_cost);
+                    write!(
+                        label,
+                        "\\nold: {old_cost} ({}%)",
+                        to_string_signed_short(diff)
+                    )?;
+                    diff
+                } else {
+                    label.push_str("\\nold: new");
+                    100f64
+                };
+                fill = format!(", fillcolor=\"{}\"", diff_color(diff));
+            }
+            writeln!(dot, "n{index} [label=\"{label}\"{fill}];")?;
+        }
+        for (from, to, cost, key, call) in &edges {
+            let mut label = format!("{cost}\\n{}x", call.count);
+            if let Some(call) = old.and_then(|old| old.calls.get(*key)) {
+                write!(
+                    label,
+                    "\\nold: {} {}x",
+                    cost_of(&call.costs, self.event_kind),
+                    call.count
+                )?;
+            }
+            let width = 1f64 + share(*cost, total) / 25f64;
+            writeln!(
+                dot,
+                "n{from} -> n{to} [label=\"{label}\", penwidth={width:.2}];"
+            )?;
+        }
+        dot.push_str("}\n");
+
+        std::fs::write(&path, dot)
+            .with_context(|| format!("Failed creating call graph file '{}'", path.display()))?;
+
+        Ok(CallGraphSummary {
+            path,
+            event_kind: self.event_kind,
+            nodes: nodes.len(),
+            edges: edges.len(),
+        })
+    }
+}
+
+impl Default for CallGraphConfig {
+    fn default() -> Self {
+        Self {
+            event_kind: EventKind::Ir,
+            threshold: DEFAULT_THRESHOLD,
+            differential: true,
+        }
+    }
+}
+
+impl From<api::CallGraphConfig> for CallGraphConfig {
+    fn from(value: api::CallGraphConfig) -> Self {
+        let api::CallGraphConfig {
+            event_kind,
+            threshold,
+            differential,
+        } = value;
+        let threshold = match threshold {
+            Some(threshold) if (0f64..=100f64).contains(&threshold) => threshold,
+            Some(threshold) => {
+                warn!(
+                    "Ignoring invalid call graph threshold '{threshold}': The threshold has to be \
+                     a percentage between 0 and 100"
+                );
+                DEFAULT_THRESHOLD
+            }
+            None => DEFAULT_THRESHOLD,
+        };
+        Self {
+            event_kind: event_kind.unwrap_or(EventKind::Ir),
+            threshold,
+            differential: differential.unwrap_or(true),
+        }
+    }
+}
+
+/// Parse the per-function costs and calls of the callgrind output files at `output_path`
+///
+/// Like the flamegraphs, the call graph is created from the costs of all dumps and threads of a
+/// callgrind run.
+pub fn parse(output_path: &ToolOutputPath, project_root: &Path) -> Result<CallgrindMap> {
+    let mut map = CallgrindMap::default();
+    for part in output_path.to_parts() {
+        let threads = part.to_threads();
+        if threads.is_empty() {
+            map.add(function_costs::parse(&part, project_root)?);
+        } else {
+            for thread in threads {
+                map.add(function_costs::parse(&thread, project_root)?);
+            }
+        }
+    }
+    Ok(map)
+}
+
+/// Return the path to the DOT file of the call graph
+///
+/// The call graph of a baseline is stored with the name of the baseline like the callgrind output
+/// files, for example `callgrind.bench_name.call_graph.base@foo.dot`.
+pub fn graph_path(output_path: &ToolOutputPath) -> PathBuf {
+    let extension = match &output_path.kind {
+        ToolOutputPathKind::Base(name) | ToolOutputPathKind::BaseLog(name) => {
+            format!("call_graph.base@{name}.dot")
+        }
+        _ => "call_graph.dot".to_owned(),
+    };
+    output_path.dir.join(format!(
+        "{}.{}.{extension}",
+        output_path.tool.id(),
+        output_path.name
+    ))
+}
+
+/// Return the fill colour of a node with the percentage `diff` of its costs
+///
+/// Increased costs are red and decreased costs are blue like in the differential flamegraphs. The
+/// colour is the more intense the higher the difference up to 100%.
+fn diff_color(diff: f64) -> String {
+    // The intensity is at most `200`, so it always fits into an `u8`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let intensity = (diff.abs().min(100f64) * 2f64).round() as u8;
+    let value = 255 - intensity;
+    if diff > 0.0 {
+        format!("#ff{value:02x}{value:02x}")
+    } else if diff < 0.0 {
+        format!("#{value:02x}{value:02x}ff")
+    } else {
+        "#ffffff".to_owned()
+    }
+}
+
+/// Return the `cost` as percentage of the `total` cost
+fn share(cost: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0f64;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let cost = cost as f64;
+    #[allow(clippy::cast_precision_loss)]
+    let total = total as f64;
+
+    cost * 100f64 / total
+}
+
+/// Escape the characters with special meaning in a quoted DOT string
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::summary::BaselineKind;
+    use crate::runner::tool::ValgrindTool;
+
+    #[rstest]
+    #[case::no_change(0f64, "#ffffff")]
+    #[case::increase(12.5f64, "#ffe6e6")]
+    #[case::decrease(-12.5f64, "#e6e6ff")]
+    #[case::max_increase(100f64, "#ff3737")]
+    #[case::above_max_increase(250f64, "#ff3737")]
+    #[case::max_decrease(-100f64, "#3737ff")]
+    fn test_diff_color(#[case] diff: f64, #[case] expected: &str) {
+        assert_eq!(diff_color(diff), expected);
+    }
+
+    #[rstest]
+    #[case::out(ToolOutputPathKind::Out, "callgrind.bench.call_graph.dot")]
+    #[case::base(
+        ToolOutputPathKind::Base("foo".to_owned()),
+        "callgrind.bench.call_graph.base@foo.dot"
+    )]
+    fn test_graph_path(#[case] kind: ToolOutputPathKind, #[case] expected: &str) {
+        let output_path = ToolOutputPath {
+            kind,
+            tool: ValgrindTool::Callgrind,
+            baseline_kind: BaselineKind::Old,
+            dir: PathBuf::from("/tmp"),
+            name: "bench".to_owned(),
+            modifiers: vec![],
+            part: None,
+            thread: None,
+        };
+
+        assert_eq!(graph_path(&output_path), Path::new("/tmp").join(expected));
+    }
+
+    #[rstest]
+    #[case::default(None, DEFAULT_THRESHOLD)]
+    #[case::zero(Some(0f64), 0f64)]
+    #[case::valid(Some(5f64), 5f64)]
+    #[case::negative(Some(-1f64), DEFAULT_THRESHOLD)]
+    #[case::too_high(Some(100.1f64), DEFAULT_THRESHOLD)]
+    #[case::nan(Some(f64::NAN), DEFAULT_THRESHOLD)]
+    fn test_call_graph_config_from_threshold(
+        #[case] threshold: Option<f64>,
+        #[case] expected: f64,
+    ) {
+        let config = CallGraphConfig::from(api::CallGraphConfig {
+            threshold,
+            ..Default::default()
+        });
+
+        let actual = config.threshold;
+        assert!((actual - expected).abs() < f64::EPSILON, "{actual} != {expected}");
+    }
+
+    #[rstest]
+    #[case::zero_total(0, 0, 0f64)]
+    #[case::half(50, 200, 25f64)]
+    #[case::all(200, 200, 100f64)]
+    fn test_share(#[case] cost: u64, #[case] total: u64, #[case] expected: f64) {
+        let actual = share(cost, total);
+        assert!((actual - expected).abs() < f64::EPSILON, "{actual} != {expected}");
+    }
+}
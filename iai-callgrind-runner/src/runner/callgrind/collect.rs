@@ -0,0 +1,252 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use glob::Pattern;
+use log::debug;
+
+use super::hashmap_parser::{CallgrindMap, HashMapParser, Id};
+use super::model::Costs;
+use super::parser::{parse_header, Sentinel};
+use crate::api;
+use crate::error::Error;
+use crate::runner::common::ModulePath;
+use crate::runner::tool::{Parser, ToolOutputPath};
+
+/// The functions to collect and to exclude of the [`api::CollectConfig`] as glob patterns
+///
+/// The functions are not passed to callgrind as `--toggle-collect` arguments, since toggles
+/// switch the collection on and off on each entry and exit of a function. Instead, callgrind
+/// collects all events as usual and the costs of the benchmark are calculated afterwards from the
+/// inclusive costs of the functions and their calls with [`CollectFilter::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectFilter {
+    /// The functions to collect
+    pub collect_only: Vec<Pattern>,
+    /// The functions to exclude
+    pub exclude: Vec<Pattern>,
+}
+
+/// Parse the [`Costs`] of a callgrind output file applying the rules of a [`CollectFilter`]
+#[derive(Debug)]
+pub struct CollectParser {
+    filter: CollectFilter,
+    sentinel: Option<Sentinel>,
+}
+
+/// The callers and callees of each function of a [`CallgrindMap`]
+struct CallGraph<'a> {
+    callers: HashMap<&'a Id, Vec<&'a Id>>,
+    callees: HashMap<&'a Id, Vec<&'a Id>>,
+}
+
+impl CollectFilter {
+    /// Create a new `CollectFilter` from the [`api::CollectConfig`]
+    ///
+    /// # Errors
+    ///
+    /// If a function is not a valid glob pattern
+    pub fn new(config: &api::CollectConfig, module_path: &ModulePath) -> Result<Self> {
+        let to_patterns = |functions: &[String]| {
+            functions
+                .iter()
+                .map(|function| {
+                    Pattern::new(function).map_err(|error| {
+                        Error::InvalidCollectConfig(
+                            module_path.clone(),
+                            format!("Invalid function pattern '{function}': {error}"),
+                        )
+                        .into()
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            collect_only: to_patterns(&config.collect_only)?,
+            exclude: to_patterns(&config.exclude)?,
+        })
+    }
+
+    /// Calculate the [`Costs`] of the collected functions without the excluded functions
+    ///
+    /// Without functions to collect, the costs are the inclusive costs of the function of the
+    /// `sentinel_key` of the `map` if present and otherwise the total costs. With functions to
+    /// collect, the costs are the inclusive costs of their calls from functions outside of the
+    /// collected functions, so nested and recursive calls of collected functions are not counted
+    /// twice. The inclusive costs of the calls of excluded functions from within the collected
+    /// functions are subtracted from these costs. Excluded functions called from outside of the
+    /// collected functions don't change the costs.
+    ///
+    /// Callgrind records the calls of a function by another function summed up over all call
+    /// stacks. So, a function called from both, inside and outside of the collected functions,
+    /// counts as outside.
+    pub fn apply(&self, map: &CallgrindMap, prototype: Costs) -> Costs {
+        let graph = CallGraph::new(map);
+        let excluded = map
+            .map
+            .keys()
+            .filter(|id| matches(&self.exclude, id))
+            .collect::<HashSet<&Id>>();
+
+        let mut costs = prototype.clone();
+        let region = if self.collect_only.is_empty() {
+            if let Some((id, value)) = map
+                .sentinel_key
+                .as_ref()
+                .and_then(|key| map.get_key_value(key))
+            {
+                costs.add(&value.costs);
+                Some(graph.enclosed(&HashSet::from([id])))
+            } else {
+                for value in map.map.values() {
+                    costs.add(&value.exclusive_costs);
+                }
+                None
+            }
+        } else {
+            let roots = map
+                .map
+                .keys()
+                .filter(|id| matches(&self.collect_only, id) && !excluded.contains(id))
+                .collect::<HashSet<&Id>>();
+            let region = graph.enclosed(&roots);
+            for id in roots {
+                graph.add_entry_costs(map, id, |caller| !region.contains(caller), &mut costs);
+            }
+            Some(region)
+        };
+
+        let is_collected = |id: &Id| region.as_ref().map_or(true, |region| region.contains(id));
+        let excluded_region = graph.enclosed(&excluded);
+        let mut excluded_costs = prototype;
+        for id in excluded {
+            // An excluded function without callers is only collected if all functions are
+            if region.is_some() && !graph.callers.contains_key(id) {
+                continue;
+            }
+            graph.add_entry_costs(
+                map,
+                id,
+                |caller| is_collected(caller) && !excluded_region.contains(caller),
+                &mut excluded_costs,
+            );
+        }
+        costs.saturating_sub(&excluded_costs);
+
+        costs
+    }
+}
+
+impl CollectParser {
+    pub fn new(filter: &CollectFilter, sentinel: Option<&Sentinel>) -> Self {
+        Self {
+            filter: filter.clone(),
+            sentinel: sentinel.cloned(),
+        }
+    }
+}
+
+impl Parser for CollectParser {
+    type Output = Costs;
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Self::Output> {
+        debug!(
+            "Parsing callgrind output file '{}' for the collected functions",
+            output_path
+        );
+
+        let config = parse_header(&mut output_path.lines()?)
+            .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
+        let map = HashMapParser {
+            sentinel: self.sentinel.clone(),
+            project_root: PathBuf::default(),
+            granularity: api::FlamegraphGranularity::Function,
+        }
+        .parse(output_path)?;
+
+        Ok(self.filter.apply(&map, config.costs_prototype))
+    }
+}
+
+impl<'a> CallGraph<'a> {
+    fn new(map: &'a CallgrindMap) -> Self {
+        let mut graph = Self {
+            callers: HashMap::new(),
+            callees: HashMap::new(),
+        };
+        for (caller, callee) in map.calls.keys() {
+            graph.callers.entry(callee).or_default().push(caller);
+            graph.callees.entry(caller).or_default().push(callee);
+        }
+        graph
+    }
+
+    /// Return the `roots` and all functions which are only called from within the `roots`
+    ///
+    /// These are the functions reachable from the `roots` without a caller outside of the
+    /// `roots` and the other enclosed functions.
+    fn enclosed(&self, roots: &HashSet<&'a Id>) -> HashSet<&'a Id> {
+        let mut enclosed = HashSet::new();
+        let mut stack = roots.iter().copied().collect::<Vec<&Id>>();
+        while let Some(id) = stack.pop() {
+            for callee in self.callees.get(id).into_iter().flatten() {
+                if !roots.contains(callee) && enclosed.insert(*callee) {
+                    stack.push(callee);
+                }
+            }
+        }
+
+        loop {
+            let outside = enclosed
+                .iter()
+                .copied()
+                .filter(|id| {
+                    self.callers.get(id).map_or(false, |callers| {
+                        callers
+                            .iter()
+                            .any(|caller| !roots.contains(caller) && !enclosed.contains(caller))
+                    })
+                })
+                .collect::<Vec<&Id>>();
+            if outside.is_empty() {
+                break;
+            }
+            for id in outside {
+                enclosed.remove(id);
+            }
+        }
+
+        enclosed.extend(roots);
+        enclosed
+    }
+
+    /// Add the inclusive costs of the calls of the function with the `id` from the callers
+    /// matching the `predicate` to the `costs`
+    ///
+    /// A function without any callers adds its inclusive costs.
+    fn add_entry_costs<F>(&self, map: &CallgrindMap, id: &Id, predicate: F, costs: &mut Costs)
+    where
+        F: Fn(&Id) -> bool,
+    {
+        match self.callers.get(id) {
+            Some(callers) => {
+                for caller in callers.iter().filter(|caller| predicate(caller)) {
+                    if let Some(call) = map.calls.get(&((*caller).clone(), id.clone())) {
+                        costs.add(&call.costs);
+                    }
+                }
+            }
+            None => {
+                if let Some(value) = map.map.get(id) {
+                    costs.add(&value.costs);
+                }
+            }
+        }
+    }
+}
+
+/// Return true if the function name of the `id` matches one of the `patterns`
+fn matches(patterns: &[Pattern], id: &Id) -> bool {
+    patterns.iter().any(|pattern| pattern.matches(&id.func))
+}
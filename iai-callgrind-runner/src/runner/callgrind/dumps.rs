@@ -0,0 +1,200 @@
+use anyhow::Result;
+use log::debug;
+
+use super::hashmap_parser::CallgrindMap;
+use super::model::Costs;
+use super::parser::parse_header;
+use crate::error::Error;
+use crate::runner::format::{print_thread_headline, VerticalFormat};
+use crate::runner::meta::Metadata;
+use crate::runner::summary::{
+    CallgrindDump, CallgrindProcess, CallgrindRegressionSummary, CallgrindThreadSummary,
+    CostsSummary, FunctionCostsSummary,
+};
+use crate::runner::tool::ToolOutputPath;
+
+/// The parsed costs of a single thread of a [`Dump`]
+#[derive(Debug)]
+pub struct ThreadCosts {
+    /// The path to the output file of this thread
+    pub path: ToolOutputPath,
+    /// The thread index as assigned by valgrind starting with `1` for the main thread
+    pub thread: usize,
+    /// The costs of this thread
+    pub costs: Costs,
+}
+
+/// The parsed costs of a single dump of a callgrind output
+///
+/// The outputs of other tools like cachegrind always consist of a single `Dump` without a part
+/// number and trigger.
+#[derive(Debug)]
+pub struct Dump {
+    /// The path to the output file of this dump
+    pub path: ToolOutputPath,
+    /// The part number from the header of the output file
+    pub part: Option<u64>,
+    /// The trigger from the header of the output file
+    pub trigger: Option<String>,
+    /// The total costs of this dump summed up over all threads
+    pub costs: Costs,
+    /// The per-function costs of all threads if needed
+    pub functions: Option<CallgrindMap>,
+    /// The costs of the single threads if callgrind was run with `--separate-threads=yes`
+    pub threads: Vec<ThreadCosts>,
+}
+
+/// The summarized costs of a single [`Dump`] compared to the old dump of the same part
+#[derive(Debug)]
+pub struct DumpCostsSummary {
+    /// The path to the output file of the new dump or of its main thread
+    pub path: ToolOutputPath,
+    /// The description of the dump if the output consists of multiple dumps
+    pub dump: Option<CallgrindDump>,
+    /// The total costs of the new dump compared to the old dump
+    pub costs_summary: CostsSummary,
+    /// The total costs without the subtracted harness overhead if the overhead was subtracted
+    pub raw_costs_summary: Option<CostsSummary>,
+    /// The detected performance regressions of this dump
+    pub regressions: Vec<CallgrindRegressionSummary>,
+    /// The costs of the top functions of this dump
+    pub functions: Vec<FunctionCostsSummary>,
+    /// The costs of the single threads of the new dump compared to the old threads
+    pub threads: Vec<CallgrindThreadSummary>,
+    /// The traced process of this dump if callgrind was run with `--trace-children=yes`
+    pub process: Option<CallgrindProcess>,
+}
+
+impl Dump {
+    /// Return the dump of the `old` dumps with the same part number as this dump
+    pub fn find_old<'a>(&self, old: &'a [Dump]) -> Option<&'a Dump> {
+        old.iter().find(|dump| dump.part == self.part)
+    }
+
+    /// Return the costs of the thread with the thread index `thread` if present
+    pub fn find_thread(&self, thread: usize) -> Option<&ThreadCosts> {
+        self.threads.iter().find(|t| t.thread == thread)
+    }
+
+    /// Return the path to the output file of this dump or of its main thread
+    ///
+    /// If the threads are separated, callgrind doesn't write an output file for the whole dump.
+    pub fn main_path(&self) -> &ToolOutputPath {
+        self.threads.first().map_or(&self.path, |thread| &thread.path)
+    }
+
+    /// Return the [`CallgrindDump`] of this dump if the part number is known
+    pub fn to_summary(&self) -> Option<CallgrindDump> {
+        self.part.map(|part| CallgrindDump {
+            part,
+            trigger: self.trigger.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Parse the part number and trigger from the header of the callgrind output file
+pub fn parse_description(output_path: &ToolOutputPath) -> Result<(Option<u64>, Option<String>)> {
+    debug!(
+        "Parsing callgrind output file '{}' for the dump description",
+        output_path
+    );
+
+    let mut iter = output_path.lines()?;
+    let properties = parse_header(&mut iter)
+        .map_err(|error| Error::ParseError((output_path.to_path(), error.to_string())))?;
+
+    Ok((properties.part, properties.trigger))
+}
+
+/// Summarize the costs of the threads of the `new` dump compared to the `old` dump
+///
+/// The threads are matched by their thread index. Only if there are multiple threads, their costs
+/// are printed.
+pub fn summarize_threads(
+    meta: &Metadata,
+    baselines: &(Option<String>, Option<String>),
+    new: &Dump,
+    old: Option<&Dump>,
+) -> Result<Vec<CallgrindThreadSummary>> {
+    let mut summaries = vec![];
+    for thread in &new.threads {
+        let old_thread = old.and_then(|dump| dump.find_thread(thread.thread));
+        let events = CostsSummary::new(&thread.costs, old_thread.map(|t| &t.costs));
+        if new.threads.len() > 1 {
+            print_thread_headline(meta, thread.thread);
+            VerticalFormat::default().print(meta, baselines.clone(), &events)?;
+        }
+        summaries.push(CallgrindThreadSummary {
+            thread: thread.thread,
+            events,
+        });
+    }
+    Ok(summaries)
+}
+
+/// Parse all dumps and threads of the callgrind output at `output_path`
+///
+/// The `parse_costs` and `parse_functions` closures are called for the output file of every single
+/// dump, or if the threads are separated, for the output file of every single thread of a dump.
+/// The costs and functions of the threads are summed up to the total of the dump.
+pub fn parse<C, F>(
+    output_path: &ToolOutputPath,
+    mut parse_costs: C,
+    mut parse_functions: F,
+) -> Result<Vec<Dump>>
+where
+    C: FnMut(&ToolOutputPath) -> Result<Costs>,
+    F: FnMut(&ToolOutputPath) -> Result<Option<CallgrindMap>>,
+{
+    let mut dumps = vec![];
+    for path in output_path.to_parts() {
+        let thread_paths = path.to_threads();
+        let Some(first) = thread_paths.first() else {
+            let (part, trigger) = parse_description(&path)?;
+            dumps.push(Dump {
+                costs: parse_costs(&path)?,
+                functions: parse_functions(&path)?,
+                threads: vec![],
+                path,
+                part,
+                trigger,
+            });
+            continue;
+        };
+
+        let (part, trigger) = parse_description(first)?;
+        let mut total: Option<Costs> = None;
+        let mut functions: Option<CallgrindMap> = None;
+        let mut threads = vec![];
+        for thread_path in thread_paths {
+            let costs = parse_costs(&thread_path)?;
+            if let Some(total) = total.as_mut() {
+                total.add(&costs);
+            } else {
+                total = Some(costs.clone());
+            }
+            if let Some(map) = parse_functions(&thread_path)? {
+                if let Some(functions) = functions.as_mut() {
+                    functions.add(map);
+                } else {
+                    functions = Some(map);
+                }
+            }
+            threads.push(ThreadCosts {
+                thread: thread_path.thread.unwrap_or_default(),
+                path: thread_path,
+                costs,
+            });
+        }
+
+        dumps.push(Dump {
+            costs: total.unwrap_or_default(),
+            functions,
+            threads,
+            path,
+            part,
+            trigger,
+        });
+    }
+    Ok(dumps)
+}
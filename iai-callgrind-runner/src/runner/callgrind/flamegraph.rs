@@ -1,32 +1,50 @@
+use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufWriter, Cursor, Write as IoWrite};
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use inferno::flamegraph::{Direction, Options};
+use log::warn;
 
 use super::flamegraph_parser::{FlamegraphMap, FlamegraphParser};
 use super::parser::Sentinel;
-use crate::api::{self, EventKind, FlamegraphKind};
+use super::speedscope::Speedscope;
+use super::stack_filter::StackFilter;
+use super::CostModel;
+use crate::api::{self, DhatMetric, EventKind, FlamegraphGranularity, FlamegraphKind};
 use crate::runner::summary::{BaselineKind, BaselineName, FlamegraphSummary};
-use crate::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind};
+use crate::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind, ValgrindTool};
+
+/// The metric of the dummy [`OutputPath`] used to organize the output files of all metrics
+const DUMMY_METRIC: &str = "dummy";
 
 #[derive(Debug)]
 pub struct BaselineFlamegraphGenerator {
     pub baseline_kind: BaselineKind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub kind: FlamegraphKind,
     pub negate_differential: bool,
     pub normalize_differential: bool,
     pub event_kinds: Vec<EventKind>,
+    pub dhat_metrics: Vec<DhatMetric>,
     pub direction: Direction,
     pub title: Option<String>,
     pub subtitle: Option<String>,
     pub min_width: f64,
+    pub speedscope: bool,
+    /// If true, write the folded stacks next to the flamegraphs
+    pub folded: bool,
+    /// The granularity of the innermost frames of the stacks
+    pub granularity: FlamegraphGranularity,
+    /// The cost model of the [`EventKind::EstimatedCycles`]
+    pub cost_model: CostModel,
+    /// The rules to filter, focus and collapse the frames of the stacks
+    pub filter: StackFilter,
 }
 
 #[derive(Debug, Clone)]
@@ -41,16 +59,17 @@ pub struct LoadBaselineFlamegraphGenerator {
 }
 
 #[derive(Debug, Clone)]
-struct OutputPath {
+pub struct OutputPath {
     pub kind: OutputPathKind,
-    pub event_kind: EventKind,
+    pub tool: ValgrindTool,
+    pub metric: String,
     pub baseline_kind: BaselineKind,
     pub dir: PathBuf,
     pub name: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum OutputPathKind {
+pub enum OutputPathKind {
     Regular,
     Old,
     Base(String),
@@ -64,14 +83,82 @@ pub struct SaveBaselineFlamegraphGenerator {
     pub baseline: BaselineName,
 }
 
+/// A metric like an [`EventKind`] by which the stacks of a flamegraph are weighted
+pub trait FlamegraphMetric: Display + Clone {
+    /// The name of the metric as used in the file name of the flamegraph
+    fn to_name(&self) -> String;
+}
+
+/// The stacks of a flamegraph which can be converted into the folded stacks format
+pub trait FlamegraphStacks<M> {
+    /// Convert the stacks into the folded stacks format applying the rules of the `filter`
+    fn to_stack_format(&self, metric: &M, filter: &StackFilter) -> Result<Vec<String>>;
+}
+
 pub trait FlamegraphGenerator {
+    /// Create the callgrind flamegraphs for all [`EventKind`]s of the [`Flamegraph`]
     fn create(
         &self,
         flamegraph: &Flamegraph,
         tool_output_path: &ToolOutputPath,
         sentinel: Option<&Sentinel>,
         project_root: &Path,
-    ) -> Result<Vec<FlamegraphSummary>>;
+    ) -> Result<Vec<FlamegraphSummary>> {
+        self.create_from(
+            flamegraph,
+            tool_output_path,
+            &flamegraph.config.event_kinds,
+            |no_differential| {
+                flamegraph.parse(tool_output_path, sentinel, project_root, no_differential)
+            },
+        )
+    }
+
+    /// Create the callgrind flamegraphs of the whole run and of every single thread
+    ///
+    /// The flamegraphs of the threads are only created if callgrind was run with
+    /// `--separate-threads=yes`. A failure to create the flamegraph of a single thread is not
+    /// fatal.
+    fn create_all(
+        &self,
+        flamegraph: &Flamegraph,
+        tool_output_path: &ToolOutputPath,
+        sentinel: Option<&Sentinel>,
+        project_root: &Path,
+    ) -> Result<Vec<FlamegraphSummary>> {
+        let mut summaries = self.create(flamegraph, tool_output_path, sentinel, project_root)?;
+        for thread_path in tool_output_path.to_threads() {
+            match self.create(flamegraph, &thread_path, sentinel, project_root) {
+                Ok(thread_summaries) => {
+                    for mut summary in thread_summaries {
+                        summary.thread = thread_path.thread;
+                        summaries.push(summary);
+                    }
+                }
+                Err(error) => warn!(
+                    "Unable to create the flamegraph of thread #{}: {error}",
+                    thread_path.thread.unwrap_or_default()
+                ),
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Create the flamegraphs for all `metrics` from the stacks returned by `parse`
+    ///
+    /// The argument of `parse` is true if no differential stacks are required. The `parse`
+    /// function returns the new stacks and the old or base stacks if present.
+    fn create_from<M, S, P>(
+        &self,
+        flamegraph: &Flamegraph,
+        tool_output_path: &ToolOutputPath,
+        metrics: &[M],
+        parse: P,
+    ) -> Result<Vec<FlamegraphSummary<M>>>
+    where
+        M: FlamegraphMetric,
+        S: FlamegraphStacks<M>,
+        P: FnOnce(bool) -> Result<(S, Option<S>)>;
 }
 
 impl From<api::FlamegraphConfig> for Config {
@@ -81,16 +168,41 @@ impl From<api::FlamegraphConfig> for Config {
             negate_differential: value.negate_differential.unwrap_or_default(),
             normalize_differential: value.normalize_differential.unwrap_or(false),
             event_kinds: value.event_kinds.unwrap_or_else(|| vec![EventKind::Ir]),
+            dhat_metrics: value
+                .dhat_metrics
+                .unwrap_or_else(|| vec![DhatMetric::TotalBytes]),
             direction: value
                 .direction
                 .map_or_else(|| Direction::Inverted, std::convert::Into::into),
             title: value.title.clone(),
             subtitle: value.subtitle.clone(),
             min_width: value.min_width.unwrap_or(0.1f64),
+            speedscope: value.speedscope.unwrap_or(false),
+            folded: value.folded.unwrap_or(false),
+            granularity: value.granularity.unwrap_or_default(),
+            cost_model: CostModel::default(),
+            filter: StackFilter::new(
+                value.include_frames.unwrap_or_default(),
+                value.exclude_frames.unwrap_or_default(),
+                value.focus,
+                value.collapse_frames.unwrap_or_default(),
+            ),
         }
     }
 }
 
+impl FlamegraphMetric for EventKind {
+    fn to_name(&self) -> String {
+        EventKind::to_name(self)
+    }
+}
+
+impl FlamegraphStacks<EventKind> for FlamegraphMap {
+    fn to_stack_format(&self, metric: &EventKind, filter: &StackFilter) -> Result<Vec<String>> {
+        FlamegraphMap::to_stack_format(self, metric, filter)
+    }
+}
+
 impl From<api::Direction> for Direction {
     fn from(value: api::Direction) -> Self {
         match value {
@@ -101,64 +213,76 @@ impl From<api::Direction> for Direction {
 }
 
 impl FlamegraphGenerator for BaselineFlamegraphGenerator {
-    fn create(
+    fn create_from<M, S, P>(
         &self,
         flamegraph: &Flamegraph,
         tool_output_path: &ToolOutputPath,
-        sentinel: Option<&Sentinel>,
-        project_root: &Path,
-    ) -> Result<Vec<FlamegraphSummary>> {
+        metrics: &[M],
+        parse: P,
+    ) -> Result<Vec<FlamegraphSummary<M>>>
+    where
+        M: FlamegraphMetric,
+        S: FlamegraphStacks<M>,
+        P: FnOnce(bool) -> Result<(S, Option<S>)>,
+    {
         // We need the dummy path just to cleanup and organize the output files independently from
-        // the EventKind of the OutputPath
-        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir);
+        // the metric of the OutputPath
+        let mut output_path = OutputPath::new(tool_output_path, DUMMY_METRIC);
         output_path.init()?;
         output_path.to_diff_path().clear(true)?;
         output_path.shift(true)?;
 
-        if flamegraph.config.kind == FlamegraphKind::None
-            || flamegraph.config.event_kinds.is_empty()
-        {
+        if flamegraph.config.kind == FlamegraphKind::None || metrics.is_empty() {
             return Ok(vec![]);
         }
 
-        let (map, base_map) = flamegraph.parse(tool_output_path, sentinel, project_root, false)?;
+        let (map, base_map) = parse(false)?;
 
+        let mut speedscope = flamegraph.speedscope();
         let mut flamegraph_summaries = vec![];
-        for event_kind in &flamegraph.config.event_kinds {
-            let mut flamegraph_summary = FlamegraphSummary::new(*event_kind);
-            output_path.set_event_kind(*event_kind);
+        for metric in metrics {
+            let mut flamegraph_summary = FlamegraphSummary::new(metric.clone());
+            output_path.set_metric(metric);
 
-            let stacks_lines = map.to_stack_format(event_kind)?;
+            let stacks_lines = map.to_stack_format(metric, &flamegraph.config.filter)?;
+            if let Some(speedscope) = speedscope.as_mut() {
+                speedscope.add(metric.to_string(), &stacks_lines)?;
+            }
 
             if flamegraph.is_regular() {
                 Flamegraph::write(
                     &output_path,
-                    &mut flamegraph.options(*event_kind, output_path.file_name()),
+                    &mut flamegraph.options(metric, output_path.file_name()),
                     stacks_lines.iter().map(std::string::String::as_str),
                 )?;
                 flamegraph_summary.regular_path = Some(output_path.to_path());
             }
+            flamegraph_summary.folded_path = flamegraph.write_folded(&output_path, &stacks_lines)?;
 
             // Is Some if FlamegraphKind::Differential or FlamegraphKind::All
             if let Some(base_map) = base_map.as_ref() {
+                let base_stacks_lines =
+                    base_map.to_stack_format(metric, &flamegraph.config.filter)?;
                 Flamegraph::create_differential(
                     &output_path,
-                    &mut flamegraph.options(*event_kind, output_path.to_diff_path().file_name()),
-                    base_map,
+                    &mut flamegraph.options(metric, output_path.to_diff_path().file_name()),
+                    &base_stacks_lines,
                     // This unwrap is safe since we always have differential options if the
                     // flamegraph kind is differential
                     flamegraph.differential_options().unwrap(),
-                    *event_kind,
                     &stacks_lines,
                 )?;
 
                 flamegraph_summary.base_path = Some(output_path.to_base_path().to_path());
                 flamegraph_summary.diff_path = Some(output_path.to_diff_path().to_path());
+                flamegraph_summary.base_folded_path =
+                    flamegraph.write_folded(&output_path.to_base_path(), &base_stacks_lines)?;
             }
 
             flamegraph_summaries.push(flamegraph_summary);
         }
 
+        Flamegraph::write_speedscope(&output_path, speedscope, &mut flamegraph_summaries)?;
         Ok(flamegraph_summaries)
     }
 }
@@ -186,7 +310,7 @@ impl Flamegraph {
         )
     }
 
-    pub fn options(&self, event_kind: EventKind, subtitle: String) -> Options {
+    pub fn options<M: Display>(&self, metric: &M, subtitle: String) -> Options {
         let mut options = Options::default();
         options.negate_differentials = self.config.negate_differential;
         options.direction = self.config.direction;
@@ -204,7 +328,7 @@ impl Flamegraph {
         };
 
         options.min_width = self.config.min_width;
-        options.count_name = event_kind.to_string();
+        options.count_name = metric.to_string();
         options
     }
 
@@ -226,38 +350,66 @@ impl Flamegraph {
     where
         P: Into<PathBuf>,
     {
-        let parser = FlamegraphParser::new(sentinel, project_root);
+        let base_path = tool_output_path.to_base_path();
+        // The thread may not have been present in the base run
+        let base_exists = if base_path.thread.is_some() {
+            base_path.to_path().exists()
+        } else {
+            base_path.exists()
+        };
+        self.parse_paths(
+            tool_output_path,
+            base_exists.then_some(&base_path),
+            sentinel,
+            project_root,
+            no_differential,
+        )
+    }
+
+    /// Parse the stacks of the output at `tool_output_path` and of the `base_path` if present
+    ///
+    /// Other than [`Flamegraph::parse`], the base path is not derived from the `tool_output_path`.
+    pub fn parse_paths<P>(
+        &self,
+        tool_output_path: &ToolOutputPath,
+        base_path: Option<&ToolOutputPath>,
+        sentinel: Option<&Sentinel>,
+        project_root: P,
+        no_differential: bool,
+    ) -> Result<(FlamegraphMap, Option<FlamegraphMap>)>
+    where
+        P: Into<PathBuf>,
+    {
+        let parser =
+            FlamegraphParser::new(sentinel, project_root).with_granularity(self.config.granularity);
         // We need this map in all remaining cases of `FlamegraphKinds`
         let mut map = parser.parse(tool_output_path)?;
         if map.is_empty() {
             return Err(anyhow!("Unable to create a flamegraph: No stacks found"));
         }
 
-        let base_path = tool_output_path.to_base_path();
-        let mut base_map = (!no_differential && self.is_differential() && base_path.exists())
-            .then(|| parser.parse(&base_path))
+        let mut base_map = base_path
+            .filter(|_| !no_differential && self.is_differential())
+            .map(|base_path| parser.parse(base_path))
             .transpose()?;
 
         if self.config.event_kinds.iter().any(EventKind::is_derived) {
-            map.make_summary()?;
+            map.make_summary(&self.config.cost_model)?;
             if let Some(map) = base_map.as_mut() {
-                map.make_summary()?;
+                map.make_summary(&self.config.cost_model)?;
             }
         }
 
         Ok((map, base_map))
     }
 
-    fn create_differential(
+    pub fn create_differential(
         output_path: &OutputPath,
         options: &mut inferno::flamegraph::Options,
-        base_map: &FlamegraphMap,
+        base_stacks_lines: &[String],
         differential_options: inferno::differential::Options,
-        event_kind: EventKind,
         stacks_lines: &[String],
     ) -> Result<()> {
-        let base_stacks_lines = base_map.to_stack_format(&event_kind)?;
-
         let cursor = Cursor::new(stacks_lines.join("\n"));
         let base_cursor = Cursor::new(base_stacks_lines.join("\n"));
         let mut result = Cursor::new(vec![]);
@@ -273,7 +425,63 @@ impl Flamegraph {
         )
     }
 
-    fn write<'stacks>(
+    /// Return a new [`Speedscope`] file if the speedscope export is enabled
+    pub fn speedscope(&self) -> Option<Speedscope> {
+        self.config.speedscope.then(|| {
+            Speedscope::new(
+                self.config
+                    .title
+                    .clone()
+                    .expect("A title must be present at this point"),
+            )
+        })
+    }
+
+    /// Write the `speedscope` file if present and record its path in all `summaries`
+    ///
+    /// All profiles of the metrics are stored in the same speedscope file.
+    pub fn write_speedscope<M>(
+        output_path: &OutputPath,
+        speedscope: Option<Speedscope>,
+        summaries: &mut [FlamegraphSummary<M>],
+    ) -> Result<()> {
+        if let Some(speedscope) = speedscope.filter(|s| !s.is_empty()) {
+            let path = output_path.to_speedscope_path();
+            speedscope.write(&path)?;
+            for summary in summaries {
+                summary.speedscope_path = Some(path.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the `stacks_lines` to the folded stacks file of the `output_path` if enabled
+    ///
+    /// Returns the path to the written file. The file is in the folded stacks format of inferno
+    /// with one stack per line, so it can be consumed by other tools like `inferno-flamegraph` or
+    /// `inferno-diff-folded`.
+    pub fn write_folded(
+        &self,
+        output_path: &OutputPath,
+        stacks_lines: &[String],
+    ) -> Result<Option<PathBuf>> {
+        if !self.config.folded {
+            return Ok(None);
+        }
+
+        let path = output_path.to_folded_path();
+        let file = File::create(&path)
+            .with_context(|| format!("Failed creating folded stacks file '{}'", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        stacks_lines
+            .iter()
+            .try_for_each(|line| writeln!(writer, "{line}"))
+            .and_then(|()| writer.flush())
+            .with_context(|| format!("Failed writing folded stacks file '{}'", path.display()))?;
+        Ok(Some(path))
+    }
+
+    pub fn write<'stacks>(
         output_path: &OutputPath,
         options: &mut Options<'_>,
         stacks: impl Iterator<Item = &'stacks str>,
@@ -290,57 +498,70 @@ impl Flamegraph {
 }
 
 impl FlamegraphGenerator for LoadBaselineFlamegraphGenerator {
-    fn create(
+    fn create_from<M, S, P>(
         &self,
         flamegraph: &Flamegraph,
         tool_output_path: &ToolOutputPath,
-        sentinel: Option<&Sentinel>,
-        project_root: &Path,
-    ) -> Result<Vec<FlamegraphSummary>> {
+        metrics: &[M],
+        parse: P,
+    ) -> Result<Vec<FlamegraphSummary<M>>>
+    where
+        M: FlamegraphMetric,
+        S: FlamegraphStacks<M>,
+        P: FnOnce(bool) -> Result<(S, Option<S>)>,
+    {
         // We need the dummy path just to cleanup and organize the output files independently from
-        // the EventKind of the OutputPath
-        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir);
+        // the metric of the OutputPath
+        let mut output_path = OutputPath::new(tool_output_path, DUMMY_METRIC);
         output_path.to_diff_path().clear(true)?;
 
         if flamegraph.config.kind == FlamegraphKind::None
-            || flamegraph.config.event_kinds.is_empty()
+            || metrics.is_empty()
             || !flamegraph.is_differential()
         {
             return Ok(vec![]);
         }
 
-        let (map, base_map) = flamegraph
-            .parse(tool_output_path, sentinel, project_root, false)
-            .map(|(m, b)| (m, b.unwrap()))?;
+        let (map, base_map) = parse(false).map(|(m, b)| (m, b.unwrap()))?;
 
+        let mut speedscope = flamegraph.speedscope();
         let mut flamegraph_summaries = vec![];
-        for event_kind in &flamegraph.config.event_kinds {
-            let mut flamegraph_summary = FlamegraphSummary::new(*event_kind);
-            output_path.set_event_kind(*event_kind);
+        for metric in metrics {
+            let mut flamegraph_summary = FlamegraphSummary::new(metric.clone());
+            output_path.set_metric(metric);
 
+            let stacks_lines = map.to_stack_format(metric, &flamegraph.config.filter)?;
+            if let Some(speedscope) = speedscope.as_mut() {
+                speedscope.add(metric.to_string(), &stacks_lines)?;
+            }
+
+            let base_stacks_lines = base_map.to_stack_format(metric, &flamegraph.config.filter)?;
             Flamegraph::create_differential(
                 &output_path,
-                &mut flamegraph.options(*event_kind, output_path.to_diff_path().file_name()),
-                &base_map,
+                &mut flamegraph.options(metric, output_path.to_diff_path().file_name()),
+                &base_stacks_lines,
                 // This unwrap is safe since we always produce a differential flamegraph
                 flamegraph.differential_options().unwrap(),
-                *event_kind,
-                &map.to_stack_format(event_kind)?,
+                &stacks_lines,
             )?;
 
             flamegraph_summary.regular_path = Some(output_path.to_path());
             flamegraph_summary.base_path = Some(output_path.to_base_path().to_path());
             flamegraph_summary.diff_path = Some(output_path.to_diff_path().to_path());
+            flamegraph_summary.folded_path = flamegraph.write_folded(&output_path, &stacks_lines)?;
+            flamegraph_summary.base_folded_path =
+                flamegraph.write_folded(&output_path.to_base_path(), &base_stacks_lines)?;
 
             flamegraph_summaries.push(flamegraph_summary);
         }
 
+        Flamegraph::write_speedscope(&output_path, speedscope, &mut flamegraph_summaries)?;
         Ok(flamegraph_summaries)
     }
 }
 
 impl OutputPath {
-    pub fn new(tool_output_path: &ToolOutputPath, event_kind: EventKind) -> Self {
+    pub fn new(tool_output_path: &ToolOutputPath, metric: &str) -> Self {
         Self {
             kind: match &tool_output_path.kind {
                 ToolOutputPathKind::Out | ToolOutputPathKind::Log => OutputPathKind::Regular,
@@ -349,10 +570,16 @@ impl OutputPath {
                     OutputPathKind::Base(name.clone())
                 }
             },
-            event_kind,
+            tool: tool_output_path.tool.clone(),
+            metric: metric.to_owned(),
             baseline_kind: tool_output_path.baseline_kind.clone(),
             dir: tool_output_path.dir.clone(),
-            name: tool_output_path.name.clone(),
+            // The flamegraphs of single threads are distinguished by the thread index like the
+            // callgrind output files
+            name: tool_output_path.thread.map_or_else(
+                || tool_output_path.name.clone(),
+                |thread| format!("{}-{thread:02}", tool_output_path.name),
+            ),
         }
     }
 
@@ -373,8 +600,8 @@ impl OutputPath {
             .with_context(|| format!("Failed creating flamegraph file '{}'", path.display()))
     }
 
-    pub fn clear(&self, ignore_event_kind: bool) -> Result<()> {
-        for path in self.real_paths(ignore_event_kind)? {
+    pub fn clear(&self, ignore_metric: bool) -> Result<()> {
+        for path in self.real_paths(ignore_metric)? {
             std::fs::remove_file(path)?;
         }
 
@@ -392,7 +619,7 @@ impl OutputPath {
             let entry = entry?;
             let file_name = entry.file_name().to_string_lossy().to_string();
             if let Some(suffix) =
-                file_name.strip_prefix(format!("callgrind.{}.flamegraph.", &self.name).as_str())
+                file_name.strip_prefix(self.prefix().as_str())
             {
                 let path = entry.path();
                 if suffix.ends_with(extension.as_str()) {
@@ -417,11 +644,11 @@ impl OutputPath {
         Ok(())
     }
 
-    pub fn shift(&self, ignore_event_kind: bool) -> Result<()> {
+    pub fn shift(&self, ignore_metric: bool) -> Result<()> {
         match &self.baseline_kind {
             BaselineKind::Old => {
-                self.to_base_path().clear(ignore_event_kind)?;
-                for path in self.real_paths(ignore_event_kind)? {
+                self.to_base_path().clear(ignore_metric)?;
+                for path in self.real_paths(ignore_metric)? {
                     let new_path = path.with_extension("old.svg");
                     std::fs::rename(&path, &new_path).with_context(|| {
                         format!(
@@ -433,7 +660,7 @@ impl OutputPath {
                 }
                 Ok(())
             }
-            BaselineKind::Name(_) => self.clear(ignore_event_kind),
+            BaselineKind::Name(_) => self.clear(ignore_metric),
         }
     }
 
@@ -467,37 +694,64 @@ impl OutputPath {
 
     pub fn extension(&self) -> String {
         match &self.kind {
-            OutputPathKind::Regular => format!("flamegraph.{}.svg", self.event_kind.to_name()),
-            OutputPathKind::Old => format!("flamegraph.{}.old.svg", self.event_kind.to_name()),
+            OutputPathKind::Regular => format!("flamegraph.{}.svg", self.metric),
+            OutputPathKind::Old => format!("flamegraph.{}.old.svg", self.metric),
             OutputPathKind::Base(name) => {
-                format!("flamegraph.{}.base@{name}.svg", self.event_kind.to_name())
+                format!("flamegraph.{}.base@{name}.svg", self.metric)
             }
             OutputPathKind::DiffOld => {
-                format!("flamegraph.{}.diff.old.svg", self.event_kind.to_name())
+                format!("flamegraph.{}.diff.old.svg", self.metric)
             }
             OutputPathKind::DiffBase(name) => {
                 format!(
                     "flamegraph.{}.diff.base@{name}.svg",
-                    self.event_kind.to_name()
+                    self.metric
                 )
             }
             OutputPathKind::DiffBases(name, base) => {
                 format!(
                     "flamegraph.{}.base@{name}.diff.base@{base}.svg",
-                    self.event_kind.to_name()
+                    self.metric
                 )
             }
         }
     }
 
-    pub fn set_event_kind(&mut self, event_kind: EventKind) {
-        self.event_kind = event_kind;
+    /// Return the path to the speedscope file with the profiles of all metrics
+    ///
+    /// The speedscope file is named like the flamegraphs but without the metric, for example
+    /// `callgrind.bench_name.speedscope.json` or `callgrind.bench_name.speedscope.base@foo.json`.
+    pub fn to_speedscope_path(&self) -> PathBuf {
+        let extension = match &self.kind {
+            OutputPathKind::Old => "speedscope.old.json".to_owned(),
+            OutputPathKind::Base(name) | OutputPathKind::DiffBases(name, _) => {
+                format!("speedscope.base@{name}.json")
+            }
+            OutputPathKind::Regular | OutputPathKind::DiffOld | OutputPathKind::DiffBase(_) => {
+                "speedscope.json".to_owned()
+            }
+        };
+        self.dir
+            .join(format!("{}.{}.{extension}", self.tool.id(), self.name))
+    }
+
+    /// Return the path to the file with the folded stacks
+    ///
+    /// The file is named like the svg flamegraph but with the extension `folded`, for example
+    /// `callgrind.bench_name.flamegraph.Ir.folded` or
+    /// `callgrind.bench_name.flamegraph.Ir.old.folded`.
+    pub fn to_folded_path(&self) -> PathBuf {
+        self.to_path().with_extension("folded")
     }
 
-    pub fn real_paths(&self, ignore_event_kind: bool) -> Result<Vec<PathBuf>> {
+    pub fn set_metric<M: FlamegraphMetric>(&mut self, metric: &M) {
+        self.metric = metric.to_name();
+    }
+
+    pub fn real_paths(&self, ignore_metric: bool) -> Result<Vec<PathBuf>> {
         let mut paths = vec![];
         let extension = self.extension();
-        let to_match = if ignore_event_kind {
+        let to_match = if ignore_metric {
             extension.splitn(3, '.').last().unwrap()
         } else {
             extension.strip_prefix("flamegraph.").unwrap()
@@ -508,12 +762,12 @@ impl OutputPath {
             let path = entry?;
             let file_name = path.file_name().to_string_lossy().to_string();
             if let Some(suffix) =
-                file_name.strip_prefix(format!("callgrind.{}.flamegraph.", &self.name).as_str())
+                file_name.strip_prefix(self.prefix().as_str())
             {
-                let is_match = if ignore_event_kind {
+                let is_match = if ignore_metric {
                     suffix
                         .split_once('.')
-                        .map_or(false, |(_event_kind, rest)| rest == to_match)
+                        .map_or(false, |(_metric, rest)| rest == to_match)
                 } else {
                     suffix == to_match
                 };
@@ -525,8 +779,13 @@ impl OutputPath {
         Ok(paths)
     }
 
+    /// The prefix of the file names of all flamegraphs of this tool and benchmark
+    fn prefix(&self) -> String {
+        format!("{}.{}.flamegraph.", self.tool.id(), self.name)
+    }
+
     pub fn file_name(&self) -> String {
-        format!("callgrind.{}.{}", self.name, self.extension())
+        format!("{}.{}.{}", self.tool.id(), self.name, self.extension())
     }
 
     pub fn to_path(&self) -> PathBuf {
@@ -535,44 +794,57 @@ impl OutputPath {
 }
 
 impl FlamegraphGenerator for SaveBaselineFlamegraphGenerator {
-    fn create(
+    fn create_from<M, S, P>(
         &self,
         flamegraph: &Flamegraph,
         tool_output_path: &ToolOutputPath,
-        sentinel: Option<&Sentinel>,
-        project_root: &Path,
-    ) -> Result<Vec<FlamegraphSummary>> {
+        metrics: &[M],
+        parse: P,
+    ) -> Result<Vec<FlamegraphSummary<M>>>
+    where
+        M: FlamegraphMetric,
+        S: FlamegraphStacks<M>,
+        P: FnOnce(bool) -> Result<(S, Option<S>)>,
+    {
         // We need the dummy path just to cleanup and organize the output files independently from
-        // the EventKind of the OutputPath
-        let mut output_path = OutputPath::new(tool_output_path, EventKind::Ir);
+        // the metric of the OutputPath
+        let mut output_path = OutputPath::new(tool_output_path, DUMMY_METRIC);
         output_path.init()?;
         output_path.clear(true)?;
         output_path.clear_diff()?;
 
         if flamegraph.config.kind == FlamegraphKind::None
-            || flamegraph.config.event_kinds.is_empty()
+            || metrics.is_empty()
             || !flamegraph.is_regular()
         {
             return Ok(vec![]);
         }
 
-        let (map, _) = flamegraph.parse(tool_output_path, sentinel, project_root, true)?;
+        let (map, _) = parse(true)?;
 
+        let mut speedscope = flamegraph.speedscope();
         let mut flamegraph_summaries = vec![];
-        for event_kind in &flamegraph.config.event_kinds {
-            let mut flamegraph_summary = FlamegraphSummary::new(*event_kind);
-            output_path.set_event_kind(*event_kind);
+        for metric in metrics {
+            let mut flamegraph_summary = FlamegraphSummary::new(metric.clone());
+            output_path.set_metric(metric);
+
+            let stacks_lines = map.to_stack_format(metric, &flamegraph.config.filter)?;
+            if let Some(speedscope) = speedscope.as_mut() {
+                speedscope.add(metric.to_string(), &stacks_lines)?;
+            }
 
             Flamegraph::write(
                 &output_path,
-                &mut flamegraph.options(*event_kind, output_path.file_name()),
-                map.to_stack_format(event_kind)?.iter().map(String::as_str),
+                &mut flamegraph.options(metric, output_path.file_name()),
+                stacks_lines.iter().map(String::as_str),
             )?;
 
             flamegraph_summary.regular_path = Some(output_path.to_path());
+            flamegraph_summary.folded_path = flamegraph.write_folded(&output_path, &stacks_lines)?;
             flamegraph_summaries.push(flamegraph_summary);
         }
 
+        Flamegraph::write_speedscope(&output_path, speedscope, &mut flamegraph_summaries)?;
         Ok(flamegraph_summaries)
     }
 }
@@ -5,9 +5,11 @@ use std::path::PathBuf;
 use anyhow::{anyhow, Result};
 use log::debug;
 
-use super::hashmap_parser::{CallgrindMap, HashMapParser, SourcePath};
+use super::hashmap_parser::{CallgrindMap, HashMapParser, Id, Location, SourcePath};
 use super::parser::Sentinel;
-use crate::api::EventKind;
+use super::stack_filter::StackFilter;
+use super::CostModel;
+use crate::api::{EventKind, FlamegraphGranularity};
 use crate::runner::tool::{Parser, ToolOutputPath};
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -17,12 +19,14 @@ pub struct FlamegraphMap(CallgrindMap);
 pub struct FlamegraphParser {
     project_root: PathBuf,
     sentinel: Option<Sentinel>,
+    granularity: FlamegraphGranularity,
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct HeapElem {
+struct HeapElem<'a> {
     source: String,
     cost: u64,
+    id: &'a Id,
 }
 
 impl FlamegraphMap {
@@ -30,26 +34,88 @@ impl FlamegraphMap {
         self.0.is_empty()
     }
 
-    pub fn make_summary(&mut self) -> Result<()> {
+    pub fn make_summary(&mut self, cost_model: &CostModel) -> Result<()> {
         for value in self.0.map.values_mut() {
             if value.costs.can_summarize() {
                 value
                     .costs
-                    .make_summary()
+                    .make_summary_with(cost_model)
                     .map_err(|error| anyhow!("Failed calculating summary events: {error}"))?;
             } else {
                 return Ok(());
             }
         }
+        for costs in self.0.locations.values_mut().flat_map(|l| l.values_mut()) {
+            costs
+                .make_summary_with(cost_model)
+                .map_err(|error| anyhow!("Failed calculating summary events: {error}"))?;
+        }
         Ok(())
     }
 
-    // Convert to stacks string format for this `EventType`
+    // Convert to stacks string format for this `EventType` applying the rules of the `filter`
+    //
+    // If the locations within the functions were recorded, the stack of a function is followed by
+    // the stacks with the locations as innermost frames. If the cost of the stack of the function
+    // is lower than the costs of its locations, the cost of the stack is split between the
+    // locations in proportion to their costs.
     //
     // # Errors
     //
     // If the event type was not present in the stacks
-    pub fn to_stack_format(&self, event_kind: &EventKind) -> Result<Vec<String>> {
+    pub fn to_stack_format(
+        &self,
+        event_kind: &EventKind,
+        filter: &StackFilter,
+    ) -> Result<Vec<String>> {
+        let mut stacks = vec![];
+        for (ids, cost) in self.to_stacks(event_kind)? {
+            let frames = ids
+                .iter()
+                .map(|id| (id.func.as_str(), frame_name(id)))
+                .collect::<Vec<(&str, String)>>();
+
+            let mut leaves = vec![];
+            let mut remaining = cost;
+            if let Some((id, locations)) = ids
+                .last()
+                .and_then(|id| self.0.locations.get(*id).map(|l| (id, l)))
+            {
+                let location_costs = locations
+                    .iter()
+                    .filter_map(|(location, costs)| {
+                        costs
+                            .cost_by_kind(event_kind)
+                            .filter(|cost| *cost > 0)
+                            .map(|cost| (location, cost))
+                    })
+                    .collect::<Vec<(&Location, u64)>>();
+                let (mut shares, rest) = split_cost(location_costs, remaining);
+                shares.sort_by(|(a, a_cost), (b, b_cost)| {
+                    b_cost.cmp(a_cost).then_with(|| a.cmp(b))
+                });
+                for (location, cost) in shares.into_iter().filter(|(_, cost)| *cost > 0) {
+                    let mut frames = frames.clone();
+                    frames.push((id.func.as_str(), location_name(location)));
+                    leaves.push((frames, cost));
+                }
+                remaining = rest;
+            }
+
+            stacks.push((frames, remaining));
+            stacks.append(&mut leaves);
+        }
+
+        Ok(filter.apply(stacks))
+    }
+
+    /// Extract the stacks with the frames from the outermost to the innermost function and their
+    /// exclusive cost of this `EventKind`
+    ///
+    /// # Errors
+    ///
+    /// If the event type was not present in the stacks
+    pub fn to_stacks(&self, event_kind: &EventKind) -> Result<Vec<(Vec<&Id>, u64)>> {
         if self.0.map.is_empty() {
             return Ok(vec![]);
         }
@@ -65,18 +131,14 @@ impl FlamegraphMap {
                 .ok_or_else(|| {
                     anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
                 })?
+        } else if let Some((_, value)) = self.0.map.iter().find(|(k, _)| k.func == "main") {
+            value.costs.cost_by_kind(event_kind).ok_or_else(|| {
+                anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
+            })?
         } else {
-            self.0
-                .map
-                .iter()
-                .find(|(k, _)| k.func == "main")
-                .expect("'main' function must be present in callgrind output")
-                .1
-                .costs
-                .cost_by_kind(event_kind)
-                .ok_or_else(|| {
-                    anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
-                })?
+            // Threads other than the main thread don't run through `main`, so all functions are
+            // included
+            u64::MAX
         };
 
         let mut heap = BinaryHeap::new();
@@ -85,65 +147,21 @@ impl FlamegraphMap {
                 anyhow!("Failed creating flamegraph stack: Missing event type '{event_kind}'")
             })?;
             if cost <= reference_cost {
-                let mut source = String::new();
-                if let Some(file) = &id.file {
-                    match file {
-                        SourcePath::Unknown => write!(source, "{}", id.func).unwrap(),
-                        SourcePath::Rust(path)
-                        | SourcePath::Relative(path)
-                        | SourcePath::Absolute(path) => {
-                            write!(source, "{}:{}", path.display(), id.func).unwrap();
-                        }
-                    }
-                } else {
-                    write!(source, "{}", id.func).unwrap();
-                };
-                if let Some(path) = &id.obj {
-                    match path {
-                        SourcePath::Unknown => {}
-                        SourcePath::Rust(path)
-                        | SourcePath::Relative(path)
-                        | SourcePath::Absolute(path) => {
-                            write!(source, " [{}]", path.display()).unwrap();
-                        }
-                    }
-                }
-                heap.push(HeapElem { source, cost });
+                heap.push(HeapElem {
+                    source: frame_name(id),
+                    cost,
+                    id,
+                });
             }
         }
 
-        let mut stacks: Vec<String> = vec![];
-        let len = heap.len();
-        if len > 1 {
-            for window in heap.into_sorted_vec().windows(2) {
-                // There is only the slice size of 2 possible due to the window size of 2
-                if let [h1, h2] = window {
-                    let stack = if let Some(last) = stacks.last() {
-                        // This unwrap is safe since the space must be present due to stack format
-                        let (split, _) = last.rsplit_once(' ').unwrap();
-                        format!("{split};{} {}", h1.source, h1.cost - h2.cost)
-                    } else {
-                        format!("{} {}", h1.source, h1.cost - h2.cost)
-                    };
-
-                    stacks.push(stack);
-
-                    // The last window needs to push the last element too
-                    if stacks.len() == len - 1 {
-                        // This unwrap is safe since we have a last element the moment we enter the
-                        // `if` statement
-                        let last = stacks.last().unwrap();
-                        let (split, _) = last.rsplit_once(' ').unwrap();
-
-                        let stack = format!("{split};{} {}", h2.source, h2.cost);
-                        stacks.push(stack);
-                    }
-                }
-            }
-        } else {
-            // This unwrap is safe since heap.len() == 1 here
-            let elem = heap.pop().unwrap();
-            stacks.push(format!("{} {}", elem.source, elem.cost));
+        let elems = heap.into_sorted_vec();
+        let mut stacks = Vec::with_capacity(elems.len());
+        for (index, elem) in elems.iter().enumerate() {
+            let cost = elems
+                .get(index + 1)
+                .map_or(elem.cost, |next| elem.cost - next.cost);
+            stacks.push((elems[..=index].iter().map(|e| e.id).collect(), cost));
         }
         Ok(stacks)
     }
@@ -157,8 +175,16 @@ impl FlamegraphParser {
         Self {
             sentinel: sentinel.cloned(),
             project_root: project_root.into(),
+            granularity: FlamegraphGranularity::Function,
         }
     }
+
+    /// Set the [`FlamegraphGranularity`] of the innermost frames of the stacks
+    #[must_use]
+    pub fn with_granularity(mut self, granularity: FlamegraphGranularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
 }
 
 impl Parser for FlamegraphParser {
@@ -170,13 +196,31 @@ impl Parser for FlamegraphParser {
         let parser = HashMapParser {
             project_root: self.project_root.clone(),
             sentinel: self.sentinel.clone(),
+            granularity: self.granularity,
         };
 
-        parser.parse(output_path).map(FlamegraphMap)
+        // The flamegraphs show the costs of all dumps and if not a single thread is selected, of
+        // all threads of a callgrind run
+        let mut paths = vec![];
+        for part in output_path.to_parts() {
+            let threads = part.to_threads();
+            if threads.is_empty() {
+                paths.push(part);
+            } else {
+                paths.extend(threads);
+            }
+        }
+        let mut paths = paths.into_iter();
+        let mut map = parser.parse(&paths.next().expect("At least one part should be present"))?;
+        for path in paths {
+            map.add(parser.parse(&path)?);
+        }
+
+        Ok(FlamegraphMap(map))
     }
 }
 
-impl Ord for HeapElem {
+impl Ord for HeapElem<'_> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.cost
             .cmp(&other.cost)
@@ -185,8 +229,94 @@ impl Ord for HeapElem {
     }
 }
 
-impl PartialOrd for HeapElem {
+impl PartialOrd for HeapElem<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
+
+/// Split the `cost` of a stack between the `locations` with their costs
+///
+/// Return the share of each location and the cost which is left for the stack itself. If the
+/// `cost` doesn't cover the costs of all locations, it is split in proportion to the costs of the
+/// locations. The rounding remainder goes to the locations with the largest fractional parts, so
+/// the shares always add up to the `cost`.
+fn split_cost(locations: Vec<(&Location, u64)>, cost: u64) -> (Vec<(&Location, u64)>, u64) {
+    let total = locations.iter().map(|(_, c)| u128::from(*c)).sum::<u128>();
+    if total <= u128::from(cost) {
+        return (locations, cost - u64::try_from(total).expect("Total should fit into u64"));
+    }
+
+    let mut shares = locations
+        .into_iter()
+        .map(|(location, c)| {
+            let product = u128::from(c) * u128::from(cost);
+            let share = u64::try_from(product / total).expect("Share should fit into u64");
+            (location, share, product % total)
+        })
+        .collect::<Vec<(&Location, u64, u128)>>();
+    let mut rest = cost - shares.iter().map(|(_, share, _)| share).sum::<u64>();
+
+    shares.sort_by(|(a, _, a_rem), (b, _, b_rem)| b_rem.cmp(a_rem).then_with(|| a.cmp(b)));
+    for (_, share, _) in &mut shares {
+        if rest == 0 {
+            break;
+        }
+        *share += 1;
+        rest -= 1;
+    }
+
+    (
+        shares
+            .into_iter()
+            .map(|(location, share, _)| (location, share))
+            .collect(),
+        0,
+    )
+}
+
+/// Return the name of the frame of the function with this [`Id`] in the flamegraph stacks
+///
+/// The name is the function name prefixed with the path of the source file and suffixed with the
+/// path of the object file in brackets if they are known.
+fn frame_name(id: &Id) -> String {
+    let mut source = String::new();
+    if let Some(file) = &id.file {
+        match file {
+            SourcePath::Unknown => write!(source, "{}", id.func).unwrap(),
+            SourcePath::Rust(path) | SourcePath::Relative(path) | SourcePath::Absolute(path) => {
+                write!(source, "{}:{}", path.display(), id.func).unwrap();
+            }
+        }
+    } else {
+        write!(source, "{}", id.func).unwrap();
+    };
+    if let Some(path) = &id.obj {
+        match path {
+            SourcePath::Unknown => {}
+            SourcePath::Rust(path) | SourcePath::Relative(path) | SourcePath::Absolute(path) => {
+                write!(source, " [{}]", path.display()).unwrap();
+            }
+        }
+    }
+    source
+}
+
+/// Return the name of the innermost frame of the [`Location`] in the flamegraph stacks
+///
+/// The name is the path of the source file suffixed with the line if present. Inlined code is
+/// prefixed with `[inlined]`.
+fn location_name(location: &Location) -> String {
+    let mut name = String::new();
+    if location.is_inlined {
+        name.push_str("[inlined] ");
+    }
+    match location.file.as_path() {
+        Some(path) => write!(name, "{}", path.display()).unwrap(),
+        None => name.push_str("???"),
+    }
+    if let Some(line) = location.line {
+        write!(name, ":{line}").unwrap();
+    }
+    name
+}
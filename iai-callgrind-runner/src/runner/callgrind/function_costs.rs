@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use log::debug;
+
+use super::hashmap_parser::{CallgrindMap, HashMapParser, Id, Value};
+use super::model::Costs;
+use crate::api::{self, EventKind, FlamegraphGranularity};
+use crate::runner::costs::Summarize;
+use crate::runner::summary::{CostsSummary, FunctionCostsSummary};
+use crate::runner::tool::{Parser, ToolOutputPath};
+
+/// The configuration of the per-function cost table of callgrind runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCostsConfig {
+    /// The number of functions with the highest costs to show
+    pub top: usize,
+    /// The [`EventKind`]s to show. The functions are sorted by the first one.
+    pub event_kinds: Vec<EventKind>,
+}
+
+impl FunctionCostsConfig {
+    /// Summarize the `top` functions of the `new` map and compare them with the `old` map
+    ///
+    /// The functions are sorted descending by the exclusive and then by the inclusive cost of the
+    /// first [`EventKind`] of this configuration. Functions with the same costs are sorted by
+    /// name.
+    pub fn summarize(
+        &self,
+        new: &CallgrindMap,
+        old: Option<&CallgrindMap>,
+    ) -> Vec<FunctionCostsSummary> {
+        let sort_kind = self.event_kinds.first().copied().unwrap_or(EventKind::Ir);
+        let mut functions = new.iter().collect::<Vec<(&Id, &Value)>>();
+        functions.sort_by_cached_key(|(id, value)| {
+            (
+                Reverse(cost_of(&value.exclusive_costs, sort_kind)),
+                Reverse(cost_of(&value.costs, sort_kind)),
+                id.func.clone(),
+                id.file.clone(),
+                id.obj.clone(),
+            )
+        });
+
+        functions
+            .into_iter()
+            .take(self.top)
+            .map(|(id, value)| {
+                let old_value = old.and_then(|old| old.get_key_value(id)).map(|(_, v)| v);
+                let new_exclusive = self.filter_costs(&value.exclusive_costs);
+                let new_inclusive = self.filter_costs(&value.costs);
+                let old_exclusive = old_value.map(|v| self.filter_costs(&v.exclusive_costs));
+                let old_inclusive = old_value.map(|v| self.filter_costs(&v.costs));
+                FunctionCostsSummary {
+                    function: id.func.clone(),
+                    file: id.file.as_ref().and_then(|f| f.as_path()).map(PathBuf::from),
+                    object: id.obj.as_ref().and_then(|o| o.as_path()).map(PathBuf::from),
+                    exclusive: CostsSummary::new(&new_exclusive, old_exclusive.as_ref()),
+                    inclusive: CostsSummary::new(&new_inclusive, old_inclusive.as_ref()),
+                }
+            })
+            .collect()
+    }
+
+    /// Return the costs of only the configured [`EventKind`]s in the configured order
+    fn filter_costs(&self, costs: &Costs) -> Costs {
+        let mut costs = Cow::Borrowed(costs);
+        EventKind::summarize(&mut costs);
+        Costs::with_event_kinds(
+            self.event_kinds
+                .iter()
+                .filter_map(|kind| costs.cost_by_kind(kind).map(|cost| (*kind, cost))),
+        )
+    }
+}
+
+impl Default for FunctionCostsConfig {
+    fn default() -> Self {
+        Self {
+            top: 10,
+            event_kinds: vec![EventKind::Ir],
+        }
+    }
+}
+
+impl From<api::FunctionCostsConfig> for FunctionCostsConfig {
+    fn from(value: api::FunctionCostsConfig) -> Self {
+        let api::FunctionCostsConfig { top, event_kinds } = value;
+        Self {
+            top: top.unwrap_or(10),
+            event_kinds: event_kinds
+                .filter(|e| !e.is_empty())
+                .unwrap_or_else(|| vec![EventKind::Ir]),
+        }
+    }
+}
+
+/// Parse the per-function costs of the callgrind output files at `output_path`
+pub fn parse(output_path: &ToolOutputPath, project_root: &Path) -> Result<CallgrindMap> {
+    debug!("Parsing function costs from file '{}'", output_path);
+
+    HashMapParser {
+        sentinel: None,
+        project_root: project_root.to_owned(),
+        granularity: FlamegraphGranularity::Function,
+    }
+    .parse(output_path)
+}
+
+/// The cost of the `event_kind` of a function, calculating the derived event kinds if necessary
+pub fn cost_of(costs: &Costs, event_kind: EventKind) -> u64 {
+    costs.cost_by_kind(&event_kind).unwrap_or_else(|| {
+        let mut costs = Cow::Borrowed(costs);
+        EventKind::summarize(&mut costs);
+        costs.cost_by_kind(&event_kind).unwrap_or(0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::runner::callgrind::hashmap_parser::SourcePath;
+
+    fn id(func: &str) -> Id {
+        Id {
+            obj: None,
+            file: Some(SourcePath::Relative(PathBuf::from("src/lib.rs"))),
+            func: func.to_owned(),
+        }
+    }
+
+    fn value(inclusive: u64, exclusive: u64) -> Value {
+        Value {
+            costs: Costs::with_event_kinds([(EventKind::Ir, inclusive)]),
+            exclusive_costs: Costs::with_event_kinds([(EventKind::Ir, exclusive)]),
+        }
+    }
+
+    fn map<T>(values: T) -> CallgrindMap
+    where
+        T: IntoIterator<Item = (Id, Value)>,
+    {
+        CallgrindMap {
+            map: values.into_iter().collect::<HashMap<Id, Value>>(),
+            sentinel: None,
+            sentinel_key: None,
+            calls: HashMap::new(),
+            locations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_function_costs_summarize_sorts_and_truncates() {
+        let config = FunctionCostsConfig {
+            top: 3,
+            event_kinds: vec![EventKind::Ir],
+        };
+        let new = map([
+            (id("main"), value(100, 10)),
+            (id("a"), value(50, 40)),
+            (id("b"), value(40, 40)),
+            (id("c"), value(10, 10)),
+        ]);
+
+        let actual = config
+            .summarize(&new, None)
+            .into_iter()
+            .map(|f| f.function)
+            .collect::<Vec<String>>();
+
+        assert_eq!(actual, vec!["a", "b", "main"]);
+    }
+
+    #[test]
+    fn test_function_costs_summarize_with_old() {
+        let config = FunctionCostsConfig::default();
+        let new = map([(id("main"), value(100, 20)), (id("new"), value(80, 80))]);
+        let old = map([(id("main"), value(50, 10)), (id("gone"), value(40, 40))]);
+
+        let actual = config.summarize(&new, Some(&old));
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].function, "new");
+        assert_eq!(actual[0].file, Some(PathBuf::from("src/lib.rs")));
+        assert_eq!(
+            actual[0].exclusive,
+            CostsSummary::new(&Costs::with_event_kinds([(EventKind::Ir, 80)]), None)
+        );
+        assert_eq!(actual[1].function, "main");
+        assert_eq!(
+            actual[1].exclusive,
+            CostsSummary::new(
+                &Costs::with_event_kinds([(EventKind::Ir, 20)]),
+                Some(&Costs::with_event_kinds([(EventKind::Ir, 10)]))
+            )
+        );
+        assert_eq!(
+            actual[1].inclusive,
+            CostsSummary::new(
+                &Costs::with_event_kinds([(EventKind::Ir, 100)]),
+                Some(&Costs::with_event_kinds([(EventKind::Ir, 50)]))
+            )
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::collections::hash_map::Iter;
+use std::collections::hash_map::{Entry, Iter};
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::{Component, Path, PathBuf};
@@ -8,8 +8,10 @@ use anyhow::Result;
 use log::trace;
 use serde::{Deserialize, Serialize};
 
-use super::model::Costs;
-use super::parser::{parse_header, Sentinel};
+use super::model::{Costs, PositionType};
+use super::parser::{parse_header, parse_position, Sentinel};
+use super::CostModel;
+use crate::api::FlamegraphGranularity;
 use crate::error::Error;
 use crate::runner::tool::{Parser, ToolOutputPath};
 
@@ -18,6 +20,22 @@ pub struct CallgrindMap {
     pub map: HashMap<Id, Value>,
     pub sentinel: Option<Sentinel>,
     pub sentinel_key: Option<Id>,
+    /// The calls from the calling function (the first [`Id`]) to the called function
+    #[serde(default)]
+    pub calls: HashMap<(Id, Id), Call>,
+    /// The exclusive costs of the [`Location`]s within a function if the [`HashMapParser`] was
+    /// run with a [`FlamegraphGranularity`] other than [`FlamegraphGranularity::Function`]
+    #[serde(default)]
+    pub locations: HashMap<Id, HashMap<Location, Costs>>,
+}
+
+/// The calls of a function by another function summed up over all call sites
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Call {
+    /// The number of calls
+    pub count: u64,
+    /// The inclusive costs of the calls
+    pub costs: Costs,
 }
 
 #[derive(Debug, Default)]
@@ -43,6 +61,11 @@ struct CurrentId {
 pub struct HashMapParser {
     pub sentinel: Option<Sentinel>,
     pub project_root: PathBuf,
+    /// With a granularity other than [`FlamegraphGranularity::Function`], the code inlined from
+    /// other source files (`fi` and `fe` lines) is attributed to the function it was inlined into
+    /// and the exclusive costs are recorded per [`Location`]
+    #[serde(default)]
+    pub granularity: FlamegraphGranularity,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -52,6 +75,17 @@ pub struct Id {
     pub func: String,
 }
 
+/// The location of exclusive costs within a function
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+pub struct Location {
+    /// The source file which is the file of the inlined code if `is_inlined` is true
+    pub file: SourcePath,
+    /// The line in the source file if recorded with [`FlamegraphGranularity::Line`]
+    pub line: Option<u64>,
+    /// True if the code was inlined from another source file
+    pub is_inlined: bool,
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum SourcePath {
     Unknown,
@@ -62,7 +96,10 @@ pub enum SourcePath {
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Value {
+    /// The inclusive costs of the function
     pub costs: Costs,
+    /// The exclusive (self) costs of the function without the costs of called functions
+    pub exclusive_costs: Costs,
 }
 
 impl CallgrindMap {
@@ -77,6 +114,72 @@ impl CallgrindMap {
     pub fn get_key_value(&self, k: &Id) -> Option<(&Id, &Value)> {
         self.map.get_key_value(k)
     }
+
+    /// Add the costs of all functions of the `other` map to the costs of this map
+    ///
+    /// Both maps need to be created from callgrind output files with the same events, like the
+    /// output files of the single dumps of a callgrind run.
+    pub fn add(&mut self, other: CallgrindMap) {
+        for (id, value) in other.map {
+            match self.map.entry(id) {
+                Entry::Occupied(mut entry) => {
+                    let entry = entry.get_mut();
+                    entry.costs.add(&value.costs);
+                    entry.exclusive_costs.add(&value.exclusive_costs);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+        for (key, call) in other.calls {
+            match self.calls.entry(key) {
+                Entry::Occupied(mut entry) => entry.get_mut().add(&call),
+                Entry::Vacant(entry) => {
+                    entry.insert(call);
+                }
+            }
+        }
+        for (id, locations) in other.locations {
+            let entry = self.locations.entry(id).or_default();
+            for (location, costs) in locations {
+                entry
+                    .entry(location)
+                    .and_modify(|c| c.add(&costs))
+                    .or_insert(costs);
+            }
+        }
+        if self.sentinel_key.is_none() {
+            self.sentinel_key = other.sentinel_key;
+        }
+    }
+
+    /// Calculate the derived events of the costs of all functions and calls with the `cost_model`
+    ///
+    /// # Errors
+    ///
+    /// If the derived events could not be calculated
+    pub fn make_summary(&mut self, cost_model: &CostModel) -> Result<()> {
+        for value in self.map.values_mut() {
+            cost_model.apply(&mut value.costs)?;
+            cost_model.apply(&mut value.exclusive_costs)?;
+        }
+        for call in self.calls.values_mut() {
+            cost_model.apply(&mut call.costs)?;
+        }
+        for costs in self.locations.values_mut().flat_map(HashMap::values_mut) {
+            cost_model.apply(costs)?;
+        }
+        Ok(())
+    }
+}
+
+impl Call {
+    /// Add the number of calls and the costs of the `other` call to this call
+    pub fn add(&mut self, other: &Call) {
+        self.count += other.count;
+        self.costs.add(&other.costs);
+    }
 }
 
 impl<'a> IntoIterator for &'a CallgrindMap {
@@ -125,9 +228,16 @@ impl Parser for HashMapParser {
 
         let mut current_id = CurrentId::default();
         let mut cfn_record = None;
+        let mut inline_file: Option<SourcePath> = None;
+        let line_index = (self.granularity != FlamegraphGranularity::Function)
+            .then(|| config.positions_prototype.index_of(PositionType::Line))
+            .flatten();
+        let mut last_line = 0;
 
         let mut cfn_totals = HashMap::<Id, Value>::new();
         let mut fn_totals = HashMap::<Id, Value>::new();
+        let mut calls = HashMap::<(Id, Id), Call>::new();
+        let mut locations = HashMap::<Id, HashMap<Location, Costs>>::new();
 
         let mut sentinel_key = None;
 
@@ -158,9 +268,11 @@ impl Parser for HashMapParser {
                 }
                 Some(("fl", file)) => {
                     current_id.file = Some(make_path(&self.project_root, file));
+                    inline_file = None;
                 }
                 Some(("fn", func)) => {
                     current_id.func = Some(func.to_owned());
+                    inline_file = None;
 
                     if self
                         .sentinel
@@ -172,7 +284,13 @@ impl Parser for HashMapParser {
                     }
                 }
                 Some(("fi" | "fe", inline)) => {
-                    current_id.file = Some(make_path(&self.project_root, inline));
+                    let path = make_path(&self.project_root, inline);
+                    if self.granularity == FlamegraphGranularity::Function {
+                        current_id.file = Some(path);
+                    } else {
+                        // A `fe` line with the file of the function ends the inlined code
+                        inline_file = (current_id.file.as_ref() != Some(&path)).then_some(path);
+                    }
                 }
                 Some(("cob", cob)) => {
                     let record = cfn_record.get_or_insert(CfnRecord::default());
@@ -199,27 +317,71 @@ impl Parser for HashMapParser {
                         .sum();
                 }
                 None if line.starts_with(|c: char| c.is_ascii_digit()) => {
+                    if let Some(line_index) = line_index {
+                        last_line = line
+                            .split_whitespace()
+                            .nth(line_index)
+                            .map_or(last_line, |pos| parse_position(pos, last_line));
+                    }
                     let mut costs = config.costs_prototype.clone();
                     costs.add_iter_str(
                         line.split_whitespace()
                             .skip(config.positions_prototype.len()),
                     );
 
+                    let id: Id = current_id.try_into().expect("A valid id");
+
+                    // Cost lines of calls don't contribute to the exclusive costs of a function
+                    let is_call = cfn_record.is_some();
+                    if !is_call {
+                        if let Some(location) =
+                            self.location(&id, inline_file.as_ref(), line_index.map(|_| last_line))
+                        {
+                            locations
+                                .entry(id.clone())
+                                .or_default()
+                                .entry(location)
+                                .and_modify(|value| value.add(&costs))
+                                .or_insert_with(|| costs.clone());
+                        }
+                    }
                     if let Some(cfn_record) = cfn_record.take() {
+                        let cfn_id = cfn_record.id.expect("cfn record id must be present");
+                        let call = Call {
+                            count: cfn_record.calls,
+                            costs: costs.clone(),
+                        };
+                        calls
+                            .entry((id.clone(), cfn_id.clone()))
+                            .and_modify(|value| value.add(&call))
+                            .or_insert(call);
                         cfn_totals
-                            .entry(cfn_record.id.expect("cfn record id must be present"))
+                            .entry(cfn_id)
                             .and_modify(|value| value.costs.add(&costs))
                             .or_insert(Value {
                                 costs: costs.clone(),
+                                exclusive_costs: config.costs_prototype.clone(),
                             });
                     }
 
-                    let id = current_id.try_into().expect("A valid id");
-                    match fn_totals.get_mut(&id) {
-                        Some(value) => value.costs.add(&costs),
-                        None => {
-                            fn_totals.insert(id.clone(), Value { costs });
+                    if let Some(value) = fn_totals.get_mut(&id) {
+                        value.costs.add(&costs);
+                        if !is_call {
+                            value.exclusive_costs.add(&costs);
                         }
+                    } else {
+                        let exclusive_costs = if is_call {
+                            config.costs_prototype.clone()
+                        } else {
+                            costs.clone()
+                        };
+                        fn_totals.insert(
+                            id.clone(),
+                            Value {
+                                costs,
+                                exclusive_costs,
+                            },
+                        );
                     }
                     current_id = id.into();
                 }
@@ -233,8 +395,11 @@ impl Parser for HashMapParser {
             }
         }
 
-        // Correct inclusive totals
-        for (key, value) in cfn_totals {
+        // Correct inclusive totals but keep the exclusive totals
+        for (key, mut value) in cfn_totals {
+            if let Some(old) = fn_totals.remove(&key) {
+                value.exclusive_costs = old.exclusive_costs;
+            }
             fn_totals.insert(key, value);
         }
 
@@ -242,10 +407,53 @@ impl Parser for HashMapParser {
             map: fn_totals,
             sentinel: self.sentinel.clone(),
             sentinel_key,
+            calls,
+            locations,
         })
     }
 }
 
+impl HashMapParser {
+    /// Return the [`Location`] of exclusive costs of the function with the `id` if it is recorded
+    ///
+    /// With [`FlamegraphGranularity::Inline`], only the locations of inlined code are recorded.
+    fn location(
+        &self,
+        id: &Id,
+        inline_file: Option<&SourcePath>,
+        line: Option<u64>,
+    ) -> Option<Location> {
+        match (self.granularity, inline_file) {
+            (FlamegraphGranularity::Function, _) | (FlamegraphGranularity::Inline, None) => None,
+            (FlamegraphGranularity::Inline, Some(file)) => Some(Location {
+                file: file.clone(),
+                line: None,
+                is_inlined: true,
+            }),
+            (FlamegraphGranularity::Line, file) => Some(Location {
+                file: file
+                    .or(id.file.as_ref())
+                    .cloned()
+                    .unwrap_or(SourcePath::Unknown),
+                line,
+                is_inlined: file.is_some(),
+            }),
+        }
+    }
+}
+
+impl SourcePath {
+    /// Return the path if it is known
+    pub fn as_path(&self) -> Option<&Path> {
+        match self {
+            SourcePath::Unknown => None,
+            SourcePath::Rust(path) | SourcePath::Relative(path) | SourcePath::Absolute(path) => {
+                Some(path)
+            }
+        }
+    }
+}
+
 impl Ord for SourcePath {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
@@ -268,7 +476,8 @@ impl PartialOrd for SourcePath {
     }
 }
 
-fn make_path(root: &Path, source: &str) -> SourcePath {
+/// Create a [`SourcePath`] from the path of a source file or object in a callgrind output file
+pub fn make_path(root: &Path, source: &str) -> SourcePath {
     if source == "???" {
         SourcePath::Unknown
     } else {
@@ -1,17 +1,31 @@
+pub mod annotate;
 pub mod args;
+pub mod cache;
+pub mod call_graph;
+pub mod collect;
+pub mod dumps;
 pub mod flamegraph;
 pub mod flamegraph_parser;
+pub mod function_costs;
 pub mod hashmap_parser;
 pub mod model;
 pub mod parser;
+pub mod pprof;
+pub mod processes;
 pub mod sentinel_parser;
+pub mod speedscope;
+pub mod stack_filter;
 pub mod summary_parser;
 
+use anyhow::Result;
 use colored::Colorize;
+use glob::Pattern;
+use log::warn;
 
+use self::hashmap_parser::CallgrindMap;
 use self::model::Costs;
-use super::summary::{CallgrindRegressionSummary, CostsSummary};
-use crate::api::{self, EventKind};
+use super::summary::{CallgrindRegressionSummary, CallgrindThreadSummary, CostsSummary};
+use crate::api::{self, CostModelPreset, EventKind};
 use crate::util::to_string_signed_short;
 
 #[derive(Clone, Debug)]
@@ -23,16 +37,39 @@ pub struct CacheSummary {
     cycles: u64,
 }
 
+/// The weights of the cost model with which the [`EventKind::EstimatedCycles`] are calculated
+///
+/// The estimated cycles are the weighted sum of the L1 hits, LL hits and RAM hits and, if
+/// callgrind runs with `--branch-sim=yes`, the mispredicted conditional and indirect branches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    pub l1_hits: u64,
+    pub ll_hits: u64,
+    pub ram_hits: u64,
+    pub branch_cond_misses: u64,
+    pub branch_indirect_misses: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct RegressionConfig {
     pub limits: Vec<(EventKind, f64)>,
+    /// The limits for the inclusive costs of the functions matching the glob pattern
+    pub function_limits: Vec<(Pattern, EventKind, f64)>,
+    /// The limits for the costs of the threads with the thread index
+    pub thread_limits: Vec<(usize, EventKind, f64)>,
+    /// The glob patterns of the commands of the child processes which are checked for regressions
+    pub child_filters: Vec<Pattern>,
     pub fail_fast: bool,
 }
 
-impl TryFrom<&Costs> for CacheSummary {
-    type Error = anyhow::Error;
-
-    fn try_from(value: &Costs) -> std::result::Result<Self, Self::Error> {
+impl CacheSummary {
+    /// Calculate the derived events of the `value` with the weights of the [`CostModel`]
+    ///
+    /// # Errors
+    ///
+    /// If the necessary cache simulation events (when running callgrind with --cache-sim) were not
+    /// present.
+    pub fn new(value: &Costs, cost_model: &CostModel) -> Result<Self> {
         use EventKind::*;
         //         0   1  2    3    4    5    6    7    8
         // events: Ir Dr Dw I1mr D1mr D1mw ILmr DLmr DLmw
@@ -57,8 +94,23 @@ impl TryFrom<&Costs> for CacheSummary {
         let total_memory_rw = instructions + total_data_cache_reads + total_data_cache_writes;
         let l1_hits = total_memory_rw - ram_hits - l3_hits;
 
-        // Uses Itamar Turner-Trauring's formula from https://pythonspeed.com/articles/consistent-benchmarking-in-ci/
-        let cycles = l1_hits + (5 * l3_hits) + (35 * ram_hits);
+        // The branch mispredictions are only present with --branch-sim=yes
+        let branch_misses = [
+            (Bcm, cost_model.branch_cond_misses),
+            (Bim, cost_model.branch_indirect_misses),
+        ]
+        .iter()
+        .filter_map(|(kind, weight)| {
+            value
+                .cost_by_kind(kind)
+                .map(|cost| cost.saturating_mul(*weight))
+        })
+        .fold(0, u64::saturating_add);
+        let cycles = l1_hits
+            .saturating_mul(cost_model.l1_hits)
+            .saturating_add(l3_hits.saturating_mul(cost_model.ll_hits))
+            .saturating_add(ram_hits.saturating_mul(cost_model.ram_hits))
+            .saturating_add(branch_misses);
 
         Ok(Self {
             l1_hits,
@@ -70,18 +122,98 @@ impl TryFrom<&Costs> for CacheSummary {
     }
 }
 
+impl TryFrom<&Costs> for CacheSummary {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Costs) -> std::result::Result<Self, Self::Error> {
+        Self::new(value, &CostModel::default())
+    }
+}
+
+impl CostModel {
+    /// Calculate the derived events of the `costs` in-place if they can be summarized
+    ///
+    /// # Errors
+    ///
+    /// If the derived events could not be calculated
+    pub fn apply(&self, costs: &mut Costs) -> Result<()> {
+        if costs.can_summarize() {
+            costs.make_summary_with(self)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for CostModel {
+    /// The default cost model is Itamar Turner-Trauring's formula from
+    /// <https://pythonspeed.com/articles/consistent-benchmarking-in-ci/>
+    fn default() -> Self {
+        Self::from(CostModelPreset::Classic)
+    }
+}
+
+impl From<CostModelPreset> for CostModel {
+    fn from(value: CostModelPreset) -> Self {
+        // The weights of the L1 hits, LL hits, RAM hits and branch mispredictions
+        let (l1, ll, ram, branch) = match value {
+            CostModelPreset::Classic => (1, 5, 35, 0),
+            CostModelPreset::Modern => (1, 12, 100, 15),
+            CostModelPreset::Embedded => (1, 4, 20, 4),
+        };
+        Self {
+            l1_hits: l1,
+            ll_hits: ll,
+            ram_hits: ram,
+            branch_cond_misses: branch,
+            branch_indirect_misses: branch,
+        }
+    }
+}
+
+impl From<api::CostModel> for CostModel {
+    fn from(value: api::CostModel) -> Self {
+        let api::CostModel {
+            preset,
+            l1_hits,
+            ll_hits,
+            ram_hits,
+            branch_cond_misses,
+            branch_indirect_misses,
+        } = value;
+        let base = preset.map_or_else(CostModel::default, Into::into);
+        Self {
+            l1_hits: l1_hits.unwrap_or(base.l1_hits),
+            ll_hits: ll_hits.unwrap_or(base.ll_hits),
+            ram_hits: ram_hits.unwrap_or(base.ram_hits),
+            branch_cond_misses: branch_cond_misses.unwrap_or(base.branch_cond_misses),
+            branch_indirect_misses: branch_indirect_misses.unwrap_or(base.branch_indirect_misses),
+        }
+    }
+}
+
 impl RegressionConfig {
     /// Check regression of the [`Costs`] for the configured [`EventKind`]s and print it
     ///
     /// If the old `Costs` is None then no regression checks are performed and this method returns
-    /// [`Ok`].
+    /// [`Ok`]. The per-function costs (the `new` and `old` [`CallgrindMap`]) are only checked if
+    /// present. The same applies to the costs of the `threads`.
     ///
     /// # Errors
     ///
     /// Returns an [`anyhow::Error`] with the only source [`crate::error::Error::RegressionError`]
     /// if a regression error occurred
-    pub fn check_and_print(&self, costs_summary: &CostsSummary) -> Vec<CallgrindRegressionSummary> {
-        let regression_summaries = self.check(costs_summary);
+    pub fn check_and_print(
+        &self,
+        costs_summary: &CostsSummary,
+        functions: Option<(&CallgrindMap, &CallgrindMap)>,
+        threads: &[CallgrindThreadSummary],
+    ) -> Vec<CallgrindRegressionSummary> {
+        let mut regression_summaries = self.check(costs_summary);
+        if let Some((new, old)) = functions {
+            regression_summaries.extend(self.check_functions(new, old));
+        }
+        regression_summaries.extend(self.check_threads(threads));
 
         for CallgrindRegressionSummary {
             event_kind,
@@ -89,13 +221,20 @@ impl RegressionConfig {
             old,
             diff_pct,
             limit,
+            function,
+            thread,
         } in &regression_summaries
         {
+            let event = match (function, thread) {
+                (Some(function), _) => format!("{event_kind} of '{function}'"),
+                (None, Some(thread)) => format!("{event_kind} of thread #{thread}"),
+                (None, None) => event_kind.to_string(),
+            };
             if limit.is_sign_positive() {
                 eprintln!(
                     "Performance has {0}: {1} ({new} > {old}) regressed by {2:>+6} (>{3:>+6})",
                     "regressed".bold().bright_red(),
-                    event_kind.to_string().bold(),
+                    event.bold(),
                     format!("{}%", to_string_signed_short(*diff_pct))
                         .bold()
                         .bright_red(),
@@ -105,7 +244,7 @@ impl RegressionConfig {
                 eprintln!(
                     "Performance has {0}: {1} ({new} < {old}) regressed by {2:>+6} (<{3:>+6})",
                     "regressed".bold().bright_red(),
-                    event_kind.to_string().bold(),
+                    event.bold(),
                     format!("{}%", to_string_signed_short(*diff_pct))
                         .bold()
                         .bright_red(),
@@ -118,52 +257,120 @@ impl RegressionConfig {
     }
 
     pub fn check(&self, costs_summary: &CostsSummary) -> Vec<CallgrindRegressionSummary> {
+        self.limits
+            .iter()
+            .filter_map(|(event_kind, limit)| check_limit(costs_summary, *event_kind, *limit))
+            .collect()
+    }
+
+    /// Check the inclusive costs of all functions matching the configured function limits
+    ///
+    /// Only functions present in the `new` and `old` [`CallgrindMap`] are checked. The regressions
+    /// are sorted by function name for each limit.
+    pub fn check_functions(
+        &self,
+        new: &CallgrindMap,
+        old: &CallgrindMap,
+    ) -> Vec<CallgrindRegressionSummary> {
+        let mut regressions = vec![];
+        for (pattern, event_kind, limit) in &self.function_limits {
+            let mut functions = new
+                .iter()
+                .filter(|(id, _)| pattern.matches(&id.func))
+                .filter_map(|(id, new)| old.get_key_value(id).map(|(_, old)| (id, new, old)))
+                .collect::<Vec<_>>();
+            functions.sort_by(|(a, ..), (b, ..)| {
+                a.func
+                    .cmp(&b.func)
+                    .then_with(|| a.file.cmp(&b.file))
+                    .then_with(|| a.obj.cmp(&b.obj))
+            });
+
+            for (id, new, old) in functions {
+                let costs_summary = CostsSummary::new(&new.costs, Some(&old.costs));
+                if let Some(mut regression) = check_limit(&costs_summary, *event_kind, *limit) {
+                    regression.function = Some(id.func.clone());
+                    regressions.push(regression);
+                }
+            }
+        }
+        regressions
+    }
+
+    /// Check the costs of the `threads` for the configured thread limits
+    ///
+    /// Limits for a thread index which is not present in the `threads` are ignored.
+    pub fn check_threads(
+        &self,
+        threads: &[CallgrindThreadSummary],
+    ) -> Vec<CallgrindRegressionSummary> {
         let mut regressions = vec![];
-        for (event_kind, limit) in &self.limits {
-            if let Some((new_cost, old_cost, pct)) = costs_summary
-                .diff_by_kind(event_kind)
-                .filter(|d| d.diff_pct.is_some())
-                // These unwraps are safe since if diff_pct is present new and old are also present
-                .map(|d| (d.new.unwrap(), d.old.unwrap(), d.diff_pct.unwrap()))
-            {
-                if limit.is_sign_positive() {
-                    if pct > *limit {
-                        let summary = CallgrindRegressionSummary {
-                            event_kind: *event_kind,
-                            new: new_cost,
-                            old: old_cost,
-                            diff_pct: pct,
-                            limit: *limit,
-                        };
-                        regressions.push(summary);
-                    }
-                } else if pct < *limit {
-                    let summary = CallgrindRegressionSummary {
-                        event_kind: *event_kind,
-                        new: new_cost,
-                        old: old_cost,
-                        diff_pct: pct,
-                        limit: *limit,
-                    };
-                    regressions.push(summary);
-                } else {
-                    // no regression
+        for (index, event_kind, limit) in &self.thread_limits {
+            if let Some(thread) = threads.iter().find(|t| t.thread == *index) {
+                if let Some(mut regression) = check_limit(&thread.events, *event_kind, *limit) {
+                    regression.thread = Some(*index);
+                    regressions.push(regression);
                 }
             }
         }
         regressions
     }
+
+    /// Return true if the child process with the `command` is checked for regressions
+    ///
+    /// All child processes are checked if there are no child filters.
+    pub fn is_checked_child(&self, command: &str) -> bool {
+        self.child_filters.is_empty()
+            || self
+                .child_filters
+                .iter()
+                .any(|pattern| pattern.matches(command))
+    }
+
+    /// Return true if there are limits for functions which require the per-function costs
+    pub fn has_function_limits(&self) -> bool {
+        !self.function_limits.is_empty()
+    }
 }
 
 impl From<api::RegressionConfig> for RegressionConfig {
     fn from(value: api::RegressionConfig) -> Self {
-        let api::RegressionConfig { limits, fail_fast } = value;
+        let api::RegressionConfig {
+            limits,
+            function_limits,
+            thread_limits,
+            child_filters,
+            fail_fast,
+        } = value;
+        let function_limits = function_limits
+            .into_iter()
+            .filter_map(|(pattern, event_kind, limit)| match Pattern::new(&pattern) {
+                Ok(pattern) => Some((pattern, event_kind, limit)),
+                Err(error) => {
+                    warn!("Ignoring limit for invalid function pattern '{pattern}': {error}");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let child_filters = child_filters
+            .into_iter()
+            .filter_map(|pattern| match Pattern::new(&pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(error) => {
+                    warn!("Ignoring invalid child filter '{pattern}': {error}");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
         RegressionConfig {
-            limits: if limits.is_empty() {
+            limits: if limits.is_empty() && function_limits.is_empty() && thread_limits.is_empty() {
                 vec![(EventKind::Ir, 10f64)]
             } else {
                 limits
             },
+            function_limits,
+            thread_limits,
+            child_filters,
             fail_fast: fail_fast.unwrap_or(false),
         }
     }
@@ -173,11 +380,42 @@ impl Default for RegressionConfig {
     fn default() -> Self {
         Self {
             limits: vec![(EventKind::Ir, 10f64)],
+            function_limits: Vec::default(),
+            thread_limits: Vec::default(),
+            child_filters: Vec::default(),
             fail_fast: Default::default(),
         }
     }
 }
 
+/// Check the `costs_summary` for a regression of the `event_kind` over the `limit`
+fn check_limit(
+    costs_summary: &CostsSummary,
+    event_kind: EventKind,
+    limit: f64,
+) -> Option<CallgrindRegressionSummary> {
+    let (new, old, diff_pct) = costs_summary
+        .diff_by_kind(&event_kind)
+        .filter(|d| d.diff_pct.is_some())
+        // These unwraps are safe since if diff_pct is present new and old are also present
+        .map(|d| (d.new.unwrap(), d.old.unwrap(), d.diff_pct.unwrap()))?;
+
+    let is_regressed = if limit.is_sign_positive() {
+        diff_pct > limit
+    } else {
+        diff_pct < limit
+    };
+    is_regressed.then_some(CallgrindRegressionSummary {
+        event_kind,
+        new,
+        old,
+        diff_pct,
+        limit,
+        function: None,
+        thread: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -286,9 +524,219 @@ mod tests {
                 old: *o,
                 diff_pct: *d,
                 limit: *l,
+                function: None,
+                thread: None,
             })
             .collect::<Vec<CallgrindRegressionSummary>>();
 
         assert_eq!(regression.check(&summary), expected);
     }
+
+    #[test]
+    fn test_regression_check_functions() {
+        use std::collections::HashMap;
+
+        use self::hashmap_parser::{Id, Value};
+
+        let map = |values: Vec<(&str, u64)>| CallgrindMap {
+            map: values
+                .into_iter()
+                .map(|(func, ir)| {
+                    (
+                        Id {
+                            obj: None,
+                            file: None,
+                            func: func.to_owned(),
+                        },
+                        Value {
+                            costs: Costs::with_event_kinds([(Ir, ir)]),
+                            exclusive_costs: Costs::with_event_kinds([(Ir, ir)]),
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+            sentinel: None,
+            sentinel_key: None,
+            calls: HashMap::new(),
+            locations: HashMap::new(),
+        };
+        let regression = RegressionConfig::from(api::RegressionConfig {
+            function_limits: vec![("my_crate::parser::*".to_owned(), Ir, 2f64)],
+            ..Default::default()
+        });
+        let new = map(vec![
+            ("my_crate::parser::parse", 110),
+            ("my_crate::parser::lex", 101),
+            ("my_crate::parser::new", 50),
+            ("my_crate::other", 200),
+            ("main", 1000),
+        ]);
+        let old = map(vec![
+            ("my_crate::parser::parse", 100),
+            ("my_crate::parser::lex", 100),
+            ("my_crate::other", 100),
+            ("main", 100),
+        ]);
+
+        assert!(regression.limits.is_empty());
+        assert_eq!(
+            regression.check_functions(&new, &old),
+            vec![CallgrindRegressionSummary {
+                event_kind: Ir,
+                new: 110,
+                old: 100,
+                diff_pct: 10f64,
+                limit: 2f64,
+                function: Some("my_crate::parser::parse".to_owned()),
+                thread: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_regression_check_threads() {
+        let regression = RegressionConfig::from(api::RegressionConfig {
+            thread_limits: vec![(2, Ir, 5f64), (3, Ir, 5f64), (4, Ir, 5f64)],
+            ..Default::default()
+        });
+        let thread = |thread: usize, new: u64, old: Option<u64>| CallgrindThreadSummary {
+            thread,
+            events: CostsSummary::new(
+                &Costs::with_event_kinds([(Ir, new)]),
+                old.map(|old| Costs::with_event_kinds([(Ir, old)])).as_ref(),
+            ),
+        };
+        let threads = vec![
+            thread(1, 200, Some(100)),
+            thread(2, 110, Some(100)),
+            thread(3, 104, Some(100)),
+            thread(4, 200, None),
+        ];
+
+        assert!(regression.limits.is_empty());
+        assert_eq!(
+            regression.check_threads(&threads),
+            vec![CallgrindRegressionSummary {
+                event_kind: Ir,
+                new: 110,
+                old: 100,
+                diff_pct: 10f64,
+                limit: 5f64,
+                function: None,
+                thread: Some(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_regression_check_functions_when_derived_event_kind() {
+        use std::collections::HashMap;
+
+        use self::hashmap_parser::{Id, Value};
+
+        let map = |costs: [u64; 9]| {
+            let mut map = CallgrindMap {
+                map: HashMap::from([(
+                    Id {
+                        obj: None,
+                        file: None,
+                        func: "my_crate::parser::parse".to_owned(),
+                    },
+                    Value {
+                        costs: cachesim_costs(costs),
+                        exclusive_costs: cachesim_costs(costs),
+                    },
+                )]),
+                sentinel: None,
+                sentinel_key: None,
+                calls: HashMap::new(),
+                locations: HashMap::new(),
+            };
+            map.make_summary(&CostModel::default()).unwrap();
+            map
+        };
+        let regression = RegressionConfig::from(api::RegressionConfig {
+            function_limits: vec![("my_crate::*".to_owned(), EstimatedCycles, 5f64)],
+            ..Default::default()
+        });
+        let new = map([96, 24, 18, 6, 0, 2, 6, 0, 2]);
+        let old = map([48, 12, 9, 3, 0, 1, 3, 0, 1]);
+
+        assert!(new
+            .iter()
+            .all(|(_, value)| value.costs.cost_by_kind(&EstimatedCycles) == Some(410)));
+        assert_eq!(
+            regression.check_functions(&new, &old),
+            vec![CallgrindRegressionSummary {
+                event_kind: EstimatedCycles,
+                new: 410,
+                old: 205,
+                diff_pct: 100f64,
+                limit: 5f64,
+                function: Some("my_crate::parser::parse".to_owned()),
+                thread: None,
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case::no_filters(&[], "/usr/bin/echo foo", true)]
+    #[case::match_command(&["*/echo *"], "/usr/bin/echo foo", true)]
+    #[case::match_any(&["*/cat", "*/echo*"], "/usr/bin/echo", true)]
+    #[case::no_match(&["*/cat*"], "/usr/bin/echo foo", false)]
+    #[case::invalid_is_ignored(&["[", "*/cat*"], "/usr/bin/echo foo", false)]
+    fn test_regression_is_checked_child(
+        #[case] child_filters: &[&str],
+        #[case] command: &str,
+        #[case] expected: bool,
+    ) {
+        let regression = RegressionConfig::from(api::RegressionConfig {
+            child_filters: child_filters.iter().map(|s| (*s).to_owned()).collect(),
+            ..Default::default()
+        });
+
+        assert_eq!(regression.is_checked_child(command), expected);
+    }
+
+    #[rstest]
+    #[case::default(CostModel::default(), 2150)]
+    #[case::classic(CostModelPreset::Classic.into(), 2150)]
+    #[case::modern(CostModelPreset::Modern.into(), 4745)]
+    #[case::custom(
+        CostModel { l1_hits: 2, ll_hits: 0, ram_hits: 0, ..CostModel::default() },
+        1600
+    )]
+    fn test_cache_summary_with_cost_model(#[case] cost_model: CostModel, #[case] expected: u64) {
+        // 800 L1 hits, 60 LL hits, 30 RAM hits and 10 + 5 mispredicted branches
+        let mut costs = cachesim_costs([600, 200, 90, 40, 40, 10, 10, 10, 10]);
+        costs.0.insert(Bcm, 10);
+        costs.0.insert(Bim, 5);
+
+        let summary = CacheSummary::new(&costs, &cost_model).unwrap();
+        assert_eq!(summary.l1_hits, 800);
+        assert_eq!(summary.l3_hits, 60);
+        assert_eq!(summary.ram_hits, 30);
+        assert_eq!(summary.cycles, expected);
+    }
+
+    #[test]
+    fn test_cost_model_from_api_with_preset_and_weights() {
+        let cost_model = CostModel::from(api::CostModel {
+            preset: Some(CostModelPreset::Modern),
+            ram_hits: Some(200),
+            branch_indirect_misses: Some(0),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            cost_model,
+            CostModel {
+                l1_hits: 1,
+                ll_hits: 12,
+                ram_hits: 200,
+                branch_cond_misses: 15,
+                branch_indirect_misses: 0,
+            }
+        );
+    }
 }
@@ -7,7 +7,7 @@ use anyhow::Result;
 use indexmap::{indexmap, IndexMap};
 use serde::{Deserialize, Serialize};
 
-use super::CacheSummary;
+use super::{CacheSummary, CostModel};
 use crate::api::EventKind;
 use crate::runner::costs::Summarize;
 
@@ -49,13 +49,26 @@ impl Costs {
     /// If the necessary cache simulation events (when running callgrind with --cache-sim) were not
     /// present.
     pub fn make_summary(&mut self) -> Result<()> {
+        self.make_summary_with(&CostModel::default())
+    }
+
+    /// Calculate and add derived summary events in-place with the weights of the [`CostModel`]
+    ///
+    /// Like [`Costs::make_summary`] but the [`EventKind::EstimatedCycles`] are calculated with the
+    /// `cost_model` instead of the default cost model.
+    ///
+    /// # Errors
+    ///
+    /// If the necessary cache simulation events (when running callgrind with --cache-sim) were not
+    /// present.
+    pub fn make_summary_with(&mut self, cost_model: &CostModel) -> Result<()> {
         let CacheSummary {
             l1_hits,
             l3_hits,
             ram_hits,
             total_memory_rw,
             cycles,
-        } = (&*self).try_into()?;
+        } = CacheSummary::new(self, cost_model)?;
 
         self.0.insert(EventKind::L1hits, l1_hits);
         self.0.insert(EventKind::LLhits, l3_hits);
@@ -133,6 +146,11 @@ impl Positions {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Return the index of the `kind` of position in the cost lines if present
+    pub fn index_of(&self, kind: PositionType) -> Option<usize> {
+        self.0.get_index_of(&kind)
+    }
 }
 
 impl Default for Positions {
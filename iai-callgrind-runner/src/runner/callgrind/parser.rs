@@ -12,6 +12,10 @@ use crate::runner::DEFAULT_TOGGLE;
 pub struct CallgrindProperties {
     pub costs_prototype: Costs,
     pub positions_prototype: Positions,
+    /// The part number of the dump from the `part:` header line
+    pub part: Option<u64>,
+    /// The trigger of the dump from the `desc: Trigger:` header line
+    pub trigger: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +134,8 @@ pub fn parse_header(iter: &mut impl Iterator<Item = String>) -> Result<Callgrind
 
     let mut positions_prototype: Option<Positions> = None;
     let mut costs_prototype: Option<Costs> = None;
+    let mut part = None;
+    let mut trigger = None;
 
     for line in iter {
         if line.is_empty() || line.starts_with('#') {
@@ -142,6 +148,16 @@ pub fn parse_header(iter: &mut impl Iterator<Item = String>) -> Result<Callgrind
                     "Version mismatch: Requires callgrind format version '1' but was '{version}'"
                 ));
             }
+            Some(("part", value)) => {
+                part = value.parse::<u64>().ok();
+                trace!("Using part: '{:?}'", part);
+            }
+            Some(("desc", desc)) if desc.starts_with("Trigger:") => {
+                trigger = desc
+                    .strip_prefix("Trigger:")
+                    .map(|value| value.trim().to_owned());
+                trace!("Using trigger: '{:?}'", trigger);
+            }
             Some(("positions", positions)) => {
                 positions_prototype = Some(positions.split_ascii_whitespace().collect());
                 trace!("Using positions: '{:?}'", positions_prototype);
@@ -165,5 +181,20 @@ pub fn parse_header(iter: &mut impl Iterator<Item = String>) -> Result<Callgrind
         costs_prototype: costs_prototype
             .ok_or_else(|| anyhow!("Header field 'events' must be present"))?,
         positions_prototype: positions_prototype.unwrap_or_default(),
+        part,
+        trigger,
     })
 }
+
+/// Parse a line position which may be relative to the `last` position (`+2`, `-1` or `*`)
+pub fn parse_position(position: &str, last: u64) -> u64 {
+    if let Some(diff) = position.strip_prefix('+') {
+        last + diff.parse::<u64>().unwrap_or_default()
+    } else if let Some(diff) = position.strip_prefix('-') {
+        last.saturating_sub(diff.parse::<u64>().unwrap_or_default())
+    } else if position == "*" {
+        last
+    } else {
+        position.parse::<u64>().unwrap_or_default()
+    }
+}
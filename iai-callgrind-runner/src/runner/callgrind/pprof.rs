@@ -0,0 +1,367 @@
+//! The export of callgrind runs to gzipped profiles in the protobuf format of pprof
+//!
+//! The profiles are built from the same stacks as the flamegraphs with one sample type per
+//! [`EventKind`]. Since pprof only needs a small part of the protobuf wire format, the profile is
+//! encoded without a protobuf library. See
+//! <https://github.com/google/pprof/blob/main/proto/profile.proto> for the definition of the
+//! profile.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::flamegraph_parser::{FlamegraphMap, FlamegraphParser};
+use super::hashmap_parser::Id;
+use super::parser::Sentinel;
+use super::CostModel;
+use crate::api::{self, EventKind};
+use crate::runner::summary::PprofSummary;
+use crate::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind};
+
+/// The configuration of the pprof profiles of callgrind runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PprofConfig {
+    /// The [`EventKind`]s of the sample types of the profile
+    pub event_kinds: Vec<EventKind>,
+    /// The cost model of the [`EventKind::EstimatedCycles`]
+    pub cost_model: CostModel,
+}
+
+/// A minimal encoder of the protobuf wire format
+#[derive(Debug, Default)]
+struct Encoder(Vec<u8>);
+
+/// The string table of a profile in which all strings are referenced by their index
+#[derive(Debug)]
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, u64>,
+}
+
+impl PprofConfig {
+    /// Export the callgrind output at `output_path` and at the `old_path` if present
+    ///
+    /// The profiles are written next to the callgrind output files. The profile of the old or
+    /// baseline run can be used as base for the comparison with `pprof -diff_base`.
+    pub fn create(
+        &self,
+        output_path: &ToolOutputPath,
+        old_path: Option<&ToolOutputPath>,
+        sentinel: Option<&Sentinel>,
+        project_root: &Path,
+    ) -> Result<PprofSummary> {
+        let path = self.export(output_path, sentinel, project_root)?;
+        let base_path = old_path
+            .filter(|old_path| old_path.exists())
+            .map(|old_path| self.export(old_path, sentinel, project_root))
+            .transpose()?;
+
+        Ok(PprofSummary {
+            path,
+            base_path,
+            event_kinds: self.event_kinds.clone(),
+        })
+    }
+
+    /// Export the callgrind output at `output_path` to a gzipped pprof profile
+    pub fn export(
+        &self,
+        output_path: &ToolOutputPath,
+        sentinel: Option<&Sentinel>,
+        project_root: &Path,
+    ) -> Result<PathBuf> {
+        let path = profile_path(output_path);
+        debug!("Creating pprof profile '{}'", path.display());
+
+        let mut map = FlamegraphParser::new(sentinel, project_root).parse(output_path)?;
+        if map.is_empty() {
+            return Err(anyhow!("Unable to create a pprof profile: No stacks found"));
+        }
+        if self.event_kinds.iter().any(EventKind::is_derived) {
+            map.make_summary(&self.cost_model)?;
+        }
+
+        let profile = self.encode(&map)?;
+        let file = File::create(&path)
+            .with_context(|| format!("Failed creating pprof profile '{}'", path.display()))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        encoder
+            .write_all(&profile)
+            .and_then(|()| encoder.finish()?.flush())
+            .with_context(|| format!("Failed writing pprof profile '{}'", path.display()))?;
+
+        Ok(path)
+    }
+
+    /// Encode the stacks of the `map` into the protobuf message of a pprof profile
+    ///
+    /// Every function is a single location with a single line. The stacks of all
+    /// [`EventKind`]s are merged into one sample per stack with one value per `EventKind`.
+    fn encode(&self, map: &FlamegraphMap) -> Result<Vec<u8>> {
+        let mut strings = StringTable::new();
+        let mut functions = IndexMap::<&Id, u64>::new();
+        let mut samples = IndexMap::<Vec<u64>, Vec<u64>>::new();
+        for (index, event_kind) in self.event_kinds.iter().enumerate() {
+            for (frames, cost) in map.to_stacks(event_kind)? {
+                // The location ids of a sample start with the innermost function
+                let locations = frames
+                    .iter()
+                    .rev()
+                    .map(|id| {
+                        let next = functions.len() as u64 + 1;
+                        *functions.entry(*id).or_insert(next)
+                    })
+                    .collect::<Vec<u64>>();
+                samples
+                    .entry(locations)
+                    .or_insert_with(|| vec![0; self.event_kinds.len()])[index] += cost;
+            }
+        }
+
+        let mut profile = Encoder::default();
+        for event_kind in &self.event_kinds {
+            let mut value_type = Encoder::default();
+            value_type.uint(1, strings.index(&format!("{event_kind:?}")));
+            value_type.uint(2, strings.index("count"));
+            profile.message(1, &value_type);
+        }
+        for (locations, values) in &samples {
+            let mut sample = Encoder::default();
+            sample.packed(1, locations.iter().copied());
+            // The values are `int64` which is encoded like an `uint64` if positive
+            sample.packed(2, values.iter().map(|value| (*value).min(i64::MAX as u64)));
+            profile.message(2, &sample);
+        }
+        for id in functions.values() {
+            let mut line = Encoder::default();
+            line.uint(1, *id);
+            let mut location = Encoder::default();
+            location.uint(1, *id);
+            location.message(4, &line);
+            profile.message(4, &location);
+        }
+        for (id, function_id) in &functions {
+            let name = strings.index(&id.func);
+            let file_name = id
+                .file
+                .as_ref()
+                .and_then(|file| file.as_path())
+                .map_or(0, |path| strings.index(&path.display().to_string()));
+            let mut function = Encoder::default();
+            function.uint(1, *function_id);
+            function.uint(2, name);
+            function.uint(3, name);
+            function.uint(4, file_name);
+            profile.message(5, &function);
+        }
+        for string in &strings.strings {
+            profile.bytes(6, string.as_bytes());
+        }
+        if let Some(event_kind) = self.event_kinds.first() {
+            profile.uint(14, strings.index(&format!("{event_kind:?}")));
+        }
+
+        Ok(profile.0)
+    }
+}
+
+impl Default for PprofConfig {
+    fn default() -> Self {
+        Self {
+            event_kinds: vec![EventKind::Ir],
+            cost_model: CostModel::default(),
+        }
+    }
+}
+
+impl From<api::PprofConfig> for PprofConfig {
+    fn from(value: api::PprofConfig) -> Self {
+        let api::PprofConfig { event_kinds } = value;
+        Self {
+            event_kinds: event_kinds
+                .filter(|e| !e.is_empty())
+                .unwrap_or_else(|| vec![EventKind::Ir]),
+            cost_model: CostModel::default(),
+        }
+    }
+}
+
+impl Encoder {
+    /// Encode the `value` as variable length integer
+    // The truncation is intended since only the lowest 7 bits are encoded in each byte
+    #[allow(clippy::cast_possible_truncation)]
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7_u32;
+        }
+        self.0.push(value as u8);
+    }
+
+    /// Encode the key of the field with the `number` and the `wire_type`
+    fn key(&mut self, number: u32, wire_type: u8) {
+        self.varint(u64::from(number << 3_u32 | u32::from(wire_type)));
+    }
+
+    /// Encode an integer field which is omitted if it has the default value `0`
+    fn uint(&mut self, number: u32, value: u64) {
+        if value != 0 {
+            self.key(number, 0);
+            self.varint(value);
+        }
+    }
+
+    /// Encode a length delimited field
+    fn bytes(&mut self, number: u32, bytes: &[u8]) {
+        self.key(number, 2);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// Encode an embedded message
+    fn message(&mut self, number: u32, message: &Encoder) {
+        self.bytes(number, &message.0);
+    }
+
+    /// Encode a packed repeated integer field
+    fn packed<T>(&mut self, number: u32, values: T)
+    where
+        T: IntoIterator<Item = u64>,
+    {
+        let mut packed = Encoder::default();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes(number, &packed.0);
+    }
+}
+
+impl StringTable {
+    /// Create a new `StringTable` with the empty string at index `0` as required by pprof
+    fn new() -> Self {
+        Self {
+            strings: vec![String::new()],
+            indices: HashMap::from([(String::new(), 0)]),
+        }
+    }
+
+    /// Return the index of the `string` adding it to the table if not present yet
+    fn index(&mut self, string: &str) -> u64 {
+        if let Some(index) = self.indices.get(string) {
+            return *index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(string.to_owned());
+        self.indices.insert(string.to_owned(), index);
+        index
+    }
+}
+
+/// Return the path to the gzipped pprof profile of the callgrind output at `output_path`
+///
+/// The profiles are named like the callgrind output files, for example
+/// `callgrind.bench_name.profile.pb.gz` or `callgrind.bench_name.profile.base@foo.pb.gz`.
+pub fn profile_path(output_path: &ToolOutputPath) -> PathBuf {
+    let extension = match &output_path.kind {
+        ToolOutputPathKind::Base(name) | ToolOutputPathKind::BaseLog(name) => {
+            format!("profile.base@{name}.pb.gz")
+        }
+        ToolOutputPathKind::OldOut | ToolOutputPathKind::OldLog => "profile.old.pb.gz".to_owned(),
+        _ => "profile.pb.gz".to_owned(),
+    };
+    output_path.dir.join(format!(
+        "{}.{}.{extension}",
+        output_path.tool.id(),
+        output_path.name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::summary::BaselineKind;
+    use crate::runner::tool::ValgrindTool;
+
+    #[rstest]
+    #[case::zero(0, &[0x00])]
+    #[case::one_byte(127, &[0x7f])]
+    #[case::two_bytes(300, &[0xac, 0x02])]
+    #[case::max(u64::MAX, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01])]
+    fn test_encoder_varint(#[case] value: u64, #[case] expected: &[u8]) {
+        let mut encoder = Encoder::default();
+        encoder.varint(value);
+        assert_eq!(encoder.0, expected);
+    }
+
+    #[test]
+    fn test_encoder_fields() {
+        let mut encoder = Encoder::default();
+        encoder.uint(1, 0);
+        encoder.uint(1, 150);
+        encoder.bytes(6, b"main");
+        encoder.packed(2, [1, 300]);
+
+        assert_eq!(
+            encoder.0,
+            [0x08, 0x96, 0x01, 0x32, 0x04, b'm', b'a', b'i', b'n', 0x12, 0x03, 0x01, 0xac, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_string_table() {
+        let mut strings = StringTable::new();
+
+        assert_eq!(strings.index(""), 0);
+        assert_eq!(strings.index("main"), 1);
+        assert_eq!(strings.index("foo"), 2);
+        assert_eq!(strings.index("main"), 1);
+        assert_eq!(strings.strings, ["", "main", "foo"]);
+    }
+
+    #[rstest]
+    #[case::out(ToolOutputPathKind::Out, "callgrind.bench.profile.pb.gz")]
+    #[case::old(ToolOutputPathKind::OldOut, "callgrind.bench.profile.old.pb.gz")]
+    #[case::base(
+        ToolOutputPathKind::Base("foo".to_owned()),
+        "callgrind.bench.profile.base@foo.pb.gz"
+    )]
+    fn test_profile_path(#[case] kind: ToolOutputPathKind, #[case] expected: &str) {
+        let output_path = ToolOutputPath {
+            kind,
+            tool: ValgrindTool::Callgrind,
+            baseline_kind: BaselineKind::Old,
+            dir: PathBuf::from("/tmp"),
+            name: "bench".to_owned(),
+            modifiers: vec![],
+            part: None,
+            thread: None,
+        };
+
+        assert_eq!(profile_path(&output_path), Path::new("/tmp").join(expected));
+    }
+
+    #[rstest]
+    #[case::default(None, vec![EventKind::Ir])]
+    #[case::empty(Some(vec![]), vec![EventKind::Ir])]
+    #[case::some(
+        Some(vec![EventKind::EstimatedCycles, EventKind::Ir]),
+        vec![EventKind::EstimatedCycles, EventKind::Ir]
+    )]
+    fn test_pprof_config_from(
+        #[case] event_kinds: Option<Vec<EventKind>>,
+        #[case] expected: Vec<EventKind>,
+    ) {
+        let config = PprofConfig::from(api::PprofConfig { event_kinds });
+        assert_eq!(config.event_kinds, expected);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::dumps::Dump;
+use crate::runner::summary::CallgrindProcess;
+use crate::runner::tool::logfile_parser::{LogfileParser, ToolLogfileParser};
+use crate::runner::tool::{ToolOutputPath, ToolOutputPathKind};
+
+/// The outfile modifier of callgrind to write a separate output file for each traced process
+pub const PID_MODIFIER: &str = "%p";
+
+/// A single process of a callgrind run with `--trace-children=yes`
+#[derive(Debug, Clone)]
+pub struct Process {
+    /// The path to the output file of this process with the pid as modifier
+    pub path: ToolOutputPath,
+    /// The pid of this process
+    pub pid: i32,
+    /// The parent pid of this process
+    pub parent_pid: Option<i32>,
+    /// The command line of this process extracted from the log file
+    pub command: String,
+    /// True if the parent process was traced too
+    pub is_child: bool,
+}
+
+/// The parsed dumps of a single process of a callgrind run
+///
+/// If the children are not traced, the output of a callgrind run consists of a single
+/// `ProcessDumps` without a [`Process`].
+#[derive(Debug)]
+pub struct ProcessDumps {
+    /// The traced process if the children are traced
+    pub process: Option<Process>,
+    /// The dumps of this process
+    pub dumps: Vec<Dump>,
+}
+
+impl ProcessDumps {
+    /// Return the process of the `old` processes matching the process at `index` of the `new`
+    /// processes
+    ///
+    /// The processes are matched by their command and the occurrence of this command in the order
+    /// of their pids. So, the second process with a command is matched with the second old
+    /// process with the same command, no matter how many processes with other commands were
+    /// spawned before. Without traced children, the single new and old outputs are always
+    /// matched.
+    pub fn find_old<'a>(
+        new: &[ProcessDumps],
+        index: usize,
+        old: &'a [ProcessDumps],
+    ) -> Option<&'a ProcessDumps> {
+        let command = new.get(index)?.command();
+        let nth = new[..index]
+            .iter()
+            .filter(|process| process.command() == command)
+            .count();
+        old.iter()
+            .filter(|process| process.command() == command)
+            .nth(nth)
+    }
+
+    /// Return the command of the traced process if present
+    fn command(&self) -> Option<&str> {
+        self.process.as_ref().map(|process| process.command.as_str())
+    }
+
+    /// Return the [`CallgrindProcess`] of the new and `old` process if the children are traced
+    pub fn to_summary(
+        &self,
+        old: Option<&ProcessDumps>,
+        is_checked: bool,
+    ) -> Option<CallgrindProcess> {
+        let old = old.and_then(|old| old.process.as_ref());
+        self.process.as_ref().map(|process| CallgrindProcess {
+            command: process.command.clone(),
+            old_pid: old.map(|old| old.pid),
+            old_parent_pid: old.and_then(|old| old.parent_pid),
+            pid: Some(process.pid),
+            parent_pid: process.parent_pid,
+            is_checked,
+        })
+    }
+}
+
+/// Find all processes of the callgrind run at `output_path` sorted by their pid
+///
+/// The pid, parent pid and command of each process are extracted from the log files which are
+/// split by pid like the output files.
+pub fn find(output_path: &ToolOutputPath, project_root: &Path) -> Result<Vec<Process>> {
+    // The log output of the old output is the new log output, so we need its base path
+    let log_path = if output_path.kind == ToolOutputPathKind::OldOut {
+        output_path.to_log_output().to_base_path()
+    } else {
+        output_path.to_log_output()
+    };
+
+    let parser = ToolLogfileParser {
+        root_dir: project_root.to_owned(),
+    };
+    let summaries = parser.parse(&log_path)?;
+    let pids = summaries
+        .iter()
+        .map(|summary| summary.pid)
+        .collect::<HashSet<i32>>();
+
+    Ok(summaries
+        .into_iter()
+        .map(|summary| Process {
+            path: output_path.with_modifiers([summary.pid.to_string()]),
+            pid: summary.pid,
+            parent_pid: summary.parent_pid,
+            command: summary.command.to_string_lossy().to_string(),
+            is_child: summary.parent_pid.map_or(false, |pid| pids.contains(&pid)),
+        })
+        .collect())
+}
+
+/// Return the root process which is the benchmarked command itself
+pub fn find_root(processes: &[ProcessDumps]) -> Option<&ProcessDumps> {
+    processes
+        .iter()
+        .find(|dumps| dumps.process.as_ref().map_or(true, |process| !process.is_child))
+}
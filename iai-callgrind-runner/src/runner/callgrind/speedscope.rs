@@ -0,0 +1,209 @@
+//! The export of the flamegraph stacks to the JSON file format of speedscope
+//!
+//! Other than the svg flamegraphs, a speedscope file can be browsed interactively with the
+//! left-heavy and sandwich views of <https://www.speedscope.app>, which works offline with a local
+//! file, too. See <https://www.speedscope.app/file-format-schema.json> for the definition of the
+//! file format.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+/// The url of the schema of the speedscope file format
+const SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+/// A speedscope file with one sampled profile per metric
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Speedscope {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    name: String,
+    exporter: String,
+    active_profile_index: usize,
+    shared: Shared,
+    profiles: Vec<Profile>,
+    #[serde(skip)]
+    indices: IndexMap<String, usize>,
+}
+
+/// The frames shared by all profiles of a [`Speedscope`] file
+#[derive(Debug, Default, Serialize)]
+struct Shared {
+    frames: Vec<Frame>,
+}
+
+/// A single frame referenced by its index in the [`Shared`] frames
+#[derive(Debug, Serialize)]
+struct Frame {
+    name: String,
+}
+
+/// A sampled profile in which every stack is a sample weighted with its cost
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Profile {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    name: String,
+    unit: &'static str,
+    start_value: u64,
+    end_value: u64,
+    samples: Vec<Vec<usize>>,
+    weights: Vec<u64>,
+}
+
+impl Speedscope {
+    /// Create a new `Speedscope` file without profiles
+    pub fn new(name: String) -> Self {
+        Self {
+            schema: SCHEMA,
+            name,
+            exporter: format!("iai-callgrind {}", env!("CARGO_PKG_VERSION")),
+            active_profile_index: 0,
+            shared: Shared::default(),
+            profiles: vec![],
+            indices: IndexMap::new(),
+        }
+    }
+
+    /// Return true if there are no profiles
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Add a profile with the `name` of the metric from the `stacks` in the folded stacks format
+    ///
+    /// Each line of the `stacks` consists of the frames separated by `;` starting with the
+    /// outermost frame and the cost of the stack separated by a space.
+    ///
+    /// # Errors
+    ///
+    /// If a line of the `stacks` has no valid cost
+    pub fn add(&mut self, name: String, stacks: &[String]) -> Result<()> {
+        let mut samples = Vec::with_capacity(stacks.len());
+        let mut weights = Vec::with_capacity(stacks.len());
+        for line in stacks {
+            let (frames, cost) = line
+                .rsplit_once(' ')
+                .and_then(|(frames, cost)| cost.parse::<u64>().ok().map(|cost| (frames, cost)))
+                .ok_or_else(|| anyhow!("Invalid line in folded stacks: '{line}'"))?;
+            if cost == 0 {
+                continue;
+            }
+            samples.push(frames.split(';').map(|frame| self.index(frame)).collect());
+            weights.push(cost);
+        }
+
+        self.profiles.push(Profile {
+            kind: "sampled",
+            name,
+            unit: "none",
+            start_value: 0,
+            end_value: weights.iter().sum(),
+            samples,
+            weights,
+        });
+        Ok(())
+    }
+
+    /// Write the speedscope file to `path`
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed creating speedscope file '{}'", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, self)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| writer.flush().map_err(Into::into))
+            .with_context(|| format!("Failed writing speedscope file '{}'", path.display()))
+    }
+
+    /// Return the index of the `frame` adding it to the shared frames if not present yet
+    fn index(&mut self, frame: &str) -> usize {
+        if let Some(index) = self.indices.get(frame) {
+            return *index;
+        }
+        let index = self.shared.frames.len();
+        self.shared.frames.push(Frame {
+            name: frame.to_owned(),
+        });
+        self.indices.insert(frame.to_owned(), index);
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_speedscope_add() {
+        let mut speedscope = Speedscope::new("bench".to_owned());
+        speedscope
+            .add(
+                "Instructions".to_owned(),
+                &[
+                    "main 10".to_owned(),
+                    "main;foo 20".to_owned(),
+                    "main;foo;bar baz 0".to_owned(),
+                    "main;bar baz 5".to_owned(),
+                ],
+            )
+            .unwrap();
+        speedscope
+            .add("Estimated Cycles".to_owned(), &["main;foo 40".to_owned()])
+            .unwrap();
+
+        let mut value = serde_json::to_value(&speedscope).unwrap();
+        value["exporter"] = Value::from("iai-callgrind");
+        let expected: Value = serde_json::from_str(
+            r#"{
+                "$schema": "https://www.speedscope.app/file-format-schema.json",
+                "name": "bench",
+                "exporter": "iai-callgrind",
+                "activeProfileIndex": 0,
+                "shared": {
+                    "frames": [{"name": "main"}, {"name": "foo"}, {"name": "bar baz"}]
+                },
+                "profiles": [
+                    {
+                        "type": "sampled",
+                        "name": "Instructions",
+                        "unit": "none",
+                        "startValue": 0,
+                        "endValue": 35,
+                        "samples": [[0], [0, 1], [0, 2]],
+                        "weights": [10, 20, 5]
+                    },
+                    {
+                        "type": "sampled",
+                        "name": "Estimated Cycles",
+                        "unit": "none",
+                        "startValue": 0,
+                        "endValue": 40,
+                        "samples": [[0, 1]],
+                        "weights": [40]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_speedscope_add_when_invalid_line_then_error() {
+        let mut speedscope = Speedscope::new("bench".to_owned());
+
+        assert!(speedscope
+            .add("Instructions".to_owned(), &["main;foo".to_owned()])
+            .is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! The filtering of the frames of the flamegraph stacks
+//!
+//! The rules of a [`StackFilter`] are applied to the stacks of regular and differential flamegraphs
+//! before they are converted into the folded stacks format. All rules match the function name of a
+//! frame, so the path of the source and object file of a callgrind frame and the location of a DHAT
+//! frame are not taken into account.
+
+use glob::Pattern;
+use indexmap::IndexMap;
+use log::warn;
+use regex::Regex;
+
+/// The rules to remove, focus and collapse frames of the flamegraph stacks
+///
+/// The rules are applied in the following order:
+///
+/// 1. `focus`: The stacks are rooted at the outermost frame matching the regex. Stacks without such
+///    a frame are removed.
+/// 2. `include` and `exclude`: Only the frames matching any of the `include` regexes (if present)
+///    and none of the `exclude` regexes are kept. Stacks without any frames left are removed.
+/// 3. `collapse`: Consecutive frames matching the same glob pattern are folded into a single frame
+///    named after the pattern.
+///
+/// Stacks which are equal after applying the rules are merged by adding up their costs.
+#[derive(Debug, Clone, Default)]
+pub struct StackFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    focus: Option<Regex>,
+    collapse: Vec<Pattern>,
+}
+
+impl StackFilter {
+    /// Create a new `StackFilter` ignoring invalid regexes and glob patterns with a warning
+    pub fn new(
+        include: Vec<String>,
+        exclude: Vec<String>,
+        focus: Option<String>,
+        collapse: Vec<String>,
+    ) -> Self {
+        let to_regexes = |regexes: Vec<String>, kind: &str| {
+            regexes
+                .into_iter()
+                .filter_map(|regex| to_regex(&regex, kind))
+                .collect::<Vec<Regex>>()
+        };
+        let collapse = collapse
+            .into_iter()
+            .filter_map(|pattern| match Pattern::new(&pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(error) => {
+                    warn!("Ignoring invalid flamegraph collapse pattern '{pattern}': {error}");
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            include: to_regexes(include, "include"),
+            exclude: to_regexes(exclude, "exclude"),
+            focus: focus.and_then(|regex| to_regex(&regex, "focus")),
+            collapse,
+        }
+    }
+
+    /// Return true if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.focus.is_none()
+            && self.collapse.is_empty()
+    }
+
+    /// Apply the rules to the `stacks` and convert them into the folded stacks format
+    ///
+    /// The frames of a stack start with the outermost frame. Each frame is a tuple of the function
+    /// name, which is matched by the rules, and the name of the frame in the flamegraph. The names
+    /// of the frames must not contain a `;`.
+    pub fn apply<'a, I>(&self, stacks: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = (Vec<(&'a str, String)>, u64)>,
+    {
+        let mut folded: IndexMap<String, u64> = IndexMap::new();
+        for (frames, cost) in stacks {
+            let Some(frames) = self.apply_to_frames(frames) else {
+                continue;
+            };
+            *folded.entry(frames.join(";")).or_default() += cost;
+        }
+
+        folded
+            .into_iter()
+            .map(|(stack, cost)| format!("{stack} {cost}"))
+            .collect()
+    }
+
+    /// Apply the rules to the `frames` of a single stack returning `None` if it is removed
+    fn apply_to_frames(&self, frames: Vec<(&str, String)>) -> Option<Vec<String>> {
+        let start = match &self.focus {
+            Some(focus) => frames.iter().position(|(func, _)| focus.is_match(func))?,
+            None => 0,
+        };
+
+        let mut result: Vec<String> = vec![];
+        let mut last_pattern = None;
+        for (func, name) in frames.into_iter().skip(start) {
+            if (!self.include.is_empty() && !self.include.iter().any(|re| re.is_match(func)))
+                || self.exclude.iter().any(|re| re.is_match(func))
+            {
+                continue;
+            }
+
+            let pattern = self.collapse.iter().position(|p| p.matches(func));
+            match pattern {
+                Some(index) if last_pattern == Some(index) => {}
+                Some(index) => result.push(self.collapse[index].as_str().to_owned()),
+                None => result.push(name),
+            }
+            last_pattern = pattern;
+        }
+
+        (!result.is_empty()).then_some(result)
+    }
+}
+
+impl PartialEq for StackFilter {
+    fn eq(&self, other: &Self) -> bool {
+        fn as_strs(regexes: &[Regex]) -> Vec<&str> {
+            regexes.iter().map(Regex::as_str).collect()
+        }
+
+        as_strs(&self.include) == as_strs(&other.include)
+            && as_strs(&self.exclude) == as_strs(&other.exclude)
+            && self.focus.as_ref().map(Regex::as_str) == other.focus.as_ref().map(Regex::as_str)
+            && self.collapse == other.collapse
+    }
+}
+
+/// Return the compiled `regex` or `None` with a warning if it is invalid
+fn to_regex(regex: &str, kind: &str) -> Option<Regex> {
+    match Regex::new(regex) {
+        Ok(regex) => Some(regex),
+        Err(error) => {
+            warn!("Ignoring invalid flamegraph {kind} regex '{regex}': {error}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| (*v).to_owned()).collect()
+    }
+
+    fn stacks() -> Vec<(Vec<(&'static str, String)>, u64)> {
+        [
+            (vec!["main"], 1),
+            (vec!["main", "bench::run"], 2),
+            (vec!["main", "bench::run", "core::iter::next"], 3),
+            (vec!["main", "bench::run", "core::iter::next", "core::iter::fold"], 4),
+            (
+                vec!["main", "bench::run", "core::iter::fold", "alloc::alloc", "malloc"],
+                5,
+            ),
+            (vec!["main", "std::rt::cleanup"], 6),
+        ]
+        .into_iter()
+        .map(|(frames, cost)| {
+            (
+                frames
+                    .into_iter()
+                    .map(|func| (func, format!("file.rs:{func}")))
+                    .collect(),
+                cost,
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_stack_filter_when_empty() {
+        let filter = StackFilter::default();
+        assert!(filter.is_empty());
+        assert_eq!(
+            filter.apply(stacks()),
+            strings(&[
+                "file.rs:main 1",
+                "file.rs:main;file.rs:bench::run 2",
+                "file.rs:main;file.rs:bench::run;file.rs:core::iter::next 3",
+                "file.rs:main;file.rs:bench::run;file.rs:core::iter::next;file.rs:core::iter::fold \
+                 4",
+                "file.rs:main;file.rs:bench::run;file.rs:core::iter::fold;file.rs:alloc::alloc;\
+                 file.rs:malloc 5",
+                "file.rs:main;file.rs:std::rt::cleanup 6",
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::include(
+        &["^main$", "^bench::"],
+        &[],
+        None,
+        &[],
+        &["file.rs:main 7", "file.rs:main;file.rs:bench::run 14"]
+    )]
+    #[case::exclude(
+        &[],
+        &["^core::", "^std::"],
+        None,
+        &[],
+        &[
+            "file.rs:main 7",
+            "file.rs:main;file.rs:bench::run 9",
+            "file.rs:main;file.rs:bench::run;file.rs:alloc::alloc;file.rs:malloc 5",
+        ]
+    )]
+    #[case::include_and_exclude(
+        &["^main$", "^bench::", "^core::"],
+        &["fold$"],
+        None,
+        &[],
+        &[
+            "file.rs:main 7",
+            "file.rs:main;file.rs:bench::run 7",
+            "file.rs:main;file.rs:bench::run;file.rs:core::iter::next 7",
+        ]
+    )]
+    #[case::focus(
+        &[],
+        &[],
+        Some("^core::iter::"),
+        &[],
+        &[
+            "file.rs:core::iter::next 3",
+            "file.rs:core::iter::next;file.rs:core::iter::fold 4",
+            "file.rs:core::iter::fold;file.rs:alloc::alloc;file.rs:malloc 5",
+        ]
+    )]
+    #[case::collapse(
+        &[],
+        &[],
+        None,
+        &["core::iter::*", "alloc::*"],
+        &[
+            "file.rs:main 1",
+            "file.rs:main;file.rs:bench::run 2",
+            "file.rs:main;file.rs:bench::run;core::iter::* 7",
+            "file.rs:main;file.rs:bench::run;core::iter::*;alloc::*;file.rs:malloc 5",
+            "file.rs:main;file.rs:std::rt::cleanup 6",
+        ]
+    )]
+    #[case::all(
+        &[],
+        &["^malloc$"],
+        Some("^bench::run$"),
+        &["core::*"],
+        &[
+            "file.rs:bench::run 2",
+            "file.rs:bench::run;core::* 7",
+            "file.rs:bench::run;core::*;file.rs:alloc::alloc 5",
+        ]
+    )]
+    fn test_stack_filter_apply(
+        #[case] include: &[&str],
+        #[case] exclude: &[&str],
+        #[case] focus: Option<&str>,
+        #[case] collapse: &[&str],
+        #[case] expected: &[&str],
+    ) {
+        let filter = StackFilter::new(
+            strings(include),
+            strings(exclude),
+            focus.map(ToOwned::to_owned),
+            strings(collapse),
+        );
+        assert!(!filter.is_empty());
+        assert_eq!(filter.apply(stacks()), strings(expected));
+    }
+
+    #[test]
+    fn test_stack_filter_when_invalid_then_ignored() {
+        let filter = StackFilter::new(
+            strings(&["(invalid"]),
+            strings(&["[invalid"]),
+            Some("(invalid".to_owned()),
+            strings(&["[invalid"]),
+        );
+        assert_eq!(filter, StackFilter::default());
+    }
+}
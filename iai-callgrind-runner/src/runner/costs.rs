@@ -38,6 +38,13 @@ impl<K: Hash + Eq + Display + Clone> Costs<K> {
         }
     }
 
+    /// Subtract the costs of the `other` costs from these costs without going below zero
+    pub fn saturating_sub(&mut self, other: &Self) {
+        for ((_, old), cost) in self.0.iter_mut().zip(other.0.iter().map(|(_, c)| c)) {
+            *old = old.saturating_sub(*cost);
+        }
+    }
+
     /// Return the cost of the event at index (of insertion order) if present
     ///
     /// This operation is O(1)
@@ -0,0 +1,208 @@
+use anyhow::{anyhow, Result};
+
+use super::json_parser::{parse_dhat_out, AllocationSite};
+use crate::api::DhatMetric;
+use crate::runner::callgrind::flamegraph::{
+    BaselineFlamegraphGenerator, Config, Flamegraph, FlamegraphGenerator, FlamegraphMetric,
+    FlamegraphStacks, LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
+};
+use crate::runner::callgrind::stack_filter::StackFilter;
+use crate::runner::summary::{BaselineKind, BaselineName, FlamegraphSummary};
+use crate::runner::tool::{ToolOutputPath, ToolOutputPathKind};
+
+/// The allocation sites of all `dhat.out` files of a benchmark run
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DhatStacks(Vec<AllocationSite>);
+
+impl DhatStacks {
+    /// Parse all `dhat.out` files (one per process) of the `tool_output_path`
+    pub fn parse(tool_output_path: &ToolOutputPath) -> Result<Self> {
+        let mut sites = vec![];
+        for path in tool_output_path.real_paths()? {
+            sites.extend(parse_dhat_out(&path)?);
+        }
+        Ok(Self(sites))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<AllocationSite>> for DhatStacks {
+    fn from(value: Vec<AllocationSite>) -> Self {
+        Self(value)
+    }
+}
+
+impl FlamegraphStacks<DhatMetric> for DhatStacks {
+    /// Convert the allocation sites into the folded stacks format of inferno
+    ///
+    /// The outermost frame comes first. Allocation sites without costs for the `metric` are
+    /// skipped. For example, the bytes at t-gmax are only present if DHAT ran in heap mode. The
+    /// rules of the `filter` match the function name of a frame without its location.
+    fn to_stack_format(&self, metric: &DhatMetric, filter: &StackFilter) -> Result<Vec<String>> {
+        let name = metric.to_string();
+        Ok(filter.apply(self.0.iter().filter_map(|site| {
+            let cost = site.costs.cost_by_kind(&name).filter(|cost| *cost > 0)?;
+            let frames = site
+                .frames
+                .iter()
+                .rev()
+                .map(|frame| {
+                    let func = frame.rsplit_once(" (").map_or(frame.as_str(), |(func, _)| func);
+                    (func, frame.replace(';', ":"))
+                })
+                .collect();
+            Some((frames, cost))
+        })))
+    }
+}
+
+impl FlamegraphMetric for DhatMetric {
+    fn to_name(&self) -> String {
+        DhatMetric::to_name(self)
+    }
+}
+
+/// Parse the stacks of the new and, if required, of the base `dhat.out` files
+///
+/// This mirrors [`Flamegraph::parse`] for the callgrind output files.
+pub fn parse(
+    flamegraph: &Flamegraph,
+    tool_output_path: &ToolOutputPath,
+    no_differential: bool,
+) -> Result<(DhatStacks, Option<DhatStacks>)> {
+    let stacks = DhatStacks::parse(tool_output_path)?;
+    if stacks.is_empty() {
+        return Err(anyhow!("Unable to create a flamegraph: No stacks found"));
+    }
+
+    let base_path = tool_output_path.to_base_path();
+    let base_stacks = (!no_differential && flamegraph.is_differential() && base_path.exists())
+        .then(|| DhatStacks::parse(&base_path))
+        .transpose()?;
+
+    Ok((stacks, base_stacks))
+}
+
+/// Create the DHAT flamegraphs for all [`DhatMetric`]s of the flamegraph `config`
+///
+/// The flamegraph generator is chosen in the same way as for callgrind: If `save_baseline` is
+/// true, the flamegraphs are saved as baseline. If the `tool_output_path` points to a loaded
+/// baseline, the loaded baseline is compared with the baseline. Otherwise, the flamegraphs of a
+/// regular benchmark run are created.
+pub fn create_flamegraphs(
+    config: &Config,
+    title: String,
+    tool_output_path: &ToolOutputPath,
+    save_baseline: bool,
+) -> Result<Vec<FlamegraphSummary<DhatMetric>>> {
+    let flamegraph = Flamegraph::new(title, config.clone());
+    let metrics = &config.dhat_metrics;
+    let parse = |no_differential| parse(&flamegraph, tool_output_path, no_differential);
+
+    match (&tool_output_path.kind, &tool_output_path.baseline_kind) {
+        (_, BaselineKind::Name(baseline)) if save_baseline => SaveBaselineFlamegraphGenerator {
+            baseline: baseline.clone(),
+        }
+        .create_from(&flamegraph, tool_output_path, metrics, parse),
+        (ToolOutputPathKind::Base(loaded), BaselineKind::Name(baseline)) => {
+            LoadBaselineFlamegraphGenerator {
+                loaded_baseline: loaded.parse::<BaselineName>().map_err(|error| anyhow!(error))?,
+                baseline: baseline.clone(),
+            }
+            .create_from(&flamegraph, tool_output_path, metrics, parse)
+        }
+        (_, baseline_kind) => BaselineFlamegraphGenerator {
+            baseline_kind: baseline_kind.clone(),
+        }
+        .create_from(&flamegraph, tool_output_path, metrics, parse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::runner::costs::Costs;
+
+    #[test]
+    fn test_dhat_stacks_to_stack_format() {
+        let stacks = DhatStacks::from(vec![
+            AllocationSite {
+                frames: vec![
+                    "malloc (in vgpreload_dhat.so)".to_owned(),
+                    "bench::setup (bench.rs:4)".to_owned(),
+                    "main (bench.rs:20)".to_owned(),
+                ],
+                costs: Costs::with_event_kinds([
+                    ("Total bytes".to_owned(), 1024),
+                    ("Total blocks".to_owned(), 2),
+                    ("At t-gmax bytes".to_owned(), 0),
+                ]),
+            },
+            AllocationSite {
+                frames: vec!["weird;frame".to_owned(), "main".to_owned()],
+                costs: Costs::with_event_kinds([
+                    ("Total bytes".to_owned(), 16),
+                    ("Total blocks".to_owned(), 1),
+                ]),
+            },
+        ]);
+        let filter = StackFilter::default();
+
+        assert_eq!(
+            stacks.to_stack_format(&DhatMetric::TotalBytes, &filter).unwrap(),
+            vec![
+                "main (bench.rs:20);bench::setup (bench.rs:4);malloc (in vgpreload_dhat.so) 1024"
+                    .to_owned(),
+                "main;weird:frame 16".to_owned(),
+            ]
+        );
+        assert_eq!(
+            stacks.to_stack_format(&DhatMetric::TotalBlocks, &filter).unwrap(),
+            vec![
+                "main (bench.rs:20);bench::setup (bench.rs:4);malloc (in vgpreload_dhat.so) 2"
+                    .to_owned(),
+                "main;weird:frame 1".to_owned(),
+            ]
+        );
+        assert!(stacks
+            .to_stack_format(&DhatMetric::AtTGmaxBytes, &filter)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_dhat_stacks_to_stack_format_with_filter() {
+        let stacks = DhatStacks::from(vec![
+            AllocationSite {
+                frames: vec![
+                    "malloc (in vgpreload_dhat.so)".to_owned(),
+                    "alloc::raw_vec::finish_grow (raw_vec.rs:10)".to_owned(),
+                    "alloc::raw_vec::grow_amortized (raw_vec.rs:20)".to_owned(),
+                    "bench::setup (bench.rs:4)".to_owned(),
+                    "main (bench.rs:20)".to_owned(),
+                ],
+                costs: Costs::with_event_kinds([("Total bytes".to_owned(), 1024)]),
+            },
+            AllocationSite {
+                frames: vec!["malloc (in vgpreload_dhat.so)".to_owned(), "main".to_owned()],
+                costs: Costs::with_event_kinds([("Total bytes".to_owned(), 16)]),
+            },
+        ]);
+        let filter = StackFilter::new(
+            vec![],
+            vec!["^malloc$".to_owned()],
+            Some("^bench::setup$".to_owned()),
+            vec!["alloc::raw_vec::*".to_owned()],
+        );
+
+        assert_eq!(
+            stacks.to_stack_format(&DhatMetric::TotalBytes, &filter).unwrap(),
+            vec!["bench::setup (bench.rs:4);alloc::raw_vec::* 1024".to_owned()]
+        );
+    }
+}
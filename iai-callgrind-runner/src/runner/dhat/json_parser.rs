@@ -0,0 +1,251 @@
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::runner::costs::Costs;
+use crate::runner::summary::{AllocationSiteSummary, CostsSummary};
+
+lazy_static! {
+    static ref FRAME_RE: Regex =
+        regex::Regex::new(r"^(?:0x[0-9a-fA-F]+: )?(?<func>.*?)(?<loc> \([^()]*\))?$")
+            .expect("Regex should compile");
+    static ref RUST_HASH_RE: Regex =
+        regex::Regex::new(r"::h[0-9a-f]{16}$").expect("Regex should compile");
+}
+
+pub const TOTAL_BYTES: &str = "Total bytes";
+pub const TOTAL_BLOCKS: &str = "Total blocks";
+pub const TOTAL_LIFETIMES: &str = "Total lifetimes";
+pub const MAXIMUM_BYTES: &str = "Maximum bytes";
+pub const MAXIMUM_BLOCKS: &str = "Maximum blocks";
+pub const AT_T_GMAX_BYTES: &str = "At t-gmax bytes";
+pub const AT_T_GMAX_BLOCKS: &str = "At t-gmax blocks";
+pub const AT_T_END_BYTES: &str = "At t-end bytes";
+pub const AT_T_END_BLOCKS: &str = "At t-end blocks";
+pub const READS_BYTES: &str = "Reads bytes";
+pub const WRITES_BYTES: &str = "Writes bytes";
+
+/// The number of allocation sites shown in the terminal output and stored in the summary
+pub const TOP_ALLOCATION_SITES: usize = 10;
+
+/// A program point (allocation site) of the `dhat.out` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocationSite {
+    /// The demangled frames without addresses, the innermost frame first
+    pub frames: Vec<String>,
+    /// The metrics of this allocation site
+    pub costs: Costs<String>,
+}
+
+/// The relevant parts of the json output of DHAT
+#[derive(Debug, Deserialize)]
+struct DhatData {
+    #[serde(rename = "pps")]
+    program_points: Vec<ProgramPoint>,
+    #[serde(rename = "ftbl")]
+    frame_table: Vec<String>,
+}
+
+/// A program point of the json output of DHAT
+///
+/// Only the total bytes and blocks are present in all modes of DHAT. The other metrics are only
+/// present in the heap mode (the default).
+#[derive(Debug, Deserialize)]
+struct ProgramPoint {
+    #[serde(rename = "tb")]
+    total_bytes: u64,
+    #[serde(rename = "tbk")]
+    total_blocks: u64,
+    #[serde(rename = "tl")]
+    total_lifetimes: Option<u64>,
+    #[serde(rename = "mb")]
+    maximum_bytes: Option<u64>,
+    #[serde(rename = "mbk")]
+    maximum_blocks: Option<u64>,
+    #[serde(rename = "gb")]
+    at_t_gmax_bytes: Option<u64>,
+    #[serde(rename = "gbk")]
+    at_t_gmax_blocks: Option<u64>,
+    #[serde(rename = "eb")]
+    at_t_end_bytes: Option<u64>,
+    #[serde(rename = "ebk")]
+    at_t_end_blocks: Option<u64>,
+    #[serde(rename = "rb")]
+    reads_bytes: Option<u64>,
+    #[serde(rename = "wb")]
+    writes_bytes: Option<u64>,
+    #[serde(rename = "fs")]
+    frames: Vec<usize>,
+}
+
+impl ProgramPoint {
+    fn to_costs(&self) -> Costs<String> {
+        let mut costs = Costs::with_event_kinds([
+            (TOTAL_BYTES.to_owned(), self.total_bytes),
+            (TOTAL_BLOCKS.to_owned(), self.total_blocks),
+        ]);
+        for (name, value) in [
+            (TOTAL_LIFETIMES, self.total_lifetimes),
+            (MAXIMUM_BYTES, self.maximum_bytes),
+            (MAXIMUM_BLOCKS, self.maximum_blocks),
+            (AT_T_GMAX_BYTES, self.at_t_gmax_bytes),
+            (AT_T_GMAX_BLOCKS, self.at_t_gmax_blocks),
+            (AT_T_END_BYTES, self.at_t_end_bytes),
+            (AT_T_END_BLOCKS, self.at_t_end_blocks),
+            (READS_BYTES, self.reads_bytes),
+            (WRITES_BYTES, self.writes_bytes),
+        ] {
+            if let Some(value) = value {
+                costs.0.insert(name.to_owned(), value);
+            }
+        }
+        costs
+    }
+}
+
+impl AllocationSite {
+    fn total_bytes(&self) -> u64 {
+        self.costs.cost_by_kind(&TOTAL_BYTES.to_owned()).unwrap_or(0)
+    }
+}
+
+/// Demangle the function name of a frame of the frame table and remove the address
+///
+/// A frame looks like `0x10A1B2: _ZN5bench8my_bench17h0123456789abcdefE (bench.rs:12)` and is
+/// converted to `bench::my_bench (bench.rs:12)`. The address has to be removed to be able to
+/// compare the frames of different runs.
+pub fn demangle_frame(frame: &str) -> String {
+    let Some(caps) = FRAME_RE.captures(frame.trim()) else {
+        return frame.to_owned();
+    };
+    let func = caps.name("func").unwrap().as_str();
+    let func = rustc_demangle::try_demangle(func).map_or_else(
+        |_| RUST_HASH_RE.replace(func, "").to_string(),
+        |demangled| format!("{demangled:#}"),
+    );
+    caps.name("loc")
+        .map_or_else(|| func.clone(), |loc| format!("{func}{}", loc.as_str()))
+}
+
+/// Parse the program points of a `dhat.out` json file into [`AllocationSite`]s
+///
+/// Program points with the same frames after demangling and removing the addresses are
+/// summarized. The allocation sites are sorted by total bytes in descending order.
+pub fn parse_dhat_out(path: &Path) -> Result<Vec<AllocationSite>> {
+    let file = File::open(path)
+        .with_context(|| format!("Error opening dhat out file '{}'", path.display()))?;
+
+    let data: DhatData =
+        serde_json::from_reader(BufReader::new(file)).map_err(|error| -> anyhow::Error {
+            Error::ParseError((path.to_owned(), error.to_string())).into()
+        })?;
+
+    let mut sites: IndexMap<Vec<String>, Costs<String>> = IndexMap::new();
+    for program_point in &data.program_points {
+        let frames = program_point
+            .frames
+            .iter()
+            .map(|index| {
+                data.frame_table.get(*index).ok_or_else(|| -> anyhow::Error {
+                    Error::ParseError((
+                        path.to_owned(),
+                        format!("Invalid index into the frame table: {index}"),
+                    ))
+                    .into()
+                })
+            })
+            .filter(|frame| !matches!(frame, Ok(frame) if *frame == "[root]"))
+            .map(|frame| frame.map(|f| demangle_frame(f)))
+            .collect::<Result<Vec<String>>>()?;
+
+        let costs = program_point.to_costs();
+        if let Some(existing) = sites.get_mut(&frames) {
+            existing.add(&costs);
+        } else {
+            sites.insert(frames, costs);
+        }
+    }
+
+    let mut sites = sites
+        .into_iter()
+        .map(|(frames, costs)| AllocationSite { frames, costs })
+        .collect::<Vec<AllocationSite>>();
+    sites.sort_by_key(|site| Reverse(site.total_bytes()));
+    Ok(sites)
+}
+
+/// Compare the top allocation sites of the `new` run with the allocation sites of the `old` run
+///
+/// The allocation sites are matched by their frames. The top allocation sites of the `old` run
+/// which are not among the top allocation sites of the `new` run follow the `new` ones, so
+/// allocation sites which shrank or disappeared are part of the comparison, too.
+pub fn summarize_allocation_sites(
+    new: &[AllocationSite],
+    old: &[AllocationSite],
+) -> Vec<AllocationSiteSummary> {
+    fn find_costs<'a>(sites: &'a [AllocationSite], frames: &[String]) -> Option<&'a Costs<String>> {
+        sites
+            .iter()
+            .find(|site| site.frames == frames)
+            .map(|site| &site.costs)
+    }
+
+    let mut summaries = new
+        .iter()
+        .take(TOP_ALLOCATION_SITES)
+        .map(|site| AllocationSiteSummary {
+            frames: site.frames.clone(),
+            costs_summary: CostsSummary::new(&site.costs, find_costs(old, &site.frames)),
+        })
+        .collect::<Vec<AllocationSiteSummary>>();
+
+    for site in old.iter().take(TOP_ALLOCATION_SITES) {
+        if summaries.iter().any(|summary| summary.frames == site.frames) {
+            continue;
+        }
+        let empty = Costs::empty();
+        let new_costs = find_costs(new, &site.frames).unwrap_or(&empty);
+        summaries.push(AllocationSiteSummary {
+            frames: site.frames.clone(),
+            costs_summary: CostsSummary::new(new_costs, Some(&site.costs)),
+        });
+    }
+
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::no_address("main", "main")]
+    #[case::address("0x10A2C3: main (bench.rs:20)", "main (bench.rs:20)")]
+    #[case::mangled(
+        "0x10A1B2: _ZN5bench8my_bench17h0123456789abcdefE (bench.rs:12)",
+        "bench::my_bench (bench.rs:12)"
+    )]
+    #[case::demangled_with_hash(
+        "0x10A1D4: bench::setup::h0123456789abcdef (bench.rs:4)",
+        "bench::setup (bench.rs:4)"
+    )]
+    #[case::object(
+        "0x4848899: malloc (in /usr/libexec/valgrind/vgpreload_dhat-amd64-linux.so)",
+        "malloc (in /usr/libexec/valgrind/vgpreload_dhat-amd64-linux.so)"
+    )]
+    #[case::unknown("0x4848899: ???", "???")]
+    fn test_demangle_frame(#[case] frame: &str, #[case] expected: &str) {
+        assert_eq!(demangle_frame(frame), expected);
+    }
+}
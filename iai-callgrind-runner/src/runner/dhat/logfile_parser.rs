@@ -7,12 +7,15 @@ use anyhow::{Context, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use super::json_parser::parse_dhat_out;
 use crate::error::Error;
 use crate::runner::costs::Costs;
 use crate::runner::summary::ToolRunSummary;
 use crate::runner::tool::logfile_parser::{
-    extract_pid, LogfileParser, LogfileSummary, EMPTY_LINE_RE, EXTRACT_FIELDS_RE, STRIP_PREFIX_RE,
+    extract_pid, parse_with_out_files, LogfileParser, LogfileSummary, EMPTY_LINE_RE,
+    EXTRACT_FIELDS_RE, STRIP_PREFIX_RE,
 };
+use crate::runner::tool::ToolOutputPath;
 use crate::util::make_relative;
 
 // The different regex have to consider --time-stamp=yes
@@ -152,8 +155,19 @@ impl LogfileParser for DhatLogfileParser {
             fields: vec![],
             details,
             error_summary: None,
+            error_reports: vec![],
             log_path: make_relative(&self.root_dir, path),
             costs,
+            allocation_sites: vec![],
+            basic_block_vector: None,
+        })
+    }
+
+    /// Parse the DHAT log files and the allocation sites of the accompanying `dhat.out` files
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Vec<LogfileSummary>> {
+        parse_with_out_files(self, output_path, |summary, out_file| {
+            summary.allocation_sites = parse_dhat_out(out_file)?;
+            Ok(())
         })
     }
 
@@ -162,26 +176,34 @@ impl LogfileParser for DhatLogfileParser {
         old: Vec<LogfileSummary>,
         new: Vec<LogfileSummary>,
     ) -> Vec<ToolRunSummary> {
-        let old = old.into_iter().map(Some).chain(iter::repeat_with(|| None));
-        let new = new.into_iter().map(Some).chain(iter::repeat_with(|| None));
-        let zip = iter::zip(old, new).take_while(|(o, n)| o.is_some() || n.is_some());
-
-        let mut res = vec![];
-        for (old, new) in zip {
-            match (old, new) {
-                (None, None) => unreachable!(),
-                (Some(old), None) => res.push(old.old_into_tool_run()),
-                (None, Some(new)) => res.push(new.new_into_tool_run()),
-                (Some(old), Some(new)) => {
-                    if old.command == new.command {
-                        res.push(new.merge(&old));
-                    } else {
-                        res.push(old.old_into_tool_run());
-                        res.push(new.new_into_tool_run());
-                    }
+        merge_by_command(old, new)
+    }
+}
+
+/// Merge the old and new summaries in order of their pids, pairing summaries with same command
+pub fn merge_by_command(
+    old: Vec<LogfileSummary>,
+    new: Vec<LogfileSummary>,
+) -> Vec<ToolRunSummary> {
+    let old = old.into_iter().map(Some).chain(iter::repeat_with(|| None));
+    let new = new.into_iter().map(Some).chain(iter::repeat_with(|| None));
+    let zip = iter::zip(old, new).take_while(|(o, n)| o.is_some() || n.is_some());
+
+    let mut res = vec![];
+    for (old, new) in zip {
+        match (old, new) {
+            (None, None) => unreachable!(),
+            (Some(old), None) => res.push(old.old_into_tool_run()),
+            (None, Some(new)) => res.push(new.new_into_tool_run()),
+            (Some(old), Some(new)) => {
+                if old.command == new.command {
+                    res.push(new.merge(&old));
+                } else {
+                    res.push(old.old_into_tool_run());
+                    res.push(new.new_into_tool_run());
                 }
             }
         }
-        res
     }
+    res
 }
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::logfile_parser::merge_by_command;
+use crate::error::Error;
+use crate::runner::costs::Costs;
+use crate::runner::summary::ToolRunSummary;
+use crate::runner::tool::logfile_parser::{
+    parse_with_out_files, LogfileParser, LogfileSummary, ToolLogfileParser,
+};
+use crate::runner::tool::ToolOutputPath;
+
+lazy_static! {
+    static ref HEAP_TREE_NODE_RE: Regex = regex::Regex::new(
+        r"^(?<indent>\s*)n[0-9]+: (?<bytes>[0-9]+) (?:0x[0-9a-fA-F]+: )?(?<label>.*)$"
+    )
+    .expect("Regex should compile");
+}
+
+pub const PEAK_HEAP_BYTES: &str = "Peak heap bytes";
+pub const PEAK_EXTRA_BYTES: &str = "Peak extra bytes";
+pub const PEAK_STACKS_BYTES: &str = "Peak stacks bytes";
+pub const SNAPSHOTS: &str = "Snapshots";
+
+/// The parser for the log files and `massif.out` files of massif
+///
+/// The log file provides the command, pids and details. The costs are extracted from the
+/// snapshots of the accompanying `massif.out` file.
+pub struct MassifLogfileParser {
+    pub root_dir: PathBuf,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Snapshot {
+    heap: u64,
+    extra: u64,
+    stacks: u64,
+    is_peak: bool,
+    tree: Vec<(String, u64)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum State {
+    Header,
+    Snapshot,
+    Tree,
+}
+
+impl Snapshot {
+    fn total(&self) -> u64 {
+        self.heap + self.extra
+    }
+}
+
+/// Parse the snapshots of a `massif.out` file
+///
+/// The peak is the snapshot marked with `heap_tree=peak`. If there's no such snapshot, the
+/// snapshot with the highest total of heap and extra heap bytes is used. The allocation tree of the
+/// peak is flattened into indented entries excluding the root node and with the addresses removed,
+/// so that the entries can be compared between different runs.
+pub fn parse_massif_out(path: &Path) -> Result<Costs<String>> {
+    let file = File::open(path)
+        .with_context(|| format!("Error opening massif out file '{}'", path.display()))?;
+
+    let mut snapshots: Vec<Snapshot> = vec![];
+    let mut state = State::Header;
+    for line in BufReader::new(file)
+        .lines()
+        .map(std::result::Result::unwrap)
+    {
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with("snapshot=") {
+            snapshots.push(Snapshot::default());
+            state = State::Snapshot;
+            continue;
+        }
+
+        match (state, snapshots.last_mut()) {
+            (State::Header, _) | (_, None) => {}
+            (State::Snapshot, Some(snapshot)) => {
+                if let Some((key, value)) = line.split_once('=') {
+                    let parse = || {
+                        value.parse::<u64>().map_err(|error| -> anyhow::Error {
+                            Error::ParseError((
+                                path.to_owned(),
+                                format!("Invalid value of '{key}': {error}"),
+                            ))
+                            .into()
+                        })
+                    };
+                    match key {
+                        "mem_heap_B" => snapshot.heap = parse()?,
+                        "mem_heap_extra_B" => snapshot.extra = parse()?,
+                        "mem_stacks_B" => snapshot.stacks = parse()?,
+                        "heap_tree" => {
+                            snapshot.is_peak = value == "peak";
+                            if value != "empty" {
+                                state = State::Tree;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            (State::Tree, Some(snapshot)) => {
+                if let Some(caps) = HEAP_TREE_NODE_RE.captures(&line) {
+                    // The root node is just the sum of all allocations in the heap
+                    let depth = caps.name("indent").unwrap().as_str().len();
+                    if depth == 0 {
+                        continue;
+                    }
+                    let bytes = caps.name("bytes").unwrap().as_str().parse().unwrap();
+                    let label = caps.name("label").unwrap().as_str();
+                    snapshot
+                        .tree
+                        .push((format!("{}{label}", "  ".repeat(depth - 1)), bytes));
+                }
+            }
+        }
+    }
+
+    if snapshots.is_empty() {
+        return Err(Error::ParseError((path.to_owned(), "No snapshots found".to_owned())).into());
+    }
+
+    let peak = snapshots
+        .iter()
+        .find(|s| s.is_peak)
+        .or_else(|| snapshots.iter().max_by_key(|s| s.total()))
+        .expect("At least one snapshot should be present");
+
+    let mut costs = Costs::with_event_kinds([
+        (PEAK_HEAP_BYTES.to_owned(), peak.heap),
+        (PEAK_EXTRA_BYTES.to_owned(), peak.extra),
+        (PEAK_STACKS_BYTES.to_owned(), peak.stacks),
+        (SNAPSHOTS.to_owned(), snapshots.len() as u64),
+    ]);
+    // Equal entries on the same level of the tree are summarized
+    for (label, bytes) in &peak.tree {
+        *costs.0.entry(label.clone()).or_insert(0) += bytes;
+    }
+
+    Ok(costs)
+}
+
+impl LogfileParser for MassifLogfileParser {
+    /// Parse the massif log file
+    ///
+    /// The costs are not part of the log file and are added in [`MassifLogfileParser::parse`]
+    fn parse_single(&self, path: PathBuf) -> Result<LogfileSummary> {
+        ToolLogfileParser {
+            root_dir: self.root_dir.clone(),
+        }
+        .parse_single(path)
+    }
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Vec<LogfileSummary>> {
+        parse_with_out_files(self, output_path, |summary, out_file| {
+            summary.costs = Some(parse_massif_out(out_file)?);
+            Ok(())
+        })
+    }
+
+    fn merge_logfile_summaries(
+        &self,
+        old: Vec<LogfileSummary>,
+        new: Vec<LogfileSummary>,
+    ) -> Vec<ToolRunSummary> {
+        merge_by_command(old, new)
+    }
+}
+
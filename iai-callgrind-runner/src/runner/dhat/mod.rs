@@ -1 +1,4 @@
+pub mod flamegraph;
+pub mod json_parser;
 pub mod logfile_parser;
+pub mod massif_parser;
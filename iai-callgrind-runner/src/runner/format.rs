@@ -9,7 +9,10 @@ use super::bin_bench::BinBench;
 use super::common::ModulePath;
 use super::lib_bench::LibBench;
 use super::meta::Metadata;
-use super::summary::{CostsDiff, CostsSummary};
+use super::summary::{
+    CallgrindDump, CallgrindProcess, CostsDiff, CostsSummary, FunctionCostsSummary,
+};
+use super::tool::format::print_compare;
 use super::tool::ValgrindTool;
 use crate::api::{self, EventKind};
 use crate::util::{make_relative, to_string_signed_short, truncate_str_utf8};
@@ -25,6 +28,7 @@ pub struct ComparisonHeader {
 pub struct BinaryBenchmarkHeader {
     inner: Header,
     has_tools_enabled: bool,
+    default_tool: ValgrindTool,
     output_format: OutputFormat,
 }
 
@@ -60,6 +64,7 @@ pub trait Formatter {
 pub struct LibraryBenchmarkHeader {
     inner: Header,
     has_tools_enabled: bool,
+    default_tool: ValgrindTool,
     output_format: OutputFormat,
 }
 
@@ -105,11 +110,12 @@ impl BinaryBenchmarkHeader {
         Self {
             inner: Header::new(
                 &bin_bench.module_path,
-                bin_bench.id.clone(),
+                header_id(bin_bench.id.as_ref(), bin_bench.cache_name.as_ref()),
                 Some(description),
                 bin_bench.truncate_description,
             ),
             has_tools_enabled: bin_bench.tools.has_tools_enabled(),
+            default_tool: bin_bench.default_tool.clone(),
             output_format: meta.args.output_format,
         }
     }
@@ -118,7 +124,7 @@ impl BinaryBenchmarkHeader {
         if self.output_format == OutputFormat::Default {
             self.inner.print();
             if self.has_tools_enabled {
-                println!("{}", tool_headline(ValgrindTool::Callgrind));
+                println!("{}", tool_headline(&self.default_tool));
             }
         }
     }
@@ -243,7 +249,7 @@ impl LibraryBenchmarkHeader {
     pub fn new(meta: &Metadata, lib_bench: &LibBench) -> Self {
         let header = Header::new(
             &lib_bench.module_path,
-            lib_bench.id.clone(),
+            header_id(lib_bench.id.as_ref(), lib_bench.cache_name.as_ref()),
             lib_bench.args.clone(),
             lib_bench.truncate_description,
         );
@@ -251,6 +257,7 @@ impl LibraryBenchmarkHeader {
         Self {
             inner: header,
             has_tools_enabled: lib_bench.tools.has_tools_enabled(),
+            default_tool: lib_bench.default_tool.clone(),
             output_format: meta.args.output_format,
         }
     }
@@ -259,7 +266,7 @@ impl LibraryBenchmarkHeader {
         if self.output_format == OutputFormat::Default {
             self.inner.print();
             if self.has_tools_enabled {
-                println!("{}", tool_headline(ValgrindTool::Callgrind));
+                println!("{}", tool_headline(&self.default_tool));
             }
         }
     }
@@ -403,7 +410,86 @@ pub fn format_vertical<'a, K: Display + 'a>(
     Ok(result)
 }
 
-pub fn tool_headline(tool: ValgrindTool) -> String {
+/// Format the exclusive and inclusive costs of the `functions` in the vertical format
+pub fn format_function_costs(functions: &[FunctionCostsSummary]) -> Result<String> {
+    let mut result = String::new();
+    for (rank, function) in functions.iter().enumerate() {
+        let file = function
+            .file
+            .as_ref()
+            .map_or_else(String::new, |file| format!(" ({})", file.display()));
+        writeln!(
+            result,
+            "  {:<18}{}{}",
+            format!("Function #{}:", rank + 1),
+            function.function.blue(),
+            file.bright_black()
+        )?;
+
+        let descriptions = function
+            .exclusive
+            .all_diffs()
+            .map(|(event_kind, diff)| (format!("Excl. {event_kind}"), diff))
+            .chain(
+                function
+                    .inclusive
+                    .all_diffs()
+                    .map(|(event_kind, diff)| (format!("Incl. {event_kind}"), diff)),
+            )
+            .collect::<Vec<(String, &CostsDiff)>>();
+        result.push_str(&format_vertical(
+            (None, None),
+            descriptions.iter().map(|(description, diff)| (description, *diff)),
+        )?);
+    }
+    Ok(result)
+}
+
+/// Print the costs of the top functions below the costs of the whole benchmark run
+pub fn print_function_costs(meta: &Metadata, functions: &[FunctionCostsSummary]) -> Result<()> {
+    if meta.args.output_format == OutputFormat::Default {
+        print!("{}", format_function_costs(functions)?);
+    }
+    Ok(())
+}
+
+/// Print the part number and trigger of a single dump above the costs of this dump
+pub fn print_dump_headline(meta: &Metadata, dump: &CallgrindDump) {
+    if meta.args.output_format == OutputFormat::Default {
+        println!(
+            "  {:<18}{}",
+            format!("Dump #{}:", dump.part),
+            dump.trigger.blue()
+        );
+    }
+}
+
+/// Print the command and the new and old pids of a traced process above the costs of this process
+pub fn print_process_headline(meta: &Metadata, process: &CallgrindProcess) {
+    if meta.args.output_format == OutputFormat::Default {
+        println!("  {:<18}{}", "Command:", process.command.blue().bold());
+        let should_compare = process.old_pid.is_some();
+        print_compare("PID:", process.old_pid, process.pid, should_compare);
+        print_compare(
+            "Parent PID:",
+            process.old_parent_pid,
+            process.parent_pid,
+            should_compare,
+        );
+        if !process.is_checked {
+            println!("  {:<18}{}", "Regressions:", "not checked".bright_black());
+        }
+    }
+}
+
+/// Print the thread index above the costs of a single thread
+pub fn print_thread_headline(meta: &Metadata, thread: usize) {
+    if meta.args.output_format == OutputFormat::Default {
+        println!("  {}", format!("Thread #{thread}:").blue());
+    }
+}
+
+pub fn tool_headline(tool: &ValgrindTool) -> String {
     let id = tool.id();
     format!(
         "  {} {} {}",
@@ -459,6 +545,15 @@ pub fn print_no_capture_footer(
     }
 }
 
+/// Return the `id` of a benchmark with the `cache_name` of the cache matrix entry appended
+fn header_id(id: Option<&String>, cache_name: Option<&String>) -> Option<String> {
+    match (id, cache_name) {
+        (Some(id), Some(cache_name)) => Some(format!("{id}.cache@{cache_name}")),
+        (None, Some(cache_name)) => Some(format!("cache@{cache_name}")),
+        (id, None) => id.cloned(),
+    }
+}
+
 fn truncate_description(description: &str, truncate_description: Option<usize>) -> Cow<'_, str> {
     if let Some(num) = truncate_description {
         let new_description = truncate_str_utf8(description, num);
@@ -481,6 +576,25 @@ mod tests {
     use super::*;
     use crate::runner::costs::Costs;
 
+    #[rstest]
+    #[case::no_cache(Some("id"), None, Some("id"))]
+    #[case::cache(Some("id"), Some("small"), Some("id.cache@small"))]
+    #[case::cache_but_no_id(None, Some("small"), Some("cache@small"))]
+    #[case::nothing(None, None, None)]
+    fn test_header_id(
+        #[case] id: Option<&str>,
+        #[case] cache_name: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let id = id.map(ToOwned::to_owned);
+        let cache_name = cache_name.map(ToOwned::to_owned);
+
+        assert_eq!(
+            header_id(id.as_ref(), cache_name.as_ref()),
+            expected.map(ToOwned::to_owned)
+        );
+    }
+
     #[rstest]
     #[case::simple("some::module", Some("id"), Some("1, 2"), "some::module id:1, 2")]
     #[case::id_but_no_description("some::module", Some("id"), None, "some::module id")]
@@ -6,23 +6,39 @@ use std::ffi::OsString;
 use std::io::stderr;
 
 use anyhow::Result;
+use log::warn;
 
+use super::args::NoCapture;
+use super::cachegrind::args::Args as CachegrindArgs;
+use super::cachegrind::summary_parser::SummaryParser as CachegrindSummaryParser;
+use super::callgrind::annotate::{self, AnnotateConfig, LineCostsMap};
 use super::callgrind::args::Args;
+use super::callgrind::cache::{self, CacheRun};
+use super::callgrind::call_graph::{self, CallGraphConfig};
+use super::callgrind::collect::{CollectFilter, CollectParser};
+use super::callgrind::dumps::{self, Dump, DumpCostsSummary};
 use super::callgrind::flamegraph::{
     BaselineFlamegraphGenerator, Config as FlamegraphConfig, Flamegraph, FlamegraphGenerator,
     LoadBaselineFlamegraphGenerator, SaveBaselineFlamegraphGenerator,
 };
+use super::callgrind::function_costs::{self, FunctionCostsConfig};
+use super::callgrind::hashmap_parser::CallgrindMap;
+use super::callgrind::model::Costs;
 use super::callgrind::parser::Sentinel;
+use super::callgrind::pprof::PprofConfig;
 use super::callgrind::sentinel_parser::SentinelParser;
-use super::callgrind::RegressionConfig;
+use super::callgrind::summary_parser::SummaryParser;
+use super::callgrind::{CostModel, RegressionConfig};
 use super::common::{Assistant, AssistantKind, Config, ModulePath};
 use super::format::{
-    print_no_capture_footer, LibraryBenchmarkHeader, OutputFormat, VerticalFormat,
+    print_dump_headline, print_function_costs, print_no_capture_footer, LibraryBenchmarkHeader,
+    OutputFormat, VerticalFormat,
 };
 use super::meta::Metadata;
 use super::summary::{
-    BaselineKind, BaselineName, BenchmarkKind, BenchmarkSummary, CallgrindRegressionSummary,
-    CallgrindSummary, CostsSummary, SummaryOutput,
+    AnnotatedSourceSummary, BaselineKind, BaselineName, BenchmarkKind, BenchmarkSummary,
+    CachegrindSummary, CallGraphSummary, CallgrindRegressionSummary, CallgrindSummary,
+    CallgrindThreadSummary, CostsSummary, FunctionCostsSummary, PprofSummary, SummaryOutput,
 };
 use super::tool::{
     Parser, RunOptions, ToolCommand, ToolConfig, ToolConfigs, ToolOutputPath, ToolOutputPathKind,
@@ -41,6 +57,53 @@ struct BaselineBenchmark {
     baseline_kind: BaselineKind,
 }
 
+/// The costs of the calibration runs measuring the overhead of the benchmark harness
+///
+/// Each bench has its own calibration run with the arguments of the bench. The calibration run
+/// directly precedes the run of the bench.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Calibration {
+    /// The costs of the new calibration run
+    new: Option<Costs>,
+    /// The costs of the old or base calibration run
+    old: Option<Costs>,
+}
+
+impl Calibration {
+    /// Subtract the harness overhead from the `new` and `old` costs
+    ///
+    /// Returns `None` if there is no new calibration run or if the overhead is not smaller than the
+    /// costs, so the adjusted costs would be zero. Without an old calibration run, the overhead of
+    /// the new calibration run is subtracted from the `old` costs.
+    fn apply(&self, new: &Costs, old: Option<&Costs>) -> Option<(Costs, Option<Costs>)> {
+        let new_overhead = self.new.as_ref()?;
+        let old_overhead = self.old.as_ref().unwrap_or(new_overhead);
+
+        if !Self::is_smaller(new_overhead, new)
+            || !old.map_or(true, |old| Self::is_smaller(old_overhead, old))
+        {
+            return None;
+        }
+
+        let mut new = new.clone();
+        new.saturating_sub(new_overhead);
+        let old = old.map(|old| {
+            let mut old = old.clone();
+            old.saturating_sub(old_overhead);
+            old
+        });
+        Some((new, old))
+    }
+
+    /// Return true if each non-zero cost of the `overhead` is smaller than the cost of the same
+    /// event kind in `costs`
+    fn is_smaller(overhead: &Costs, costs: &Costs) -> bool {
+        overhead.iter().all(|(kind, overhead)| {
+            *overhead == 0 || costs.cost_by_kind(kind).map_or(true, |cost| cost > *overhead)
+        })
+    }
+}
+
 // A `Group` is the organizational unit and counterpart of the `library_benchmark_group!` macro
 #[derive(Debug)]
 struct Group {
@@ -59,7 +122,7 @@ struct Groups(Vec<Group>);
 /// A `LibBench` represents a single benchmark under the `#[library_benchmark]` attribute macro
 ///
 /// It needs an implementation of `Benchmark` to be run.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LibBench {
     pub bench_index: usize,
     pub index: usize,
@@ -68,11 +131,21 @@ pub struct LibBench {
     pub args: Option<String>,
     pub run_options: RunOptions,
     pub callgrind_args: Args,
+    pub cache_name: Option<String>,
+    pub cachegrind_args: CachegrindArgs,
+    pub default_tool: ValgrindTool,
     pub flamegraph_config: Option<FlamegraphConfig>,
+    pub function_costs_config: Option<FunctionCostsConfig>,
+    pub annotate_config: Option<AnnotateConfig>,
+    pub call_graph_config: Option<CallGraphConfig>,
+    pub pprof_config: Option<PprofConfig>,
+    pub collect_filter: Option<CollectFilter>,
+    pub cost_model: CostModel,
     pub regression_config: Option<RegressionConfig>,
     pub tools: ToolConfigs,
     pub module_path: ModulePath,
     pub entry_point: Option<String>,
+    pub harness_calibration: bool,
     pub truncate_description: Option<usize>,
 }
 
@@ -110,15 +183,22 @@ struct SaveBaselineBenchmark {
 trait Benchmark: std::fmt::Debug {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath;
     fn baselines(&self) -> (Option<String>, Option<String>);
-    fn run(&self, lib_bench: &LibBench, config: &Config, group: &Group)
-    -> Result<BenchmarkSummary>;
+    fn calibrate(&self, lib_bench: &LibBench, config: &Config, group: &Group)
+    -> Result<Calibration>;
+    fn run(
+        &self,
+        lib_bench: &LibBench,
+        config: &Config,
+        group: &Group,
+        calibration: Option<&Calibration>,
+    ) -> Result<BenchmarkSummary>;
 }
 
 impl Benchmark for BaselineBenchmark {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Out,
-            ValgrindTool::Callgrind,
+            lib_bench.default_tool.clone(),
             &self.baseline_kind,
             &config.meta.target_dir,
             &group.module_path,
@@ -133,27 +213,49 @@ impl Benchmark for BaselineBenchmark {
         }
     }
 
+    fn calibrate(
+        &self,
+        lib_bench: &LibBench,
+        config: &Config,
+        group: &Group,
+    ) -> Result<Calibration> {
+        let out_path = ToolOutputPath::new(
+            ToolOutputPathKind::Out,
+            lib_bench.default_tool.clone(),
+            &self.baseline_kind,
+            &config.meta.target_dir,
+            &group.module_path,
+            &lib_bench.calibration_name(),
+        );
+        out_path.init()?;
+        out_path.shift()?;
+        out_path.to_log_output().shift()?;
+
+        lib_bench.run_calibration(config, group, &out_path)?;
+
+        Ok(Calibration {
+            new: lib_bench.parse_calibration(&out_path)?,
+            old: lib_bench.parse_calibration(&out_path.to_base_path())?,
+        })
+    }
+
     fn run(
         &self,
         lib_bench: &LibBench,
         config: &Config,
         group: &Group,
+        calibration: Option<&Calibration>,
     ) -> Result<BenchmarkSummary> {
         let header = LibraryBenchmarkHeader::new(&config.meta, lib_bench);
         header.print();
 
-        let callgrind_command = ToolCommand::new(
-            ValgrindTool::Callgrind,
+        let command = ToolCommand::new(
+            lib_bench.default_tool.clone(),
             &config.meta,
             config.meta.args.nocapture,
         );
 
-        let mut callgrind_args = lib_bench.callgrind_args.clone();
-        if let Some(entry_point) = lib_bench.entry_point.as_ref() {
-            callgrind_args.insert_toggle_collect(entry_point);
-        }
-
-        let tool_config = ToolConfig::new(ValgrindTool::Callgrind, true, callgrind_args, None);
+        let tool_config = lib_bench.default_tool_config();
 
         let bench_args = lib_bench.bench_args(group);
 
@@ -178,11 +280,11 @@ impl Benchmark for BaselineBenchmark {
             header.description(),
         )?;
 
-        let output = callgrind_command.run(
+        let output = command.run(
             tool_config,
             &config.bench_bin,
             &bench_args,
-            lib_bench.run_options.clone(),
+            lib_bench.default_tool_run_options(),
             &out_path,
             &lib_bench.module_path,
             None,
@@ -194,45 +296,63 @@ impl Benchmark for BaselineBenchmark {
             lib_bench.run_options.stderr.as_ref(),
         );
 
-        let new_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
-
-        let old_costs = old_path
-            .exists()
-            .then(|| SentinelParser::new(&sentinel).parse(&old_path))
-            .transpose()?;
+        let new_dumps = lib_bench.parse_dumps(&config.meta, &sentinel, &out_path)?;
+        let old_dumps = if old_path.exists() {
+            lib_bench.parse_dumps(&config.meta, &sentinel, &old_path)?
+        } else {
+            vec![]
+        };
+        let old_lines = lib_bench.parse_line_costs(&config.meta, &old_path)?;
+        let old_calls = lib_bench.parse_call_graph(&config.meta, &old_path)?;
 
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
-        VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
+        let dumps = lib_bench.summarize_dumps(
+            &config.meta,
+            &self.baselines(),
+            &new_dumps,
+            &old_dumps,
+            calibration,
+        )?;
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = lib_bench.check_and_print_regressions(&costs_summary);
-
-        let callgrind_summary = benchmark_summary
-            .callgrind_summary
-            .insert(CallgrindSummary::new(
-                log_path.real_paths()?,
-                out_path.real_paths()?,
-            ));
-
-        callgrind_summary.add_summary(
-            &config.bench_bin,
+        if let Some(callgrind_summary) = lib_bench.add_default_tool_summary(
+            &mut benchmark_summary,
+            config,
             &bench_args,
-            &old_path,
-            costs_summary,
-            regressions,
-        );
-
-        if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
-            callgrind_summary.flamegraphs = BaselineFlamegraphGenerator {
-                baseline_kind: self.baseline_kind.clone(),
+            &log_path,
+            &out_path,
+            dumps,
+        )? {
+            if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
+                callgrind_summary.flamegraphs = BaselineFlamegraphGenerator {
+                    baseline_kind: self.baseline_kind.clone(),
+                }
+                .create_all(
+                    &Flamegraph::new(header.to_title(), flamegraph_config),
+                    &out_path,
+                    Some(&sentinel),
+                    &config.meta.project_root,
+                )?;
             }
-            .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+
+            callgrind_summary.annotated_source = lib_bench.annotate(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                old_lines.as_ref(),
+            )?;
+            callgrind_summary.call_graph = lib_bench.create_call_graph(
+                &header.to_title(),
+                &config.meta,
                 &out_path,
-                Some(&sentinel),
-                &config.meta.project_root,
+                old_calls.as_ref(),
+            )?;
+            callgrind_summary.pprof = lib_bench.export_pprof(
+                &config.meta,
+                &out_path,
+                Some(&old_path),
+                &sentinel,
             )?;
         }
 
@@ -264,6 +384,7 @@ impl Groups {
         let global_config = benchmark_groups.config;
         let mut groups = vec![];
         let meta_callgrind_args = meta.args.callgrind_args.clone().unwrap_or_default();
+        let meta_cachegrind_args = meta.args.cachegrind_args.clone().unwrap_or_default();
 
         for library_benchmark_group in benchmark_groups.groups {
             let group_module_path = module_path.join(&library_benchmark_group.id);
@@ -313,12 +434,105 @@ impl Groups {
                     ]);
                     let envs = config.resolve_envs();
 
-                    let callgrind_args =
-                        Args::from_raw_args(&[&config.raw_callgrind_args, &meta_callgrind_args])?;
+                    let mut cachegrind_args = CachegrindArgs::from_raw_args(&[
+                        &config.raw_cachegrind_args,
+                        &meta_cachegrind_args,
+                    ])?;
+                    // The benchmark harness starts the instrumentation right before the benchmark
+                    // function
+                    cachegrind_args.set_default_instr_at_start(false);
+                    let default_tool =
+                        ValgrindTool::resolve_default_tool(meta, config.default_tool.clone())?;
 
-                    let flamegraph_config = config.flamegraph_config.map(Into::into);
                     let module_path =
                         group_module_path.join(&library_benchmark_bench.function_name);
+                    let cache_runs =
+                        match (&default_tool, &config.cache_config, &config.cache_matrix) {
+                            (ValgrindTool::Cachegrind, None, None) => vec![CacheRun::default()],
+                            (ValgrindTool::Cachegrind, ..) => {
+                                warn!(
+                                    "{module_path}: Ignoring cache configuration: The typed \
+                                     cache configuration is only available if callgrind is the \
+                                     default tool"
+                                );
+                                vec![CacheRun::default()]
+                            }
+                            (_, cache_config, cache_matrix) => cache::resolve(
+                                &module_path,
+                                cache_config.as_ref(),
+                                cache_matrix.as_deref(),
+                            )?,
+                        };
+                    let cost_model = api::update_option(&config.cost_model, &meta.cost_model)
+                        .map(Into::into)
+                        .unwrap_or_default();
+                    let flamegraph_config = default_tool
+                        .callgrind_only(
+                            &module_path,
+                            "flamegraph configuration",
+                            config.flamegraph_config,
+                        )
+                        .map(|config| FlamegraphConfig {
+                            cost_model,
+                            ..config.into()
+                        });
+                    let function_costs_config = default_tool
+                        .callgrind_only(
+                            &module_path,
+                            "function costs configuration",
+                            config.function_costs_config,
+                        )
+                        .map(Into::into);
+                    let annotate_config = default_tool
+                        .callgrind_only(
+                            &module_path,
+                            "annotate configuration",
+                            config.annotate_config,
+                        )
+                        .map(Into::into);
+                    let call_graph_config = default_tool
+                        .callgrind_only(
+                            &module_path,
+                            "call graph configuration",
+                            config.call_graph_config,
+                        )
+                        .map(Into::into);
+                    let pprof_config = default_tool
+                        .callgrind_only(&module_path, "pprof configuration", config.pprof_config)
+                        .map(|config| PprofConfig {
+                            cost_model,
+                            ..config.into()
+                        });
+                    let collect_filter = default_tool
+                        .callgrind_only(
+                            &module_path,
+                            "collect configuration",
+                            config.collect_config,
+                        )
+                        .map(|config| CollectFilter::new(&config, &module_path))
+                        .transpose()?;
+                    let harness_calibration = match (
+                        &default_tool,
+                        &collect_filter,
+                        config.harness_calibration.unwrap_or(false),
+                    ) {
+                        (_, _, false) => false,
+                        (ValgrindTool::Cachegrind, _, true) => {
+                            warn!(
+                                "{module_path}: Ignoring harness calibration: The calibration is \
+                                 only available if callgrind is the default tool"
+                            );
+                            false
+                        }
+                        (_, Some(filter), true) if !filter.collect_only.is_empty() => {
+                            warn!(
+                                "{module_path}: Ignoring harness calibration: The calibration is \
+                                 not available if only a set of functions is collected"
+                            );
+                            false
+                        }
+                        (_, _, true) => true,
+                    };
 
                     let lib_bench = LibBench {
                         bench_index,
@@ -327,23 +541,51 @@ impl Groups {
                         function_name: library_benchmark_bench.function_name,
                         args: library_benchmark_bench.args,
                         entry_point: Some(DEFAULT_TOGGLE.to_owned()),
+                        harness_calibration,
                         run_options: RunOptions {
                             env_clear: config.env_clear.unwrap_or(true),
                             envs,
                             ..Default::default()
                         },
-                        callgrind_args,
+                        callgrind_args: Args::default(),
+                        cache_name: None,
+                        cachegrind_args,
+                        default_tool: default_tool.clone(),
                         flamegraph_config,
+                        function_costs_config,
+                        annotate_config,
+                        call_graph_config,
+                        pprof_config,
+                        collect_filter,
+                        cost_model,
                         regression_config: api::update_option(
                             &config.regression_config,
                             &meta.regression_config,
                         )
                         .map(Into::into),
-                        tools: ToolConfigs(config.tools.0.into_iter().map(Into::into).collect()),
+                        tools: ToolConfigs::new(config.tools, &default_tool, meta),
                         module_path,
                         truncate_description: config.truncate_description.unwrap_or(Some(50)),
                     };
-                    group.benches.push(lib_bench);
+                    for cache_run in cache_runs {
+                        let mut callgrind_args = Args::from_raw_args_and_cache(
+                            &config.raw_callgrind_args,
+                            &cache_run.config,
+                            &meta_callgrind_args,
+                        )?;
+                        if lib_bench.annotate_config.is_some() {
+                            callgrind_args.set_annotate();
+                        }
+                        if let Some(flamegraph_config) = &lib_bench.flamegraph_config {
+                            callgrind_args
+                                .set_flamegraph_granularity(flamegraph_config.granularity);
+                        }
+                        group.benches.push(LibBench {
+                            callgrind_args,
+                            cache_name: cache_run.name,
+                            ..lib_bench.clone()
+                        });
+                    }
                 }
             }
 
@@ -369,9 +611,14 @@ impl Groups {
                     .regression_config
                     .as_ref()
                     .map_or(false, |r| r.fail_fast);
-                let summary = benchmark.run(bench, config, group)?;
+                let calibration = bench
+                    .harness_calibration
+                    .then(|| benchmark.calibrate(bench, config, group))
+                    .transpose()?;
+                let summary = benchmark.run(bench, config, group, calibration.as_ref())?;
                 summary.print_and_save(&config.meta.args.output_format)?;
-                summary.check_regression(&mut is_regressed, fail_fast)?;
+                summary.check_regression(&mut is_regressed, fail_fast, &bench.tools)?;
+                summary.check_tool_errors(&bench.tools)?;
 
                 if group.compare_by_id && config.meta.args.output_format == OutputFormat::Default {
                     if let Some(id) = &summary.id {
@@ -402,18 +649,91 @@ impl Groups {
 
 impl LibBench {
     /// The name of this `LibBench` consisting of the name of the benchmark function and if present,
-    /// the id of the bench attribute (`#[bench::ID(...)]`)
+    /// the id of the bench attribute (`#[bench::ID(...)]`) and the name of the cache matrix entry
     ///
     /// The name is used to identify a benchmark run within the same [`Group`] and has therefore to
     /// be unique within the same [`Group`]
     fn name(&self) -> String {
-        if let Some(bench_id) = &self.id {
+        let name = if let Some(bench_id) = &self.id {
             format!("{}.{}", &self.function_name, bench_id)
         } else {
             self.function_name.clone()
+        };
+        if let Some(cache_name) = &self.cache_name {
+            format!("{name}.cache@{cache_name}")
+        } else {
+            name
         }
     }
 
+    /// The name of the calibration run of this `LibBench`
+    ///
+    /// The name consists of the name of the benchmark function and if present, the id of the bench
+    /// attribute and the name of the cache matrix entry. The `@` can't be part of a bench id, so
+    /// the name doesn't clash with the name of another `LibBench`.
+    fn calibration_name(&self) -> String {
+        let name = if let Some(bench_id) = &self.id {
+            format!("{}.{}@calibration", &self.function_name, bench_id)
+        } else {
+            format!("{}@calibration", &self.function_name)
+        };
+        if let Some(cache_name) = &self.cache_name {
+            format!("{name}.cache@{cache_name}")
+        } else {
+            name
+        }
+    }
+
+    /// The arguments for the `bench_bin` to run the calibration of this `LibBench`
+    fn calibration_args(&self, group: &Group) -> Vec<OsString> {
+        vec![
+            OsString::from("--iai-run".to_owned()),
+            OsString::from(&group.id),
+            OsString::from("calibration"),
+            OsString::from(self.bench_index.to_string()),
+            OsString::from(self.index.to_string()),
+            OsString::from(self.module_path.to_string()),
+        ]
+    }
+
+    /// Run the calibration of this `LibBench` with the output at `out_path`
+    ///
+    /// The output of the calibration run is always captured.
+    fn run_calibration(
+        &self,
+        config: &Config,
+        group: &Group,
+        out_path: &ToolOutputPath,
+    ) -> Result<()> {
+        let output = ToolCommand::new(self.default_tool.clone(), &config.meta, NoCapture::False)
+            .run(
+                self.default_tool_config(),
+                &config.bench_bin,
+                &self.calibration_args(group),
+                self.run_options.clone(),
+                out_path,
+                &self.module_path,
+                None,
+            )?;
+        output.dump_log(log::Level::Debug);
+        Ok(())
+    }
+
+    /// Parse the total costs of the calibration run at `output_path` if it exists
+    fn parse_calibration(&self, output_path: &ToolOutputPath) -> Result<Option<Costs>> {
+        if !output_path.exists() {
+            return Ok(None);
+        }
+
+        let sentinel = Sentinel::default();
+        let dumps = dumps::parse(
+            output_path,
+            |path| self.parse_costs(&sentinel, path),
+            |_| Ok(None),
+        )?;
+        Ok(dumps.into_iter().last().map(|dump| dump.costs))
+    }
+
     /// The arguments for the `bench_bin` to actually run the benchmark function
     fn bench_args(&self, group: &Group) -> Vec<OsString> {
         vec![
@@ -425,6 +745,320 @@ impl LibBench {
         ]
     }
 
+    /// The callgrind [`Args`] with the `entry_point` inserted as first toggle
+    fn callgrind_args(&self) -> Args {
+        let mut callgrind_args = self.callgrind_args.clone();
+        if let Some(entry_point) = self.entry_point.as_ref() {
+            callgrind_args.insert_toggle_collect(entry_point);
+        }
+        callgrind_args
+    }
+
+    /// The [`ToolConfig`] of the default tool
+    fn default_tool_config(&self) -> ToolConfig {
+        if self.default_tool == ValgrindTool::Cachegrind {
+            ToolConfig::new(
+                ValgrindTool::Cachegrind,
+                true,
+                self.cachegrind_args.clone(),
+                None,
+            )
+        } else {
+            ToolConfig::new(ValgrindTool::Callgrind, true, self.callgrind_args(), None)
+        }
+    }
+
+    /// The [`RunOptions`] of the default tool
+    ///
+    /// If cachegrind doesn't start the instrumentation at the start of the program, the benchmark
+    /// harness is told to start and stop the instrumentation around the benchmark function.
+    fn default_tool_run_options(&self) -> RunOptions {
+        let mut run_options = self.run_options.clone();
+        if self.default_tool == ValgrindTool::Cachegrind
+            && !self.cachegrind_args.is_instr_at_start()
+        {
+            run_options.envs.push((
+                OsString::from(api::CACHEGRIND_INSTRUMENTATION_ENV),
+                OsString::from("yes"),
+            ));
+        }
+        run_options
+    }
+
+    /// Parse the [`Costs`] from the output file of the default tool
+    ///
+    /// Callgrind output files are parsed with the [`SentinelParser`]. Cachegrind has no concept
+    /// of toggles but only instruments the benchmark function, so the total costs of the
+    /// `summary:` line of cachegrind output files are used. If the threads are separated, only the
+    /// main thread runs the benchmark function, so the totals of the output files of all other
+    /// threads are used. With a [`CollectFilter`], the costs are calculated with the
+    /// [`CollectParser`] instead.
+    fn parse_costs(&self, sentinel: &Sentinel, output_path: &ToolOutputPath) -> Result<Costs> {
+        let is_main_thread = output_path.thread.map_or(true, |thread| thread == 1);
+        let mut costs = if self.default_tool == ValgrindTool::Cachegrind {
+            CachegrindSummaryParser.parse(output_path)
+        } else if let Some(filter) = &self.collect_filter {
+            CollectParser::new(filter, is_main_thread.then_some(sentinel)).parse(output_path)
+        } else if is_main_thread {
+            SentinelParser::new(sentinel).parse(output_path)
+        } else {
+            SummaryParser.parse(output_path)
+        }?;
+        self.cost_model.apply(&mut costs)?;
+        Ok(costs)
+    }
+
+    /// Parse the per-function costs if needed by the [`FunctionCostsConfig`] or the function
+    /// limits of the [`RegressionConfig`]
+    ///
+    /// Returns `None` if the per-function costs are not needed or the `output_path` doesn't exist.
+    fn parse_function_costs(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+    ) -> Result<Option<CallgrindMap>> {
+        let is_needed = self.function_costs_config.is_some()
+            || self
+                .regression_config
+                .as_ref()
+                .map_or(false, RegressionConfig::has_function_limits);
+        if self.default_tool != ValgrindTool::Cachegrind && is_needed && output_path.exists() {
+            let mut map = function_costs::parse(output_path, &meta.project_root)?;
+            map.make_summary(&self.cost_model)?;
+            Ok(Some(map))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse the costs of all dumps of the output of the default tool at `output_path`
+    ///
+    /// Only callgrind can split its output into multiple dumps and threads. All other outputs
+    /// consist of a single dump without part number and trigger.
+    fn parse_dumps(
+        &self,
+        meta: &Metadata,
+        sentinel: &Sentinel,
+        output_path: &ToolOutputPath,
+    ) -> Result<Vec<Dump>> {
+        if self.default_tool == ValgrindTool::Cachegrind {
+            Ok(vec![Dump {
+                costs: self.parse_costs(sentinel, output_path)?,
+                functions: None,
+                threads: vec![],
+                path: output_path.clone(),
+                part: None,
+                trigger: None,
+            }])
+        } else {
+            dumps::parse(
+                output_path,
+                |path| self.parse_costs(sentinel, path),
+                |path| self.parse_function_costs(meta, path),
+            )
+        }
+    }
+
+    /// Summarize and print the costs of all `new` dumps and check them for regressions
+    ///
+    /// Each dump is compared with the `old` dump of the same part. Only if there are multiple
+    /// dumps, the part number and trigger are printed and recorded in the summary. If there is a
+    /// single dump and a [`Calibration`], the harness overhead is subtracted from the total costs.
+    fn summarize_dumps(
+        &self,
+        meta: &Metadata,
+        baselines: &(Option<String>, Option<String>),
+        new: &[Dump],
+        old: &[Dump],
+        calibration: Option<&Calibration>,
+    ) -> Result<Vec<DumpCostsSummary>> {
+        let calibration = match calibration {
+            Some(_) if new.len() > 1 => {
+                warn!(
+                    "{}: Ignoring harness calibration: The calibration is not available if the \
+                     output consists of multiple dumps",
+                    self.module_path
+                );
+                None
+            }
+            calibration => calibration,
+        };
+
+        let mut summaries = vec![];
+        for new_dump in new {
+            let old_dump = new_dump.find_old(old);
+            let new_functions = new_dump.functions.as_ref();
+            let old_functions = old_dump.and_then(|dump| dump.functions.as_ref());
+
+            let dump = if new.len() > 1 {
+                new_dump.to_summary()
+            } else {
+                None
+            };
+            if let Some(dump) = &dump {
+                print_dump_headline(meta, dump);
+            }
+
+            let old_costs = old_dump.map(|dump| &dump.costs);
+            let adjusted = calibration.and_then(|calibration| {
+                let adjusted = calibration.apply(&new_dump.costs, old_costs);
+                if adjusted.is_none() && calibration.new.is_some() {
+                    warn!(
+                        "{}: Ignoring harness calibration: The overhead of the harness is not \
+                         smaller than the costs of the benchmark",
+                        self.module_path
+                    );
+                }
+                adjusted
+            });
+            let (costs_summary, raw_costs_summary) = match adjusted {
+                Some((new_adjusted, old_adjusted)) => (
+                    CostsSummary::new(&new_adjusted, old_adjusted.as_ref()),
+                    Some(CostsSummary::new(&new_dump.costs, old_costs)),
+                ),
+                None => (CostsSummary::new(&new_dump.costs, old_costs), None),
+            };
+            let functions = self.summarize_function_costs(new_functions, old_functions);
+
+            VerticalFormat::default().print(meta, baselines.clone(), &costs_summary)?;
+            print_function_costs(meta, &functions)?;
+            let threads = dumps::summarize_threads(meta, baselines, new_dump, old_dump)?;
+
+            let regressions = self.check_and_print_regressions(
+                &costs_summary,
+                new_functions,
+                old_functions,
+                &threads,
+            );
+
+            summaries.push(DumpCostsSummary {
+                path: new_dump.main_path().clone(),
+                dump,
+                costs_summary,
+                raw_costs_summary,
+                regressions,
+                functions,
+                threads,
+                process: None,
+            });
+        }
+        Ok(summaries)
+    }
+
+    /// Summarize the costs of the top functions if configured with a [`FunctionCostsConfig`]
+    fn summarize_function_costs(
+        &self,
+        new: Option<&CallgrindMap>,
+        old: Option<&CallgrindMap>,
+    ) -> Vec<FunctionCostsSummary> {
+        match (&self.function_costs_config, new) {
+            (Some(config), Some(new)) => config.summarize(new, old),
+            _ => vec![],
+        }
+    }
+
+    /// Parse the line costs of the output at `output_path` if needed by the [`AnnotateConfig`]
+    ///
+    /// Returns `None` if the line costs are not needed or the `output_path` doesn't exist.
+    fn parse_line_costs(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+    ) -> Result<Option<LineCostsMap>> {
+        if self.annotate_config.is_some() && output_path.exists() {
+            let mut map = annotate::parse(output_path, &meta.project_root)?;
+            map.make_summary(&self.cost_model)?;
+            Ok(Some(map))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Create the annotated source report if configured with an [`AnnotateConfig`]
+    fn annotate(
+        &self,
+        title: &str,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        old: Option<&LineCostsMap>,
+    ) -> Result<Option<AnnotatedSourceSummary>> {
+        match (&self.annotate_config, self.parse_line_costs(meta, output_path)?) {
+            (Some(config), Some(new)) => config
+                .create(title, &new, old, output_path, &meta.project_root)
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse the function costs and calls of the output at `output_path` if needed by the
+    /// [`CallGraphConfig`]
+    ///
+    /// Returns `None` if the call graph is not created or the `output_path` doesn't exist.
+    fn parse_call_graph(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+    ) -> Result<Option<CallgrindMap>> {
+        if self.call_graph_config.is_some() && output_path.exists() {
+            let mut map = call_graph::parse(output_path, &meta.project_root)?;
+            map.make_summary(&self.cost_model)?;
+            Ok(Some(map))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Create the call graph if configured with a [`CallGraphConfig`]
+    fn create_call_graph(
+        &self,
+        title: &str,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        old: Option<&CallgrindMap>,
+    ) -> Result<Option<CallGraphSummary>> {
+        match (&self.call_graph_config, self.parse_call_graph(meta, output_path)?) {
+            (Some(config), Some(new)) => config.create(title, &new, old, output_path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Export the callgrind output to pprof profiles if configured with a [`PprofConfig`]
+    ///
+    /// The profile of the `old_path` is created only if the old output exists.
+    fn export_pprof(
+        &self,
+        meta: &Metadata,
+        output_path: &ToolOutputPath,
+        old_path: Option<&ToolOutputPath>,
+        sentinel: &Sentinel,
+    ) -> Result<Option<PprofSummary>> {
+        self.pprof_config
+            .as_ref()
+            .map(|config| config.create(output_path, old_path, Some(sentinel), &meta.project_root))
+            .transpose()
+    }
+
+    /// Create the initial [`CallgrindSummary`] of the callgrind output files
+    ///
+    /// The effective cache geometry and the toggles in effect are recorded for all kinds of
+    /// baselines, also if the costs are loaded from a baseline.
+    fn create_callgrind_summary(
+        &self,
+        log_path: &ToolOutputPath,
+        out_path: &ToolOutputPath,
+    ) -> Result<CallgrindSummary> {
+        let mut callgrind_summary =
+            CallgrindSummary::new(log_path.real_paths()?, out_path.real_paths()?);
+        callgrind_summary.cache_geometry = self
+            .callgrind_args
+            .cache_geometry(self.cache_name.clone());
+        callgrind_summary.collect = Some(
+            self.callgrind_args()
+                .collect_summary(self.collect_filter.as_ref()),
+        );
+        Ok(callgrind_summary)
+    }
+
     /// This method creates the initial [`BenchmarkSummary`]
     fn create_benchmark_summary(
         &self,
@@ -460,20 +1094,79 @@ impl LibBench {
     fn check_and_print_regressions(
         &self,
         costs_summary: &CostsSummary,
+        new_functions: Option<&CallgrindMap>,
+        old_functions: Option<&CallgrindMap>,
+        threads: &[CallgrindThreadSummary],
     ) -> Vec<CallgrindRegressionSummary> {
         if let Some(regression_config) = &self.regression_config {
-            regression_config.check_and_print(costs_summary)
+            regression_config.check_and_print(
+                costs_summary,
+                new_functions.zip(old_functions),
+                threads,
+            )
         } else {
             vec![]
         }
     }
+
+    /// Add the summaries of all `dumps` to the summary of the default tool
+    ///
+    /// Returns the [`CallgrindSummary`] if callgrind is the default tool, so the callgrind specific
+    /// reports can be added.
+    fn add_default_tool_summary<'a>(
+        &self,
+        benchmark_summary: &'a mut BenchmarkSummary,
+        config: &Config,
+        bench_args: &[OsString],
+        log_path: &ToolOutputPath,
+        out_path: &ToolOutputPath,
+        dumps: Vec<DumpCostsSummary>,
+    ) -> Result<Option<&'a mut CallgrindSummary>> {
+        if self.default_tool == ValgrindTool::Cachegrind {
+            let cachegrind_summary = benchmark_summary
+                .cachegrind_summary
+                .insert(CachegrindSummary::new(
+                    log_path.real_paths()?,
+                    out_path.real_paths()?,
+                ));
+            for dump in dumps {
+                cachegrind_summary.add_summary(
+                    &config.bench_bin,
+                    bench_args,
+                    &dump.path.to_base_path(),
+                    dump.costs_summary,
+                    dump.regressions,
+                );
+            }
+            Ok(None)
+        } else {
+            let callgrind_summary = benchmark_summary
+                .callgrind_summary
+                .insert(self.create_callgrind_summary(log_path, out_path)?);
+            for dump in dumps {
+                callgrind_summary.add_summary(
+                    &config.bench_bin,
+                    bench_args,
+                    &dump.path.to_base_path(),
+                    dump.costs_summary,
+                    dump.raw_costs_summary,
+                    dump.regressions,
+                    dump.functions,
+                    dump.dump,
+                    dump.threads,
+                    dump.process,
+                );
+            }
+            Ok(Some(callgrind_summary))
+        }
+    }
 }
 
 impl Benchmark for LoadBaselineBenchmark {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.loaded_baseline.to_string()),
-            ValgrindTool::Callgrind,
+            lib_bench.default_tool.clone(),
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
             &group.module_path,
@@ -488,11 +1181,33 @@ impl Benchmark for LoadBaselineBenchmark {
         )
     }
 
+    fn calibrate(
+        &self,
+        lib_bench: &LibBench,
+        config: &Config,
+        group: &Group,
+    ) -> Result<Calibration> {
+        let out_path = ToolOutputPath::new(
+            ToolOutputPathKind::Base(self.loaded_baseline.to_string()),
+            lib_bench.default_tool.clone(),
+            &BaselineKind::Name(self.baseline.clone()),
+            &config.meta.target_dir,
+            &group.module_path,
+            &lib_bench.calibration_name(),
+        );
+
+        Ok(Calibration {
+            new: lib_bench.parse_calibration(&out_path)?,
+            old: lib_bench.parse_calibration(&out_path.to_base_path())?,
+        })
+    }
+
     fn run(
         &self,
         lib_bench: &LibBench,
         config: &Config,
         group: &Group,
+        calibration: Option<&Calibration>,
     ) -> Result<BenchmarkSummary> {
         let header = LibraryBenchmarkHeader::new(&config.meta, lib_bench);
         header.print();
@@ -510,45 +1225,63 @@ impl Benchmark for LoadBaselineBenchmark {
             header.description(),
         )?;
 
-        let new_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
-        let old_costs = Some(SentinelParser::new(&sentinel).parse(&old_path)?);
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
-
-        VerticalFormat::default().print(&config.meta, self.baselines(), &costs_summary)?;
-
-        let regressions = lib_bench.check_and_print_regressions(&costs_summary);
+        let new_dumps = lib_bench.parse_dumps(&config.meta, &sentinel, &out_path)?;
+        let old_dumps = lib_bench.parse_dumps(&config.meta, &sentinel, &old_path)?;
+        let old_lines = lib_bench.parse_line_costs(&config.meta, &old_path)?;
+        let old_calls = lib_bench.parse_call_graph(&config.meta, &old_path)?;
 
-        let callgrind_summary = benchmark_summary
-            .callgrind_summary
-            .insert(CallgrindSummary::new(
-                log_path.real_paths()?,
-                out_path.real_paths()?,
-            ));
+        let dumps = lib_bench.summarize_dumps(
+            &config.meta,
+            &self.baselines(),
+            &new_dumps,
+            &old_dumps,
+            calibration,
+        )?;
 
-        callgrind_summary.add_summary(
-            &config.bench_bin,
+        if let Some(callgrind_summary) = lib_bench.add_default_tool_summary(
+            &mut benchmark_summary,
+            config,
             &bench_args,
-            &old_path,
-            costs_summary,
-            regressions,
-        );
-
-        if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
-            callgrind_summary.flamegraphs = LoadBaselineFlamegraphGenerator {
-                loaded_baseline: self.loaded_baseline.clone(),
-                baseline: self.baseline.clone(),
+            &log_path,
+            &out_path,
+            dumps,
+        )? {
+            if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
+                callgrind_summary.flamegraphs = LoadBaselineFlamegraphGenerator {
+                    loaded_baseline: self.loaded_baseline.clone(),
+                    baseline: self.baseline.clone(),
+                }
+                .create_all(
+                    &Flamegraph::new(header.to_title(), flamegraph_config),
+                    &out_path,
+                    Some(&sentinel),
+                    &config.meta.project_root,
+                )?;
             }
-            .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+
+            callgrind_summary.annotated_source = lib_bench.annotate(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                old_lines.as_ref(),
+            )?;
+            callgrind_summary.call_graph = lib_bench.create_call_graph(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                old_calls.as_ref(),
+            )?;
+            callgrind_summary.pprof = lib_bench.export_pprof(
+                &config.meta,
                 &out_path,
-                Some(&sentinel),
-                &config.meta.project_root,
+                Some(&old_path),
+                &sentinel,
             )?;
         }
 
         benchmark_summary.tool_summaries = lib_bench
             .tools
-            .run_loaded_vs_base(&config.meta, &out_path)?;
+            .run_loaded_vs_base(&config.meta, &out_path, &lib_bench.module_path)?;
 
         Ok(benchmark_summary)
     }
@@ -629,7 +1362,7 @@ impl Benchmark for SaveBaselineBenchmark {
     fn output_path(&self, lib_bench: &LibBench, config: &Config, group: &Group) -> ToolOutputPath {
         ToolOutputPath::new(
             ToolOutputPathKind::Base(self.baseline.to_string()),
-            ValgrindTool::Callgrind,
+            lib_bench.default_tool.clone(),
             &BaselineKind::Name(self.baseline.clone()),
             &config.meta.target_dir,
             &group.module_path,
@@ -644,27 +1377,52 @@ impl Benchmark for SaveBaselineBenchmark {
         )
     }
 
+    fn calibrate(
+        &self,
+        lib_bench: &LibBench,
+        config: &Config,
+        group: &Group,
+    ) -> Result<Calibration> {
+        let out_path = ToolOutputPath::new(
+            ToolOutputPathKind::Base(self.baseline.to_string()),
+            lib_bench.default_tool.clone(),
+            &BaselineKind::Name(self.baseline.clone()),
+            &config.meta.target_dir,
+            &group.module_path,
+            &lib_bench.calibration_name(),
+        );
+        out_path.init()?;
+
+        // The old calibration needs to be parsed before the old output files are cleared
+        let old = lib_bench.parse_calibration(&out_path)?;
+        out_path.clear()?;
+        out_path.to_log_output().clear()?;
+
+        lib_bench.run_calibration(config, group, &out_path)?;
+
+        Ok(Calibration {
+            new: lib_bench.parse_calibration(&out_path)?,
+            old,
+        })
+    }
+
     fn run(
         &self,
         lib_bench: &LibBench,
         config: &Config,
         group: &Group,
+        calibration: Option<&Calibration>,
     ) -> Result<BenchmarkSummary> {
         let header = LibraryBenchmarkHeader::new(&config.meta, lib_bench);
         header.print();
 
-        let callgrind_command = ToolCommand::new(
-            ValgrindTool::Callgrind,
+        let command = ToolCommand::new(
+            lib_bench.default_tool.clone(),
             &config.meta,
             config.meta.args.nocapture,
         );
 
-        let mut callgrind_args = lib_bench.callgrind_args.clone();
-        if let Some(entry_point) = lib_bench.entry_point.as_ref() {
-            callgrind_args.insert_toggle_collect(entry_point);
-        }
-
-        let tool_config = ToolConfig::new(ValgrindTool::Callgrind, true, callgrind_args, None);
+        let tool_config = lib_bench.default_tool_config();
 
         let bench_args = lib_bench.bench_args(group);
         let baselines = self.baselines();
@@ -673,14 +1431,16 @@ impl Benchmark for SaveBaselineBenchmark {
         let out_path = self.output_path(lib_bench, config, group);
         out_path.init()?;
 
-        let old_costs = out_path
-            .exists()
-            .then(|| {
-                SentinelParser::new(&sentinel)
-                    .parse(&out_path)
-                    .and_then(|costs| out_path.clear().map(|()| costs))
-            })
-            .transpose()?;
+        // The old dumps need to be parsed before the old output files are cleared
+        let old_lines = lib_bench.parse_line_costs(&config.meta, &out_path)?;
+        let old_calls = lib_bench.parse_call_graph(&config.meta, &out_path)?;
+        let old_dumps = if out_path.exists() {
+            let old_dumps = lib_bench.parse_dumps(&config.meta, &sentinel, &out_path)?;
+            out_path.clear()?;
+            old_dumps
+        } else {
+            vec![]
+        };
 
         let log_path = out_path.to_log_output();
         log_path.clear()?;
@@ -692,11 +1452,11 @@ impl Benchmark for SaveBaselineBenchmark {
             header.description(),
         )?;
 
-        let output = callgrind_command.run(
+        let output = command.run(
             tool_config,
             &config.bench_bin,
             &bench_args,
-            lib_bench.run_options.clone(),
+            lib_bench.default_tool_run_options(),
             &out_path,
             &lib_bench.module_path,
             None,
@@ -708,39 +1468,55 @@ impl Benchmark for SaveBaselineBenchmark {
             lib_bench.run_options.stderr.as_ref(),
         );
 
-        let new_costs = SentinelParser::new(&sentinel).parse(&out_path)?;
-        let costs_summary = CostsSummary::new(&new_costs, old_costs.as_ref());
-        VerticalFormat::default().print(&config.meta, baselines.clone(), &costs_summary)?;
+        let new_dumps = lib_bench.parse_dumps(&config.meta, &sentinel, &out_path)?;
+        let dumps = lib_bench.summarize_dumps(
+            &config.meta,
+            &baselines,
+            &new_dumps,
+            &old_dumps,
+            calibration,
+        )?;
 
         output.dump_log(log::Level::Info);
         log_path.dump_log(log::Level::Info, &mut stderr())?;
 
-        let regressions = lib_bench.check_and_print_regressions(&costs_summary);
-
-        let callgrind_summary = benchmark_summary
-            .callgrind_summary
-            .insert(CallgrindSummary::new(
-                log_path.real_paths()?,
-                out_path.real_paths()?,
-            ));
-
-        callgrind_summary.add_summary(
-            &config.bench_bin,
+        if let Some(callgrind_summary) = lib_bench.add_default_tool_summary(
+            &mut benchmark_summary,
+            config,
             &bench_args,
+            &log_path,
             &out_path,
-            costs_summary,
-            regressions,
-        );
-
-        if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
-            callgrind_summary.flamegraphs = SaveBaselineFlamegraphGenerator {
-                baseline: self.baseline.clone(),
+            dumps,
+        )? {
+            if let Some(flamegraph_config) = lib_bench.flamegraph_config.clone() {
+                callgrind_summary.flamegraphs = SaveBaselineFlamegraphGenerator {
+                    baseline: self.baseline.clone(),
+                }
+                .create_all(
+                    &Flamegraph::new(header.to_title(), flamegraph_config),
+                    &out_path,
+                    Some(&sentinel),
+                    &config.meta.project_root,
+                )?;
             }
-            .create(
-                &Flamegraph::new(header.to_title(), flamegraph_config),
+
+            callgrind_summary.annotated_source = lib_bench.annotate(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                old_lines.as_ref(),
+            )?;
+            callgrind_summary.call_graph = lib_bench.create_call_graph(
+                &header.to_title(),
+                &config.meta,
+                &out_path,
+                old_calls.as_ref(),
+            )?;
+            callgrind_summary.pprof = lib_bench.export_pprof(
+                &config.meta,
                 &out_path,
-                Some(&sentinel),
-                &config.meta.project_root,
+                None,
+                &sentinel,
             )?;
         }
 
@@ -766,3 +1542,218 @@ impl Benchmark for SaveBaselineBenchmark {
 pub fn run(benchmark_groups: LibraryBenchmarkGroups, config: Config) -> Result<()> {
     Runner::new(benchmark_groups, config)?.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::api::{EventKind, RawArgs};
+    use crate::runner::summary::{CacheGeometrySummary, CollectSummary};
+
+    fn lib_bench(raw_args: &[&str], cache_name: Option<&str>) -> LibBench {
+        LibBench {
+            bench_index: 0,
+            index: 0,
+            id: None,
+            function_name: "bench".to_owned(),
+            args: None,
+            run_options: RunOptions::default(),
+            callgrind_args: Args::from_raw_args(&[&RawArgs::from_iter(raw_args)]).unwrap(),
+            cache_name: cache_name.map(ToOwned::to_owned),
+            cachegrind_args: CachegrindArgs::default(),
+            default_tool: ValgrindTool::Callgrind,
+            flamegraph_config: None,
+            function_costs_config: None,
+            annotate_config: None,
+            call_graph_config: None,
+            pprof_config: None,
+            collect_filter: None,
+            cost_model: CostModel::default(),
+            regression_config: None,
+            tools: ToolConfigs(vec![]),
+            module_path: ModulePath::new("bench_file::group"),
+            entry_point: Some(DEFAULT_TOGGLE.to_owned()),
+            harness_calibration: false,
+            truncate_description: None,
+        }
+    }
+
+    fn costs(ir: u64, dr: u64) -> Costs {
+        Costs::with_event_kinds([(EventKind::Ir, ir), (EventKind::Dr, dr)])
+    }
+
+    #[rstest]
+    #[case::baseline(ToolOutputPathKind::Out, BaselineKind::Old)]
+    #[case::load_baseline(
+        ToolOutputPathKind::Base("foo".to_owned()),
+        BaselineKind::Name("bar".parse().unwrap())
+    )]
+    #[case::save_baseline(
+        ToolOutputPathKind::Base("foo".to_owned()),
+        BaselineKind::Name("foo".parse().unwrap())
+    )]
+    fn test_create_callgrind_summary(
+        #[case] kind: ToolOutputPathKind,
+        #[case] baseline_kind: BaselineKind,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lib_bench = LibBench {
+            collect_filter: Some(
+                CollectFilter::new(
+                    &api::CollectConfig {
+                        collect_only: vec!["my_lib::decode".to_owned()],
+                        exclude: vec![],
+                    },
+                    &ModulePath::new("bench_file::group"),
+                )
+                .unwrap(),
+            ),
+            ..lib_bench(
+                &[
+                    "--cache-sim=yes",
+                    "--I1=16384,4,64",
+                    "--toggle-collect=my_lib::log",
+                ],
+                Some("small"),
+            )
+        };
+        let out_path = ToolOutputPath::new(
+            kind,
+            ValgrindTool::Callgrind,
+            &baseline_kind,
+            temp_dir.path(),
+            &lib_bench.module_path,
+            &lib_bench.name(),
+        );
+        let log_path = out_path.to_log_output();
+        out_path.init().unwrap();
+        std::fs::write(out_path.to_path(), "").unwrap();
+        std::fs::write(log_path.to_path(), "").unwrap();
+
+        let summary = lib_bench
+            .create_callgrind_summary(&log_path, &out_path)
+            .unwrap();
+
+        assert_eq!(summary.out_paths, vec![out_path.to_path()]);
+        assert_eq!(summary.log_paths, vec![log_path.to_path()]);
+        assert_eq!(
+            summary.cache_geometry,
+            Some(CacheGeometrySummary {
+                name: Some("small".to_owned()),
+                i1: cache::parse("16384,4,64").unwrap(),
+                d1: cache::DEFAULT_D1,
+                ll: cache::DEFAULT_LL,
+            })
+        );
+        assert_eq!(
+            summary.collect,
+            Some(CollectSummary {
+                collect_atstart: false,
+                toggle_collect: vec![DEFAULT_TOGGLE.to_owned(), "my_lib::log".to_owned()],
+                collect_only: vec!["my_lib::decode".to_owned()],
+                exclude: vec![],
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::no_calibration(None, None, None, None)]
+    #[case::no_new_calibration(None, Some(costs(1, 1)), Some(costs(10, 10)), None)]
+    #[case::new_without_old(
+        Some(costs(3, 2)),
+        None,
+        None,
+        Some((costs(97, 48), None))
+    )]
+    #[case::old_without_old_calibration(
+        Some(costs(3, 2)),
+        None,
+        Some(costs(10, 10)),
+        Some((costs(97, 48), Some(costs(7, 8))))
+    )]
+    #[case::old_with_old_calibration(
+        Some(costs(3, 2)),
+        Some(costs(5, 1)),
+        Some(costs(10, 10)),
+        Some((costs(97, 48), Some(costs(5, 9))))
+    )]
+    #[case::new_not_larger_than_overhead(
+        Some(costs(100, 2)),
+        Some(costs(5, 1)),
+        Some(costs(10, 10)),
+        None
+    )]
+    #[case::old_not_larger_than_overhead(
+        Some(costs(3, 2)),
+        Some(costs(5, 10)),
+        Some(costs(10, 10)),
+        None
+    )]
+    #[case::old_not_larger_than_new_overhead(
+        Some(costs(3, 20)),
+        None,
+        Some(costs(10, 10)),
+        None
+    )]
+    #[case::zero_overhead(
+        Some(costs(0, 2)),
+        None,
+        Some(costs(0, 10)),
+        Some((costs(100, 48), Some(costs(0, 8))))
+    )]
+    fn test_calibration_apply(
+        #[case] new_overhead: Option<Costs>,
+        #[case] old_overhead: Option<Costs>,
+        #[case] old: Option<Costs>,
+        #[case] expected: Option<(Costs, Option<Costs>)>,
+    ) {
+        let calibration = Calibration {
+            new: new_overhead,
+            old: old_overhead,
+        };
+        assert_eq!(calibration.apply(&costs(100, 50), old.as_ref()), expected);
+    }
+
+    #[rstest]
+    #[case::no_id(None, None, "bench@calibration")]
+    #[case::id(Some("first"), None, "bench.first@calibration")]
+    #[case::cache(None, Some("small"), "bench@calibration.cache@small")]
+    #[case::id_and_cache(Some("first"), Some("small"), "bench.first@calibration.cache@small")]
+    fn test_calibration_name(
+        #[case] id: Option<&str>,
+        #[case] cache_name: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let mut lib_bench = lib_bench(&[], cache_name);
+        lib_bench.id = id.map(ToOwned::to_owned);
+        assert_eq!(lib_bench.calibration_name(), expected);
+    }
+
+    #[test]
+    fn test_calibration_args() {
+        let mut lib_bench = lib_bench(&[], None);
+        lib_bench.bench_index = 1;
+        lib_bench.index = 2;
+        let group = Group {
+            id: "my_group".to_owned(),
+            benches: vec![],
+            compare_by_id: false,
+            module_path: ModulePath::new("bench_file::my_group"),
+            setup: None,
+            teardown: None,
+        };
+        assert_eq!(
+            lib_bench.calibration_args(&group),
+            vec![
+                OsString::from("--iai-run"),
+                OsString::from("my_group"),
+                OsString::from("calibration"),
+                OsString::from("1"),
+                OsString::from("2"),
+                OsString::from("bench_file::group"),
+            ]
+        );
+    }
+}
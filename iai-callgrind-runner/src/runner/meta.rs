@@ -8,7 +8,8 @@ use log::debug;
 
 use super::args::CommandLineArgs;
 use super::envs;
-use crate::api::RegressionConfig;
+use super::tool::ValgrindTool;
+use crate::api::{CostModel, RegressionConfig, ToolRegressionConfig};
 use crate::util::resolve_binary_path;
 
 #[derive(Debug, Clone)]
@@ -27,6 +28,8 @@ pub struct Metadata {
     pub valgrind: Cmd,
     pub valgrind_wrapper: Option<Cmd>,
     pub regression_config: Option<RegressionConfig>,
+    pub tool_regression_configs: Vec<(ValgrindTool, ToolRegressionConfig)>,
+    pub cost_model: Option<CostModel>,
     pub args: CommandLineArgs,
     pub bench_name: String,
 }
@@ -140,6 +143,8 @@ impl Metadata {
             valgrind_wrapper,
             project_root,
             regression_config: Into::<Option<RegressionConfig>>::into(&args),
+            tool_regression_configs: Into::<Vec<(ValgrindTool, ToolRegressionConfig)>>::into(&args),
+            cost_model: args.cost_model.clone(),
             args,
             bench_name,
         })
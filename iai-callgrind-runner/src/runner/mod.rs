@@ -1,5 +1,7 @@
 mod args;
+pub mod bbv;
 mod bin_bench;
+pub mod cachegrind;
 pub mod callgrind;
 pub mod common;
 pub mod costs;
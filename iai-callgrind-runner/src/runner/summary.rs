@@ -20,8 +20,9 @@ use super::common::ModulePath;
 use super::costs::Costs;
 use super::format::{ComparisonHeader, OutputFormat, VerticalFormat};
 use super::meta::Metadata;
-use super::tool::{ToolOutputPath, ValgrindTool};
-use crate::api::EventKind;
+use super::tool::format::format_error_report;
+use super::tool::{ToolConfigs, ToolOutputPath, ValgrindTool};
+use crate::api::{CacheGeometry, DhatMetric, EventKind};
 use crate::error::Error;
 use crate::runner::costs::Summarize;
 use crate::util::{factor_diff, make_absolute, percentage_diff};
@@ -33,6 +34,63 @@ lazy_static! {
     .expect("Regex should compile");
 }
 
+/// The `AllocationSiteSummary` of a program point (allocation site) of DHAT
+///
+/// The allocation sites are extracted from the `dhat.out` file of DHAT.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AllocationSiteSummary {
+    /// The demangled frames of the allocation site, the innermost frame first
+    pub frames: Vec<String>,
+    /// The metrics of this allocation site like `Total bytes` compared to the old run
+    pub costs_summary: CostsSummary<String>,
+}
+
+/// The summary of the annotated source report of a callgrind run
+///
+/// The report shows the costs of every line of the source files with the highest costs like
+/// `callgrind_annotate` together with the difference to the old or baseline run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AnnotatedSourceSummary {
+    /// The path to the html file of the report
+    pub path: PathBuf,
+    /// The paths of the annotated source files sorted descending by their costs
+    pub files: Vec<PathBuf>,
+}
+
+/// A basic block of the basic block vector of BBV
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct BasicBlockSummary {
+    /// The id of the basic block as assigned by BBV
+    pub id: u64,
+    /// The address of the basic block if present in the `pc` out file
+    pub address: Option<String>,
+    /// The function of the basic block if present in the `pc` out file
+    pub function: Option<String>,
+    /// The number of instructions executed in this basic block summed up over all intervals
+    pub instructions: u64,
+}
+
+/// The `BasicBlockVectorSummary` of the `bb` out file of BBV
+///
+/// The number of intervals, basic blocks and instructions are part of the costs summary of the
+/// [`ToolRunSummary`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct BasicBlockVectorSummary {
+    /// The hottest basic blocks sorted by the number of executed instructions
+    pub hottest_blocks: Vec<BasicBlockSummary>,
+    /// The distance between the new and old basic block vector if both are present
+    ///
+    /// This is the manhattan distance between the normalized basic block vectors of the whole
+    /// program run in the range from `0` (identical execution profile) to `1` (no common basic
+    /// blocks). The basic blocks are matched by their address if the `pc` out files are present,
+    /// otherwise by their id.
+    pub distance: Option<f64>,
+}
+
 /// A `Baseline` depending on the [`BaselineKind`] which points to the corresponding path
 ///
 /// This baseline is used for comparisons with the new output of valgrind tools.
@@ -104,10 +162,68 @@ pub struct BenchmarkSummary {
     pub details: Option<String>,
     /// The summary of the callgrind run
     pub callgrind_summary: Option<CallgrindSummary>,
+    /// The summary of the cachegrind run if cachegrind was used instead of callgrind
+    pub cachegrind_summary: Option<CachegrindSummary>,
     /// The summary of other valgrind tool runs
     pub tool_summaries: Vec<ToolSummary>,
 }
 
+/// The `CacheGeometrySummary` records the effective geometry of the simulated caches of a
+/// callgrind run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CacheGeometrySummary {
+    /// The name of the entry of the cache matrix if the benchmark was run with a cache matrix
+    pub name: Option<String>,
+    /// The geometry of the I1 cache
+    pub i1: CacheGeometry,
+    /// The geometry of the D1 cache
+    pub d1: CacheGeometry,
+    /// The geometry of the LL cache
+    pub ll: CacheGeometry,
+}
+
+/// The `CachegrindRunSummary` containing the recorded events, performance regressions of a single
+/// cachegrind run
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CachegrindRunSummary {
+    /// The executed command extracted from Valgrind output
+    pub command: String,
+    /// If present, the `Baseline` used to compare the new with the old output
+    pub baseline: Option<Baseline>,
+    /// All recorded costs for `EventKinds`
+    pub events: CostsSummary<EventKind>,
+    /// All detected performance regressions
+    pub regressions: Vec<CallgrindRegressionSummary>,
+}
+
+/// The `CachegrindSummary` summarizes all cachegrind runs
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CachegrindSummary {
+    /// The paths to the `*.log` files
+    pub log_paths: Vec<PathBuf>,
+    /// The paths to the `*.out` files
+    pub out_paths: Vec<PathBuf>,
+    /// The summaries of all cachegrind runs
+    pub summaries: Vec<CachegrindRunSummary>,
+}
+
+/// The summary of the call graph of a callgrind run exported in the DOT format of Graphviz
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CallGraphSummary {
+    /// The path to the `*.dot` file
+    pub path: PathBuf,
+    /// The [`EventKind`] of the costs of the nodes and edges
+    pub event_kind: EventKind,
+    /// The number of functions shown in the pruned call graph
+    pub nodes: usize,
+    /// The number of calls shown in the pruned call graph
+    pub edges: usize,
+}
+
 /// The `CallgrindRegressionSummary` describing a single event based performance regression
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
@@ -122,6 +238,52 @@ pub struct CallgrindRegressionSummary {
     pub diff_pct: f64,
     /// The value of the limit which was exceeded to cause a performance regression
     pub limit: f64,
+    /// The name of the function if the regression was detected by a limit for functions
+    pub function: Option<String>,
+    /// The thread index if the regression was detected by a limit for a single thread
+    pub thread: Option<usize>,
+}
+
+/// The `CallgrindDump` describes a single dump of a callgrind run with multiple dumps
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CallgrindDump {
+    /// The part number of the dump starting with `1`
+    pub part: u64,
+    /// The trigger of the dump like `Client Request: phase1` or `Program termination`
+    pub trigger: String,
+}
+
+/// The `CallgrindProcess` describes a single process of a callgrind run with
+/// `--trace-children=yes`
+///
+/// The new and old processes are matched in the order of their pids if their commands are equal.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CallgrindProcess {
+    /// The command line of this process extracted from Valgrind output
+    pub command: String,
+    /// The old pid of this process
+    pub old_pid: Option<i32>,
+    /// The old parent pid of this process
+    pub old_parent_pid: Option<i32>,
+    /// The pid of this process
+    pub pid: Option<i32>,
+    /// The parent pid of this process
+    pub parent_pid: Option<i32>,
+    /// True if this process is checked for performance regressions
+    pub is_checked: bool,
+}
+
+/// The `CallgrindThreadSummary` contains the costs of a single thread of a callgrind run with
+/// `--separate-threads=yes`
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CallgrindThreadSummary {
+    /// The thread index as assigned by valgrind starting with `1` for the main thread
+    pub thread: usize,
+    /// All recorded costs for `EventKinds` of this thread
+    pub events: CostsSummary<EventKind>,
 }
 
 /// The `CallgrindRunSummary` containing the recorded events, performance regressions of a single
@@ -131,12 +293,26 @@ pub struct CallgrindRegressionSummary {
 pub struct CallgrindRunSummary {
     /// The executed command extracted from Valgrind output
     pub command: String,
+    /// If present, the traced process of a callgrind run with `--trace-children=yes` this summary
+    /// belongs to
+    pub process: Option<CallgrindProcess>,
+    /// If present, the dump of a callgrind run with multiple dumps this summary belongs to
+    pub dump: Option<CallgrindDump>,
     /// If present, the `Baseline` used to compare the new with the old output
     pub baseline: Option<Baseline>,
     /// All recorded costs for `EventKinds`
+    ///
+    /// If the harness overhead was subtracted, these are the adjusted costs.
     pub events: CostsSummary<EventKind>,
+    /// If present, the recorded costs before the harness overhead measured by the calibration run
+    /// was subtracted
+    pub raw_events: Option<CostsSummary<EventKind>>,
     /// All detected performance regressions
     pub regressions: Vec<CallgrindRegressionSummary>,
+    /// The costs of the top functions if configured with a `FunctionCostsConfig`
+    pub functions: Vec<FunctionCostsSummary>,
+    /// The costs of the single threads if callgrind was run with `--separate-threads=yes`
+    pub threads: Vec<CallgrindThreadSummary>,
 }
 
 /// The `CallgrindSummary` summarizes all callgrind runs
@@ -149,10 +325,35 @@ pub struct CallgrindSummary {
     pub out_paths: Vec<PathBuf>,
     /// The summaries of possibly created flamegraphs
     pub flamegraphs: Vec<FlamegraphSummary>,
+    /// The summary of the annotated source report if created
+    pub annotated_source: Option<AnnotatedSourceSummary>,
+    /// The summary of the call graph if created
+    pub call_graph: Option<CallGraphSummary>,
+    /// The summary of the pprof profiles if created
+    pub pprof: Option<PprofSummary>,
+    /// The effective geometry of the simulated caches if the cache simulation was enabled
+    pub cache_geometry: Option<CacheGeometrySummary>,
+    /// The toggles which were in effect for the collection of events
+    pub collect: Option<CollectSummary>,
     /// The summaries of all callgrind runs
     pub summaries: Vec<CallgrindRunSummary>,
 }
 
+/// The `CollectSummary` records the functions in which the events were collected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CollectSummary {
+    /// True if the events were collected from the start of the callgrind run
+    pub collect_atstart: bool,
+    /// The functions which toggled the collection of events on entry and exit in the order of the
+    /// `--toggle-collect` arguments
+    pub toggle_collect: Vec<String>,
+    /// The patterns of the functions of which the costs of the benchmark were calculated
+    pub collect_only: Vec<String>,
+    /// The patterns of the functions which were excluded from the costs of the benchmark
+    pub exclude: Vec<String>,
+}
+
 /// The `CostsDiff` describes the difference between an single optional `new` and `old` cost as
 /// percentage and factor.
 ///
@@ -176,6 +377,26 @@ pub struct CostsDiff {
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct CostsSummary<K: Hash + Eq = EventKind>(IndexMap<K, CostsDiff>);
 
+/// A single error reported by Memcheck, Helgrind or DRD
+///
+/// The `ErrorReport` is extracted from the `<error>` elements of the xml output of the tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ErrorReport {
+    /// The kind of the error like `InvalidRead`, `Leak_DefinitelyLost` or `Race`
+    pub kind: String,
+    /// The main description of the error
+    pub message: String,
+    /// Additional descriptions of the error like the location of the accessed memory
+    pub aux_messages: Vec<String>,
+    /// How often this error occurred
+    pub count: u64,
+    /// The stack traces of this error, the first one being the stack trace of the error itself
+    pub stacks: Vec<Vec<StackFrame>>,
+    /// The name of the suppression generated by valgrind if `--gen-suppressions` was used
+    pub suppression: Option<String>,
+}
+
 /// The `ErrorSummary` of tools which have it (Memcheck, DRD, Helgrind)
 ///
 /// The `ErrorSummary` is extracted from the `ERROR SUMMARY` line in the log file output.
@@ -195,18 +416,73 @@ pub struct ErrorSummary {
 /// The `FlamegraphSummary` records all created paths for an [`EventKind`] specific flamegraph
 ///
 /// Either the `regular_path`, `old_path` or the `diff_path` are present. Never can all of them be
-/// absent.
+/// absent. The flamegraphs of DHAT are specific to a [`DhatMetric`] instead of an [`EventKind`].
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
-pub struct FlamegraphSummary {
-    /// The `EventKind` of the flamegraph
-    pub event_kind: EventKind,
+pub struct FlamegraphSummary<K = EventKind> {
+    /// The `EventKind` (or `DhatMetric`) of the flamegraph
+    pub event_kind: K,
     /// If present, the path to the file of the regular (non-differential) flamegraph
     pub regular_path: Option<PathBuf>,
     /// If present, the path to the file of the old regular (non-differential) flamegraph
     pub base_path: Option<PathBuf>,
     /// If present, the path to the file of the differential flamegraph
     pub diff_path: Option<PathBuf>,
+    /// If present, the path to the speedscope file with the profiles of all `EventKinds` (or
+    /// `DhatMetrics`)
+    pub speedscope_path: Option<PathBuf>,
+    /// If present, the path to the file with the folded stacks of the regular flamegraph
+    pub folded_path: Option<PathBuf>,
+    /// If present, the path to the file with the folded stacks of the old or base flamegraph
+    pub base_folded_path: Option<PathBuf>,
+    /// If present, the thread index if this is the flamegraph of a single thread
+    pub thread: Option<usize>,
+}
+
+/// The exclusive and inclusive costs of a single function of a callgrind run
+///
+/// The new and old functions are matched by their name, source file and object file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct FunctionCostsSummary {
+    /// The name of the function as recorded by callgrind
+    pub function: String,
+    /// The source file of the function if known
+    pub file: Option<PathBuf>,
+    /// The object (binary or library) the function belongs to if known
+    pub object: Option<PathBuf>,
+    /// The costs of the function itself without the costs of the called functions
+    pub exclusive: CostsSummary,
+    /// The costs of the function including the costs of the called functions
+    pub inclusive: CostsSummary,
+}
+
+/// The summary of the gzipped pprof profiles of a callgrind run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PprofSummary {
+    /// The path to the profile of the new run
+    pub path: PathBuf,
+    /// The path to the profile of the old or baseline run if present
+    pub base_path: Option<PathBuf>,
+    /// The [`EventKind`]s of the sample types of the profiles
+    pub event_kinds: Vec<EventKind>,
+}
+
+/// A single frame of the stack trace of an [`ErrorReport`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct StackFrame {
+    /// The instruction pointer
+    pub ip: String,
+    /// The object (binary or library) the instruction pointer belongs to
+    pub object: Option<PathBuf>,
+    /// The name of the function if available
+    pub function: Option<String>,
+    /// The source file if debug information is available
+    pub file: Option<PathBuf>,
+    /// The line in the source file if debug information is available
+    pub line: Option<u64>,
 }
 
 /// The format (json, ...) in which the summary file should be saved or printed
@@ -229,6 +505,22 @@ pub struct SummaryOutput {
     path: PathBuf,
 }
 
+/// The `ToolRegressionSummary` describing a single performance regression of a tool metric
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ToolRegressionSummary {
+    /// The name of the metric which is affected by a performance regression
+    pub metric: String,
+    /// The value of the new benchmark run
+    pub new: u64,
+    /// The value of the old benchmark run
+    pub old: u64,
+    /// The difference between new and old in percent
+    pub diff_pct: f64,
+    /// The value of the limit which was exceeded to cause a performance regression
+    pub limit: f64,
+}
+
 /// The `ToolRunSummary` which contains all information about a single tool run process
 ///
 /// There's a separate process and therefore `ToolRunSummary` for the parent process and each child
@@ -256,8 +548,16 @@ pub struct ToolRunSummary {
     /// `4 errors from 3 contexts (suppressed: 2 from 1)`
     /// results in `ErrorSummary {errors: 4, contexts: 3, supp_errors: 2, supp_contexts: 1}`
     pub error_summary: Option<ErrorSummary>,
+    /// The errors reported by tools with xml output like Memcheck, DRD, Helgrind
+    pub error_reports: Vec<ErrorReport>,
     /// The tool specific cost summary extracted from Valgrind output
     pub costs_summary: Option<CostsSummary<String>>,
+    /// The top allocation sites of DHAT sorted by total bytes
+    pub allocation_sites: Vec<AllocationSiteSummary>,
+    /// The summary of the basic block vector of BBV
+    pub basic_block_vector: Option<BasicBlockVectorSummary>,
+    /// All detected performance regressions of the tool specific costs
+    pub regressions: Vec<ToolRegressionSummary>,
     /// The path to the full logfile from the tool run
     pub log_path: PathBuf,
 }
@@ -268,6 +568,28 @@ impl ToolRunSummary {
             .as_ref()
             .map_or(false, ErrorSummary::has_errors)
     }
+
+    /// Return the error contexts of this run if present
+    ///
+    /// These are the formatted [`ErrorReport`]s or, if the errors weren't written to an xml file,
+    /// the details of the log file which contain the error contexts.
+    pub fn error_contexts(&self) -> Option<String> {
+        if self.error_reports.is_empty() {
+            self.details.clone()
+        } else {
+            Some(
+                self.error_reports
+                    .iter()
+                    .flat_map(format_error_report)
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            )
+        }
+    }
+
+    pub fn is_regressed(&self) -> bool {
+        !self.regressions.is_empty()
+    }
 }
 
 /// The `ToolSummary` containing all information about a valgrind tool run
@@ -283,6 +605,17 @@ pub struct ToolSummary {
     pub out_paths: Vec<PathBuf>,
     /// All [`ToolRunSummary`]s
     pub summaries: Vec<ToolRunSummary>,
+    /// The summaries of the flamegraphs. Currently, only DHAT can create flamegraphs
+    pub flamegraphs: Vec<FlamegraphSummary<DhatMetric>>,
+}
+
+impl AllocationSiteSummary {
+    /// Return true if any metric of this allocation site differs from the old run
+    pub fn is_changed(&self) -> bool {
+        self.costs_summary
+            .all_diffs()
+            .any(|(_, diff)| diff.new != diff.old)
+    }
 }
 
 impl FromStr for BaselineName {
@@ -333,6 +666,7 @@ impl BenchmarkSummary {
             id,
             details,
             callgrind_summary: None,
+            cachegrind_summary: None,
             tool_summaries: vec![],
             summary_output: output,
             project_root,
@@ -387,48 +721,150 @@ impl BenchmarkSummary {
     /// Check if this `BenchmarkSummary` has recorded any performance regressions
     ///
     /// If the regressions are configured to be not `fail_fast` and there is a regressions, then the
-    /// `is_regressed` variable is updated to true.
+    /// `is_regressed` variable is updated to true. The regressions of the default tool are subject
+    /// to `fail_fast`, the regressions of the other tools are subject to the `fail_fast` setting of
+    /// their configuration in `tools`.
     ///
     /// # Errors
     ///
     /// If the regressions are configured to be `fail_fast` an error is returned
-    pub fn check_regression(&self, is_regressed: &mut bool, fail_fast: bool) -> Result<()> {
-        if let Some(callgrind_summary) = &self.callgrind_summary {
-            let benchmark_is_regressed = callgrind_summary.is_regressed();
-            if benchmark_is_regressed && fail_fast {
+    pub fn check_regression(
+        &self,
+        is_regressed: &mut bool,
+        fail_fast: bool,
+        tools: &ToolConfigs,
+    ) -> Result<()> {
+        let benchmark_is_regressed = self
+            .callgrind_summary
+            .as_ref()
+            .map_or(false, CallgrindSummary::is_regressed)
+            || self
+                .cachegrind_summary
+                .as_ref()
+                .map_or(false, CachegrindSummary::is_regressed);
+
+        if benchmark_is_regressed && fail_fast {
+            return Err(Error::RegressionError(true).into());
+        }
+
+        let mut tools_are_regressed = false;
+        for tool_summary in self.tool_summaries.iter().filter(|t| t.is_regressed()) {
+            if tools.is_fail_fast(&tool_summary.tool) {
                 return Err(Error::RegressionError(true).into());
             }
+            tools_are_regressed = true;
+        }
+
+        *is_regressed |= benchmark_is_regressed || tools_are_regressed;
+
+        Ok(())
+    }
+
+    /// Check if a tool configured to `fail_on_errors` has reported any errors
+    ///
+    /// The error contexts of the failing tool runs are printed to stderr for all output formats,
+    /// so they show up next to the error which aborts the benchmarks.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ToolError`] if a tool configured in `tools` to fail on errors has reported
+    /// errors which were not suppressed
+    pub fn check_tool_errors(&self, tools: &ToolConfigs) -> Result<()> {
+        for tool_summary in self
+            .tool_summaries
+            .iter()
+            .filter(|t| tools.is_fail_on_errors(&t.tool))
+        {
+            let (mut errors, mut contexts) = (0, 0);
+            for summary in tool_summary.summaries.iter().filter(|s| s.has_errors()) {
+                if let Some(error_summary) = &summary.error_summary {
+                    errors += error_summary.errors;
+                    contexts += error_summary.contexts;
+                    if let Some(contexts) = summary.error_contexts() {
+                        eprintln!("{}: {}\n{contexts}", tool_summary.tool, summary.command);
+                    }
+                }
+            }
 
-            *is_regressed |= benchmark_is_regressed;
+            if errors > 0 {
+                return Err(Error::ToolError(
+                    tool_summary.tool.clone(),
+                    ModulePath::new(&self.module_path),
+                    errors,
+                    contexts,
+                )
+                .into());
+            }
         }
 
         Ok(())
     }
 
     pub fn compare_and_print(&self, id: &str, meta: &Metadata, other: &Self) -> Result<()> {
-        if let (Some(callgrind_summary), Some(other_callgrind_summary)) =
-            (&self.callgrind_summary, &other.callgrind_summary)
-        {
-            for (summary, other_summary) in callgrind_summary
-                .summaries
-                .iter()
-                .zip(&other_callgrind_summary.summaries)
+        for (events, other_events) in self.events().into_iter().zip(other.events()) {
+            if let ((Some(new_costs), _), (Some(other_costs), _)) =
+                (events.extract_costs(), other_events.extract_costs())
             {
-                if let ((Some(new_costs), _), (Some(other_costs), _)) = (
-                    summary.events.extract_costs(),
-                    other_summary.events.extract_costs(),
-                ) {
-                    let costs_summary = CostsSummary::new(&new_costs, Some(&other_costs));
+                let costs_summary = CostsSummary::new(&new_costs, Some(&other_costs));
 
-                    ComparisonHeader::new(self.function_name.clone(), id, self.details.clone())
-                        .print();
-                    VerticalFormat::default().print(meta, (None, None), &costs_summary)?;
-                }
+                ComparisonHeader::new(self.function_name.clone(), id, self.details.clone()).print();
+                VerticalFormat::default().print(meta, (None, None), &costs_summary)?;
             }
         }
 
         Ok(())
     }
+
+    /// Return the recorded [`CostsSummary`] of all callgrind or cachegrind runs
+    fn events(&self) -> Vec<&CostsSummary> {
+        if let Some(callgrind_summary) = &self.callgrind_summary {
+            callgrind_summary.summaries.iter().map(|s| &s.events).collect()
+        } else if let Some(cachegrind_summary) = &self.cachegrind_summary {
+            cachegrind_summary
+                .summaries
+                .iter()
+                .map(|s| &s.events)
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+impl CachegrindSummary {
+    /// Create a new `CachegrindSummary`
+    pub fn new(log_paths: Vec<PathBuf>, out_paths: Vec<PathBuf>) -> CachegrindSummary {
+        Self {
+            log_paths,
+            out_paths,
+            summaries: Vec::default(),
+        }
+    }
+
+    /// Return true if there are any recorded regressions in this `CachegrindSummary`
+    pub fn is_regressed(&self) -> bool {
+        self.summaries.iter().any(|r| !r.regressions.is_empty())
+    }
+
+    /// Create and add a [`CachegrindRunSummary`] to this `CachegrindSummary`
+    pub fn add_summary(
+        &mut self,
+        bench_bin: &Path,
+        bench_args: &[OsString],
+        old_path: &ToolOutputPath,
+        events: CostsSummary,
+        regressions: Vec<CallgrindRegressionSummary>,
+    ) {
+        self.summaries.push(CachegrindRunSummary {
+            command: format_command(bench_bin, bench_args),
+            baseline: old_path.exists().then(|| Baseline {
+                kind: old_path.baseline_kind.clone(),
+                path: old_path.to_path(),
+            }),
+            events,
+            regressions,
+        });
+    }
 }
 
 impl CallgrindSummary {
@@ -438,6 +874,11 @@ impl CallgrindSummary {
             log_paths,
             out_paths,
             flamegraphs: Vec::default(),
+            annotated_source: None,
+            call_graph: None,
+            pprof: None,
+            cache_geometry: None,
+            collect: None,
             summaries: Vec::default(),
         }
     }
@@ -454,29 +895,29 @@ impl CallgrindSummary {
         bench_args: &[OsString],
         old_path: &ToolOutputPath,
         events: CostsSummary,
+        raw_events: Option<CostsSummary>,
         regressions: Vec<CallgrindRegressionSummary>,
+        functions: Vec<FunctionCostsSummary>,
+        dump: Option<CallgrindDump>,
+        threads: Vec<CallgrindThreadSummary>,
+        process: Option<CallgrindProcess>,
     ) {
         self.summaries.push(CallgrindRunSummary {
-            command: format!(
-                "{} {}",
-                bench_bin.display(),
-                shlex::try_join(
-                    bench_args
-                        .iter()
-                        .map(|s| s.to_string_lossy().to_string())
-                        .collect::<Vec<String>>()
-                        .as_slice()
-                        .iter()
-                        .map(String::as_str)
-                )
-                .unwrap()
+            command: process.as_ref().map_or_else(
+                || format_command(bench_bin, bench_args),
+                |process| process.command.clone(),
             ),
+            process,
+            dump,
             baseline: old_path.exists().then(|| Baseline {
                 kind: old_path.baseline_kind.clone(),
                 path: old_path.to_path(),
             }),
             events,
+            raw_events,
             regressions,
+            functions,
+            threads,
         });
     }
 }
@@ -602,14 +1043,18 @@ impl FromStr for ErrorSummary {
     }
 }
 
-impl FlamegraphSummary {
+impl<K> FlamegraphSummary<K> {
     /// Create a new `FlamegraphSummary`
-    pub fn new(event_kind: EventKind) -> Self {
+    pub fn new(event_kind: K) -> Self {
         Self {
             event_kind,
             regular_path: Option::default(),
             base_path: Option::default(),
             diff_path: Option::default(),
+            speedscope_path: Option::default(),
+            folded_path: Option::default(),
+            base_folded_path: Option::default(),
+            thread: Option::default(),
         }
     }
 }
@@ -646,3 +1091,184 @@ impl SummaryOutput {
         File::create(&self.path).with_context(|| "Failed to create json summary file")
     }
 }
+
+impl ToolSummary {
+    /// Return true if any [`ToolRunSummary`] of this tool has a performance regression
+    pub fn is_regressed(&self) -> bool {
+        self.summaries.iter().any(ToolRunSummary::is_regressed)
+    }
+}
+
+/// Format the `bench_bin` and the `bench_args` as a shell escaped command string
+fn format_command(bench_bin: &Path, bench_args: &[OsString]) -> String {
+    format!(
+        "{} {}",
+        bench_bin.display(),
+        shlex::try_join(
+            bench_args
+                .iter()
+                .map(|s| s.to_string_lossy().to_string())
+                .collect::<Vec<String>>()
+                .as_slice()
+                .iter()
+                .map(String::as_str)
+        )
+        .unwrap()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::api;
+    use crate::runner::args::CommandLineArgs;
+    use crate::runner::meta::Cmd;
+
+    fn meta(tools_fail_on_error: Option<bool>) -> Metadata {
+        let args = match tools_fail_on_error {
+            Some(value) => CommandLineArgs::parse_from([format!("--tools-fail-on-error={value}")]),
+            None => CommandLineArgs::parse_from(Vec::<String>::new()),
+        };
+        Metadata {
+            arch: "x86_64".to_owned(),
+            project_root: PathBuf::from("/root/project"),
+            target_dir: PathBuf::from("/root/project/target"),
+            valgrind: Cmd {
+                bin: PathBuf::from("valgrind"),
+                args: vec![],
+            },
+            valgrind_wrapper: None,
+            regression_config: None,
+            tool_regression_configs: vec![],
+            cost_model: None,
+            args,
+            bench_name: "bench".to_owned(),
+        }
+    }
+
+    fn memcheck(fail_on_errors: Option<bool>) -> api::Tool {
+        api::Tool {
+            kind: api::ValgrindTool::Memcheck,
+            enable: None,
+            raw_args: api::RawArgs::default(),
+            outfile_modifier: None,
+            show_log: None,
+            regression_config: None,
+            fail_on_errors,
+            flamegraph_config: None,
+        }
+    }
+
+    fn benchmark_summary(errors: u64, supp_errors: u64) -> BenchmarkSummary {
+        let mut summary = BenchmarkSummary::new(
+            BenchmarkKind::LibraryBenchmark,
+            PathBuf::from("/root/project"),
+            PathBuf::from("/root/project"),
+            PathBuf::from("benches/bench.rs"),
+            PathBuf::from("target/release/deps/bench-1234"),
+            &ModulePath::new("bench::group::function"),
+            "function",
+            None,
+            None,
+            None,
+        );
+        summary.tool_summaries.push(ToolSummary {
+            tool: ValgrindTool::Memcheck,
+            log_paths: vec![],
+            out_paths: vec![],
+            summaries: vec![ToolRunSummary {
+                command: "target/release/deps/bench-1234".to_owned(),
+                old_pid: None,
+                old_parent_pid: None,
+                pid: Some(2345_i32),
+                parent_pid: Some(2344_i32),
+                summary: IndexMap::new(),
+                details: Some("Invalid read of size 4".to_owned()),
+                error_summary: Some(ErrorSummary {
+                    errors,
+                    contexts: errors.min(1),
+                    supp_errors,
+                    supp_contexts: supp_errors.min(1),
+                }),
+                error_reports: vec![],
+                costs_summary: None,
+                allocation_sites: vec![],
+                basic_block_vector: None,
+                regressions: vec![],
+                log_path: PathBuf::from("memcheck.log"),
+            }],
+            flamegraphs: vec![],
+        });
+        summary
+    }
+
+    #[rstest]
+    #[case::no_errors(Some(true), None, 0, 0, false)]
+    #[case::unsuppressed_errors(Some(true), None, 2, 0, true)]
+    #[case::only_suppressed_errors(Some(true), None, 0, 5, false)]
+    #[case::not_fail_on_errors(Some(false), None, 2, 0, false)]
+    #[case::default_is_not_fail_on_errors(None, None, 2, 0, false)]
+    #[case::cli_overrides_false(Some(false), Some(true), 2, 0, true)]
+    #[case::cli_overrides_true(Some(true), Some(false), 2, 0, false)]
+    #[case::cli_when_not_configured(None, Some(true), 2, 0, true)]
+    fn test_check_tool_errors(
+        #[case] fail_on_errors: Option<bool>,
+        #[case] tools_fail_on_error: Option<bool>,
+        #[case] errors: u64,
+        #[case] supp_errors: u64,
+        #[case] expect_error: bool,
+    ) {
+        let tools = ToolConfigs::new(
+            api::Tools(vec![memcheck(fail_on_errors)]),
+            &ValgrindTool::Callgrind,
+            &meta(tools_fail_on_error),
+        );
+        let result = benchmark_summary(errors, supp_errors).check_tool_errors(&tools);
+
+        if expect_error {
+            assert_eq!(
+                result.unwrap_err().downcast::<Error>().unwrap(),
+                Error::ToolError(
+                    ValgrindTool::Memcheck,
+                    ModulePath::new("bench::group::function"),
+                    errors,
+                    1
+                )
+            );
+        } else {
+            assert!(result.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_tool_run_summary_error_contexts() {
+        let mut summary = benchmark_summary(1, 0).tool_summaries.remove(0).summaries.remove(0);
+        assert_eq!(
+            summary.error_contexts().as_deref(),
+            Some("Invalid read of size 4")
+        );
+
+        summary.error_reports = vec![ErrorReport {
+            kind: "InvalidRead".to_owned(),
+            message: "Invalid read of size 4".to_owned(),
+            count: 1,
+            stacks: vec![vec![StackFrame {
+                ip: "0x10A1B2".to_owned(),
+                function: Some("bench::my_bench".to_owned()),
+                file: Some(PathBuf::from("benches/bench.rs")),
+                line: Some(12),
+                ..Default::default()
+            }]],
+            ..Default::default()
+        }];
+        assert_eq!(
+            summary.error_contexts().as_deref(),
+            Some(
+                "InvalidRead: Invalid read of size 4\nat bench::my_bench (benches/bench.rs:12)"
+            )
+        );
+    }
+}
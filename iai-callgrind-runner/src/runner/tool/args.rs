@@ -18,18 +18,20 @@ pub struct ToolArgs {
 impl ToolArgs {
     pub fn from_raw_args(tool: ValgrindTool, raw_args: api::RawArgs) -> Self {
         let mut tool_args = Self {
-            tool,
-            output_paths: Vec::default(),
-            log_path: Option::default(),
             error_exitcode: match tool {
                 ValgrindTool::Memcheck | ValgrindTool::Helgrind | ValgrindTool::DRD => {
                     "201".to_owned()
                 }
                 ValgrindTool::Callgrind
+                | ValgrindTool::Cachegrind
                 | ValgrindTool::Massif
                 | ValgrindTool::DHAT
-                | ValgrindTool::BBV => "0".to_owned(),
+                | ValgrindTool::BBV
+                | ValgrindTool::Custom(_) => "0".to_owned(),
             },
+            tool,
+            output_paths: Vec::default(),
+            log_path: Option::default(),
             verbose: false,
             other: Vec::default(),
         };
@@ -40,16 +42,17 @@ impl ToolArgs {
                 .split_once('=')
                 .map(|(k, v)| (k.trim(), v.trim()))
             {
-                Some(("--tool", _)) => warn!("Ignoring {} argument '{arg}'", tool.id()),
+                Some(("--tool", _)) => warn!("Ignoring {} argument '{arg}'", tool_args.tool.id()),
                 Some((
-                    "--dhat-out-file" | "--massif-out-file" | "--bb-out-file" | "--pc-out-file"
-                    | "--log-file" | "--log-fd" | "--log-socket" | "--xml" | "--xml-file"
-                    | "--xml-fd" | "--xml-socket" | "--xml-user-comment",
+                    "--callgrind-out-file" | "--cachegrind-out-file" | "--dhat-out-file"
+                    | "--massif-out-file" | "--bb-out-file" | "--pc-out-file" | "--log-file"
+                    | "--log-fd" | "--log-socket" | "--xml" | "--xml-file" | "--xml-fd"
+                    | "--xml-socket" | "--xml-user-comment",
                     _,
                 )) => warn!(
                     "Ignoring {} argument '{arg}': Output/Log files of tools are managed by \
                      iai-callgrind",
-                    tool.id()
+                    tool_args.tool.id()
                 ),
                 Some(("--error-exitcode", value)) => {
                     value.clone_into(&mut tool_args.error_exitcode);
@@ -64,7 +67,7 @@ impl ToolArgs {
                         | "--quiet"
                 ) =>
                 {
-                    warn!("Ignoring {} argument '{arg}'", tool.id());
+                    warn!("Ignoring {} argument '{arg}'", tool_args.tool.id());
                 }
                 None if matches!(arg.as_str(), "--verbose") => tool_args.verbose = true,
                 None | Some(_) => tool_args.other.push(arg),
@@ -94,6 +97,16 @@ impl ToolArgs {
                 arg.push(callgrind_out_path.to_path());
                 self.output_paths.push(arg);
             }
+            ValgrindTool::Cachegrind => {
+                let mut arg = OsString::from("--cachegrind-out-file=");
+                let cachegrind_out_path = if let Some(modifier) = modifier {
+                    output_path.with_modifiers([modifier.as_ref()])
+                } else {
+                    output_path.clone()
+                };
+                arg.push(cachegrind_out_path.to_path());
+                self.output_paths.push(arg);
+            }
             ValgrindTool::Massif => {
                 let mut arg = OsString::from("--massif-out-file=");
                 let massif_out_path = if let Some(modifier) = modifier {
@@ -133,8 +146,19 @@ impl ToolArgs {
                 self.output_paths.push(bb_arg);
                 self.output_paths.push(pc_arg);
             }
-            // The other tools don't have an outfile
-            _ => {}
+            ValgrindTool::Memcheck | ValgrindTool::Helgrind | ValgrindTool::DRD => {
+                let mut arg = OsString::from("--xml-file=");
+                let xml_out_path = if let Some(modifier) = modifier {
+                    output_path.with_modifiers([modifier.as_ref()])
+                } else {
+                    output_path.clone()
+                };
+                arg.push(xml_out_path.to_path());
+                self.output_paths.push(OsString::from("--xml=yes"));
+                self.output_paths.push(arg);
+            }
+            // Custom tools only have log files
+            ValgrindTool::Custom(_) => {}
         }
     }
 
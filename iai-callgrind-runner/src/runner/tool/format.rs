@@ -4,13 +4,51 @@ use anyhow::Result;
 use colored::Colorize;
 
 use crate::runner::format::{format_vertical, NOT_AVAILABLE};
-use crate::runner::summary::ToolRunSummary;
+use crate::runner::summary::{ErrorReport, StackFrame, ToolRunSummary};
+
+/// The maximum number of frames of the stack trace of an [`ErrorReport`] in the terminal output
+const ERROR_REPORT_FRAMES: usize = 3;
 
 pub struct ToolRunSummaryFormatter;
 
 pub struct ToolSummaryFormatter;
 
-fn print_compare<T: Display>(
+/// Format a single [`StackFrame`] like valgrind does in its log output
+///
+/// The location is the file and line if available, otherwise the object.
+fn format_frame(frame: &StackFrame) -> String {
+    let function = frame.function.as_deref().unwrap_or("???");
+    match (&frame.file, frame.line, &frame.object) {
+        (Some(file), Some(line), _) => format!("{function} ({}:{line})", file.display()),
+        (Some(file), None, _) => format!("{function} ({})", file.display()),
+        (None, _, Some(object)) => format!("{function} (in {})", object.display()),
+        (None, _, None) => format!("{} {function}", frame.ip),
+    }
+}
+
+/// Format the [`ErrorReport`] into the headline and the top frames of its stack trace
+///
+/// The headline consists of the kind, the message and, if the error occurred more than once, the
+/// count of the error. Only the first [`ERROR_REPORT_FRAMES`] frames of the stack trace of the
+/// error itself are included.
+pub fn format_error_report(report: &ErrorReport) -> Vec<String> {
+    let mut lines = vec![if report.count > 1 {
+        format!("{}: {} ({} times)", report.kind, report.message, report.count)
+    } else {
+        format!("{}: {}", report.kind, report.message)
+    }];
+
+    let frames = report.stacks.first().map_or(&[][..], Vec::as_slice);
+    for (index, frame) in frames.iter().take(ERROR_REPORT_FRAMES).enumerate() {
+        let prefix = if index == 0 { "at" } else { "by" };
+        lines.push(format!("{prefix} {}", format_frame(frame)));
+    }
+
+    lines
+}
+
+/// Print the `new` and `old` value side by side if `should_compare` is true
+pub fn print_compare<T: Display>(
     description: &str,
     old: Option<T>,
     new: Option<T>,
@@ -56,6 +94,55 @@ impl ToolRunSummaryFormatter {
             print!("{}", format_vertical((None, None), costs.all_diffs())?);
         }
 
+        // Unchanged allocation sites are only interesting in verbose mode
+        let is_compared = summary.pid.is_some() && summary.old_pid.is_some();
+        for (rank, site) in summary
+            .allocation_sites
+            .iter()
+            .enumerate()
+            .filter(|(_, site)| verbose || (is_compared && site.is_changed()))
+        {
+            let mut frames = site.frames.iter();
+            let head_line = frames.next().map_or(NOT_AVAILABLE, String::as_str);
+            println!("  {:<18}{}", format!("Site #{}:", rank + 1), head_line.blue());
+            for frame in frames {
+                println!("                    {frame}");
+            }
+            print!(
+                "{}",
+                format_vertical((None, None), site.costs_summary.all_diffs())?
+            );
+        }
+
+        if let Some(bbv) = &summary.basic_block_vector {
+            if let Some(distance) = bbv.distance {
+                println!(
+                    "  {:<18}{}",
+                    "BBV Distance:",
+                    format!("{distance:.5}").bold()
+                );
+            }
+            // The hottest blocks are only interesting in verbose mode
+            let hottest_blocks = if verbose {
+                bbv.hottest_blocks.as_slice()
+            } else {
+                &[]
+            };
+            for (rank, block) in hottest_blocks.iter().enumerate() {
+                let location = match (&block.function, &block.address) {
+                    (Some(function), Some(address)) => format!("{function} ({address})"),
+                    (None, Some(address)) => address.clone(),
+                    _ => format!("block {}", block.id),
+                };
+                println!(
+                    "  {:<18}{:>15} {}",
+                    format!("Block #{}:", rank + 1),
+                    block.instructions.to_string().bold(),
+                    location.blue()
+                );
+            }
+        }
+
         for field in &summary.summary {
             println!("  {:<18}{}", format!("{}:", field.0), field.1.bold());
         }
@@ -70,6 +157,23 @@ impl ToolRunSummaryFormatter {
             }
         }
 
+        // The error contexts aren't part of the details if the errors are written to xml
+        if verbose || summary.has_errors() {
+            for (index, report) in summary.error_reports.iter().enumerate() {
+                let mut lines = format_error_report(report).into_iter();
+                if let Some(head_line) = lines.next() {
+                    println!(
+                        "  {:<18}{}",
+                        format!("Error #{}:", index + 1),
+                        head_line.blue()
+                    );
+                }
+                for line in lines {
+                    println!("                    {line}");
+                }
+            }
+        }
+
         if let Some(error_summary) = summary.error_summary.as_ref() {
             println!(
                 "  {:<18}{}",
@@ -96,3 +200,73 @@ impl ToolRunSummaryFormatter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn frame(function: Option<&str>, file: Option<&str>, line: Option<u64>) -> StackFrame {
+        StackFrame {
+            ip: "0x10A1B2".to_owned(),
+            object: Some(PathBuf::from("target/release/deps/bench-1234")),
+            function: function.map(ToOwned::to_owned),
+            file: file.map(PathBuf::from),
+            line,
+        }
+    }
+
+    #[rstest]
+    #[case::file_and_line(
+        frame(Some("bench::my_bench"), Some("benches/bench.rs"), Some(12)),
+        "bench::my_bench (benches/bench.rs:12)"
+    )]
+    #[case::file(
+        frame(Some("bench::my_bench"), Some("benches/bench.rs"), None),
+        "bench::my_bench (benches/bench.rs)"
+    )]
+    #[case::object(
+        frame(Some("malloc"), None, None),
+        "malloc (in target/release/deps/bench-1234)"
+    )]
+    #[case::no_function(frame(None, None, None), "??? (in target/release/deps/bench-1234)")]
+    fn test_format_frame(#[case] frame: StackFrame, #[case] expected: &str) {
+        assert_eq!(format_frame(&frame), expected);
+    }
+
+    #[rstest]
+    #[case::single(1, 1, &["InvalidRead: Invalid read of size 4", "at main (bench.rs:1)"])]
+    #[case::top_frames(
+        2,
+        5,
+        &[
+            "InvalidRead: Invalid read of size 4 (2 times)",
+            "at main (bench.rs:1)",
+            "by main (bench.rs:2)",
+            "by main (bench.rs:3)",
+        ]
+    )]
+    fn test_format_error_report(
+        #[case] count: u64,
+        #[case] num_frames: u64,
+        #[case] expected: &[&str],
+    ) {
+        let report = ErrorReport {
+            kind: "InvalidRead".to_owned(),
+            message: "Invalid read of size 4".to_owned(),
+            count,
+            stacks: vec![
+                (1..=num_frames)
+                    .map(|line| frame(Some("main"), Some("bench.rs"), Some(line)))
+                    .collect(),
+                vec![frame(Some("malloc"), None, None)],
+            ],
+            ..Default::default()
+        };
+        assert_eq!(format_error_report(&report), expected);
+    }
+}
@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::{Context, Result};
@@ -8,11 +8,16 @@ use lazy_static::lazy_static;
 use log::debug;
 use regex::Regex;
 
+use super::xml_parser::XmlLogfileParser;
 use super::{ToolOutputPath, ValgrindTool};
 use crate::error::Error;
+use crate::runner::bbv::bb_parser::{summarize_basic_block_vectors, BasicBlockVector};
+use crate::runner::bbv::logfile_parser::BbvLogfileParser;
 use crate::runner::costs::Costs;
+use crate::runner::dhat::json_parser::{summarize_allocation_sites, AllocationSite};
 use crate::runner::dhat::logfile_parser::DhatLogfileParser;
-use crate::runner::summary::{CostsSummary, ErrorSummary, ToolRunSummary};
+use crate::runner::dhat::massif_parser::MassifLogfileParser;
+use crate::runner::summary::{CostsSummary, ErrorReport, ErrorSummary, ToolRunSummary};
 use crate::util::make_relative;
 
 // The different regex have to consider --time-stamp=yes
@@ -50,7 +55,10 @@ pub struct LogfileSummary {
     pub fields: Vec<(String, String)>,
     pub details: Vec<String>,
     pub error_summary: Option<ErrorSummary>,
+    pub error_reports: Vec<ErrorReport>,
     pub costs: Option<Costs<String>>,
+    pub allocation_sites: Vec<AllocationSite>,
+    pub basic_block_vector: Option<BasicBlockVector>,
     pub log_path: PathBuf,
 }
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -106,7 +114,11 @@ impl LogfileSummary {
             summary: self.fields.into_iter().collect(),
             details: (!self.details.is_empty()).then(|| self.details.join("\n")),
             error_summary: self.error_summary,
+            error_reports: self.error_reports,
             costs_summary: None,
+            allocation_sites: vec![],
+            basic_block_vector: None,
+            regressions: vec![],
             log_path: self.log_path,
         }
     }
@@ -115,24 +127,34 @@ impl LogfileSummary {
             .costs
             .as_ref()
             .map(|x| CostsSummary::new(&Costs::empty(), Some(x)));
+        let allocation_sites = summarize_allocation_sites(&[], &self.allocation_sites);
+        let basic_block_vector =
+            summarize_basic_block_vectors(None, self.basic_block_vector.as_ref());
         let old_pid = Some(self.pid);
         let old_parent_pid = self.parent_pid;
         ToolRunSummary {
             old_pid,
             old_parent_pid,
             costs_summary,
+            allocation_sites,
+            basic_block_vector,
             ..self.raw_into_tool_run()
         }
     }
 
     pub fn new_into_tool_run(self) -> ToolRunSummary {
         let costs_summary = self.costs.as_ref().map(|x| CostsSummary::new(x, None));
+        let allocation_sites = summarize_allocation_sites(&self.allocation_sites, &[]);
+        let basic_block_vector =
+            summarize_basic_block_vectors(self.basic_block_vector.as_ref(), None);
         let pid = Some(self.pid);
         let parent_pid = self.parent_pid;
         ToolRunSummary {
             pid,
             parent_pid,
             costs_summary,
+            allocation_sites,
+            basic_block_vector,
             ..self.raw_into_tool_run()
         }
     }
@@ -143,6 +165,12 @@ impl LogfileSummary {
             let emp = Costs::empty();
             CostsSummary::new(self.costs.as_ref().unwrap_or(&emp), old.costs.as_ref())
         });
+        let allocation_sites =
+            summarize_allocation_sites(&self.allocation_sites, &old.allocation_sites);
+        let basic_block_vector = summarize_basic_block_vectors(
+            self.basic_block_vector.as_ref(),
+            old.basic_block_vector.as_ref(),
+        );
         let old_pid = Some(old.pid);
         let old_parent_pid = old.parent_pid;
         let pid = Some(self.pid);
@@ -153,6 +181,8 @@ impl LogfileSummary {
             pid,
             parent_pid,
             costs_summary,
+            allocation_sites,
+            basic_block_vector,
             ..self.raw_into_tool_run()
         }
     }
@@ -241,8 +271,11 @@ impl LogfileParser for ToolLogfileParser {
             fields: Vec::default(),
             details,
             error_summary,
+            error_reports: vec![],
             log_path: make_relative(&self.root_dir, path),
             costs: None,
+            allocation_sites: vec![],
+            basic_block_vector: None,
         })
     }
 
@@ -257,6 +290,81 @@ impl LogfileParser for ToolLogfileParser {
     }
 }
 
+/// Convert the path of a log file to the path of the accompanying out file
+///
+/// The `prefix` is the part of the file name preceding the extension (`$TOOL.$NAME.`). The log
+/// file `$TOOL.$NAME.log.$MODIFIERS` corresponds to the out file `$TOOL.$NAME.out.$MODIFIERS`.
+pub fn to_out_file(log_file: &Path, prefix: &str) -> Option<PathBuf> {
+    to_out_file_with_extension(log_file, prefix, "out")
+}
+
+/// Like [`to_out_file`] but with a custom `extension` of the out file
+///
+/// Some tools like BBV write multiple out files with extensions like `out.bb` and `out.pc`.
+pub fn to_out_file_with_extension(
+    log_file: &Path,
+    prefix: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let file_name = log_file.file_name()?.to_string_lossy().to_string();
+    let suffix = file_name.strip_prefix(prefix)?.strip_prefix("log")?;
+    Some(log_file.with_file_name(format!("{prefix}{extension}{suffix}")))
+}
+
+/// Parse the log files of the `output_path` together with their accompanying out files
+///
+/// The `attach` function is called with the summary of each log file and the path to the out
+/// file, if the out file exists.
+pub fn parse_with_out_files<P, F>(
+    parser: &P,
+    output_path: &ToolOutputPath,
+    attach: F,
+) -> Result<Vec<LogfileSummary>>
+where
+    P: LogfileParser + ?Sized,
+    F: FnMut(&mut LogfileSummary, &Path) -> Result<()>,
+{
+    parse_with_out_files_with_extension(parser, output_path, "out", attach)
+}
+
+/// Like [`parse_with_out_files`] but with a custom `extension` of the out files
+pub fn parse_with_out_files_with_extension<P, F>(
+    parser: &P,
+    output_path: &ToolOutputPath,
+    extension: &str,
+    mut attach: F,
+) -> Result<Vec<LogfileSummary>>
+where
+    P: LogfileParser + ?Sized,
+    F: FnMut(&mut LogfileSummary, &Path) -> Result<()>,
+{
+    let log_path = output_path.to_log_output();
+    debug!("{}: Parsing log file '{}'", output_path.tool.id(), log_path);
+
+    let Ok(paths) = log_path.real_paths() else {
+        return Ok(vec![]);
+    };
+
+    let prefix = format!("{}.{}.", log_path.tool.id(), log_path.name);
+    let mut summaries = vec![];
+    for path in paths {
+        let out_file =
+            to_out_file_with_extension(&path, &prefix, extension).filter(|p| p.exists());
+        let mut summary = parser.parse_single(path)?;
+        if let Some(out_file) = out_file {
+            debug!(
+                "{}: Parsing out file '{}'",
+                log_path.tool.id(),
+                out_file.display()
+            );
+            attach(&mut summary, &out_file)?;
+        }
+        summaries.push(summary);
+    }
+    summaries.sort_by_key(|x| x.pid);
+    Ok(summaries)
+}
+
 pub fn extract_pid(line: &str) -> i32 {
     EXTRACT_PID_RE
         .captures(line.trim())
@@ -269,9 +377,14 @@ pub fn extract_pid(line: &str) -> i32 {
 }
 
 impl ValgrindTool {
-    pub fn to_parser(self, root_dir: PathBuf) -> Box<dyn LogfileParser> {
+    pub fn to_parser(&self, root_dir: PathBuf) -> Box<dyn LogfileParser> {
         match self {
             ValgrindTool::DHAT => Box::new(DhatLogfileParser { root_dir }),
+            ValgrindTool::Massif => Box::new(MassifLogfileParser { root_dir }),
+            ValgrindTool::BBV => Box::new(BbvLogfileParser { root_dir }),
+            ValgrindTool::Memcheck | ValgrindTool::Helgrind | ValgrindTool::DRD => {
+                Box::new(XmlLogfileParser { root_dir })
+            }
             _ => Box::new(ToolLogfileParser { root_dir }),
         }
     }
@@ -284,3 +397,26 @@ impl LogfileSummary {
             .map_or(false, ErrorSummary::has_errors)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::no_modifiers("massif.some.log", Some("massif.some.out"))]
+    #[case::with_modifier("massif.some.log.#1234", Some("massif.some.out.#1234"))]
+    #[case::old("massif.some.log.old", Some("massif.some.out.old"))]
+    #[case::base("massif.some.log.base@log", Some("massif.some.out.base@log"))]
+    #[case::not_a_log("massif.some.out", None)]
+    #[case::other_name("massif.other.log", None)]
+    fn test_to_out_file(#[case] log_file: &str, #[case] expected: Option<&str>) {
+        let dir = PathBuf::from("/some/dir");
+        assert_eq!(
+            to_out_file(&dir.join(log_file), "massif.some."),
+            expected.map(|e| dir.join(e))
+        );
+    }
+}
@@ -2,6 +2,8 @@
 pub mod args;
 pub mod format;
 pub mod logfile_parser;
+pub mod regression;
+pub mod xml_parser;
 
 use std::ffi::OsString;
 use std::fmt::Display;
@@ -12,7 +14,7 @@ use std::process::{Child, Command, ExitStatus, Output};
 
 use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
-use log::{debug, error, log_enabled, Level};
+use log::{debug, error, log_enabled, warn, Level};
 #[cfg(feature = "schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -20,12 +22,15 @@ use serde::{Deserialize, Serialize};
 use self::args::ToolArgs;
 use self::format::ToolRunSummaryFormatter;
 use self::logfile_parser::LogfileSummary;
+use self::regression::ToolRegressionConfig;
 use super::args::NoCapture;
+use super::callgrind::flamegraph::Config as FlamegraphConfig;
 use super::common::{Assistant, Config, ModulePath, Sandbox};
+use super::dhat;
 use super::format::{print_no_capture_footer, tool_headline, OutputFormat};
 use super::meta::Metadata;
-use super::summary::{BaselineKind, ToolRunSummary, ToolSummary};
-use crate::api::{self, ExitWith, Stream};
+use super::summary::{BaselineKind, FlamegraphSummary, ToolRunSummary, ToolSummary};
+use crate::api::{self, DhatMetric, ExitWith, Stream};
 use crate::error::Error;
 use crate::util::{self, make_relative, resolve_binary_path, truncate_str_utf8};
 
@@ -40,15 +45,18 @@ pub struct RunOptions {
     pub stderr: Option<api::Stdio>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ToolConfig {
     pub tool: ValgrindTool,
     pub is_enabled: bool,
     pub args: ToolArgs,
     pub outfile_modifier: Option<String>,
+    pub regression_config: Option<ToolRegressionConfig>,
+    pub fail_on_errors: bool,
+    pub flamegraph_config: Option<FlamegraphConfig>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ToolConfigs(pub Vec<ToolConfig>);
 
 pub struct ToolCommand {
@@ -70,6 +78,10 @@ pub struct ToolOutputPath {
     pub dir: PathBuf,
     pub name: String,
     pub modifiers: Vec<String>,
+    /// The part number of a single dump if the callgrind output is split into multiple dumps
+    pub part: Option<u64>,
+    /// The thread index if the callgrind output is split into multiple threads
+    pub thread: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -82,16 +94,18 @@ pub enum ToolOutputPathKind {
     Base(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum ValgrindTool {
     Callgrind,
+    Cachegrind,
     Memcheck,
     Helgrind,
     DRD,
     Massif,
     DHAT,
     BBV,
+    Custom(String),
 }
 
 pub trait Parser {
@@ -112,7 +126,7 @@ impl ToolCommand {
     pub fn env_clear(&mut self) -> &mut Self {
         debug!("{}: Clearing environment variables", self.tool.id());
         for (key, _) in std::env::vars() {
-            match (key.as_str(), self.tool) {
+            match (key.as_str(), &self.tool) {
                 (key @ ("DEBUGINFOD_URLS" | "PATH" | "HOME"), ValgrindTool::Memcheck)
                 | (key @ ("LD_PRELOAD" | "LD_LIBRARY_PATH"), _) => {
                     debug!(
@@ -189,7 +203,7 @@ impl ToolCommand {
             .args(executable_args)
             .envs(envs);
 
-        if self.tool == ValgrindTool::Callgrind {
+        if self.tool.is_default_tool() {
             debug!("Applying --nocapture options");
             self.nocapture.apply(&mut self.command);
         }
@@ -198,23 +212,27 @@ impl ToolCommand {
             stdin
                 .apply(&mut self.command, Stream::Stdin, child.as_mut())
                 .map_err(|error| {
-                    Error::BenchmarkError(ValgrindTool::Callgrind, module_path.clone(), error)
+                    Error::BenchmarkError(self.tool.clone(), module_path.clone(), error)
                 })?;
         }
         if let Some(stdout) = stdout {
             stdout
                 .apply(&mut self.command, Stream::Stdout)
-                .map_err(|error| Error::BenchmarkError(self.tool, module_path.clone(), error))?;
+                .map_err(|error| {
+                    Error::BenchmarkError(self.tool.clone(), module_path.clone(), error)
+                })?;
         }
         if let Some(stderr) = stderr {
             stderr
                 .apply(&mut self.command, Stream::Stderr)
-                .map_err(|error| Error::BenchmarkError(self.tool, module_path.clone(), error))?;
+                .map_err(|error| {
+                    Error::BenchmarkError(self.tool.clone(), module_path.clone(), error)
+                })?;
         }
 
         let output = match self.nocapture {
             NoCapture::True | NoCapture::Stderr | NoCapture::Stdout
-                if self.tool == ValgrindTool::Callgrind =>
+                if self.tool.is_default_tool() =>
             {
                 self.command
                     .status()
@@ -223,7 +241,7 @@ impl ToolCommand {
                     })
                     .and_then(|status| {
                         check_exit(
-                            self.tool,
+                            &self.tool,
                             &executable,
                             None,
                             status,
@@ -242,7 +260,7 @@ impl ToolCommand {
                 .and_then(|output| {
                     let status = output.status;
                     check_exit(
-                        self.tool,
+                        &self.tool,
                         &executable,
                         Some(output),
                         status,
@@ -267,7 +285,7 @@ impl ToolCommand {
         }
 
         Ok(ToolOutput {
-            tool: self.tool,
+            tool: self.tool.clone(),
             output,
         })
     }
@@ -283,6 +301,42 @@ impl ToolConfig {
             is_enabled,
             args: args.into(),
             outfile_modifier: modifier,
+            regression_config: None,
+            fail_on_errors: false,
+            flamegraph_config: None,
+        }
+    }
+
+    /// Check the costs of all [`ToolRunSummary`]s for performance regressions and print them
+    ///
+    /// The detected regressions are stored in the [`ToolRunSummary`].
+    fn check_and_print_regressions(&self, summaries: &mut [ToolRunSummary]) {
+        if let Some(regression_config) = &self.regression_config {
+            for summary in summaries {
+                if let Some(costs_summary) = &summary.costs_summary {
+                    summary.regressions = regression_config.check_and_print(costs_summary);
+                }
+            }
+        }
+    }
+
+    /// Create the flamegraphs of the tool if configured
+    ///
+    /// Currently, only DHAT can create flamegraphs.
+    fn create_flamegraphs(
+        &self,
+        output_path: &ToolOutputPath,
+        module_path: &ModulePath,
+        save_baseline: bool,
+    ) -> Result<Vec<FlamegraphSummary<DhatMetric>>> {
+        match (&self.tool, &self.flamegraph_config) {
+            (ValgrindTool::DHAT, Some(flamegraph_config)) => dhat::flamegraph::create_flamegraphs(
+                flamegraph_config,
+                module_path.to_string(),
+                output_path,
+                save_baseline,
+            ),
+            _ => Ok(vec![]),
         }
     }
 
@@ -296,10 +350,11 @@ impl ToolConfig {
         let old_summaries = parser.as_ref().parse(&log_path.to_base_path())?;
         let summaries = parser.as_ref().parse_merge(log_path, old_summaries)?;
         let tool_summary = ToolSummary {
-            tool: self.tool,
+            tool: self.tool.clone(),
             log_paths: log_path.real_paths()?,
             out_paths: out_path.map_or_else(|| Ok(Vec::default()), ToolOutputPath::real_paths)?,
             summaries,
+            flamegraphs: vec![],
         };
 
         Ok(tool_summary)
@@ -308,32 +363,109 @@ impl ToolConfig {
 
 impl From<api::Tool> for ToolConfig {
     fn from(value: api::Tool) -> Self {
-        let tool = value.kind.into();
+        let tool: ValgrindTool = value.kind.into();
         Self {
+            args: ToolArgs::from_raw_args(tool.clone(), value.raw_args),
             tool,
             is_enabled: value.enable.unwrap_or(true),
-            args: ToolArgs::from_raw_args(tool, value.raw_args),
             outfile_modifier: value.outfile_modifier,
+            regression_config: value.regression_config.map(Into::into),
+            fail_on_errors: value.fail_on_errors.unwrap_or(false),
+            flamegraph_config: value.flamegraph_config.map(Into::into),
         }
     }
 }
 
 impl ToolConfigs {
+    /// Create new `ToolConfigs` from the [`api::Tools`] without the `default_tool`
+    ///
+    /// The default tool is always run and cannot be configured as additional tool. The tool
+    /// regression configurations and `--tools-fail-on-error` of the command-line in the
+    /// [`Metadata`] override the configuration of the tools.
+    pub fn new(tools: api::Tools, default_tool: &ValgrindTool, meta: &Metadata) -> Self {
+        for (tool, _) in &meta.tool_regression_configs {
+            if !tools.0.iter().any(|t| ValgrindTool::from(t.kind.clone()) == *tool) {
+                warn!(
+                    "Ignoring the --tool-regression limits for {tool}: {tool} is not configured \
+                     as tool of this benchmark"
+                );
+            }
+        }
+
+        Self(
+            tools
+                .0
+                .into_iter()
+                .map(|mut tool| {
+                    let kind = ValgrindTool::from(tool.kind.clone());
+                    tool.regression_config = api::update_option(
+                        &tool.regression_config,
+                        &meta
+                            .tool_regression_configs
+                            .iter()
+                            .find_map(|(t, c)| (*t == kind).then(|| c.clone())),
+                    );
+                    tool.fail_on_errors =
+                        api::update_option(&tool.fail_on_errors, &meta.args.tools_fail_on_error);
+                    if kind != ValgrindTool::DHAT && tool.flamegraph_config.take().is_some() {
+                        warn!(
+                            "Ignoring flamegraph configuration for {kind}: Flamegraphs are only \
+                             available for DHAT"
+                        );
+                    }
+                    ToolConfig::from(tool)
+                })
+                .filter(|tool_config| match &tool_config.tool {
+                    tool if tool == default_tool => {
+                        warn!(
+                            "Ignoring tool configuration for {default_tool}: {default_tool} is \
+                             already the default tool"
+                        );
+                        false
+                    }
+                    ValgrindTool::Custom(id) if !ValgrindTool::is_valid_id(id) => {
+                        warn!(
+                            "Ignoring tool configuration for custom tool '{id}': The name of a \
+                             tool can only consist of ascii alphanumeric characters, '-' or '_'"
+                        );
+                        false
+                    }
+                    _ => true,
+                })
+                .collect(),
+        )
+    }
+
     pub fn has_tools_enabled(&self) -> bool {
         self.0.iter().any(|t| t.is_enabled)
     }
 
+    /// Return true if the `tool` is configured to fail on the first performance regression
+    pub fn is_fail_fast(&self, tool: &ValgrindTool) -> bool {
+        self.0.iter().any(|t| {
+            t.tool == *tool
+                && t.regression_config
+                    .as_ref()
+                    .map_or(false, |r| r.fail_fast)
+        })
+    }
+
+    /// Return true if the `tool` is configured to fail the benchmark if it reports errors
+    pub fn is_fail_on_errors(&self, tool: &ValgrindTool) -> bool {
+        self.0.iter().any(|t| t.tool == *tool && t.fail_on_errors)
+    }
+
     pub fn output_paths(&self, output_path: &ToolOutputPath) -> Vec<ToolOutputPath> {
         self.0
             .iter()
             .filter(|t| t.is_enabled)
-            .map(|t| output_path.to_tool_output(t.tool))
+            .map(|t| output_path.to_tool_output(t.tool.clone()))
             .collect()
     }
 
     fn print_headline(meta: &Metadata, tool_config: &ToolConfig) {
         if meta.args.output_format == OutputFormat::Default {
-            println!("{}", tool_headline(tool_config.tool));
+            println!("{}", tool_headline(&tool_config.tool));
         }
     }
 
@@ -379,10 +511,11 @@ impl ToolConfigs {
         let summaries = parser.as_ref().parse_merge(log_path, old_summaries)?;
 
         Ok(ToolSummary {
-            tool: tool_config.tool,
+            tool: tool_config.tool.clone(),
             log_paths: log_path.real_paths()?,
             out_paths: out_path.map_or_else(|| Ok(Vec::default()), ToolOutputPath::real_paths)?,
             summaries,
+            flamegraphs: vec![],
         })
     }
 
@@ -390,17 +523,20 @@ impl ToolConfigs {
         &self,
         meta: &Metadata,
         output_path: &ToolOutputPath,
+        module_path: &ModulePath,
     ) -> Result<Vec<ToolSummary>> {
         let mut tool_summaries = vec![];
         for tool_config in self.0.iter().filter(|t| t.is_enabled) {
-            let tool = tool_config.tool;
+            let tool = &tool_config.tool;
 
-            let output_path = output_path.to_tool_output(tool);
+            let output_path = output_path.to_tool_output(tool.clone());
             let log_path = output_path.to_log_output();
 
             Self::print_headline(meta, tool_config);
 
-            let tool_summary = tool_config.parse_load(meta, &log_path, None)?;
+            let mut tool_summary = tool_config.parse_load(meta, &log_path, None)?;
+            tool_summary.flamegraphs =
+                tool_config.create_flamegraphs(&output_path, module_path, false)?;
 
             Self::print(
                 meta,
@@ -408,6 +544,7 @@ impl ToolConfigs {
                 &tool_summary.summaries,
                 &tool_summary.out_paths,
             )?;
+            tool_config.check_and_print_regressions(&mut tool_summary.summaries);
 
             log_path.dump_log(log::Level::Info, &mut stderr())?;
 
@@ -432,11 +569,11 @@ impl ToolConfigs {
     ) -> Result<Vec<ToolSummary>> {
         let mut tool_summaries = vec![];
         for tool_config in self.0.iter().filter(|t| t.is_enabled) {
-            let tool = tool_config.tool;
+            let tool = &tool_config.tool;
 
-            let command = ToolCommand::new(tool, &config.meta, NoCapture::False);
+            let command = ToolCommand::new(tool.clone(), &config.meta, NoCapture::False);
 
-            let output_path = output_path.to_tool_output(tool);
+            let output_path = output_path.to_tool_output(tool.clone());
             let log_path = output_path.to_log_output();
 
             Self::print_headline(&config.meta, tool_config);
@@ -483,13 +620,15 @@ impl ToolConfigs {
                 sandbox.reset()?;
             }
 
-            let tool_summary = Self::parse(
+            let mut tool_summary = Self::parse(
                 tool_config,
                 &config.meta,
                 &log_path,
                 tool.has_output_file().then_some(&output_path),
                 old_summaries,
             )?;
+            tool_summary.flamegraphs =
+                tool_config.create_flamegraphs(&output_path, module_path, save_baseline)?;
 
             Self::print(
                 &config.meta,
@@ -497,6 +636,7 @@ impl ToolConfigs {
                 &tool_summary.summaries,
                 &tool_summary.out_paths,
             )?;
+            tool_config.check_and_print_regressions(&mut tool_summary.summaries);
 
             output.dump_log(log::Level::Info);
             log_path.dump_log(log::Level::Info, &mut stderr())?;
@@ -556,6 +696,8 @@ impl ToolOutputPath {
                 .join(sanitized_name),
             name: sanitized_name.to_owned(),
             modifiers: vec![],
+            part: None,
+            thread: None,
         }
     }
 
@@ -646,11 +788,13 @@ impl ToolOutputPath {
                 ) => ToolOutputPathKind::BaseLog(name.to_string()),
                 (kind, _) => kind.clone(),
             },
-            tool: self.tool,
+            tool: self.tool.clone(),
             baseline_kind: self.baseline_kind.clone(),
             name: self.name.clone(),
             dir: self.dir.clone(),
             modifiers: self.modifiers.clone(),
+            part: self.part,
+            thread: self.thread,
         }
     }
 
@@ -662,6 +806,8 @@ impl ToolOutputPath {
             name: self.name.clone(),
             dir: self.dir.clone(),
             modifiers: self.modifiers.clone(),
+            part: self.part,
+            thread: self.thread,
         }
     }
 
@@ -672,11 +818,13 @@ impl ToolOutputPath {
                 ToolOutputPathKind::Base(name) => ToolOutputPathKind::BaseLog(name.clone()),
                 kind => kind.clone(),
             },
-            tool: self.tool,
+            tool: self.tool.clone(),
             baseline_kind: self.baseline_kind.clone(),
             name: self.name.clone(),
             dir: self.dir.clone(),
             modifiers: self.modifiers.clone(),
+            part: self.part,
+            thread: self.thread,
         }
     }
 
@@ -724,7 +872,7 @@ impl ToolOutputPath {
     }
 
     pub fn extension(&self) -> String {
-        match (&self.kind, self.modifiers.is_empty()) {
+        let extension = match (&self.kind, self.modifiers.is_empty()) {
             (ToolOutputPathKind::Out, true) => "out".to_owned(),
             (ToolOutputPathKind::Out, false) => format!("out.{}", self.modifiers.join(".")),
             (ToolOutputPathKind::Log, true) => "log".to_owned(),
@@ -743,6 +891,24 @@ impl ToolOutputPath {
             (ToolOutputPathKind::Base(name), false) => {
                 format!("out.{}.base@{name}", self.modifiers.join("."))
             }
+        };
+
+        // Callgrind appends the part number and the thread index to the whole output file name,
+        // but the `*.old` files are created by us with `shift` which appends `.old` to the whole
+        // file name.
+        let suffix = match (self.part, self.thread) {
+            (Some(part), Some(thread)) => format!(".{part}-{thread:02}"),
+            (Some(part), None) => format!(".{part}"),
+            (None, Some(thread)) => format!("-{thread:02}"),
+            (None, None) => String::new(),
+        };
+        match &self.kind {
+            ToolOutputPathKind::Out | ToolOutputPathKind::Base(_) => format!("{extension}{suffix}"),
+            ToolOutputPathKind::OldOut => {
+                let extension = extension.strip_suffix(".old").unwrap_or(&extension);
+                format!("{extension}{suffix}.old")
+            }
+            _ => extension,
         }
     }
 
@@ -753,14 +919,88 @@ impl ToolOutputPath {
     {
         Self {
             kind: self.kind.clone(),
-            tool: self.tool,
+            tool: self.tool.clone(),
             baseline_kind: self.baseline_kind.clone(),
             dir: self.dir.clone(),
             name: self.name.clone(),
             modifiers: modifiers.into_iter().map(Into::into).collect(),
+            part: self.part,
+            thread: self.thread,
         }
     }
 
+    pub fn with_part(&self, part: u64) -> Self {
+        Self {
+            kind: self.kind.clone(),
+            tool: self.tool.clone(),
+            baseline_kind: self.baseline_kind.clone(),
+            dir: self.dir.clone(),
+            name: self.name.clone(),
+            modifiers: self.modifiers.clone(),
+            part: Some(part),
+            thread: self.thread,
+        }
+    }
+
+    pub fn with_thread(&self, thread: usize) -> Self {
+        Self {
+            kind: self.kind.clone(),
+            tool: self.tool.clone(),
+            baseline_kind: self.baseline_kind.clone(),
+            dir: self.dir.clone(),
+            name: self.name.clone(),
+            modifiers: self.modifiers.clone(),
+            part: self.part,
+            thread: Some(thread),
+        }
+    }
+
+    /// Return the output paths of all dumps of a callgrind output sorted by the part number
+    ///
+    /// Without `--combine-dumps=yes`, callgrind writes every dump triggered before the program
+    /// terminates into a separate file with the part number appended (`*.out.1`, `*.out.2`, ...).
+    /// The dump at program termination is written to the output file without a part number. So,
+    /// this output path itself is always the last element.
+    pub fn to_parts(&self) -> Vec<Self> {
+        let mut parts = vec![];
+        let mut part = 1;
+        loop {
+            let path = self.with_part(part);
+            if !path.to_path().exists() && path.to_threads().is_empty() {
+                break;
+            }
+            parts.push(path);
+            part += 1;
+        }
+        parts.push(self.clone());
+        parts
+    }
+
+    /// Return the output paths of all threads of this dump sorted by the thread index
+    ///
+    /// With `--separate-threads=yes`, callgrind writes the costs of every thread into a separate
+    /// file with the thread index appended (`*.out-01`, `*.out-02`, ...). Valgrind assigns the
+    /// thread indices in ascending order starting with `1` for the main thread. The returned
+    /// `Vec` is empty if the threads are not separated or if this is already the output path of a
+    /// single thread.
+    pub fn to_threads(&self) -> Vec<Self> {
+        let mut threads = vec![];
+        if self.thread.is_some() {
+            return threads;
+        }
+
+        let mut thread = 1;
+        loop {
+            let path = self.with_thread(thread);
+            if !path.to_path().exists() {
+                break;
+            }
+            threads.push(path);
+            thread += 1;
+        }
+        threads
+    }
+
     pub fn to_path(&self) -> PathBuf {
         self.dir.join(format!(
             "{}.{}.{}",
@@ -788,9 +1028,7 @@ impl ToolOutputPath {
                     ToolOutputPathKind::Out => {
                         suffix.starts_with("out")
                             && !(suffix.ends_with(".old")
-                                || suffix
-                                    .rsplit_once('.')
-                                    .map_or(false, |(_, b)| b.starts_with("base@")))
+                                || suffix.split('.').any(|b| b.starts_with("base@")))
                     }
                     ToolOutputPathKind::Log => {
                         suffix.starts_with("log")
@@ -810,8 +1048,12 @@ impl ToolOutputPath {
                             && suffix.ends_with(format!(".base@{name}").as_str())
                     }
                     ToolOutputPathKind::Base(name) => {
+                        // The files of the single dumps and threads of callgrind end with the part
+                        // number and thread index
                         suffix.starts_with("out")
-                            && suffix.ends_with(format!(".base@{name}").as_str())
+                            && suffix
+                                .split_once(format!(".base@{name}").as_str())
+                                .map_or(false, |(_, rest)| is_part_and_thread_suffix(rest))
                     }
                 };
 
@@ -836,20 +1078,93 @@ impl ValgrindTool {
         match self {
             ValgrindTool::DHAT => "dhat".to_owned(),
             ValgrindTool::Callgrind => "callgrind".to_owned(),
+            ValgrindTool::Cachegrind => "cachegrind".to_owned(),
             ValgrindTool::Memcheck => "memcheck".to_owned(),
             ValgrindTool::Helgrind => "helgrind".to_owned(),
             ValgrindTool::DRD => "drd".to_owned(),
             ValgrindTool::Massif => "massif".to_owned(),
             ValgrindTool::BBV => "exp-bbv".to_owned(),
+            ValgrindTool::Custom(id) => id.clone(),
         }
     }
 
+    /// Return true if the `id` can be used as name of a custom tool
+    ///
+    /// The id is part of the file names of the output files, so only a restricted set of
+    /// characters is allowed.
+    pub fn is_valid_id(id: &str) -> bool {
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
     pub fn has_output_file(&self) -> bool {
         matches!(
             self,
-            ValgrindTool::Callgrind | ValgrindTool::DHAT | ValgrindTool::BBV | ValgrindTool::Massif
+            ValgrindTool::Callgrind
+                | ValgrindTool::Cachegrind
+                | ValgrindTool::DHAT
+                | ValgrindTool::BBV
+                | ValgrindTool::Massif
+                | ValgrindTool::Memcheck
+                | ValgrindTool::Helgrind
+                | ValgrindTool::DRD
         )
     }
+
+    /// Return true if this tool can be used as the main measuring tool of a benchmark run
+    pub fn is_default_tool(&self) -> bool {
+        matches!(self, ValgrindTool::Callgrind | ValgrindTool::Cachegrind)
+    }
+
+    /// Resolve the default tool from the command-line arguments and the benchmark configuration
+    ///
+    /// The command-line arguments take precedence over the configuration. If neither specifies a
+    /// default tool, the default tool is callgrind.
+    pub fn resolve_default_tool(
+        meta: &Metadata,
+        config: Option<api::ValgrindTool>,
+    ) -> Result<Self> {
+        let tool = meta
+            .args
+            .default_tool
+            .clone()
+            .or_else(|| config.map(Into::into))
+            .unwrap_or(ValgrindTool::Callgrind);
+
+        if tool.is_default_tool() {
+            Ok(tool)
+        } else {
+            Err(anyhow!(
+                "Invalid default tool '{tool}': Only callgrind or cachegrind can be used as \
+                 default tool"
+            ))
+        }
+    }
+
+    /// Return the `config` only if this tool is callgrind
+    ///
+    /// If this default tool is not callgrind, a present `config` is ignored with a warning. The
+    /// `name` of the configuration is used in the warning.
+    pub fn callgrind_only<T>(
+        &self,
+        module_path: &ModulePath,
+        name: &str,
+        config: Option<T>,
+    ) -> Option<T> {
+        match (self, config) {
+            (ValgrindTool::Callgrind, config) => config,
+            (_, Some(_)) => {
+                warn!(
+                    "{module_path}: Ignoring {name}: This configuration is only available if \
+                     callgrind is the default tool"
+                );
+                None
+            }
+            (_, None) => None,
+        }
+    }
 }
 
 impl Display for ValgrindTool {
@@ -861,12 +1176,18 @@ impl Display for ValgrindTool {
 impl From<api::ValgrindTool> for ValgrindTool {
     fn from(value: api::ValgrindTool) -> Self {
         match value {
+            api::ValgrindTool::Callgrind => ValgrindTool::Callgrind,
+            api::ValgrindTool::Cachegrind => ValgrindTool::Cachegrind,
             api::ValgrindTool::Memcheck => ValgrindTool::Memcheck,
             api::ValgrindTool::Helgrind => ValgrindTool::Helgrind,
             api::ValgrindTool::DRD => ValgrindTool::DRD,
             api::ValgrindTool::Massif => ValgrindTool::Massif,
             api::ValgrindTool::DHAT => ValgrindTool::DHAT,
             api::ValgrindTool::BBV => ValgrindTool::BBV,
+            // A custom tool with the id of a known tool is the known tool
+            api::ValgrindTool::Custom(id) => {
+                ValgrindTool::try_from(id.as_str()).unwrap_or(ValgrindTool::Custom(id))
+            }
         }
     }
 }
@@ -878,6 +1199,7 @@ impl TryFrom<&str> for ValgrindTool {
         match value {
             "dhat" => Ok(ValgrindTool::DHAT),
             "callgrind" => Ok(ValgrindTool::Callgrind),
+            "cachegrind" => Ok(ValgrindTool::Cachegrind),
             "memcheck" => Ok(ValgrindTool::Memcheck),
             "helgrind" => Ok(ValgrindTool::Helgrind),
             "drd" => Ok(ValgrindTool::DRD),
@@ -888,8 +1210,19 @@ impl TryFrom<&str> for ValgrindTool {
     }
 }
 
+/// Return true if the `suffix` is empty or consists of a part number (`.N`) and/or a thread index
+/// (`-NN`) as appended by callgrind to the output file name
+fn is_part_and_thread_suffix(suffix: &str) -> bool {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (part, thread) = suffix
+        .split_once('-')
+        .map_or((suffix, None), |(part, thread)| (part, Some(thread)));
+    (part.is_empty() || part.strip_prefix('.').map_or(false, is_number))
+        && thread.map_or(true, is_number)
+}
+
 pub fn check_exit(
-    tool: ValgrindTool,
+    tool: &ValgrindTool,
     executable: &Path,
     output: Option<Output>,
     status: ExitStatus,
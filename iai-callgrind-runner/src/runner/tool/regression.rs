@@ -0,0 +1,179 @@
+use colored::Colorize;
+
+use crate::api;
+use crate::runner::summary::{CostsSummary, ToolRegressionSummary};
+use crate::util::to_string_signed_short;
+
+/// The performance regression limits for the metrics of a tool other than callgrind
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolRegressionConfig {
+    pub limits: Vec<(String, f64)>,
+    pub fail_fast: bool,
+}
+
+/// Normalize the name of a tool metric, so it can be matched against a limit
+///
+/// The metric names of the tools (like `Total bytes` or `At t-gmax bytes` of DHAT) are converted
+/// to lowercase and all non-alphanumeric characters are replaced with an underscore, so `Total
+/// bytes` can be matched with `total_bytes` and `At t-gmax bytes` with `at_t_gmax_bytes`.
+pub fn normalize_metric(metric: &str) -> String {
+    metric
+        .trim()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+impl ToolRegressionConfig {
+    /// Check the [`CostsSummary`] for regressions and print them
+    pub fn check_and_print(
+        &self,
+        costs_summary: &CostsSummary<String>,
+    ) -> Vec<ToolRegressionSummary> {
+        let regression_summaries = self.check(costs_summary);
+
+        for ToolRegressionSummary {
+            metric,
+            new,
+            old,
+            diff_pct,
+            limit,
+        } in &regression_summaries
+        {
+            if limit.is_sign_positive() {
+                eprintln!(
+                    "Performance has {0}: {1} ({new} > {old}) regressed by {2:>+6} (>{3:>+6})",
+                    "regressed".bold().bright_red(),
+                    metric.bold(),
+                    format!("{}%", to_string_signed_short(*diff_pct))
+                        .bold()
+                        .bright_red(),
+                    to_string_signed_short(*limit).bright_black()
+                );
+            } else {
+                eprintln!(
+                    "Performance has {0}: {1} ({new} < {old}) regressed by {2:>+6} (<{3:>+6})",
+                    "regressed".bold().bright_red(),
+                    metric.bold(),
+                    format!("{}%", to_string_signed_short(*diff_pct))
+                        .bold()
+                        .bright_red(),
+                    to_string_signed_short(*limit).bright_black()
+                );
+            }
+        }
+
+        regression_summaries
+    }
+
+    /// Check the [`CostsSummary`] for regressions of the configured metrics
+    ///
+    /// Limits for metrics which are not present in the `costs_summary` or without old costs are
+    /// ignored.
+    pub fn check(&self, costs_summary: &CostsSummary<String>) -> Vec<ToolRegressionSummary> {
+        let mut regressions = vec![];
+        for (metric, limit) in &self.limits {
+            let metric = normalize_metric(metric);
+            for (name, diff) in costs_summary.all_diffs() {
+                if normalize_metric(name) != metric {
+                    continue;
+                }
+
+                // These unwraps are safe since if diff_pct is present new and old are also present
+                if let Some((new, old, pct)) = diff
+                    .diff_pct
+                    .map(|pct| (diff.new.unwrap(), diff.old.unwrap(), pct))
+                {
+                    if (limit.is_sign_positive() && pct > *limit)
+                        || (limit.is_sign_negative() && pct < *limit)
+                    {
+                        regressions.push(ToolRegressionSummary {
+                            metric: name.clone(),
+                            new,
+                            old,
+                            diff_pct: pct,
+                            limit: *limit,
+                        });
+                    }
+                }
+            }
+        }
+        regressions
+    }
+}
+
+impl From<api::ToolRegressionConfig> for ToolRegressionConfig {
+    fn from(value: api::ToolRegressionConfig) -> Self {
+        let api::ToolRegressionConfig { limits, fail_fast } = value;
+        Self {
+            limits,
+            fail_fast: fail_fast.unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::runner::costs::Costs;
+
+    #[rstest]
+    #[case::already_normalized("total_bytes", "total_bytes")]
+    #[case::uppercase("Total bytes", "total_bytes")]
+    #[case::dash("At t-gmax bytes", "at_t_gmax_bytes")]
+    #[case::multiple_whitespace("  Peak   heap bytes ", "peak_heap_bytes")]
+    fn test_normalize_metric(#[case] metric: &str, #[case] expected: &str) {
+        assert_eq!(normalize_metric(metric), expected);
+    }
+
+    #[rstest]
+    #[case::no_regression(vec![("total_bytes", 10f64)], vec![])]
+    #[case::regression(vec![("total_bytes", 5f64)], vec![("Total bytes", 110, 100, 10f64, 5f64)])]
+    #[case::negative_limit(
+        vec![("Total blocks", -5f64)],
+        vec![("Total blocks", 90, 100, -10f64, -5f64)]
+    )]
+    #[case::negative_limit_no_regression(vec![("total_blocks", -20f64)], vec![])]
+    #[case::unknown_metric(vec![("unknown", 0f64)], vec![])]
+    #[case::multiple(
+        vec![("total_bytes", 0f64), ("total_blocks", -5f64)],
+        vec![
+            ("Total bytes", 110, 100, 10f64, 0f64),
+            ("Total blocks", 90, 100, -10f64, -5f64)
+        ]
+    )]
+    fn test_tool_regression_config_check(
+        #[case] limits: Vec<(&str, f64)>,
+        #[case] expected: Vec<(&str, u64, u64, f64, f64)>,
+    ) {
+        let new = Costs::with_event_kinds([
+            ("Total bytes".to_owned(), 110),
+            ("Total blocks".to_owned(), 90),
+        ]);
+        let old = Costs::with_event_kinds([
+            ("Total bytes".to_owned(), 100),
+            ("Total blocks".to_owned(), 100),
+        ]);
+        let config = ToolRegressionConfig {
+            limits: limits.into_iter().map(|(m, l)| (m.to_owned(), l)).collect(),
+            fail_fast: false,
+        };
+        let expected = expected
+            .into_iter()
+            .map(|(metric, new, old, diff_pct, limit)| ToolRegressionSummary {
+                metric: metric.to_owned(),
+                new,
+                old,
+                diff_pct,
+                limit,
+            })
+            .collect::<Vec<ToolRegressionSummary>>();
+
+        assert_eq!(config.check(&CostsSummary::new(&new, Some(&old))), expected);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::logfile_parser::{
+    parse_with_out_files, LogfileParser, LogfileSummary, ToolLogfileParser,
+};
+use super::ToolOutputPath;
+use crate::error::Error;
+use crate::runner::summary::{ErrorReport, ErrorSummary, StackFrame, ToolRunSummary};
+use crate::util::make_relative;
+
+/// The parser for the log files and xml output files of Memcheck, Helgrind and DRD
+///
+/// The log file provides the command, pids, details and the error summary. The structured error
+/// reports are extracted from the accompanying xml file which has the same name as the log file
+/// but with the `out` extension.
+pub struct XmlLogfileParser {
+    pub root_dir: PathBuf,
+}
+
+/// The errors and error counts extracted from the xml output of a tool
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlOutput {
+    pub error_reports: Vec<ErrorReport>,
+    pub error_summary: ErrorSummary,
+}
+
+#[derive(Debug, Default)]
+struct Pair {
+    count: u64,
+    unique: Option<String>,
+    name: Option<String>,
+}
+
+fn parse_count(path: &Path, value: &str) -> Result<u64> {
+    value.parse::<u64>().map_err(|error| -> anyhow::Error {
+        Error::ParseError((path.to_owned(), format!("Invalid count '{value}': {error}"))).into()
+    })
+}
+
+/// Parse the xml output of Memcheck, Helgrind or DRD
+///
+/// Every `<error>` element is converted into an [`ErrorReport`]. The count of an error is taken
+/// from the `<errorcounts>` element. The [`ErrorSummary`] is calculated from the `<errorcounts>`
+/// and `<suppcounts>` elements. Paths to source files are made relative to the `root_dir`.
+pub fn parse_xml_out(path: &Path, root_dir: &Path) -> Result<XmlOutput> {
+    let to_parse_error = |error: quick_xml::Error| -> anyhow::Error {
+        Error::ParseError((path.to_owned(), error.to_string())).into()
+    };
+
+    let mut reader = Reader::from_file(path).map_err(to_parse_error)?;
+    reader.trim_text(true);
+
+    let mut buf = vec![];
+    let mut elements: Vec<String> = vec![];
+    let mut error: Option<(String, ErrorReport)> = None;
+    let mut frame_dir: Option<PathBuf> = None;
+    let mut pair: Option<Pair> = None;
+
+    let mut errors: Vec<(String, ErrorReport)> = vec![];
+    let mut error_counts: HashMap<String, u64> = HashMap::new();
+    let mut supp_counts: HashMap<String, u64> = HashMap::new();
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf).map_err(to_parse_error)? {
+            Event::Eof => break,
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+                match (name.as_str(), error.as_mut()) {
+                    ("error", _) => error = Some((String::new(), ErrorReport::default())),
+                    ("stack", Some((_, report))) => report.stacks.push(vec![]),
+                    ("frame", Some((_, report))) => {
+                        frame_dir = None;
+                        if let Some(stack) = report.stacks.last_mut() {
+                            stack.push(StackFrame::default());
+                        }
+                    }
+                    ("pair", _) => pair = Some(Pair::default()),
+                    _ => {}
+                }
+                elements.push(name);
+            }
+            Event::End(_) => match elements.pop().as_deref() {
+                Some("error") => {
+                    if let Some(error) = error.take() {
+                        errors.push(error);
+                    }
+                }
+                Some("pair") => match (elements.last().map(String::as_str), pair.take()) {
+                    (Some("errorcounts"), Some(Pair { count, unique: Some(unique), .. })) => {
+                        error_counts.insert(unique, count);
+                    }
+                    (Some("suppcounts"), Some(Pair { count, name: Some(name), .. })) => {
+                        supp_counts.insert(name, count);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            },
+            Event::Text(text) => {
+                let value = text.unescape().map_err(to_parse_error)?.to_string();
+                let len = elements.len();
+                let (parent, current) = match len {
+                    0 => continue,
+                    1 => ("", elements[0].as_str()),
+                    _ => (elements[len - 2].as_str(), elements[len - 1].as_str()),
+                };
+
+                if let Some(pair) = pair.as_mut() {
+                    match current {
+                        "count" => pair.count = parse_count(path, &value)?,
+                        "unique" => pair.unique = Some(value),
+                        "name" => pair.name = Some(value),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                let Some((unique, report)) = error.as_mut() else {
+                    continue;
+                };
+                match (parent, current) {
+                    ("error", "unique") => *unique = value,
+                    ("error", "kind") => report.kind = value,
+                    ("error", "what") | ("xwhat", "text") => report.message = value,
+                    ("error", "auxwhat") | ("xauxwhat", "text") => report.aux_messages.push(value),
+                    ("suppression", "sname") => report.suppression = Some(value),
+                    ("frame", field) => {
+                        let Some(frame) = report.stacks.last_mut().and_then(|s| s.last_mut())
+                        else {
+                            continue;
+                        };
+                        match field {
+                            "ip" => frame.ip = value,
+                            "obj" => frame.object = Some(make_relative(root_dir, value)),
+                            "fn" => frame.function = Some(value),
+                            "dir" => frame_dir = Some(PathBuf::from(value)),
+                            "file" => {
+                                let file = frame_dir
+                                    .as_ref()
+                                    .map_or_else(|| PathBuf::from(&value), |dir| dir.join(&value));
+                                frame.file = Some(make_relative(root_dir, file));
+                            }
+                            "line" => frame.line = Some(parse_count(path, &value)?),
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let error_reports = errors
+        .into_iter()
+        .map(|(unique, mut report)| {
+            report.count = error_counts.get(&unique).copied().unwrap_or(1);
+            report
+        })
+        .collect::<Vec<ErrorReport>>();
+
+    let error_summary = ErrorSummary {
+        errors: error_reports.iter().map(|r| r.count).sum(),
+        contexts: error_reports.len() as u64,
+        supp_errors: supp_counts.values().sum(),
+        supp_contexts: supp_counts.len() as u64,
+    };
+
+    Ok(XmlOutput {
+        error_reports,
+        error_summary,
+    })
+}
+
+impl LogfileParser for XmlLogfileParser {
+    /// Parse the log file of the tool
+    ///
+    /// The error reports are not part of the log file and are added in [`XmlLogfileParser::parse`]
+    fn parse_single(&self, path: PathBuf) -> Result<LogfileSummary> {
+        ToolLogfileParser {
+            root_dir: self.root_dir.clone(),
+        }
+        .parse_single(path)
+    }
+
+    fn parse(&self, output_path: &ToolOutputPath) -> Result<Vec<LogfileSummary>> {
+        parse_with_out_files(self, output_path, |summary, xml_file| {
+            let xml_output = parse_xml_out(xml_file, &self.root_dir)?;
+            summary.error_reports = xml_output.error_reports;
+            // The `ERROR SUMMARY` of the log file takes precedence over the summary calculated
+            // from the xml file
+            if summary.error_summary.is_none() {
+                summary.error_summary = Some(xml_output.error_summary);
+            }
+            Ok(())
+        })
+    }
+
+    fn merge_logfile_summaries(
+        &self,
+        _: Vec<LogfileSummary>,
+        new: Vec<LogfileSummary>,
+    ) -> Vec<ToolRunSummary> {
+        new.into_iter()
+            .map(LogfileSummary::new_into_tool_run)
+            .collect()
+    }
+}
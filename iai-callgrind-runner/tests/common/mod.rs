@@ -55,17 +55,56 @@ impl Fixtures {
         tool: ValgrindTool,
         kind: ToolOutputPathKind,
         name: &str,
+    ) -> ToolOutputPath {
+        Fixtures::tool_output_path_in(&Fixtures::get_path().join(dir), tool, kind, name)
+    }
+
+    /// Return the [`ToolOutputPath`] of the callgrind fixture `valid.with_calls` with calls
+    /// between functions
+    pub fn get_with_calls_output() -> ToolOutputPath {
+        Fixtures::get_tool_output_path(
+            "callgrind.out",
+            ValgrindTool::Callgrind,
+            ToolOutputPathKind::Out,
+            "valid.with_calls",
+        )
+    }
+
+    /// Return the [`ToolOutputPath`] in the directory `dir` which doesn't need to be a fixture
+    pub fn tool_output_path_in(
+        dir: &Path,
+        tool: ValgrindTool,
+        kind: ToolOutputPathKind,
+        name: &str,
     ) -> ToolOutputPath {
         ToolOutputPath {
             kind,
             tool,
             baseline_kind: BaselineKind::Old,
-            dir: Fixtures::get_path().join(dir),
+            dir: dir.to_owned(),
             name: name.to_owned(),
             modifiers: vec![],
+            part: None,
+            thread: None,
         }
     }
 
+    /// Copy the fixture at `output_path` into `dir` and return the path of the copy with the
+    /// given `kind`
+    pub fn copy_tool_output_path(
+        output_path: &ToolOutputPath,
+        dir: &Path,
+        kind: ToolOutputPathKind,
+    ) -> ToolOutputPath {
+        let copy = ToolOutputPath {
+            kind,
+            dir: dir.to_owned(),
+            ..output_path.clone()
+        };
+        std::fs::copy(output_path.to_path(), copy.to_path()).unwrap();
+        copy
+    }
+
     pub fn load_serialized<T, N>(name: N) -> Result<T, serde_yaml::Error>
     where
         T: for<'de> Deserialize<'de>,
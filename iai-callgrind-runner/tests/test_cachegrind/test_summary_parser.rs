@@ -0,0 +1,63 @@
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::cachegrind::summary_parser::SummaryParser;
+use iai_callgrind_runner::runner::callgrind::model::Costs;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+
+use crate::common::{assert_parse_error, Fixtures};
+
+#[test]
+fn test_summary_parser() {
+    // Ir I1mr ILmr Dr D1mr DLmr Dw D1mw DLmw
+    let expected_costs = Costs::with_event_kinds([
+        (EventKind::Ir, 1806),
+        (EventKind::I1mr, 55),
+        (EventKind::ILmr, 53),
+        (EventKind::Dr, 531),
+        (EventKind::D1mr, 27),
+        (EventKind::DLmr, 24),
+        (EventKind::Dw, 329),
+        (EventKind::D1mw, 12),
+        (EventKind::DLmw, 11),
+    ]);
+
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        "valid.minimal_main",
+    );
+
+    let actual_costs = SummaryParser.parse(&cachegrind_output).unwrap();
+
+    assert_eq!(actual_costs, expected_costs);
+}
+
+#[test]
+fn test_summary_parser_when_no_cache_sim() {
+    let expected_costs = Costs::with_event_kinds([(EventKind::Ir, 6)]);
+
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        "no_cache_sim",
+    );
+
+    let actual_costs = SummaryParser.parse(&cachegrind_output).unwrap();
+
+    assert_eq!(actual_costs, expected_costs);
+}
+
+#[test]
+fn test_summary_parser_when_not_found_then_error() {
+    let cachegrind_output = Fixtures::get_tool_output_path(
+        "cachegrind.out",
+        ValgrindTool::Cachegrind,
+        ToolOutputPathKind::Out,
+        "no_summary",
+    );
+
+    let result = SummaryParser.parse(&cachegrind_output);
+    assert_parse_error(&cachegrind_output.to_path(), result, "No summary line found");
+}
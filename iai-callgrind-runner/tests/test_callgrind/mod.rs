@@ -1,5 +1,13 @@
+mod test_annotate;
+mod test_call_graph;
+mod test_collect;
+mod test_dumps;
+mod test_flamegraph;
 mod test_flamegraph_parser;
 mod test_hashmap_parser;
+mod test_pprof;
+mod test_processes;
 mod test_sentinel_parser;
 mod test_summary_parser;
+mod test_threads;
 mod test_tools;
@@ -0,0 +1,87 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::annotate::{self, AnnotateConfig, LineCostsMap};
+use iai_callgrind_runner::runner::callgrind::hashmap_parser::SourcePath;
+use iai_callgrind_runner::runner::callgrind::model::Costs;
+use iai_callgrind_runner::runner::tool::{ToolOutputPath, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+
+use crate::common::Fixtures;
+
+fn with_inlined_output() -> ToolOutputPath {
+    Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_inlined",
+    )
+}
+
+fn costs(ir: u64, dr: u64) -> Costs {
+    Costs::with_event_kinds([(EventKind::Ir, ir), (EventKind::Dr, dr)])
+}
+
+#[test]
+fn test_parse_line_costs() {
+    let expected = LineCostsMap(HashMap::from([
+        (
+            SourcePath::Relative(PathBuf::from("src/main.rs")),
+            BTreeMap::from([(1, costs(10, 1)), (2, costs(5, 0)), (3, costs(6, 1))]),
+        ),
+        (
+            SourcePath::Relative(PathBuf::from("src/inlined.rs")),
+            BTreeMap::from([(7, costs(6, 1))]),
+        ),
+        (
+            SourcePath::Relative(PathBuf::from("src/lib.rs")),
+            BTreeMap::from([(5, costs(30, 3))]),
+        ),
+    ]));
+
+    let actual = annotate::parse(&with_inlined_output(), &Fixtures::get_path()).unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_annotate_create() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+    std::fs::write(
+        temp_dir.path().join("src/main.rs"),
+        "fn main() {\n    let a = foo(1 < 2);\n}\n",
+    )
+    .unwrap();
+    let output_path = ToolOutputPath {
+        dir: temp_dir.path().to_owned(),
+        ..with_inlined_output()
+    };
+    let new = annotate::parse(&with_inlined_output(), temp_dir.path()).unwrap();
+    let config = AnnotateConfig {
+        top: 2,
+        event_kinds: vec![EventKind::Ir, EventKind::EstimatedCycles],
+        context: 1,
+    };
+
+    let summary = config
+        .create("bench", &new, Some(&new), &output_path, temp_dir.path())
+        .unwrap();
+
+    assert_eq!(
+        summary.path,
+        temp_dir.path().join("callgrind.with_inlined.annotate.html")
+    );
+    assert_eq!(
+        summary.files,
+        vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]
+    );
+    let html = std::fs::read_to_string(&summary.path).unwrap();
+    assert!(html.contains(
+        "<th>Instructions</th><th>Instructions (old)</th><th>Diff</th><th>Line</th>"
+    ));
+    assert!(!html.contains("Estimated Cycles"));
+    assert!(html.contains("<td class=\"source\">    let a = foo(1 &lt; 2);</td>"));
+    assert!(!html.contains("src/inlined.rs"));
+}
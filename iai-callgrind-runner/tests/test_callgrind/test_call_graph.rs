@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::call_graph::{self, CallGraphConfig};
+use iai_callgrind_runner::runner::callgrind::hashmap_parser::{Id, SourcePath};
+use iai_callgrind_runner::runner::callgrind::model::Costs;
+use iai_callgrind_runner::runner::tool::ToolOutputPath;
+use pretty_assertions::assert_eq;
+
+use crate::common::Fixtures;
+
+const HEADER: &str = "digraph \"call graph\" {
+label=\"bench\";
+labelloc=t;
+node [shape=box, style=filled, fillcolor=\"#ffffff\", fontname=monospace];
+edge [fontname=monospace];
+";
+
+fn id(func: &str) -> Id {
+    Id {
+        obj: None,
+        file: Some(SourcePath::Relative(PathBuf::from("src/main.rs"))),
+        func: func.to_owned(),
+    }
+}
+
+#[test]
+fn test_call_graph_parse_calls() {
+    let map = call_graph::parse(&Fixtures::get_with_calls_output(), &Fixtures::get_path()).unwrap();
+
+    let call = map.calls.get(&(id("main"), id("foo"))).unwrap();
+    assert_eq!(call.count, 2);
+    assert_eq!(call.costs.cost_by_kind(&EventKind::Ir), Some(30));
+    assert_eq!(map.calls.len(), 1);
+}
+
+#[test]
+fn test_call_graph_create_regular() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let output_path = ToolOutputPath {
+        dir: temp_dir.path().to_owned(),
+        ..Fixtures::get_with_calls_output()
+    };
+    let new = call_graph::parse(&Fixtures::get_with_calls_output(), &Fixtures::get_path()).unwrap();
+
+    let summary = CallGraphConfig::default()
+        .create("bench", &new, None, &output_path)
+        .unwrap();
+
+    assert_eq!(
+        summary.path,
+        temp_dir
+            .path()
+            .join("callgrind.valid.with_calls.call_graph.dot")
+    );
+    assert_eq!((summary.nodes, summary.edges), (2, 1));
+    assert_eq!(
+        std::fs::read_to_string(&summary.path).unwrap(),
+        format!(
+            "{HEADER}n0 [label=\"main\\nsrc/main.rs\\nInstructions: 45 (100.00%)\\nself: \
+             15\\ncalls: 0\"];\nn1 [label=\"foo\\nsrc/main.rs\\nInstructions: 30 \
+             (66.67%)\\nself: 30\\ncalls: 2\"];\nn0 -> n1 [label=\"30\\n2x\", \
+             penwidth=3.67];\n}}\n"
+        )
+    );
+}
+
+#[test]
+fn test_call_graph_create_differential() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let output_path = ToolOutputPath {
+        dir: temp_dir.path().to_owned(),
+        ..Fixtures::get_with_calls_output()
+    };
+    let new = call_graph::parse(&Fixtures::get_with_calls_output(), &Fixtures::get_path()).unwrap();
+    let mut old =
+        call_graph::parse(&Fixtures::get_with_calls_output(), &Fixtures::get_path()).unwrap();
+    old.map.get_mut(&id("main")).unwrap().costs =
+        Costs::with_event_kinds([(EventKind::Ir, 40)]);
+    old.map.remove(&id("foo"));
+    old.calls.get_mut(&(id("main"), id("foo"))).unwrap().count = 1;
+
+    let summary = CallGraphConfig::default()
+        .create("bench", &new, Some(&old), &output_path)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&summary.path).unwrap(),
+        format!(
+            "{HEADER}n0 [label=\"main\\nsrc/main.rs\\nInstructions: 45 (100.00%)\\nself: \
+             15\\ncalls: 0\\nold: 40 (+12.5000%)\", fillcolor=\"#ffe6e6\"];\nn1 \
+             [label=\"foo\\nsrc/main.rs\\nInstructions: 30 (66.67%)\\nself: 30\\ncalls: \
+             2\\nold: new\", fillcolor=\"#ff3737\"];\nn0 -> n1 [label=\"30\\n2x\\nold: 30 \
+             1x\", penwidth=3.67];\n}}\n"
+        )
+    );
+}
+
+#[test]
+fn test_call_graph_create_when_below_threshold_then_pruned() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let output_path = ToolOutputPath {
+        dir: temp_dir.path().to_owned(),
+        ..Fixtures::get_with_calls_output()
+    };
+    let new = call_graph::parse(&Fixtures::get_with_calls_output(), &Fixtures::get_path()).unwrap();
+    let config = CallGraphConfig {
+        threshold: 70.0,
+        ..Default::default()
+    };
+
+    let summary = config.create("bench", &new, None, &output_path).unwrap();
+
+    assert_eq!((summary.nodes, summary.edges), (1, 0));
+    assert_eq!(
+        std::fs::read_to_string(&summary.path).unwrap(),
+        format!(
+            "{HEADER}n0 [label=\"main\\nsrc/main.rs\\nInstructions: 45 (100.00%)\\nself: \
+             15\\ncalls: 0\"];\n}}\n"
+        )
+    );
+}
@@ -0,0 +1,71 @@
+use iai_callgrind_runner::api::{CollectConfig, EventKind};
+use iai_callgrind_runner::error::Error;
+use iai_callgrind_runner::runner::callgrind::collect::{CollectFilter, CollectParser};
+use iai_callgrind_runner::runner::callgrind::model::Costs;
+use iai_callgrind_runner::runner::callgrind::parser::Sentinel;
+use iai_callgrind_runner::runner::common::ModulePath;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::Fixtures;
+
+fn collect_filter(collect_only: &[&str], exclude: &[&str]) -> anyhow::Result<CollectFilter> {
+    CollectFilter::new(
+        &CollectConfig {
+            collect_only: collect_only.iter().map(|s| (*s).to_owned()).collect(),
+            exclude: exclude.iter().map(|s| (*s).to_owned()).collect(),
+        },
+        &ModulePath::new("file::group::bench"),
+    )
+}
+
+// `main` calls the recursive `decode`, `parse` and `log::info`. `decode` calls `parse` and
+// `log::debug` and `parse` calls `log::debug`, too.
+#[rstest]
+#[case::all(&[], &[], None, 52)]
+#[case::sentinel(&[], &[], Some("^main$"), 52)]
+#[case::recursive(&["decode"], &[], None, 30)]
+#[case::nested(&["decode", "pars*"], &[], None, 37)]
+#[case::nested_with_sentinel(&["parse"], &[], Some("^main$"), 20)]
+#[case::exclude(&[], &["log::*"], None, 40)]
+#[case::exclude_with_sentinel(&[], &["log::info"], Some("^main$"), 47)]
+#[case::exclude_outside_of_collected(&["decode", "parse"], &["log::*"], None, 30)]
+#[case::exclude_collected(&["decode", "parse"], &["parse"], None, 17)]
+#[case::no_match(&["encode"], &[], None, 0)]
+fn test_collect_parser(
+    #[case] collect_only: &[&str],
+    #[case] exclude: &[&str],
+    #[case] sentinel: Option<&str>,
+    #[case] expected: u64,
+) {
+    let output_path = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_nested_collect",
+    );
+    let filter = collect_filter(collect_only, exclude).unwrap();
+    let sentinel = sentinel.map(|s| Sentinel::new(s).unwrap());
+
+    let actual = CollectParser::new(&filter, sentinel.as_ref())
+        .parse(&output_path)
+        .unwrap();
+
+    assert_eq!(actual, Costs::with_event_kinds([(EventKind::Ir, expected)]));
+}
+
+#[rstest]
+#[case::collect_only(&["decode["], &[])]
+#[case::exclude(&[], &["log::***"])]
+fn test_collect_filter_when_invalid_pattern_then_error(
+    #[case] collect_only: &[&str],
+    #[case] exclude: &[&str],
+) {
+    let error = collect_filter(collect_only, exclude).unwrap_err();
+
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::InvalidCollectConfig(..))
+    ));
+}
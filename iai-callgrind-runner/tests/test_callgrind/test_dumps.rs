@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::dumps::parse_description;
+use iai_callgrind_runner::runner::callgrind::hashmap_parser::{HashMapParser, Id, SourcePath};
+use iai_callgrind_runner::runner::callgrind::summary_parser::SummaryParser;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::Fixtures;
+
+fn with_dumps_output() -> ToolOutputPath {
+    Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_dumps",
+    )
+}
+
+#[rstest]
+#[case::out(ToolOutputPathKind::Out, "callgrind.bench.out.2")]
+#[case::old_out(ToolOutputPathKind::OldOut, "callgrind.bench.out.2.old")]
+#[case::base(ToolOutputPathKind::Base("foo".to_owned()), "callgrind.bench.out.base@foo.2")]
+#[case::log(ToolOutputPathKind::Log, "callgrind.bench.log")]
+fn test_tool_output_path_with_part(#[case] kind: ToolOutputPathKind, #[case] expected: &str) {
+    let output_path =
+        Fixtures::tool_output_path_in(Path::new("/tmp"), ValgrindTool::Callgrind, kind, "bench");
+
+    assert_eq!(
+        output_path.with_part(2).to_path(),
+        PathBuf::from("/tmp").join(expected)
+    );
+}
+
+#[rstest]
+#[case::out(ToolOutputPathKind::Out, &["callgrind.bench.out", "callgrind.bench.out.1"])]
+#[case::old_out(ToolOutputPathKind::OldOut, &["callgrind.bench.out.1.old"])]
+#[case::base(
+    ToolOutputPathKind::Base("foo".to_owned()),
+    &["callgrind.bench.out.base@foo", "callgrind.bench.out.base@foo.1"]
+)]
+fn test_tool_output_path_real_paths_with_parts(
+    #[case] kind: ToolOutputPathKind,
+    #[case] expected: &[&str],
+) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for file_name in [
+        "callgrind.bench.out",
+        "callgrind.bench.out.1",
+        "callgrind.bench.out.1.old",
+        "callgrind.bench.out.base@foo",
+        "callgrind.bench.out.base@foo.1",
+        "callgrind.bench.out.base@foobar.1",
+    ] {
+        std::fs::write(temp_dir.path().join(file_name), "").unwrap();
+    }
+    let output_path =
+        Fixtures::tool_output_path_in(temp_dir.path(), ValgrindTool::Callgrind, kind, "bench");
+
+    let mut actual = output_path.real_paths().unwrap();
+    actual.sort();
+
+    assert_eq!(
+        actual,
+        expected
+            .iter()
+            .map(|file_name| temp_dir.path().join(file_name))
+            .collect::<Vec<PathBuf>>()
+    );
+}
+
+#[test]
+fn test_to_parts() {
+    let output_path = with_dumps_output();
+
+    let actual = output_path
+        .to_parts()
+        .iter()
+        .map(ToolOutputPath::to_path)
+        .collect::<Vec<PathBuf>>();
+
+    assert_eq!(
+        actual,
+        vec![output_path.with_part(1).to_path(), output_path.to_path()]
+    );
+}
+
+#[test]
+fn test_to_parts_when_single_dump() {
+    let output_path = Fixtures::get_with_calls_output();
+
+    assert_eq!(output_path.to_parts(), vec![output_path]);
+}
+
+#[rstest]
+#[case::first(Some(1), 1, "Client Request: phase1", 40)]
+#[case::last(None, 2, "Program termination", 25)]
+fn test_parse_description_and_costs_of_parts(
+    #[case] part: Option<u64>,
+    #[case] expected_part: u64,
+    #[case] expected_trigger: &str,
+    #[case] expected_ir: u64,
+) {
+    let output_path = with_dumps_output();
+    let output_path = part.map_or_else(|| output_path.clone(), |p| output_path.with_part(p));
+
+    let (actual_part, actual_trigger) = parse_description(&output_path).unwrap();
+    let costs = SummaryParser.parse(&output_path).unwrap();
+
+    assert_eq!(actual_part, Some(expected_part));
+    assert_eq!(actual_trigger.as_deref(), Some(expected_trigger));
+    assert_eq!(costs.cost_by_kind(&EventKind::Ir), Some(expected_ir));
+}
+
+#[test]
+fn test_callgrind_map_add_parts() {
+    let parser = HashMapParser::default();
+    let output_path = with_dumps_output();
+
+    let mut actual = parser.parse(&output_path.with_part(1)).unwrap();
+    actual.add(parser.parse(&output_path).unwrap());
+
+    let id = |func: &str| Id {
+        obj: None,
+        file: Some(SourcePath::Relative(PathBuf::from("src/main.rs"))),
+        func: func.to_owned(),
+    };
+    let (_, main) = actual.get_key_value(&id("main")).unwrap();
+    assert_eq!(main.costs.cost_by_kind(&EventKind::Ir), Some(65));
+    assert_eq!(main.exclusive_costs.cost_by_kind(&EventKind::Ir), Some(15));
+    for (func, cost) in [("parse", 30), ("serialize", 20)] {
+        let (_, value) = actual.get_key_value(&id(func)).unwrap();
+        assert_eq!(value.exclusive_costs.cost_by_kind(&EventKind::Ir), Some(cost));
+    }
+}
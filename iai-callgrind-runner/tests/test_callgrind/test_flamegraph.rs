@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+
+use iai_callgrind_runner::api::{self, EventKind, FlamegraphKind};
+use iai_callgrind_runner::runner::callgrind::flamegraph::{
+    BaselineFlamegraphGenerator, Flamegraph, FlamegraphGenerator, SaveBaselineFlamegraphGenerator,
+};
+use iai_callgrind_runner::runner::summary::{BaselineKind, BaselineName, FlamegraphSummary};
+use iai_callgrind_runner::runner::tool::{ToolOutputPath, ToolOutputPathKind};
+use pretty_assertions::assert_eq;
+
+use crate::common::Fixtures;
+
+fn speedscope_flamegraph(kind: FlamegraphKind) -> Flamegraph {
+    Flamegraph::new(
+        "bench".to_owned(),
+        api::FlamegraphConfig {
+            kind: Some(kind),
+            event_kinds: Some(vec![EventKind::Ir, EventKind::EstimatedCycles]),
+            speedscope: Some(true),
+            ..Default::default()
+        }
+        .into(),
+    )
+}
+
+/// Return the names of the profiles of the speedscope file at `path`
+fn profile_names(path: &Path) -> Vec<String> {
+    let file: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+    file["profiles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|profile| profile["name"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+fn speedscope_paths(summaries: &[FlamegraphSummary]) -> Vec<Option<PathBuf>> {
+    summaries
+        .iter()
+        .map(|summary| summary.speedscope_path.clone())
+        .collect()
+}
+
+#[test]
+fn test_baseline_flamegraph_generator_with_speedscope() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_with_calls_output();
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+    Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::OldOut);
+
+    let summaries = BaselineFlamegraphGenerator {
+        baseline_kind: BaselineKind::Old,
+    }
+    .create(
+        &speedscope_flamegraph(FlamegraphKind::All),
+        &output_path,
+        None,
+        &Fixtures::get_path(),
+    )
+    .unwrap();
+
+    let expected = temp_dir
+        .path()
+        .join("callgrind.valid.with_calls.speedscope.json");
+    assert_eq!(
+        speedscope_paths(&summaries),
+        vec![Some(expected.clone()), Some(expected.clone())]
+    );
+    assert_eq!(profile_names(&expected), vec!["Instructions", "Estimated Cycles"]);
+}
+
+#[test]
+fn test_save_baseline_flamegraph_generator_with_speedscope() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let baseline = "foo".parse::<BaselineName>().unwrap();
+    let output_path = ToolOutputPath {
+        baseline_kind: BaselineKind::Name(baseline.clone()),
+        ..Fixtures::copy_tool_output_path(
+            &Fixtures::get_with_calls_output(),
+            temp_dir.path(),
+            ToolOutputPathKind::Base(baseline.to_string()),
+        )
+    };
+
+    let summaries = SaveBaselineFlamegraphGenerator { baseline }
+        .create(
+            &speedscope_flamegraph(FlamegraphKind::Regular),
+            &output_path,
+            None,
+            &Fixtures::get_path(),
+        )
+        .unwrap();
+
+    let expected = temp_dir
+        .path()
+        .join("callgrind.valid.with_calls.speedscope.base@foo.json");
+    assert_eq!(
+        speedscope_paths(&summaries),
+        vec![Some(expected.clone()), Some(expected.clone())]
+    );
+    assert_eq!(profile_names(&expected), vec!["Instructions", "Estimated Cycles"]);
+    assert!(!temp_dir
+        .path()
+        .join("callgrind.valid.with_calls.speedscope.json")
+        .exists());
+}
@@ -1,8 +1,10 @@
 use anyhow::Result;
-use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::api::{EventKind, FlamegraphGranularity};
 use iai_callgrind_runner::runner::callgrind::flamegraph_parser::FlamegraphParser;
 use iai_callgrind_runner::runner::callgrind::parser::Sentinel;
+use iai_callgrind_runner::runner::callgrind::stack_filter::StackFilter;
 use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
 use rstest::rstest;
 
 use crate::common::{get_project_root, Fixtures};
@@ -23,7 +25,7 @@ fn test_flamegraph_parser(#[case] name: &str, #[case] sentinel: Option<Result<Se
     let parser = FlamegraphParser::new(sentinel.as_ref(), get_project_root());
 
     let result = parser.parse(&output).unwrap();
-    let stacks = result.to_stack_format(&EventKind::Ir).unwrap();
+    let stacks = result.to_stack_format(&EventKind::Ir, &StackFilter::default()).unwrap();
 
     assert_eq!(stacks.len(), expected_stacks.len());
     // Assert line by line or else the output on error is unreadable. Also, provide an additional
@@ -49,3 +51,87 @@ fn test_flamegraph_parser(#[case] name: &str, #[case] sentinel: Option<Result<Se
 
     assert!(!failed);
 }
+
+#[rstest]
+#[case::inline(
+    FlamegraphGranularity::Inline,
+    &[
+        "src/main.rs:main 21",
+        "src/main.rs:main;[inlined] src/inlined.rs 4",
+        "src/main.rs:main;src/lib.rs:foo 0",
+        "src/main.rs:main;src/lib.rs:foo;src/main.rs:foo 28",
+        "src/main.rs:main;src/lib.rs:foo;src/main.rs:foo;/usr/include/header.h:bar 0",
+        "src/main.rs:main;src/lib.rs:foo;src/main.rs:foo;/usr/include/header.h:bar;[inlined] \
+         src/inlined.rs 2",
+    ]
+)]
+#[case::line(
+    FlamegraphGranularity::Line,
+    &[
+        "src/main.rs:main 0",
+        "src/main.rs:main;src/main.rs:1 10",
+        "src/main.rs:main;src/main.rs:3 6",
+        "src/main.rs:main;src/main.rs:2 5",
+        "src/main.rs:main;[inlined] src/inlined.rs:7 4",
+        "src/main.rs:main;src/lib.rs:foo 0",
+        "src/main.rs:main;src/lib.rs:foo;src/main.rs:foo 28",
+        "src/main.rs:main;src/lib.rs:foo;src/main.rs:foo;/usr/include/header.h:bar 0",
+        "src/main.rs:main;src/lib.rs:foo;src/main.rs:foo;/usr/include/header.h:bar;[inlined] \
+         src/inlined.rs:7 2",
+    ]
+)]
+fn test_flamegraph_parser_with_granularity(
+    #[case] granularity: FlamegraphGranularity,
+    #[case] expected: &[&str],
+) {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_inlined",
+    );
+    let parser = FlamegraphParser::new(None, get_project_root()).with_granularity(granularity);
+
+    let map = parser.parse(&output).unwrap();
+
+    assert_eq!(
+        map.to_stack_format(&EventKind::Ir, &StackFilter::default()).unwrap(),
+        expected
+            .iter()
+            .map(|stack| (*stack).to_owned())
+            .collect::<Vec<String>>()
+    );
+}
+
+#[test]
+fn test_flamegraph_parser_with_granularity_when_called_from_two_sites() {
+    let output = Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_two_call_sites",
+    );
+    let parser = FlamegraphParser::new(None, get_project_root())
+        .with_granularity(FlamegraphGranularity::Line);
+    // The stack of `foo` costs less than its lines, so the cost of the stack is split between the
+    // lines in proportion to their costs (30 : 14)
+    let expected = [
+        "src/main.rs:main 6",
+        "src/main.rs:main;src/main.rs:1 4",
+        "src/main.rs:main;src/main.rs:foo 0",
+        "src/main.rs:main;src/main.rs:foo;src/main.rs:10 10",
+        "src/main.rs:main;src/main.rs:foo;src/main.rs:11 4",
+        "src/main.rs:main;src/main.rs:foo;src/main.rs:bar 24",
+        "src/main.rs:main;src/main.rs:foo;src/main.rs:bar;src/main.rs:20 6",
+    ];
+
+    let map = parser.parse(&output).unwrap();
+
+    assert_eq!(
+        map.to_stack_format(&EventKind::Ir, &StackFilter::default()).unwrap(),
+        expected
+            .iter()
+            .map(|stack| (*stack).to_owned())
+            .collect::<Vec<String>>()
+    );
+}
@@ -1,4 +1,9 @@
-use iai_callgrind_runner::runner::callgrind::hashmap_parser::{CallgrindMap, HashMapParser};
+use std::path::PathBuf;
+
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::hashmap_parser::{
+    CallgrindMap, HashMapParser, Id, SourcePath,
+};
 use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPathKind, ValgrindTool};
 use pretty_assertions::assert_eq;
 use rstest::rstest;
@@ -67,3 +72,23 @@ fn test_when_no_records(#[case] name: &str) {
 
     assert_eq!(actual_map, expected_map);
 }
+
+#[test]
+fn test_valid_with_calls_then_exclusive_and_inclusive_costs() {
+    let parser = HashMapParser::default();
+    let output = Fixtures::get_with_calls_output();
+
+    let actual_map = parser.parse(&output).unwrap();
+
+    let id = |func: &str| Id {
+        obj: None,
+        file: Some(SourcePath::Relative(PathBuf::from("src/main.rs"))),
+        func: func.to_owned(),
+    };
+    let (_, main) = actual_map.get_key_value(&id("main")).unwrap();
+    assert_eq!(main.costs.cost_by_kind(&EventKind::Ir), Some(45));
+    assert_eq!(main.exclusive_costs.cost_by_kind(&EventKind::Ir), Some(15));
+    let (_, foo) = actual_map.get_key_value(&id("foo")).unwrap();
+    assert_eq!(foo.costs.cost_by_kind(&EventKind::Ir), Some(30));
+    assert_eq!(foo.exclusive_costs.cost_by_kind(&EventKind::Ir), Some(30));
+}
@@ -0,0 +1,285 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::pprof::{self, PprofConfig};
+use iai_callgrind_runner::runner::tool::ToolOutputPathKind;
+use pretty_assertions::assert_eq;
+use prost::Message;
+
+use crate::common::Fixtures;
+
+/// The messages of the pprof `profile.proto` in the form generated by `prost-build`
+///
+/// Only the fields written by the pprof export are included. Unknown fields are skipped by prost.
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Profile {
+        #[prost(message, repeated, tag = "1")]
+        pub sample_type: Vec<ValueType>,
+        #[prost(message, repeated, tag = "2")]
+        pub sample: Vec<Sample>,
+        #[prost(message, repeated, tag = "4")]
+        pub location: Vec<Location>,
+        #[prost(message, repeated, tag = "5")]
+        pub function: Vec<Function>,
+        #[prost(string, repeated, tag = "6")]
+        pub string_table: Vec<String>,
+        #[prost(int64, tag = "14")]
+        pub default_sample_type: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ValueType {
+        #[prost(int64, tag = "1")]
+        pub r#type: i64,
+        #[prost(int64, tag = "2")]
+        pub unit: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sample {
+        #[prost(uint64, repeated, tag = "1")]
+        pub location_id: Vec<u64>,
+        #[prost(int64, repeated, tag = "2")]
+        pub value: Vec<i64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Location {
+        #[prost(uint64, tag = "1")]
+        pub id: u64,
+        #[prost(message, repeated, tag = "4")]
+        pub line: Vec<Line>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Line {
+        #[prost(uint64, tag = "1")]
+        pub function_id: u64,
+        #[prost(int64, tag = "2")]
+        pub line: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Function {
+        #[prost(uint64, tag = "1")]
+        pub id: u64,
+        #[prost(int64, tag = "2")]
+        pub name: i64,
+        #[prost(int64, tag = "3")]
+        pub system_name: i64,
+        #[prost(int64, tag = "4")]
+        pub filename: i64,
+    }
+}
+
+/// The decoded pprof profile with the fields written by the pprof export
+#[derive(Debug, PartialEq, Eq)]
+struct Profile {
+    /// The type and unit of each sample value
+    sample_types: Vec<(String, String)>,
+    /// The location ids and values of each sample
+    samples: Vec<(Vec<u64>, Vec<u64>)>,
+    /// The id of each location and the function id of its line
+    locations: Vec<(u64, u64)>,
+    /// The id, the name and the file name of each function
+    functions: Vec<(u64, String, String)>,
+    default_sample_type: String,
+}
+
+fn decode(path: &Path) -> Profile {
+    let mut bytes = vec![];
+    GzDecoder::new(File::open(path).unwrap())
+        .read_to_end(&mut bytes)
+        .unwrap();
+
+    let profile = proto::Profile::decode(bytes.as_slice()).unwrap();
+    let string = |index: i64| profile.string_table[usize::try_from(index).unwrap()].clone();
+
+    Profile {
+        sample_types: profile
+            .sample_type
+            .iter()
+            .map(|value_type| (string(value_type.r#type), string(value_type.unit)))
+            .collect(),
+        samples: profile
+            .sample
+            .iter()
+            .map(|sample| {
+                (
+                    sample.location_id.clone(),
+                    sample
+                        .value
+                        .iter()
+                        .map(|value| u64::try_from(*value).unwrap())
+                        .collect(),
+                )
+            })
+            .collect(),
+        locations: profile
+            .location
+            .iter()
+            .map(|location| {
+                assert_eq!(location.line.len(), 1);
+                (location.id, location.line[0].function_id)
+            })
+            .collect(),
+        functions: profile
+            .function
+            .iter()
+            .map(|function| {
+                assert_eq!(function.name, function.system_name);
+                (function.id, string(function.name), string(function.filename))
+            })
+            .collect(),
+        default_sample_type: string(profile.default_sample_type),
+    }
+}
+
+impl Profile {
+    /// Return the function names of the stack of each sample from the outermost function to the
+    /// innermost function with the values of the sample
+    fn stacks(&self) -> Vec<(Vec<&str>, Vec<u64>)> {
+        let mut stacks = self
+            .samples
+            .iter()
+            .map(|(locations, values)| {
+                let names = locations
+                    .iter()
+                    .rev()
+                    .map(|id| {
+                        let (_, function_id) =
+                            self.locations.iter().find(|(l, _)| l == id).unwrap();
+                        let (_, name, _) =
+                            self.functions.iter().find(|(f, ..)| f == function_id).unwrap();
+                        name.as_str()
+                    })
+                    .collect();
+                (names, values.clone())
+            })
+            .collect::<Vec<_>>();
+        stacks.sort();
+        stacks
+    }
+
+    /// Return the sum of the values of all samples for each sample type
+    fn totals(&self) -> Vec<u64> {
+        self.samples
+            .iter()
+            .fold(vec![0; self.sample_types.len()], |mut totals, (_, values)| {
+                for (total, value) in totals.iter_mut().zip(values) {
+                    *total += value;
+                }
+                totals
+            })
+    }
+}
+
+#[test]
+fn test_pprof_create_without_old() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_with_calls_output();
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+
+    let summary = PprofConfig::default()
+        .create(
+            &output_path,
+            Some(&output_path.to_base_path()),
+            None,
+            &Fixtures::get_path(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        summary.path,
+        temp_dir.path().join("callgrind.valid.with_calls.profile.pb.gz")
+    );
+    assert_eq!(summary.base_path, None);
+    assert_eq!(summary.event_kinds, vec![EventKind::Ir]);
+
+    let profile = decode(&summary.path);
+    assert_eq!(
+        profile.sample_types,
+        vec![("Ir".to_owned(), "count".to_owned())]
+    );
+    assert_eq!(profile.default_sample_type, "Ir");
+    assert_eq!(
+        profile.functions,
+        vec![
+            (1, "main".to_owned(), "src/main.rs".to_owned()),
+            (2, "foo".to_owned(), "src/main.rs".to_owned())
+        ]
+    );
+    assert_eq!(profile.locations, vec![(1, 1), (2, 2)]);
+    assert_eq!(
+        profile.stacks(),
+        vec![(vec!["main"], vec![15]), (vec!["main", "foo"], vec![30])]
+    );
+    // The total Ir of the callgrind output file
+    assert_eq!(profile.totals(), vec![45]);
+}
+
+#[test]
+fn test_pprof_create_with_old() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_with_calls_output();
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+    let old_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::OldOut);
+
+    let summary = PprofConfig::default()
+        .create(&output_path, Some(&old_path), None, &Fixtures::get_path())
+        .unwrap();
+
+    assert_eq!(summary.path, pprof::profile_path(&output_path));
+    assert_eq!(
+        summary.base_path,
+        Some(temp_dir.path().join("callgrind.valid.with_calls.profile.old.pb.gz"))
+    );
+    assert_eq!(decode(&summary.path), decode(&summary.base_path.unwrap()));
+}
+
+#[test]
+fn test_pprof_export_multiple_event_kinds() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_with_calls_output();
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+
+    let config = PprofConfig {
+        event_kinds: vec![EventKind::EstimatedCycles, EventKind::Ir],
+        ..Default::default()
+    };
+    let path = config
+        .export(&output_path, None, &Fixtures::get_path())
+        .unwrap();
+
+    assert_eq!(
+        path,
+        temp_dir.path().join("callgrind.valid.with_calls.profile.pb.gz")
+    );
+    let profile = decode(&path);
+    assert_eq!(
+        profile.sample_types,
+        vec![
+            ("EstimatedCycles".to_owned(), "count".to_owned()),
+            ("Ir".to_owned(), "count".to_owned())
+        ]
+    );
+    assert_eq!(profile.default_sample_type, "EstimatedCycles");
+    assert_eq!(profile.locations, vec![(1, 1), (2, 2)]);
+    assert_eq!(
+        profile.stacks(),
+        vec![
+            (vec!["main"], vec![19, 15]),
+            (vec!["main", "foo"], vec![36, 30])
+        ]
+    );
+    // The total EstimatedCycles and Ir of the callgrind output file
+    assert_eq!(profile.totals(), vec![55, 45]);
+}
@@ -0,0 +1,151 @@
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::dumps;
+use iai_callgrind_runner::runner::callgrind::processes::{self, ProcessDumps};
+use iai_callgrind_runner::runner::callgrind::summary_parser::SummaryParser;
+use iai_callgrind_runner::runner::summary::CallgrindProcess;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+
+use crate::common::Fixtures;
+
+fn with_children_output() -> ToolOutputPath {
+    Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_children",
+    )
+}
+
+fn parse_processes(output_path: &ToolOutputPath) -> Vec<ProcessDumps> {
+    processes::find(output_path, &Fixtures::get_path())
+        .unwrap()
+        .into_iter()
+        .map(|process| ProcessDumps {
+            dumps: dumps::parse(
+                &process.path,
+                |path| SummaryParser.parse(path),
+                |_| Ok(None),
+            )
+            .unwrap(),
+            process: Some(process),
+        })
+        .collect()
+}
+
+#[test]
+fn test_find_processes() {
+    let output_path = with_children_output();
+
+    let actual = processes::find(&output_path, &Fixtures::get_path())
+        .unwrap()
+        .into_iter()
+        .map(|p| (p.path, p.pid, p.parent_pid, p.command, p.is_child))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        actual,
+        vec![
+            (
+                output_path.with_modifiers(["100"]),
+                100,
+                Some(99),
+                "/usr/bin/my-bin --spawn".to_owned(),
+                false
+            ),
+            (
+                output_path.with_modifiers(["101"]),
+                101,
+                Some(100),
+                "/usr/bin/my-helper --work".to_owned(),
+                true
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_costs_of_processes() {
+    let actual = parse_processes(&with_children_output())
+        .iter()
+        .map(|p| p.dumps.iter().map(|d| d.costs.cost_by_kind(&EventKind::Ir)).collect())
+        .collect::<Vec<Vec<Option<u64>>>>();
+
+    assert_eq!(actual, vec![vec![Some(50)], vec![Some(30)]]);
+}
+
+#[test]
+fn test_find_root_process() {
+    let processes = parse_processes(&with_children_output());
+
+    let root = processes::find_root(&processes)
+        .and_then(|p| p.process.as_ref())
+        .unwrap();
+
+    assert_eq!(root.pid, 100);
+}
+
+#[test]
+fn test_find_old_process_and_summary() {
+    let new = parse_processes(&with_children_output());
+    let mut old = parse_processes(&with_children_output());
+    old.truncate(1);
+
+    assert!(ProcessDumps::find_old(&new, 1, &old).is_none());
+    let old_root = ProcessDumps::find_old(&new, 0, &old);
+    assert_eq!(
+        new[0].to_summary(old_root, true),
+        Some(CallgrindProcess {
+            command: "/usr/bin/my-bin --spawn".to_owned(),
+            old_pid: Some(100),
+            old_parent_pid: Some(99),
+            pid: Some(100),
+            parent_pid: Some(99),
+            is_checked: true,
+        })
+    );
+}
+
+#[test]
+fn test_find_old_process_when_order_differs() {
+    let new = parse_processes(&with_children_output());
+    let old = parse_processes(&with_children_output())
+        .into_iter()
+        .rev()
+        .collect::<Vec<_>>();
+
+    let actual = (0..new.len())
+        .map(|index| {
+            ProcessDumps::find_old(&new, index, &old)
+                .and_then(|p| p.process.as_ref())
+                .map(|p| p.pid)
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(actual, vec![Some(100), Some(101)]);
+}
+
+#[test]
+fn test_find_old_process_when_command_differs() {
+    let new = parse_processes(&with_children_output());
+    let mut old = parse_processes(&with_children_output());
+    old[1].process.as_mut().unwrap().command = "/usr/bin/my-helper --other".to_owned();
+
+    assert!(ProcessDumps::find_old(&new, 0, &old).is_some());
+    assert!(ProcessDumps::find_old(&new, 1, &old).is_none());
+}
+
+#[test]
+fn test_find_old_process_by_occurrence_of_command() {
+    let mut new = parse_processes(&with_children_output());
+    new[0].process.as_mut().unwrap().command = "/usr/bin/my-helper --work".to_owned();
+    let mut old = parse_processes(&with_children_output());
+    old[0].process.as_mut().unwrap().command = "/usr/bin/my-bin --other".to_owned();
+
+    let old_process = ProcessDumps::find_old(&new, 0, &old)
+        .and_then(|p| p.process.as_ref())
+        .unwrap();
+
+    assert_eq!(old_process.pid, 101);
+    assert!(ProcessDumps::find_old(&new, 1, &old).is_none());
+}
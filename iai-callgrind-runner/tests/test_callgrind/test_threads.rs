@@ -0,0 +1,170 @@
+use std::path::{Path, PathBuf};
+
+use iai_callgrind_runner::api::EventKind;
+use iai_callgrind_runner::runner::callgrind::dumps;
+use iai_callgrind_runner::runner::callgrind::flamegraph_parser::FlamegraphParser;
+use iai_callgrind_runner::runner::callgrind::stack_filter::StackFilter;
+use iai_callgrind_runner::runner::callgrind::summary_parser::SummaryParser;
+use iai_callgrind_runner::runner::tool::{Parser, ToolOutputPath, ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::{get_project_root, Fixtures};
+
+fn with_threads_output() -> ToolOutputPath {
+    Fixtures::get_tool_output_path(
+        "callgrind.out",
+        ValgrindTool::Callgrind,
+        ToolOutputPathKind::Out,
+        "with_threads",
+    )
+}
+
+#[rstest]
+#[case::out(ToolOutputPathKind::Out, None, "callgrind.bench.out-02")]
+#[case::out_with_part(ToolOutputPathKind::Out, Some(1), "callgrind.bench.out.1-02")]
+#[case::old_out(ToolOutputPathKind::OldOut, None, "callgrind.bench.out-02.old")]
+#[case::old_out_with_part(ToolOutputPathKind::OldOut, Some(1), "callgrind.bench.out.1-02.old")]
+#[case::base(
+    ToolOutputPathKind::Base("foo".to_owned()),
+    None,
+    "callgrind.bench.out.base@foo-02"
+)]
+#[case::log(ToolOutputPathKind::Log, None, "callgrind.bench.log")]
+fn test_tool_output_path_with_thread(
+    #[case] kind: ToolOutputPathKind,
+    #[case] part: Option<u64>,
+    #[case] expected: &str,
+) {
+    let output_path = ToolOutputPath {
+        part,
+        ..Fixtures::tool_output_path_in(Path::new("/tmp"), ValgrindTool::Callgrind, kind, "bench")
+    };
+
+    assert_eq!(
+        output_path.with_thread(2).to_path(),
+        PathBuf::from("/tmp").join(expected)
+    );
+}
+
+#[rstest]
+#[case::out(ToolOutputPathKind::Out, &["callgrind.bench.out-01", "callgrind.bench.out.1-02"])]
+#[case::old_out(ToolOutputPathKind::OldOut, &["callgrind.bench.out-01.old"])]
+#[case::base(
+    ToolOutputPathKind::Base("foo".to_owned()),
+    &["callgrind.bench.out.base@foo-01", "callgrind.bench.out.base@foo.1-02"]
+)]
+fn test_tool_output_path_real_paths_with_threads(
+    #[case] kind: ToolOutputPathKind,
+    #[case] expected: &[&str],
+) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for file_name in [
+        "callgrind.bench.out-01",
+        "callgrind.bench.out.1-02",
+        "callgrind.bench.out-01.old",
+        "callgrind.bench.out.base@foo-01",
+        "callgrind.bench.out.base@foo.1-02",
+        "callgrind.bench.out.base@foo-",
+        "callgrind.bench.out.base@foobar-01",
+    ] {
+        std::fs::write(temp_dir.path().join(file_name), "").unwrap();
+    }
+    let output_path =
+        Fixtures::tool_output_path_in(temp_dir.path(), ValgrindTool::Callgrind, kind, "bench");
+
+    let mut actual = output_path.real_paths().unwrap();
+    actual.sort();
+
+    assert_eq!(
+        actual,
+        expected
+            .iter()
+            .map(|file_name| temp_dir.path().join(file_name))
+            .collect::<Vec<PathBuf>>()
+    );
+}
+
+#[test]
+fn test_to_threads() {
+    let output_path = with_threads_output();
+
+    assert_eq!(
+        output_path.to_threads(),
+        vec![output_path.with_thread(1), output_path.with_thread(2)]
+    );
+    assert_eq!(output_path.to_parts(), vec![output_path.clone()]);
+    assert!(output_path.with_thread(1).to_threads().is_empty());
+}
+
+#[test]
+fn test_parse_dumps_with_threads() {
+    let output_path = with_threads_output();
+
+    let actual =
+        dumps::parse(&output_path, |path| SummaryParser.parse(path), |_| Ok(None)).unwrap();
+
+    assert_eq!(actual.len(), 1);
+    let dump = &actual[0];
+    assert_eq!(dump.part, Some(1));
+    assert_eq!(dump.trigger.as_deref(), Some("Program termination"));
+    assert_eq!(dump.costs.cost_by_kind(&EventKind::Ir), Some(80));
+    assert_eq!(dump.main_path(), &output_path.with_thread(1));
+    assert_eq!(
+        dump.threads
+            .iter()
+            .map(|t| (t.thread, t.costs.cost_by_kind(&EventKind::Ir)))
+            .collect::<Vec<_>>(),
+        vec![(1, Some(50)), (2, Some(30))]
+    );
+}
+
+#[test]
+fn test_flamegraph_stacks_of_thread_without_main() {
+    let parser = FlamegraphParser::new(None, get_project_root());
+
+    let map = parser.parse(&with_threads_output().with_thread(2)).unwrap();
+
+    assert_eq!(
+        map.to_stack_format(&EventKind::Ir, &StackFilter::default()).unwrap(),
+        vec![
+            "src/main.rs:worker 5".to_owned(),
+            "src/main.rs:worker;src/main.rs:work 25".to_owned()
+        ]
+    );
+}
+
+#[test]
+fn test_flamegraph_parser_merges_threads() {
+    let parser = FlamegraphParser::new(None, get_project_root());
+
+    let map = parser.parse(&with_threads_output()).unwrap();
+
+    assert_eq!(
+        map.to_stack_format(&EventKind::Ir, &StackFilter::default()).unwrap(),
+        vec![
+            "src/main.rs:main 10".to_owned(),
+            "src/main.rs:main;src/main.rs:spawn 10".to_owned(),
+            "src/main.rs:main;src/main.rs:spawn;src/main.rs:worker 5".to_owned(),
+            "src/main.rs:main;src/main.rs:spawn;src/main.rs:worker;src/main.rs:work 25".to_owned()
+        ]
+    );
+}
+
+#[test]
+fn test_flamegraph_parser_merges_threads_with_filter() {
+    let parser = FlamegraphParser::new(None, get_project_root());
+    let filter = StackFilter::new(
+        vec![],
+        vec!["^spawn$".to_owned()],
+        Some("^main$".to_owned()),
+        vec!["work*".to_owned()],
+    );
+
+    let map = parser.parse(&with_threads_output()).unwrap();
+
+    assert_eq!(
+        map.to_stack_format(&EventKind::Ir, &filter).unwrap(),
+        vec!["src/main.rs:main 20".to_owned(), "src/main.rs:main;work* 30".to_owned()]
+    );
+}
@@ -1,9 +1,30 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use iai_callgrind_runner::api::{self, DhatMetric};
+use iai_callgrind_runner::runner::bbv::bb_parser::{
+    parse_bb_out, parse_pc_out, BasicBlockLocation, BasicBlockVector,
+};
+use iai_callgrind_runner::runner::bbv::logfile_parser::BbvLogfileParser;
+use iai_callgrind_runner::runner::callgrind::flamegraph::FlamegraphStacks;
+use iai_callgrind_runner::runner::callgrind::stack_filter::StackFilter;
 use iai_callgrind_runner::runner::costs::Costs;
+use iai_callgrind_runner::runner::dhat::flamegraph::{create_flamegraphs, DhatStacks};
+use iai_callgrind_runner::runner::dhat::json_parser::{
+    parse_dhat_out, summarize_allocation_sites, AllocationSite,
+};
 use iai_callgrind_runner::runner::dhat::logfile_parser::DhatLogfileParser;
-use iai_callgrind_runner::runner::summary::{CostsSummary, ToolRunSummary};
+use iai_callgrind_runner::runner::dhat::massif_parser::{parse_massif_out, MassifLogfileParser};
+use iai_callgrind_runner::runner::summary::{
+    BasicBlockSummary, CostsSummary, ErrorReport, ErrorSummary, StackFrame, ToolRunSummary,
+};
 use iai_callgrind_runner::runner::tool::logfile_parser::{LogfileParser, LogfileSummary};
+use iai_callgrind_runner::runner::tool::xml_parser::{parse_xml_out, XmlLogfileParser};
+use iai_callgrind_runner::runner::tool::{ToolOutputPathKind, ValgrindTool};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use crate::common::{assert_parse_error, Fixtures};
 
 fn dummy_cost(cost: u64) -> Costs<String> {
     Costs::with_event_kinds([("cost".to_string(), cost)])
@@ -16,7 +37,10 @@ fn dummy_summary(cmd: &str, pid: i32, cost: u64) -> LogfileSummary {
         fields: vec![],
         details: vec![],
         error_summary: None,
+        error_reports: vec![],
         costs: Some(dummy_cost(cost)),
+        allocation_sites: vec![],
+        basic_block_vector: None,
         log_path: PathBuf::new(),
     }
 }
@@ -40,7 +64,11 @@ fn dummy_tool_run_summary(
         summary: Default::default(),
         details: None,
         error_summary: None,
+        error_reports: vec![],
         costs_summary,
+        allocation_sites: vec![],
+        basic_block_vector: None,
+        regressions: vec![],
         log_path: Default::default(),
     }
 }
@@ -75,3 +103,608 @@ fn test_dhat_merge_logfile_summaries() {
         vec![s1bo(), s2n()]
     );
 }
+
+fn expected_massif_costs() -> Costs<String> {
+    Costs::with_event_kinds([
+        ("Peak heap bytes".to_owned(), 5120),
+        ("Peak extra bytes".to_owned(), 40),
+        ("Peak stacks bytes".to_owned(), 0),
+        ("Snapshots".to_owned(), 4),
+        ("alloc::alloc::alloc (alloc.rs:98)".to_owned(), 4096),
+        ("  bench::my_bench (bench.rs:10)".to_owned(), 3072),
+        ("  bench::setup (bench.rs:4)".to_owned(), 1024),
+        ("std::rt::lang_start_internal (rt.rs:148)".to_owned(), 1024),
+        ("in 1 place, below massif's threshold (1.00%)".to_owned(), 0),
+    ])
+}
+
+#[test]
+fn test_massif_parse_out_file() {
+    let path = Fixtures::get_path_of("massif.out/massif.valid.out");
+    assert_eq!(parse_massif_out(&path).unwrap(), expected_massif_costs());
+}
+
+#[test]
+fn test_massif_parse_out_file_when_no_snapshots_then_error() {
+    let path = Fixtures::get_path_of("massif.out/massif.no_snapshots.out");
+    assert_parse_error(&path, parse_massif_out(&path), "No snapshots found");
+}
+
+#[test]
+fn test_massif_parse_and_merge() {
+    let output_path = Fixtures::get_tool_output_path(
+        "massif.out",
+        ValgrindTool::Massif,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let massif = MassifLogfileParser {
+        root_dir: PathBuf::new(),
+    };
+
+    let summaries = massif.parse(&output_path).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].pid, 1234);
+    assert_eq!(summaries[0].parent_pid, Some(1233));
+    assert_eq!(summaries[0].costs, Some(expected_massif_costs()));
+
+    let actual = massif.merge_logfile_summaries(summaries.clone(), summaries);
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].old_pid, Some(1234));
+    let costs_summary = actual[0].costs_summary.as_ref().unwrap();
+    for (_, diff) in costs_summary.all_diffs() {
+        assert_eq!(diff.new, diff.old);
+    }
+}
+
+fn frame(
+    ip: &str,
+    object: &str,
+    function: &str,
+    file: Option<&str>,
+    line: Option<u64>,
+) -> StackFrame {
+    StackFrame {
+        ip: ip.to_owned(),
+        object: Some(PathBuf::from(object)),
+        function: Some(function.to_owned()),
+        file: file.map(PathBuf::from),
+        line,
+    }
+}
+
+fn expected_memcheck_error_reports() -> Vec<ErrorReport> {
+    let bench = "target/release/deps/bench-1234";
+    let preload = "/usr/libexec/valgrind/vgpreload_memcheck-amd64-linux.so";
+    vec![
+        ErrorReport {
+            kind: "InvalidRead".to_owned(),
+            message: "Invalid read of size 4".to_owned(),
+            aux_messages: vec![
+                "Address 0x4a9b044 is 0 bytes after a block of size 4 alloc'd".to_owned(),
+            ],
+            count: 2,
+            stacks: vec![
+                vec![
+                    frame("0x10A1B2", bench, "bench::my_bench", Some("benches/bench.rs"), Some(12)),
+                    frame("0x10A2C3", bench, "main", Some("benches/bench.rs"), Some(20)),
+                ],
+                vec![
+                    frame(
+                        "0x4848899",
+                        preload,
+                        "malloc",
+                        Some("./coregrind/m_replacemalloc/vg_replace_malloc.c"),
+                        Some(381),
+                    ),
+                    frame("0x10A1A0", bench, "bench::my_bench", Some("benches/bench.rs"), Some(11)),
+                ],
+            ],
+            suppression: Some("insert_a_suppression_name_here".to_owned()),
+        },
+        ErrorReport {
+            kind: "Leak_DefinitelyLost".to_owned(),
+            message: "16 bytes in 1 blocks are definitely lost in loss record 1 of 1".to_owned(),
+            aux_messages: vec![],
+            count: 1,
+            stacks: vec![vec![
+                frame("0x4848899", preload, "malloc", None, None),
+                frame("0x10A1D4", bench, "bench::leak", Some("benches/bench.rs"), Some(6)),
+            ]],
+            suppression: None,
+        },
+    ]
+}
+
+#[test]
+fn test_memcheck_parse_xml_out_file() {
+    let path = Fixtures::get_path_of("memcheck.out/memcheck.valid.out");
+    let actual = parse_xml_out(&path, &PathBuf::from("/root/project")).unwrap();
+    assert_eq!(actual.error_reports, expected_memcheck_error_reports());
+    assert_eq!(
+        actual.error_summary,
+        ErrorSummary {
+            errors: 3,
+            contexts: 2,
+            supp_errors: 5,
+            supp_contexts: 1
+        }
+    );
+}
+
+#[test]
+fn test_memcheck_parse_xml_out_file_when_invalid_then_error() {
+    let path = Fixtures::get_path_of("memcheck.out/memcheck.invalid.out");
+    assert_parse_error(
+        &path,
+        parse_xml_out(&path, &PathBuf::from("/root/project")),
+        "Expecting </error> found </valgrindoutput>",
+    );
+}
+
+#[test]
+fn test_memcheck_parse_log_and_xml_files() {
+    let output_path = Fixtures::get_tool_output_path(
+        "memcheck.out",
+        ValgrindTool::Memcheck,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let memcheck = XmlLogfileParser {
+        root_dir: PathBuf::from("/root/project"),
+    };
+
+    let summaries = memcheck.parse(&output_path).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].pid, 2345);
+    assert_eq!(summaries[0].parent_pid, Some(2344));
+    assert!(summaries[0].has_errors());
+    // In xml mode, the log file doesn't contain the errors or the error summary
+    assert!(summaries[0].details.is_empty());
+    assert_eq!(
+        summaries[0].error_summary,
+        Some(ErrorSummary {
+            errors: 3,
+            contexts: 2,
+            supp_errors: 5,
+            supp_contexts: 1
+        })
+    );
+    assert_eq!(summaries[0].error_reports, expected_memcheck_error_reports());
+
+    let actual = memcheck.merge_logfile_summaries(vec![], summaries);
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].error_reports, expected_memcheck_error_reports());
+}
+
+#[test]
+fn test_memcheck_parse_log_file_when_no_xml_file() {
+    let output_path = Fixtures::get_tool_output_path(
+        "memcheck.out",
+        ValgrindTool::Memcheck,
+        ToolOutputPathKind::Out,
+        "text",
+    );
+    let memcheck = XmlLogfileParser {
+        root_dir: PathBuf::from("/root/project"),
+    };
+
+    let summaries = memcheck.parse(&output_path).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].pid, 2345);
+    assert!(summaries[0].has_errors());
+    assert!(summaries[0].error_reports.is_empty());
+    assert_eq!(
+        summaries[0].error_summary,
+        Some(ErrorSummary {
+            errors: 3,
+            contexts: 2,
+            supp_errors: 5,
+            supp_contexts: 1
+        })
+    );
+    // Without the xml file, the error contexts are only part of the details
+    assert_eq!(
+        summaries[0].details.first().map(String::as_str),
+        Some("Invalid read of size 4")
+    );
+}
+
+fn expected_dhat_allocation_sites() -> Vec<AllocationSite> {
+    let site = |frame: &str, costs: [u64; 11]| AllocationSite {
+        frames: [
+            "malloc (in /usr/libexec/valgrind/vgpreload_dhat-amd64-linux.so)",
+            "alloc::alloc::alloc (alloc.rs:98)",
+            frame,
+        ]
+        .into_iter()
+        .chain((frame != "bench::setup (bench.rs:4)").then_some("main (bench.rs:20)"))
+        .map(ToOwned::to_owned)
+        .collect(),
+        costs: Costs::with_event_kinds(
+            [
+                "Total bytes",
+                "Total blocks",
+                "Total lifetimes",
+                "Maximum bytes",
+                "Maximum blocks",
+                "At t-gmax bytes",
+                "At t-gmax blocks",
+                "At t-end bytes",
+                "At t-end blocks",
+                "Reads bytes",
+                "Writes bytes",
+            ]
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .zip(costs),
+        ),
+    };
+    vec![
+        site(
+            "bench::my_bench (bench.rs:12)",
+            [1024, 1, 20000, 1024, 1, 1024, 1, 0, 0, 512, 1024],
+        ),
+        site(
+            "bench::helper (bench.rs:30)",
+            [1024, 2, 20000, 1024, 2, 1024, 2, 0, 0, 512, 1024],
+        ),
+        site(
+            "bench::setup (bench.rs:4)",
+            [32, 1, 500, 32, 1, 0, 0, 0, 0, 16, 32],
+        ),
+    ]
+}
+
+#[test]
+fn test_dhat_parse_out_file() {
+    let path = Fixtures::get_path_of("dhat.out/dhat.valid.out");
+    assert_eq!(
+        parse_dhat_out(&path).unwrap(),
+        expected_dhat_allocation_sites()
+    );
+}
+
+#[test]
+fn test_dhat_parse_out_file_when_invalid_frame_then_error() {
+    let path = Fixtures::get_path_of("dhat.out/dhat.invalid_frame.out");
+    assert_parse_error(
+        &path,
+        parse_dhat_out(&path),
+        "Invalid index into the frame table: 2",
+    );
+}
+
+#[test]
+fn test_dhat_parse_and_merge_allocation_sites() {
+    let output_path = Fixtures::get_tool_output_path(
+        "dhat.out",
+        ValgrindTool::DHAT,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let dhat = DhatLogfileParser {
+        root_dir: PathBuf::new(),
+    };
+
+    let summaries = dhat.parse(&output_path).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].pid, 3456);
+    assert_eq!(
+        summaries[0].allocation_sites,
+        expected_dhat_allocation_sites()
+    );
+
+    let actual = dhat.merge_logfile_summaries(summaries.clone(), summaries);
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].allocation_sites.len(), 3);
+    assert!(actual[0].allocation_sites.iter().all(|s| !s.is_changed()));
+}
+
+#[test]
+fn test_dhat_summarize_allocation_sites() {
+    let new = expected_dhat_allocation_sites();
+    let mut old = expected_dhat_allocation_sites();
+    old.remove(1);
+    old[0].costs.0.insert("Total bytes".to_owned(), 512);
+
+    let actual = summarize_allocation_sites(&new, &old);
+    assert_eq!(actual.len(), 3);
+    assert_eq!(actual[0].frames, new[0].frames);
+    assert!(actual[0].is_changed());
+    let diff = actual[0].costs_summary.diff_by_kind(&"Total bytes".to_owned());
+    assert_eq!(diff.and_then(|d| d.diff_pct), Some(100f64));
+    assert!(actual[1].is_changed());
+    assert_eq!(actual[1].costs_summary.diff_by_kind(&"Total bytes".to_owned()).unwrap().old, None);
+    assert!(!actual[2].is_changed());
+}
+
+#[test]
+fn test_dhat_summarize_allocation_sites_when_old_sites_dropped_out() {
+    let site = |name: &str, bytes: u64| AllocationSite {
+        frames: vec![name.to_owned()],
+        costs: Costs::with_event_kinds([("Total bytes".to_owned(), bytes)]),
+    };
+    // The `shrank` site is still present in the new run but not among the top 10 anymore
+    let new = (0..10)
+        .map(|index| site(&format!("new_{index}"), 1000 - index))
+        .chain([site("shrank", 10)])
+        .collect::<Vec<AllocationSite>>();
+    let old = vec![site("shrank", 500), site("disappeared", 400), site("new_0", 300)];
+
+    let actual = summarize_allocation_sites(&new, &old);
+    assert_eq!(actual.len(), 12);
+    assert_eq!(actual[0].frames, vec!["new_0".to_owned()]);
+    assert_eq!(actual[10].frames, vec!["shrank".to_owned()]);
+    assert_eq!(actual[11].frames, vec!["disappeared".to_owned()]);
+
+    let total_bytes = "Total bytes".to_owned();
+    let diff = |index: usize| {
+        let diff = actual[index].costs_summary.diff_by_kind(&total_bytes).unwrap();
+        (diff.new, diff.old)
+    };
+    assert_eq!(diff(0), (Some(1000), Some(300)));
+    assert_eq!(diff(10), (Some(10), Some(500)));
+    assert_eq!(diff(11), (None, Some(400)));
+    assert!(actual[10].is_changed());
+    assert!(actual[11].is_changed());
+}
+
+#[test]
+fn test_dhat_create_flamegraphs() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_tool_output_path(
+        "dhat.out",
+        ValgrindTool::DHAT,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+    Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::OldOut);
+    let config = api::FlamegraphConfig {
+        dhat_metrics: Some(vec![DhatMetric::TotalBytes, DhatMetric::TotalBlocks]),
+        ..Default::default()
+    };
+
+    let summaries =
+        create_flamegraphs(&config.into(), "bench".to_owned(), &output_path, false).unwrap();
+
+    assert_eq!(summaries.len(), 2);
+    for (summary, (metric, name)) in summaries.iter().zip([
+        (DhatMetric::TotalBytes, "TotalBytes"),
+        (DhatMetric::TotalBlocks, "TotalBlocks"),
+    ]) {
+        assert_eq!(summary.event_kind, metric);
+        assert_eq!(
+            summary.regular_path,
+            Some(temp_dir.path().join(format!("dhat.valid.flamegraph.{name}.svg")))
+        );
+        assert_eq!(
+            summary.diff_path,
+            Some(temp_dir.path().join(format!("dhat.valid.flamegraph.{name}.diff.old.svg")))
+        );
+        assert!(summary.regular_path.as_ref().unwrap().exists());
+        assert!(summary.diff_path.as_ref().unwrap().exists());
+    }
+}
+
+#[test]
+fn test_dhat_create_flamegraphs_with_speedscope() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_tool_output_path(
+        "dhat.out",
+        ValgrindTool::DHAT,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+    let config = api::FlamegraphConfig {
+        dhat_metrics: Some(vec![DhatMetric::TotalBytes, DhatMetric::TotalBlocks]),
+        speedscope: Some(true),
+        ..Default::default()
+    };
+
+    let summaries =
+        create_flamegraphs(&config.into(), "bench".to_owned(), &output_path, false).unwrap();
+
+    let expected = temp_dir.path().join("dhat.valid.speedscope.json");
+    assert_eq!(summaries.len(), 2);
+    for summary in &summaries {
+        assert_eq!(summary.speedscope_path.as_ref(), Some(&expected));
+    }
+
+    let speedscope: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(&expected).unwrap()).unwrap();
+    let names = speedscope["profiles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|profile| profile["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            DhatMetric::TotalBytes.to_string(),
+            DhatMetric::TotalBlocks.to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_dhat_create_flamegraphs_with_folded() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fixture = Fixtures::get_tool_output_path(
+        "dhat.out",
+        ValgrindTool::DHAT,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let output_path =
+        Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::Out);
+    Fixtures::copy_tool_output_path(&fixture, temp_dir.path(), ToolOutputPathKind::OldOut);
+    let config = api::FlamegraphConfig {
+        dhat_metrics: Some(vec![DhatMetric::TotalBytes]),
+        folded: Some(true),
+        ..Default::default()
+    };
+    let expected = DhatStacks::parse(&output_path)
+        .unwrap()
+        .to_stack_format(&DhatMetric::TotalBytes, &StackFilter::default())
+        .unwrap();
+
+    let summaries =
+        create_flamegraphs(&config.into(), "bench".to_owned(), &output_path, false).unwrap();
+
+    assert_eq!(summaries.len(), 1);
+    let summary = &summaries[0];
+    assert_eq!(
+        summary.folded_path,
+        Some(temp_dir.path().join("dhat.valid.flamegraph.TotalBytes.folded"))
+    );
+    assert_eq!(
+        summary.base_folded_path,
+        Some(temp_dir.path().join("dhat.valid.flamegraph.TotalBytes.old.folded"))
+    );
+    for path in [&summary.folded_path, &summary.base_folded_path] {
+        let content = std::fs::read_to_string(path.as_ref().unwrap()).unwrap();
+        assert_eq!(content.lines().collect::<Vec<_>>(), expected);
+    }
+}
+
+fn expected_bbv_locations() -> HashMap<u64, BasicBlockLocation> {
+    [
+        (1, "0x4000810", Some("_start")),
+        (2, "0x401a2b", Some("bench::my_bench")),
+        (3, "0x401b00", Some("bench::setup")),
+        (4, "0x402c10", None),
+    ]
+    .into_iter()
+    .map(|(id, address, function)| {
+        (
+            id,
+            BasicBlockLocation {
+                address: address.to_owned(),
+                function: function.map(ToOwned::to_owned),
+            },
+        )
+    })
+    .collect()
+}
+
+#[test]
+fn test_bbv_parse_bb_out_file() {
+    let path = Fixtures::get_path_of("bbv.out/exp-bbv.valid.out.bb");
+    assert_eq!(
+        parse_bb_out(&path).unwrap(),
+        BasicBlockVector {
+            intervals: 3,
+            counts: [(1, 500), (2, 1500), (3, 100), (4, 1050)]
+                .into_iter()
+                .collect(),
+            locations: HashMap::new(),
+        }
+    );
+}
+
+#[test]
+fn test_bbv_parse_bb_out_file_when_invalid_then_error() {
+    let path = Fixtures::get_path_of("bbv.out/exp-bbv.invalid.out.bb");
+    assert_parse_error(&path, parse_bb_out(&path), "Invalid entry ':2'");
+}
+
+#[test]
+fn test_bbv_parse_pc_out_file() {
+    let path = Fixtures::get_path_of("bbv.out/exp-bbv.valid.out.pc");
+    assert_eq!(parse_pc_out(&path).unwrap(), expected_bbv_locations());
+}
+
+#[test]
+fn test_bbv_parse_and_merge_basic_block_vectors() {
+    let output_path = Fixtures::get_tool_output_path(
+        "bbv.out",
+        ValgrindTool::BBV,
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    let bbv = BbvLogfileParser {
+        root_dir: PathBuf::new(),
+    };
+
+    let summaries = bbv.parse(&output_path).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].pid, 4567);
+    assert_eq!(
+        summaries[0].costs,
+        Some(Costs::with_event_kinds([
+            ("Intervals".to_owned(), 3),
+            ("Basic blocks".to_owned(), 4),
+            ("Instructions".to_owned(), 3150),
+        ]))
+    );
+    assert_eq!(
+        summaries[0].basic_block_vector.as_ref().unwrap().locations,
+        expected_bbv_locations()
+    );
+
+    let actual = bbv.merge_logfile_summaries(summaries.clone(), summaries);
+    assert_eq!(actual.len(), 1);
+    let summary = actual[0].basic_block_vector.as_ref().unwrap();
+    assert_eq!(summary.distance, Some(0f64));
+    assert_eq!(
+        summary.hottest_blocks[0],
+        BasicBlockSummary {
+            id: 2,
+            address: Some("0x401a2b".to_owned()),
+            function: Some("bench::my_bench".to_owned()),
+            instructions: 1500
+        }
+    );
+    assert_eq!(
+        summary
+            .hottest_blocks
+            .iter()
+            .map(|b| b.id)
+            .collect::<Vec<u64>>(),
+        vec![2, 4, 1, 3]
+    );
+}
+
+#[rstest]
+#[case::unknown("lackey", ValgrindTool::Custom("lackey".to_owned()))]
+#[case::experimental("exp-dhat", ValgrindTool::Custom("exp-dhat".to_owned()))]
+#[case::known("dhat", ValgrindTool::DHAT)]
+#[case::known_experimental("exp-bbv", ValgrindTool::BBV)]
+fn test_custom_tool_from_api(#[case] id: &str, #[case] expected: ValgrindTool) {
+    let tool = ValgrindTool::from(api::ValgrindTool::Custom(id.to_owned()));
+    assert_eq!(tool.id(), id);
+    assert_eq!(tool, expected);
+}
+
+#[test]
+fn test_custom_tool_parse_logfile() {
+    let tool = ValgrindTool::Custom("lackey".to_owned());
+    let output_path = Fixtures::get_tool_output_path(
+        "lackey.out",
+        tool.clone(),
+        ToolOutputPathKind::Out,
+        "valid",
+    );
+    assert_eq!(
+        output_path.to_log_output().to_path(),
+        Fixtures::get_path_of("lackey.out/lackey.valid.log")
+    );
+
+    let parser = tool.to_parser(PathBuf::new());
+    let summaries = parser.parse(&output_path).unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].pid, 5678);
+    assert_eq!(summaries[0].parent_pid, Some(5677));
+    assert_eq!(summaries[0].costs, None);
+    assert_eq!(summaries[0].details[0], "Counted 1 call to main()");
+
+    let actual = parser.merge_logfile_summaries(vec![], summaries);
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].pid, Some(5678));
+}
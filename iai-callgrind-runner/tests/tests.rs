@@ -1,6 +1,8 @@
 #[cfg(feature = "default")]
 mod common;
 #[cfg(feature = "default")]
+mod test_cachegrind;
+#[cfg(feature = "default")]
 mod test_callgrind;
 
 #[cfg(feature = "default")]
@@ -7,7 +7,7 @@ use std::path::{Path, PathBuf};
 use derive_more::AsRef;
 use iai_callgrind_macros::IntoInner;
 
-use crate::{internal, Stdin, Stdio};
+use crate::{internal, Stdin, Stdio, ValgrindTool};
 
 /// [low level api](`crate::binary_benchmark_group`) only: Create a new benchmark id
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -964,6 +964,68 @@ impl BinaryBenchmarkConfig {
         self
     }
 
+    /// Pass arguments to valgrind's cachegrind
+    ///
+    /// These arguments are only applied if cachegrind is the default tool (See
+    /// [`BinaryBenchmarkConfig::default_tool`]). Like for callgrind, it's not needed to pass the
+    /// arguments with flags. The default settings are:
+    ///
+    /// * `--I1=32768,8,64`
+    /// * `--D1=32768,8,64`
+    /// * `--LL=8388608,16,64`
+    /// * `--cache-sim=yes`
+    ///
+    /// See also [Cachegrind Command-line
+    /// Options](https://valgrind.org/docs/manual/cg-manual.html#cg-manual.cgopts) for a full
+    /// overview of possible arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use iai_callgrind::BinaryBenchmarkConfig;
+    ///
+    /// BinaryBenchmarkConfig::default().raw_cachegrind_args(["branch-sim=yes"]);
+    /// ```
+    pub fn raw_cachegrind_args<I, T>(&mut self, args: T) -> &mut Self
+    where
+        I: AsRef<str>,
+        T: IntoIterator<Item = I>,
+    {
+        self.0.raw_cachegrind_args.extend_ignore_flag(args);
+        self
+    }
+
+    /// Change the default tool from callgrind to another [`crate::ValgrindTool`]
+    ///
+    /// The default tool is the tool which collects the metrics shown in the main output of a
+    /// benchmark run and which is used for the performance regression checks. Currently, only
+    /// [`crate::ValgrindTool::Callgrind`] (the default) and [`crate::ValgrindTool::Cachegrind`]
+    /// can be the default tool. Flamegraphs can only be created if callgrind is the default tool.
+    ///
+    /// Cachegrind doesn't have an equivalent to callgrind's `--toggle-collect` option, so the whole
+    /// command is measured.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, ValgrindTool};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default().default_tool(ValgrindTool::Cachegrind);
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn default_tool(&mut self, tool: ValgrindTool) -> &mut Self {
+        self.0.default_tool = Some(tool);
+        self
+    }
+
     /// Add an environment variable to the [`Command`]
     ///
     /// These environment variables are available independently of the setting of
@@ -1241,6 +1303,255 @@ impl BinaryBenchmarkConfig {
         self
     }
 
+    /// Show the costs of the top functions with a [`crate::FunctionCostsConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, FunctionCostsConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .function_costs(FunctionCostsConfig::default().top(5));
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn function_costs<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalFunctionCostsConfig>,
+    {
+        self.0.function_costs_config = Some(config.into());
+        self
+    }
+
+    /// Create an annotated source report with a [`crate::AnnotateConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, AnnotateConfig, BinaryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .annotate(AnnotateConfig::default().top(5));
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn annotate<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalAnnotateConfig>,
+    {
+        self.0.annotate_config = Some(config.into());
+        self
+    }
+
+    /// Export the call graph in the DOT format of Graphviz with a [`crate::CallGraphConfig`]
+    ///
+    /// If the children are traced, the call graph is created for the benchmarked command only.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, CallGraphConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .call_graph(CallGraphConfig::default().threshold(1.0));
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn call_graph<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalCallGraphConfig>,
+    {
+        self.0.call_graph_config = Some(config.into());
+        self
+    }
+
+    /// Export the callgrind runs to pprof profiles with a [`crate::PprofConfig`]
+    ///
+    /// If the children are traced, the profile is created for the benchmarked command only.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, PprofConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .pprof(PprofConfig::default());
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn pprof<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalPprofConfig>,
+    {
+        self.0.pprof_config = Some(config.into());
+        self
+    }
+
+    /// Calculate the `EstimatedCycles` with the weights of a [`crate::CostModel`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, CostModel, CostModelPreset};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .cost_model(CostModel::default().preset(CostModelPreset::Modern));
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cost_model<T>(&mut self, cost_model: T) -> &mut Self
+    where
+        T: Into<internal::InternalCostModel>,
+    {
+        self.0.cost_model = Some(cost_model.into());
+        self
+    }
+
+    /// Configure the geometry of the caches simulated by callgrind with a [`crate::CacheConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, CacheConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .cache_config(CacheConfig::default().ll(4_194_304, 16, 64));
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cache_config<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalCacheConfig>,
+    {
+        self.0.cache_config = Some(config.into());
+        self
+    }
+
+    /// Run each benchmark once for each named [`crate::CacheConfig`] of a cache matrix
+    ///
+    /// This allows to see how a benchmark behaves with different cache geometries in a single
+    /// benchmark run. The cache matrix replaces the single run of a benchmark with one run for each
+    /// entry of the matrix. The cache levels which are not set in an entry are taken from the
+    /// [`BinaryBenchmarkConfig::cache_config`] or are the default ones. Each run is reported
+    /// separately with the name of the entry appended to the benchmark id (for example
+    /// `my_id.cache@small`) and has its own output files, so each run is compared with the old run
+    /// of the same entry.
+    ///
+    /// The names of the entries have to be unique and may only consist of alphanumeric characters,
+    /// `-` and `_`. An invalid name or cache geometry is an error. The cache matrix has no effect
+    /// if cachegrind is the default tool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, CacheConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .cache_matrix([
+    ///             ("small", CacheConfig::default().ll(1_048_576, 8, 64)),
+    ///             ("large", CacheConfig::default().ll(33_554_432, 16, 64)),
+    ///         ]);
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cache_matrix<N, T, I>(&mut self, matrix: I) -> &mut Self
+    where
+        N: Into<String>,
+        T: Into<internal::InternalCacheConfig>,
+        I: IntoIterator<Item = (N, T)>,
+    {
+        self.0.cache_matrix = Some(
+            matrix
+                .into_iter()
+                .map(|(name, config)| (name.into(), config.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Measure only a set of functions and exclude others with a [`crate::CollectConfig`]
+    ///
+    /// Without any functions to collect, the costs of the benchmark are the total costs of the
+    /// benchmarked binary. See [`crate::CollectConfig`] for the details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{binary_benchmark_group};
+    /// # binary_benchmark_group!(
+    /// #    name = my_group;
+    /// #    benchmarks = |_group: &mut BinaryBenchmarkGroup| {});
+    /// use iai_callgrind::{main, BinaryBenchmarkConfig, CollectConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = BinaryBenchmarkConfig::default()
+    ///         .collect(
+    ///             CollectConfig::default()
+    ///                 .collect_only(["my_crate::decode"])
+    ///                 .exclude(["my_crate::log::*"])
+    ///         );
+    ///     binary_benchmark_groups = my_group
+    /// );
+    /// # }
+    /// ```
+    pub fn collect<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalCollectConfig>,
+    {
+        self.0.collect_config = Some(config.into());
+        self
+    }
+
     /// Enable performance regression checks with a [`crate::RegressionConfig`]
     ///
     /// # Examples
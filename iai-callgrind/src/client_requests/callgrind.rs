@@ -70,6 +70,10 @@ pub fn dump_stats() {
 ///
 /// The argument is appended to a string stating the reason which triggered the dump. This string is
 /// written as a description field into the profile data dump.
+///
+/// Iai-Callgrind reports the costs of each dump separately and labels them with this trigger, for
+/// example `Client Request: phase1`. Pass `--combine-dumps=yes` to callgrind to get the combined
+/// costs of all dumps instead.
 #[inline(always)]
 pub fn dump_stats_at<T>(c_str: T)
 where
@@ -3,7 +3,183 @@
 use derive_more::AsRef;
 use iai_callgrind_macros::IntoInner;
 
-use super::{internal, Direction, EventKind, FlamegraphKind, ValgrindTool};
+use super::{
+    internal, CostModelPreset, DhatMetric, Direction, EventKind, FlamegraphGranularity,
+    FlamegraphKind, ValgrindTool,
+};
+
+/// The `AnnotateConfig` to create an annotated source report of callgrind runs
+///
+/// If configured, an html report like the output of `callgrind_annotate` is written next to the
+/// callgrind output files (for example `callgrind.my_bench.annotate.html`). The report shows the
+/// exclusive costs of every line of the source files with the highest costs. If there is an old or
+/// baseline run, the costs of each line are compared with the costs of the same line in the old
+/// run. The costs of calls to other functions are not added to the line of the call.
+///
+/// The source files are read from the project root if the path is relative. The sources of the
+/// rust standard library are not shown. The report needs the line numbers of the costs, so the
+/// callgrind argument `--dump-line=no` is overridden with a warning.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, AnnotateConfig, LibraryBenchmarkConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .annotate(AnnotateConfig::default());
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct AnnotateConfig(internal::InternalAnnotateConfig);
+
+/// The `CacheConfig` to configure the geometry of the caches simulated by callgrind
+///
+/// Callgrind simulates a first level instruction cache (I1), a first level data cache (D1) and a
+/// last level cache (LL). The geometry of each cache level consists of the size and the line size
+/// in bytes and the associativity. The defaults are `32768,8,64` for the I1 and D1 cache and
+/// `8388608,16,64` for the LL cache. Only the levels which are set replace the defaults.
+///
+/// This is the typed alternative to the `--I1`, `--D1` and `--LL` callgrind arguments with the
+/// same checks callgrind applies to the values: The size, associativity and line size have to be
+/// greater than `0`, the line size has to be a power of two and at least `16` bytes and the number
+/// of sets (size / (line size * associativity)) has to be a power of two. An invalid geometry is an
+/// error. The effective geometry is recorded in the summary of the callgrind run.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, CacheConfig, LibraryBenchmarkConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .cache_config(CacheConfig::default().ll(4_194_304, 16, 64));
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct CacheConfig(internal::InternalCacheConfig);
+
+/// The `CallGraphConfig` to export the call graph of callgrind runs in the DOT format of Graphviz
+///
+/// If configured, a pruned call graph is written next to the callgrind output files (for example
+/// `callgrind.my_bench.call_graph.dot`). The nodes are the functions with their inclusive and
+/// exclusive costs and how often they were called. The edges are the calls from one function to
+/// another with the number of calls and their inclusive costs. As opposed to flamegraphs, the call
+/// counts make it easy to spot functions which are called more often than expected.
+///
+/// Only the functions and calls with an inclusive cost above the [`CallGraphConfig::threshold`]
+/// are shown. If there is an old or baseline run, the nodes are coloured by the change of their
+/// inclusive costs, red for an increase and blue for a decrease. The DOT file can be rendered for
+/// example with `dot -Tsvg callgrind.my_bench.call_graph.dot -o call_graph.svg`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, CallGraphConfig, LibraryBenchmarkConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .call_graph(CallGraphConfig::default());
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct CallGraphConfig(internal::InternalCallGraphConfig);
+
+/// The `CollectConfig` to measure only a set of functions and to exclude others
+///
+/// This is the typed alternative to the `--toggle-collect` callgrind arguments. Unlike toggles,
+/// the functions are not passed to callgrind. Callgrind collects the events as usual and the costs
+/// of the benchmark are calculated afterwards from the inclusive costs of the functions and their
+/// calls:
+///
+/// * The costs of the [`CollectConfig::collect_only`] functions are the inclusive costs of their
+///   calls from outside of the collected functions. So, functions calling each other or
+///   themselves recursively are counted only once. Without any function to collect, the costs are
+///   the costs of the benchmark function of library benchmarks and the total costs of binary
+///   benchmarks.
+/// * The inclusive costs of the [`CollectConfig::exclude`] functions are subtracted if they are
+///   called from within the collected functions. Excluded functions called from outside of the
+///   collected functions don't change the costs.
+///
+/// Callgrind records the calls of a function by another function summed up over all call stacks.
+/// So, a function which is called from both, inside and outside of the collected functions, counts
+/// as outside. Only the costs of the benchmark are affected, not the costs of single functions,
+/// the flamegraphs or other reports. The functions are matched by their full name (for example
+/// `my_crate::decode`) and can be glob patterns with `*` and `?`. An invalid pattern is an error.
+/// The patterns and the toggles which were in effect are recorded in the summary of the callgrind
+/// run. The collect configuration has no effect if cachegrind is the default tool.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, CollectConfig, LibraryBenchmarkConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .collect(
+///                     CollectConfig::default()
+///                         .collect_only(["my_crate::decode"])
+///                         .exclude(["my_crate::log::*"])
+///                 );
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct CollectConfig(internal::InternalCollectConfig);
+
+/// The `CostModel` with which the [`EventKind::EstimatedCycles`] are calculated
+///
+/// The estimated cycles are the weighted sum of the L1 hits, LL hits and RAM hits and, if
+/// callgrind runs with `--branch-sim=yes`, the mispredicted conditional and indirect branches. The
+/// default weights are the ones of the [`CostModelPreset::Classic`]. The weights can be set
+/// individually or based on one of the [`CostModelPreset`]s to reflect the target hardware more
+/// closely. The cost model applies to the `EstimatedCycles` everywhere, for example in the
+/// summaries, the regression checks and the flamegraphs.
+///
+/// The cost model can also be set with the command-line argument `--cost-model` (for example
+/// `--cost-model=modern,ram=200`) which replaces the cost model configured in the benchmarks.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, CostModel, CostModelPreset, LibraryBenchmarkConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .cost_model(CostModel::default().preset(CostModelPreset::Modern).ram_hits(200));
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct CostModel(internal::InternalCostModel);
 
 /// The `FlamegraphConfig` which allows the customization of the created flamegraphs
 ///
@@ -31,6 +207,65 @@ use super::{internal, Direction, EventKind, FlamegraphKind, ValgrindTool};
 #[derive(Debug, Clone, Default, IntoInner, AsRef)]
 pub struct FlamegraphConfig(internal::InternalFlamegraphConfig);
 
+/// The `FunctionCostsConfig` to show the costs of the functions with the highest costs
+///
+/// If configured, the exclusive and inclusive costs of the top functions of a callgrind run are
+/// shown below the costs of the whole benchmark and stored in the summary. The exclusive costs of a
+/// function are the costs of the function itself, the inclusive costs include the costs of all
+/// functions called by it. If there is an old run, the costs are compared with the costs of the
+/// same function in the old run. This allows to trace a regression to a function without running
+/// `callgrind_annotate`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, FunctionCostsConfig, LibraryBenchmarkConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .function_costs(FunctionCostsConfig::default());
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct FunctionCostsConfig(internal::InternalFunctionCostsConfig);
+
+/// The `PprofConfig` to export callgrind runs to profiles in the format of `pprof`
+///
+/// If configured, a gzipped profile in the protobuf format of
+/// [pprof](https://github.com/google/pprof) is written next to the callgrind output files (for
+/// example `callgrind.my_bench.profile.pb.gz`). The profile contains the same stacks as the
+/// flamegraphs with one sample type per [`EventKind`] and can be inspected with `go tool pprof`,
+/// `pprof -http` or any other tool understanding the pprof format.
+///
+/// If there is an old or baseline run, a profile of the old run is written too (for example
+/// `callgrind.my_bench.profile.old.pb.gz`), which can be used as base of a comparison with
+/// `pprof -diff_base callgrind.my_bench.profile.old.pb.gz callgrind.my_bench.profile.pb.gz`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, LibraryBenchmarkConfig, PprofConfig};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .pprof(PprofConfig::default());
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, IntoInner, AsRef)]
+pub struct PprofConfig(internal::InternalPprofConfig);
+
 /// Configure performance regression checks and behavior
 ///
 /// A performance regression check consists of an [`EventKind`] and a percentage over which a
@@ -84,9 +319,384 @@ pub struct RegressionConfig(internal::InternalRegressionConfig);
 /// );
 /// # }
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, IntoInner, AsRef)]
+#[derive(Debug, Clone, PartialEq, IntoInner, AsRef)]
 pub struct Tool(internal::InternalTool);
 
+/// Configure performance regression checks for the metrics of a [`Tool`]
+///
+/// In contrast to the [`RegressionConfig`], a performance regression check of a `Tool` consists of
+/// the name of a metric as shown in the output of the `Tool` (like `Total bytes` of DHAT) and a
+/// percentage over which a regression is assumed. If the percentage is negative, then a regression
+/// is assumed to be below this limit. The metric is matched case insensitive with all
+/// non-alphanumeric characters treated as `_`, so `Total bytes` is matched by `total_bytes` and
+/// `At t-gmax bytes` by `at_t_gmax_bytes`. There are no default limits.
+///
+/// If `fail_fast` is set to true, then the whole benchmark run fails on the first encountered
+/// regression. Else, the default behavior is, that the benchmark run fails with a regression error
+/// after all benchmarks have been run.
+///
+/// # Examples
+///
+/// ```rust
+/// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+/// use iai_callgrind::{main, LibraryBenchmarkConfig, Tool, ToolRegressionConfig, ValgrindTool};
+/// # #[library_benchmark]
+/// # fn some_func() {}
+/// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+/// # fn main() {
+/// main!(
+///     config = LibraryBenchmarkConfig::default()
+///                 .tool(Tool::new(ValgrindTool::DHAT)
+///                     .regression(ToolRegressionConfig::default().limits([("total_bytes", 5f64)]))
+///                 );
+///     library_benchmark_groups = some_group
+/// );
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, IntoInner, AsRef)]
+pub struct ToolRegressionConfig(internal::InternalToolRegressionConfig);
+
+impl AnnotateConfig {
+    /// The number of source files with the highest costs to annotate
+    ///
+    /// The default is `10`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::AnnotateConfig;
+    ///
+    /// let config = AnnotateConfig::default().top(3);
+    /// ```
+    pub fn top(&mut self, top: usize) -> &mut Self {
+        self.0.top = Some(top);
+        self
+    }
+
+    /// The [`EventKind`]s for which the costs of each line are shown
+    ///
+    /// The default is [`EventKind::Ir`].
+    ///
+    /// Each [`EventKind`] is a column in the table of the annotated files and next to the source
+    /// lines. The `top` source files are selected by the costs of the first [`EventKind`]. Columns
+    /// of `EventKinds` without costs in the callgrind output, like [`EventKind::Bc`] without
+    /// `--branch-sim=yes`, are left out of the report.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{AnnotateConfig, EventKind};
+    ///
+    /// let config =
+    ///     AnnotateConfig::default().event_kinds([EventKind::Ir, EventKind::EstimatedCycles]);
+    /// ```
+    pub fn event_kinds<T>(&mut self, event_kinds: T) -> &mut Self
+    where
+        T: IntoIterator<Item = EventKind>,
+    {
+        self.0.event_kinds = Some(event_kinds.into_iter().collect());
+        self
+    }
+
+    /// The number of lines without costs shown around the lines with costs
+    ///
+    /// The default is `8` like the `--context` option of `callgrind_annotate`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::AnnotateConfig;
+    ///
+    /// let config = AnnotateConfig::default().context(3);
+    /// ```
+    pub fn context(&mut self, context: usize) -> &mut Self {
+        self.0.context = Some(context);
+        self
+    }
+}
+
+impl CacheConfig {
+    /// The geometry of the first level instruction cache (I1)
+    ///
+    /// This is the same as the callgrind argument `--I1=<size>,<associativity>,<line_size>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CacheConfig;
+    ///
+    /// let config = CacheConfig::default().i1(16384, 4, 64);
+    /// ```
+    pub fn i1(&mut self, size: u64, associativity: u64, line_size: u64) -> &mut Self {
+        self.0.i1 = Some(internal::InternalCacheGeometry {
+            size,
+            associativity,
+            line_size,
+        });
+        self
+    }
+
+    /// The geometry of the first level data cache (D1)
+    ///
+    /// This is the same as the callgrind argument `--D1=<size>,<associativity>,<line_size>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CacheConfig;
+    ///
+    /// let config = CacheConfig::default().d1(16384, 4, 64);
+    /// ```
+    pub fn d1(&mut self, size: u64, associativity: u64, line_size: u64) -> &mut Self {
+        self.0.d1 = Some(internal::InternalCacheGeometry {
+            size,
+            associativity,
+            line_size,
+        });
+        self
+    }
+
+    /// The geometry of the last level cache (LL)
+    ///
+    /// This is the same as the callgrind argument `--LL=<size>,<associativity>,<line_size>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CacheConfig;
+    ///
+    /// let config = CacheConfig::default().ll(4_194_304, 16, 64);
+    /// ```
+    pub fn ll(&mut self, size: u64, associativity: u64, line_size: u64) -> &mut Self {
+        self.0.ll = Some(internal::InternalCacheGeometry {
+            size,
+            associativity,
+            line_size,
+        });
+        self
+    }
+}
+
+impl CallGraphConfig {
+    /// The [`EventKind`] of the costs of the nodes and edges
+    ///
+    /// The default is [`EventKind::Ir`]. See [`FlamegraphConfig::event_kinds`] for a list of the
+    /// available `EventKinds`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{CallGraphConfig, EventKind};
+    ///
+    /// let config = CallGraphConfig::default().event_kind(EventKind::EstimatedCycles);
+    /// ```
+    pub fn event_kind(&mut self, event_kind: EventKind) -> &mut Self {
+        self.0.event_kind = Some(event_kind);
+        self
+    }
+
+    /// The minimum inclusive cost in percent of the total cost of the functions and calls shown
+    ///
+    /// The default is `0.5`. Similar to [`FlamegraphConfig::min_width`], a higher value prunes
+    /// more functions from the call graph. A threshold of `0` shows all functions. Values which
+    /// are not between `0` and `100` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CallGraphConfig;
+    ///
+    /// let config = CallGraphConfig::default().threshold(5.0);
+    /// ```
+    pub fn threshold(&mut self, threshold: f64) -> &mut Self {
+        self.0.threshold = Some(threshold);
+        self
+    }
+
+    /// If true, colour the nodes by the change of their costs compared to the old or baseline run
+    ///
+    /// The default is `true`. The labels of the nodes and edges show the costs and call counts of
+    /// the old run next to the new ones. If there is no old run, the call graph is always a
+    /// regular call graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CallGraphConfig;
+    ///
+    /// let config = CallGraphConfig::default().differential(false);
+    /// ```
+    pub fn differential(&mut self, value: bool) -> &mut Self {
+        self.0.differential = Some(value);
+        self
+    }
+}
+
+impl PprofConfig {
+    /// The [`EventKind`]s of the sample types of the profile
+    ///
+    /// The default is [`EventKind::Ir`]. The first `EventKind` is the default sample type shown
+    /// by pprof. See [`FlamegraphConfig::event_kinds`] for a list of the available `EventKinds`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, PprofConfig};
+    ///
+    /// let config =
+    ///     PprofConfig::default().event_kinds([EventKind::Ir, EventKind::EstimatedCycles]);
+    /// ```
+    pub fn event_kinds<T>(&mut self, event_kinds: T) -> &mut Self
+    where
+        T: IntoIterator<Item = EventKind>,
+    {
+        self.0.event_kinds = Some(event_kinds.into_iter().collect());
+        self
+    }
+}
+
+impl CollectConfig {
+    /// Calculate the costs of the benchmark only of the functions matching one of these patterns
+    ///
+    /// The costs of the benchmark are the inclusive costs of these functions, counting nested and
+    /// recursive calls only once. Each call adds the patterns to the ones already present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CollectConfig;
+    ///
+    /// let config = CollectConfig::default().collect_only(["my_crate::decode", "my_crate::parse"]);
+    /// ```
+    pub fn collect_only<T, P>(&mut self, functions: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = P>,
+    {
+        self.0
+            .collect_only
+            .extend(functions.into_iter().map(Into::into));
+        self
+    }
+
+    /// Exclude the functions matching one of these patterns from the collection of events
+    ///
+    /// The inclusive costs of these functions, so including all functions called by them, are
+    /// subtracted from the costs of the benchmark if they are called from within a collected
+    /// function. Each call adds the patterns to the ones already present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CollectConfig;
+    ///
+    /// let config = CollectConfig::default().exclude(["my_crate::log::*"]);
+    /// ```
+    pub fn exclude<T, P>(&mut self, functions: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = P>,
+    {
+        self.0.exclude.extend(functions.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl CostModel {
+    /// The [`CostModelPreset`] with the base weights
+    ///
+    /// The weights which are set explicitly replace the weights of the preset. The default is
+    /// [`CostModelPreset::Classic`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{CostModel, CostModelPreset};
+    ///
+    /// let config = CostModel::default().preset(CostModelPreset::Embedded);
+    /// ```
+    pub fn preset(&mut self, preset: CostModelPreset) -> &mut Self {
+        self.0.preset = Some(preset);
+        self
+    }
+
+    /// The estimated cycles of a hit in the L1 cache
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CostModel;
+    ///
+    /// let config = CostModel::default().l1_hits(1);
+    /// ```
+    pub fn l1_hits(&mut self, weight: u64) -> &mut Self {
+        self.0.l1_hits = Some(weight);
+        self
+    }
+
+    /// The estimated cycles of a hit in the last level (LL) cache
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CostModel;
+    ///
+    /// let config = CostModel::default().ll_hits(10);
+    /// ```
+    pub fn ll_hits(&mut self, weight: u64) -> &mut Self {
+        self.0.ll_hits = Some(weight);
+        self
+    }
+
+    /// The estimated cycles of a RAM access (a miss in the LL cache)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CostModel;
+    ///
+    /// let config = CostModel::default().ram_hits(80);
+    /// ```
+    pub fn ram_hits(&mut self, weight: u64) -> &mut Self {
+        self.0.ram_hits = Some(weight);
+        self
+    }
+
+    /// The penalty in estimated cycles of a mispredicted conditional branch ([`EventKind::Bcm`])
+    ///
+    /// The mispredicted branches are only available if callgrind runs with `--branch-sim=yes`.
+    /// Otherwise, this penalty is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CostModel;
+    ///
+    /// let config = CostModel::default().branch_cond_misses(15);
+    /// ```
+    pub fn branch_cond_misses(&mut self, weight: u64) -> &mut Self {
+        self.0.branch_cond_misses = Some(weight);
+        self
+    }
+
+    /// The penalty in estimated cycles of a mispredicted indirect branch ([`EventKind::Bim`])
+    ///
+    /// Like [`CostModel::branch_cond_misses`], this penalty is only applied if callgrind runs
+    /// with `--branch-sim=yes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::CostModel;
+    ///
+    /// let config = CostModel::default().branch_indirect_misses(15);
+    /// ```
+    pub fn branch_indirect_misses(&mut self, weight: u64) -> &mut Self {
+        self.0.branch_indirect_misses = Some(weight);
+        self
+    }
+}
+
 impl FlamegraphConfig {
     /// Option to change the [`FlamegraphKind`]
     ///
@@ -197,6 +807,31 @@ impl FlamegraphConfig {
         self
     }
 
+    /// Option to change the displayed [`DhatMetric`]s of the DHAT flamegraphs
+    ///
+    /// This option only applies to flamegraphs configured with [`Tool::flamegraph`] for
+    /// [`ValgrindTool::DHAT`]. The stacks of the allocation sites are weighted by the chosen
+    /// metric, one flamegraph per metric. The bytes at t-gmax are only recorded by DHAT in the
+    /// default heap mode.
+    ///
+    /// The default is [`DhatMetric::TotalBytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{DhatMetric, FlamegraphConfig};
+    ///
+    /// let config = FlamegraphConfig::default()
+    ///     .dhat_metrics([DhatMetric::TotalBytes, DhatMetric::TotalBlocks]);
+    /// ```
+    pub fn dhat_metrics<T>(&mut self, dhat_metrics: T) -> &mut Self
+    where
+        T: IntoIterator<Item = DhatMetric>,
+    {
+        self.0.dhat_metrics = Some(dhat_metrics.into_iter().collect());
+        self
+    }
+
     /// Set the [`Direction`] in which the flamegraph should grow.
     ///
     /// The default is [`Direction::TopToBottom`].
@@ -260,6 +895,213 @@ impl FlamegraphConfig {
         self.0.min_width = Some(min_width);
         self
     }
+
+    /// If true, export the flamegraph stacks to a [speedscope](https://www.speedscope.app) file
+    ///
+    /// The default is `false`. The speedscope file contains one profile per
+    /// [`FlamegraphConfig::event_kinds`] and is written next to the flamegraphs (for example
+    /// `callgrind.my_bench.speedscope.json`). As opposed to the svg flamegraphs, the file can be
+    /// browsed interactively with the left-heavy and sandwich views of speedscope, which also
+    /// works offline by opening the local file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().speedscope(true);
+    /// ```
+    pub fn speedscope(&mut self, value: bool) -> &mut Self {
+        self.0.speedscope = Some(value);
+        self
+    }
+
+    /// If true, write the folded stacks of the flamegraphs to text files
+    ///
+    /// The default is `false`. The files are written next to the flamegraphs and named like them
+    /// but with the extension `folded` (for example `callgrind.my_bench.flamegraph.Ir.folded`). If
+    /// a differential flamegraph is created, the folded stacks of the old or base run are written,
+    /// too (for example `callgrind.my_bench.flamegraph.Ir.old.folded`). The folded stacks are the
+    /// ones after applying the rules like [`FlamegraphConfig::exclude_frames`]. Tools like
+    /// `inferno-flamegraph` or `inferno-diff-folded` can consume these files, so flamegraphs can be
+    /// re-rendered without running the benchmarks again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().folded(true);
+    /// ```
+    pub fn folded(&mut self, value: bool) -> &mut Self {
+        self.0.folded = Some(value);
+        self
+    }
+
+    /// Set the [`FlamegraphGranularity`] of the innermost frames of the callgrind flamegraphs
+    ///
+    /// The default is [`FlamegraphGranularity::Function`]. Rust code is inlined heavily, so the
+    /// costs of a flamegraph often end up in a few large functions. With
+    /// [`FlamegraphGranularity::Inline`], the code inlined from other source files is shown as
+    /// innermost frame (for example `[inlined] /rustc/.../core/src/iter/range.rs`) of the function
+    /// it was inlined into. With [`FlamegraphGranularity::Line`], the innermost frames are the
+    /// source lines (`file:line`) of the functions. Callgrind is run with `--read-inline-info=yes`
+    /// for both granularities and with `--dump-line=yes` for [`FlamegraphGranularity::Line`]. The
+    /// flamegraphs of callgrind are no exact call stacks, so if the cost of the stack of a
+    /// function is lower than the costs of its inlined code or lines, the cost is split between
+    /// them in proportion to their costs. This setting has no effect on the flamegraphs of DHAT.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{FlamegraphConfig, FlamegraphGranularity};
+    ///
+    /// let config = FlamegraphConfig::default().granularity(FlamegraphGranularity::Line);
+    /// ```
+    pub fn granularity(&mut self, granularity: FlamegraphGranularity) -> &mut Self {
+        self.0.granularity = Some(granularity);
+        self
+    }
+
+    /// Show only the frames of functions matching one of these regexes
+    ///
+    /// The regexes match the function name of a frame (for example `^my_crate::`) without the
+    /// path of the source and object file or the location of a DHAT frame. The costs of removed
+    /// frames are attributed to the innermost remaining frame of the stack and stacks without any
+    /// remaining frames are removed. The rules apply to regular and differential flamegraphs and to
+    /// the speedscope file. Invalid regexes are ignored with a warning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().include_frames(["^main$", "^my_crate::"]);
+    /// ```
+    pub fn include_frames<T, P>(&mut self, regexes: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = P>,
+    {
+        self.0
+            .include_frames
+            .get_or_insert_with(Vec::new)
+            .extend(regexes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Remove the frames of functions matching one of these regexes
+    ///
+    /// See [`FlamegraphConfig::include_frames`] for how the regexes are matched and what happens to
+    /// the costs of removed frames.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().exclude_frames(["^std::", "^core::", "^alloc::"]);
+    /// ```
+    pub fn exclude_frames<T, P>(&mut self, regexes: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = P>,
+    {
+        self.0
+            .exclude_frames
+            .get_or_insert_with(Vec::new)
+            .extend(regexes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Focus on the function matching this regex
+    ///
+    /// The flamegraph is rooted at the function: All frames outside of the outermost frame matching
+    /// the regex are removed and stacks without a matching frame are removed completely. The focus
+    /// is applied before [`FlamegraphConfig::include_frames`] and
+    /// [`FlamegraphConfig::exclude_frames`]. An invalid regex is ignored with a warning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().focus("^my_crate::parser::parse$");
+    /// ```
+    pub fn focus<T>(&mut self, regex: T) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.0.focus = Some(regex.into());
+        self
+    }
+
+    /// Fold consecutive frames of functions matching the same glob pattern into a single frame
+    ///
+    /// The folded frame is named after the glob pattern, so for example all consecutive
+    /// `core::iter::*` frames show up as a single `core::iter::*` frame with the summed up costs.
+    /// The patterns are applied after all other rules. Invalid patterns are ignored with a warning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FlamegraphConfig;
+    ///
+    /// let config = FlamegraphConfig::default().collapse_frames(["core::iter::*", "alloc::*"]);
+    /// ```
+    pub fn collapse_frames<T, P>(&mut self, patterns: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = P>,
+    {
+        self.0
+            .collapse_frames
+            .get_or_insert_with(Vec::new)
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl FunctionCostsConfig {
+    /// The number of functions with the highest costs to show
+    ///
+    /// The default is `10`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::FunctionCostsConfig;
+    ///
+    /// let config = FunctionCostsConfig::default().top(5);
+    /// ```
+    pub fn top(&mut self, top: usize) -> &mut Self {
+        self.0.top = Some(top);
+        self
+    }
+
+    /// The [`EventKind`]s for which the exclusive and inclusive costs are shown
+    ///
+    /// The default is [`EventKind::Ir`].
+    ///
+    /// The functions are sorted by the exclusive and then by the inclusive costs of the first
+    /// [`EventKind`]. `EventKinds` which weren't recorded by callgrind are not shown. See
+    /// [`FlamegraphConfig::event_kinds`] for a list of the available `EventKinds`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, FunctionCostsConfig};
+    ///
+    /// let config =
+    ///     FunctionCostsConfig::default().event_kinds([EventKind::EstimatedCycles, EventKind::Ir]);
+    /// ```
+    pub fn event_kinds<T>(&mut self, event_kinds: T) -> &mut Self
+    where
+        T: IntoIterator<Item = EventKind>,
+    {
+        self.0.event_kinds = Some(event_kinds.into_iter().collect());
+        self
+    }
 }
 
 /// Enable performance regression checks with a [`RegressionConfig`]
@@ -315,6 +1157,101 @@ impl RegressionConfig {
         self
     }
 
+    /// Configure the limits percentages for the costs of single functions
+    ///
+    /// A limit for functions consists of a glob pattern matching the function names, an
+    /// [`EventKind`] and a percentage over which a regression is assumed. If the percentage is
+    /// negative, then a regression is assumed to be below this limit. The limits are checked
+    /// against the inclusive costs of each function matching the pattern (for example
+    /// `my_crate::parser::*`), so hot functions can be guarded even when the total costs of the
+    /// benchmark stay flat. Functions which are not present in the old benchmark run are not
+    /// checked. A regression of a function is reported together with the name of the function.
+    ///
+    /// Limits for functions are only available if callgrind is the default tool. If only limits for
+    /// functions are specified, the default limit of [`EventKind::Ir`] for the total costs is not
+    /// applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, RegressionConfig};
+    ///
+    /// let config = RegressionConfig::default()
+    ///     .function_limits([("my_crate::parser::*", EventKind::Ir, 2f64)]);
+    /// ```
+    pub fn function_limits<T, P>(&mut self, targets: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = (P, EventKind, f64)>,
+    {
+        self.0.function_limits.extend(
+            targets
+                .into_iter()
+                .map(|(pattern, event_kind, limit)| (pattern.into(), event_kind, limit)),
+        );
+        self
+    }
+
+    /// Configure the limits percentages for the costs of single threads
+    ///
+    /// A limit for threads consists of the thread index, an [`EventKind`] and a percentage over
+    /// which a regression is assumed. If the percentage is negative, then a regression is assumed
+    /// to be below this limit. Valgrind assigns the thread indices in the order of creation
+    /// starting with `1` for the main thread. Threads which are not present in the new and old
+    /// benchmark run are not checked.
+    ///
+    /// The costs are only split into threads if callgrind is run with `--separate-threads=yes`.
+    /// Otherwise, the limits for threads have no effect. If only limits for threads are specified,
+    /// the default limit of [`EventKind::Ir`] for the total costs is not applied. Note that
+    /// callgrind collects the costs of threads other than the main thread only if they run through
+    /// a function specified with `--toggle-collect` (or with `--collect-atstart=yes`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{EventKind, LibraryBenchmarkConfig, RegressionConfig};
+    ///
+    /// let config = LibraryBenchmarkConfig::default()
+    ///     .raw_callgrind_args(["--separate-threads=yes"])
+    ///     .regression(RegressionConfig::default().thread_limits([(2, EventKind::Ir, 5f64)]));
+    /// ```
+    pub fn thread_limits<T>(&mut self, targets: T) -> &mut Self
+    where
+        T: IntoIterator<Item = (usize, EventKind, f64)>,
+    {
+        self.0.thread_limits.extend(targets);
+        self
+    }
+
+    /// Select the child processes which are checked for regressions
+    ///
+    /// If callgrind is run with `--trace-children=yes` in binary benchmarks, the costs of each
+    /// traced process are reported separately. The benchmarked command itself is always checked
+    /// for regressions. The child processes are checked only if their command line matches one of
+    /// the glob patterns (for example `*/my-helper *`). Without any filters, all child processes
+    /// are checked. The command line is the one valgrind reports in the log file, the path to the
+    /// executable followed by its arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{BinaryBenchmarkConfig, RegressionConfig};
+    ///
+    /// let config = BinaryBenchmarkConfig::default()
+    ///     .raw_callgrind_args(["--trace-children=yes"])
+    ///     .regression(RegressionConfig::default().child_filters(["*/my-helper*"]));
+    /// ```
+    pub fn child_filters<T, P>(&mut self, targets: T) -> &mut Self
+    where
+        P: Into<String>,
+        T: IntoIterator<Item = P>,
+    {
+        self.0
+            .child_filters
+            .extend(targets.into_iter().map(Into::into));
+        self
+    }
+
     /// If set to true, then the benchmarks fail on the first encountered regression
     ///
     /// The default is `false` and the whole benchmark run fails with a regression error after all
@@ -343,6 +1280,16 @@ impl Tool {
     ///
     /// let tool = Tool::new(ValgrindTool::DHAT);
     /// ```
+    ///
+    /// Valgrind tools without a dedicated [`ValgrindTool`] variant, like `lackey`, can be run with
+    /// [`ValgrindTool::Custom`] and the name of the tool as passed to `valgrind --tool`. The output
+    /// of custom tools is not interpreted besides the log files.
+    ///
+    /// ```
+    /// use iai_callgrind::{Tool, ValgrindTool};
+    ///
+    /// let tool = Tool::new(ValgrindTool::Custom("lackey".to_owned()));
+    /// ```
     pub fn new(tool: ValgrindTool) -> Self {
         Self(internal::InternalTool {
             kind: tool,
@@ -350,6 +1297,9 @@ impl Tool {
             outfile_modifier: Option::default(),
             show_log: Option::default(),
             raw_args: internal::InternalRawArgs::default(),
+            regression_config: Option::default(),
+            fail_on_errors: Option::default(),
+            flamegraph_config: Option::default(),
         })
     }
 
@@ -432,6 +1382,114 @@ impl Tool {
         self.0.outfile_modifier = Some(modifier.into());
         self
     }
+
+    /// If true, fail the benchmark if this `Tool` reports errors (Default: false)
+    ///
+    /// This setting applies to tools with an error summary like [`ValgrindTool::Memcheck`],
+    /// [`ValgrindTool::Helgrind`] and [`ValgrindTool::DRD`]. Suppressed errors don't fail the
+    /// benchmark. The error contexts are printed before the benchmark run is aborted. This setting
+    /// can be overridden on the command-line with `--tools-fail-on-error`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{Tool, ValgrindTool};
+    ///
+    /// let tool = Tool::new(ValgrindTool::Memcheck).fail_on_errors(true);
+    /// ```
+    pub fn fail_on_errors(&mut self, value: bool) -> &mut Self {
+        self.0.fail_on_errors = Some(value);
+        self
+    }
+
+    /// Enable performance regression checks of the metrics of this `Tool`
+    ///
+    /// Regression checks are only performed for tools with metrics, like
+    /// [`ValgrindTool::DHAT`] or [`ValgrindTool::Massif`]. See [`ToolRegressionConfig`] for more
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{Tool, ToolRegressionConfig, ValgrindTool};
+    ///
+    /// let tool = Tool::new(ValgrindTool::DHAT).regression(
+    ///     ToolRegressionConfig::default().limits([("total_bytes", 5f64), ("total_blocks", 0f64)]),
+    /// );
+    /// ```
+    pub fn regression<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalToolRegressionConfig>,
+    {
+        self.0.regression_config = Some(config.into());
+        self
+    }
+
+    /// Create flamegraphs from the output of this `Tool`
+    ///
+    /// Currently, only [`ValgrindTool::DHAT`] supports flamegraphs. The stacks of the allocation
+    /// sites are weighted by the [`DhatMetric`]s of the [`FlamegraphConfig`]. Like the callgrind
+    /// flamegraphs, regular and differential flamegraphs can be created. The flamegraph
+    /// configuration of all other tools is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::{DhatMetric, FlamegraphConfig, Tool, ValgrindTool};
+    ///
+    /// let tool = Tool::new(ValgrindTool::DHAT)
+    ///     .flamegraph(FlamegraphConfig::default().dhat_metrics([DhatMetric::AtTGmaxBytes]));
+    /// ```
+    pub fn flamegraph<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalFlamegraphConfig>,
+    {
+        self.0.flamegraph_config = Some(config.into());
+        self
+    }
+}
+
+impl ToolRegressionConfig {
+    /// Configure the limits percentages over/below which a performance regression can be assumed
+    ///
+    /// A performance regression check consists of the name of a metric of the [`Tool`] and a
+    /// percentage over which a regression is assumed. If the percentage is negative, then a
+    /// regression is assumed to be below this limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::ToolRegressionConfig;
+    ///
+    /// let config = ToolRegressionConfig::default().limits([("at_t_gmax_bytes", 5f64)]);
+    /// ```
+    pub fn limits<T, K>(&mut self, targets: T) -> &mut Self
+    where
+        K: Into<String>,
+        T: IntoIterator<Item = (K, f64)>,
+    {
+        self.0
+            .limits
+            .extend(targets.into_iter().map(|(k, l)| (k.into(), l)));
+        self
+    }
+
+    /// If set to true, then the benchmarks fail on the first encountered regression
+    ///
+    /// The default is `false` and the whole benchmark run fails with a regression error after all
+    /// benchmarks have been run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use iai_callgrind::ToolRegressionConfig;
+    ///
+    /// let config = ToolRegressionConfig::default().fail_fast(true);
+    /// ```
+    pub fn fail_fast(&mut self, value: bool) -> &mut Self {
+        self.0.fail_fast = Some(value);
+        self
+    }
 }
 
 /// __DEPRECATED__: A function that is opaque to the optimizer
@@ -2,21 +2,27 @@
 //! structs
 #![allow(missing_docs)]
 
+use iai_callgrind_runner::api::CACHEGRIND_INSTRUMENTATION_ENV;
 pub use iai_callgrind_runner::api::{
-    BinaryBenchmark as InternalBinaryBenchmark,
+    AnnotateConfig as InternalAnnotateConfig, BinaryBenchmark as InternalBinaryBenchmark,
     BinaryBenchmarkBench as InternalBinaryBenchmarkBench,
     BinaryBenchmarkConfig as InternalBinaryBenchmarkConfig,
     BinaryBenchmarkGroup as InternalBinaryBenchmarkGroup,
-    BinaryBenchmarkGroups as InternalBinaryBenchmarkGroups, Command as InternalCommand,
+    BinaryBenchmarkGroups as InternalBinaryBenchmarkGroups,
+    CacheConfig as InternalCacheConfig, CacheGeometry as InternalCacheGeometry,
+    CallGraphConfig as InternalCallGraphConfig, CollectConfig as InternalCollectConfig,
+    Command as InternalCommand, CostModel as InternalCostModel,
     ExitWith as InternalExitWith, Fixtures as InternalFixtures,
     FlamegraphConfig as InternalFlamegraphConfig,
+    FunctionCostsConfig as InternalFunctionCostsConfig,
     LibraryBenchmark as InternalLibraryBenchmarkBenches,
     LibraryBenchmarkBench as InternalLibraryBenchmarkBench,
     LibraryBenchmarkConfig as InternalLibraryBenchmarkConfig,
     LibraryBenchmarkGroup as InternalLibraryBenchmarkGroup,
-    LibraryBenchmarkGroups as InternalLibraryBenchmarkGroups, RawArgs as InternalRawArgs,
+    LibraryBenchmarkGroups as InternalLibraryBenchmarkGroups, PprofConfig as InternalPprofConfig,
+    RawArgs as InternalRawArgs,
     RegressionConfig as InternalRegressionConfig, Sandbox as InternalSandbox, Tool as InternalTool,
-    Tools as InternalTools,
+    ToolRegressionConfig as InternalToolRegressionConfig, Tools as InternalTools,
 };
 
 #[derive(Debug, Clone)]
@@ -36,3 +42,122 @@ pub struct InternalMacroBinBench {
     pub teardown: Option<fn()>,
     pub config: Option<fn() -> crate::internal::InternalBinaryBenchmarkConfig>,
 }
+
+/// Run the benchmark function `func` of a library benchmark
+///
+/// If cachegrind is the default tool, the runner starts cachegrind with `--instr-at-start=no` and
+/// sets the environment variable [`CACHEGRIND_INSTRUMENTATION_ENV`]. The instrumentation is then
+/// started right before and stopped right after `func`.
+#[inline(never)]
+pub fn run_lib_bench(func: fn()) {
+    if std::env::var_os(CACHEGRIND_INSTRUMENTATION_ENV).is_some() {
+        cachegrind::start_instrumentation();
+        func();
+        cachegrind::stop_instrumentation();
+    } else {
+        func();
+    }
+}
+
+/// The cachegrind client requests to start and stop the instrumentation
+///
+/// These are the same client requests as in `client_requests::cachegrind` (valgrind >= 3.22) but
+/// they are available without the `client_requests` feature. On other targets than the ones below,
+/// the client requests do nothing.
+#[allow(clippy::inline_always)]
+mod cachegrind {
+    /// `VG_USERREQ_TOOL_BASE('C','G')` of `cachegrind.h`
+    const START_INSTRUMENTATION: usize = 0x4347_0000;
+    const STOP_INSTRUMENTATION: usize = 0x4347_0001;
+
+    #[inline(always)]
+    pub fn start_instrumentation() {
+        do_client_request(START_INSTRUMENTATION);
+    }
+
+    #[inline(always)]
+    pub fn stop_instrumentation() {
+        do_client_request(STOP_INSTRUMENTATION);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[inline(always)]
+    fn do_client_request(request: usize) {
+        let args: [usize; 6] = [request, 0, 0, 0, 0, 0];
+        // SAFETY: These assembly instructions do nothing when not run under valgrind
+        unsafe {
+            core::arch::asm! {
+                "rol rdi,3",
+                "rol rdi,13",
+                "rol rdi,61",
+                "rol rdi,51",
+                "xchg rbx, rbx",
+                inout("rdx") 0usize => _,
+                in("rax") args.as_ptr(),
+            };
+        }
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[inline(always)]
+    fn do_client_request(request: usize) {
+        let args: [usize; 6] = [request, 0, 0, 0, 0, 0];
+        // SAFETY: These assembly instructions do nothing when not run under valgrind
+        unsafe {
+            core::arch::asm! {
+                "rol edi,3",
+                "rol edi,13",
+                "rol edi,29",
+                "rol edi,19",
+                "xchg ebx, ebx",
+                inout("edx") 0usize => _,
+                in("eax") args.as_ptr(),
+            };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[inline(always)]
+    fn do_client_request(request: usize) {
+        let args: [usize; 6] = [request, 0, 0, 0, 0, 0];
+        // SAFETY: These assembly instructions do nothing when not run under valgrind
+        unsafe {
+            core::arch::asm! {
+                "ror x12, x12, 3",
+                "ror x12, x12, 13",
+                "ror x12, x12, 51",
+                "ror x12, x12, 61",
+                "orr x10, x10, x10",
+                inout("x3") 0usize => _,
+                in("x4") args.as_ptr(),
+            };
+        }
+    }
+
+    #[cfg(target_arch = "arm")]
+    #[inline(always)]
+    fn do_client_request(request: usize) {
+        let args: [usize; 6] = [request, 0, 0, 0, 0, 0];
+        // SAFETY: These assembly instructions do nothing when not run under valgrind
+        unsafe {
+            core::arch::asm! {
+                "ror r12, r12, 3",
+                "ror r12, r12, 13",
+                "ror r12, r12, 29",
+                "ror r12, r12, 19",
+                "orr r10, r10, r10",
+                inout("r3") 0usize => _,
+                in("r4") args.as_ptr(),
+            };
+        }
+    }
+
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm"
+    )))]
+    #[inline(always)]
+    fn do_client_request(_request: usize) {}
+}
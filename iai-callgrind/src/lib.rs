@@ -411,14 +411,19 @@ pub use bin_bench::{
 #[cfg(feature = "default")]
 pub use bincode;
 #[cfg(feature = "default")]
-pub use common::{black_box, FlamegraphConfig, RegressionConfig, Tool};
+pub use common::{
+    black_box, AnnotateConfig, CacheConfig, CallGraphConfig, CollectConfig, CostModel,
+    FlamegraphConfig, FunctionCostsConfig, PprofConfig, RegressionConfig, Tool,
+    ToolRegressionConfig,
+};
 #[cfg(feature = "client_requests_defs")]
 pub use cty;
 #[cfg(feature = "default")]
 pub use iai_callgrind_macros::{binary_benchmark, library_benchmark};
 #[cfg(feature = "default")]
 pub use iai_callgrind_runner::api::{
-    Direction, EventKind, FlamegraphKind, Pipe, Stdin, Stdio, ValgrindTool,
+    CostModelPreset, DhatMetric, Direction, EventKind, FlamegraphGranularity, FlamegraphKind, Pipe,
+    Stdin, Stdio, ValgrindTool,
 };
 #[cfg(feature = "default")]
 pub use lib_bench::LibraryBenchmarkConfig;
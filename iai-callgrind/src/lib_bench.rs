@@ -3,7 +3,7 @@ use std::ffi::OsString;
 use derive_more::AsRef;
 use iai_callgrind_macros::IntoInner;
 
-use crate::internal;
+use crate::{internal, ValgrindTool};
 
 /// The main configuration of a library benchmark.
 ///
@@ -58,8 +58,19 @@ impl LibraryBenchmarkConfig {
         Self(internal::InternalLibraryBenchmarkConfig {
             env_clear: Option::default(),
             raw_callgrind_args: internal::InternalRawArgs::from_iter(args),
+            raw_cachegrind_args: internal::InternalRawArgs::default(),
+            default_tool: Option::default(),
             envs: Vec::default(),
             flamegraph_config: Option::default(),
+            function_costs_config: Option::default(),
+            annotate_config: Option::default(),
+            call_graph_config: Option::default(),
+            pprof_config: Option::default(),
+            cost_model: Option::default(),
+            cache_config: Option::default(),
+            cache_matrix: Option::default(),
+            collect_config: Option::default(),
+            harness_calibration: Option::default(),
             regression_config: Option::default(),
             tools: internal::InternalTools::default(),
             tools_override: Option::default(),
@@ -152,6 +163,83 @@ impl LibraryBenchmarkConfig {
         self
     }
 
+    /// Add cachegrind arguments to this `LibraryBenchmarkConfig`
+    ///
+    /// These arguments are only applied if cachegrind is the default tool (See
+    /// [`LibraryBenchmarkConfig::default_tool`]). Like for callgrind, the arguments don't need to
+    /// start with a flag. The default settings are:
+    ///
+    /// * `--I1=32768,8,64`
+    /// * `--D1=32768,8,64`
+    /// * `--LL=8388608,16,64`
+    /// * `--cache-sim=yes`
+    /// * `--instr-at-start=no`
+    ///
+    /// See also [Cachegrind Command-line
+    /// Options](https://valgrind.org/docs/manual/cg-manual.html#cg-manual.cgopts)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{LibraryBenchmarkConfig, main, ValgrindTool};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///                 .default_tool(ValgrindTool::Cachegrind)
+    ///                 .raw_cachegrind_args(["branch-sim=yes"]);
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn raw_cachegrind_args<I, T>(&mut self, args: T) -> &mut Self
+    where
+        I: AsRef<str>,
+        T: IntoIterator<Item = I>,
+    {
+        self.0.raw_cachegrind_args.extend_ignore_flag(args);
+        self
+    }
+
+    /// Change the default tool from callgrind to another [`crate::ValgrindTool`]
+    ///
+    /// The default tool is the tool which collects the metrics shown in the main output of a
+    /// benchmark run and which is used for the performance regression checks. Currently, only
+    /// [`crate::ValgrindTool::Callgrind`] (the default) and [`crate::ValgrindTool::Cachegrind`]
+    /// can be the default tool. Flamegraphs can only be created if callgrind is the default tool.
+    ///
+    /// Cachegrind doesn't have an equivalent to callgrind's `--toggle-collect` option. Instead,
+    /// cachegrind runs with `--instr-at-start=no` and the benchmark harness starts and stops the
+    /// instrumentation right before and after the benchmark function with cachegrind's client
+    /// requests. These client requests need valgrind >= 3.22. With `--instr-at-start=yes` (See
+    /// [`LibraryBenchmarkConfig::raw_cachegrind_args`]), the whole benchmark binary is measured
+    /// including the setup code of `iai-callgrind`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{LibraryBenchmarkConfig, main, ValgrindTool};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default().default_tool(ValgrindTool::Cachegrind);
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn default_tool(&mut self, tool: ValgrindTool) -> &mut Self {
+        self.0.default_tool = Some(tool);
+        self
+    }
+
     /// Clear the environment variables before running a benchmark (Default: true)
     ///
     /// # Examples
@@ -331,6 +419,285 @@ impl LibraryBenchmarkConfig {
         self
     }
 
+    /// Show the costs of the top functions with a [`crate::FunctionCostsConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{LibraryBenchmarkConfig, main, FunctionCostsConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .function_costs(FunctionCostsConfig::default().top(5));
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn function_costs<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalFunctionCostsConfig>,
+    {
+        self.0.function_costs_config = Some(config.into());
+        self
+    }
+
+    /// Create an annotated source report with a [`crate::AnnotateConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{AnnotateConfig, LibraryBenchmarkConfig, main};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .annotate(AnnotateConfig::default().top(5));
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn annotate<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalAnnotateConfig>,
+    {
+        self.0.annotate_config = Some(config.into());
+        self
+    }
+
+    /// Export the call graph in the DOT format of Graphviz with a [`crate::CallGraphConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{CallGraphConfig, LibraryBenchmarkConfig, main};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .call_graph(CallGraphConfig::default().threshold(1.0));
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn call_graph<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalCallGraphConfig>,
+    {
+        self.0.call_graph_config = Some(config.into());
+        self
+    }
+
+    /// Export the callgrind runs to pprof profiles with a [`crate::PprofConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{LibraryBenchmarkConfig, PprofConfig, main};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .pprof(PprofConfig::default());
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn pprof<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalPprofConfig>,
+    {
+        self.0.pprof_config = Some(config.into());
+        self
+    }
+
+    /// Calculate the `EstimatedCycles` with the weights of a [`crate::CostModel`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{CostModel, CostModelPreset, LibraryBenchmarkConfig, main};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .cost_model(CostModel::default().preset(CostModelPreset::Modern));
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cost_model<T>(&mut self, cost_model: T) -> &mut Self
+    where
+        T: Into<internal::InternalCostModel>,
+    {
+        self.0.cost_model = Some(cost_model.into());
+        self
+    }
+
+    /// Configure the geometry of the caches simulated by callgrind with a [`crate::CacheConfig`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{main, LibraryBenchmarkConfig, CacheConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .cache_config(CacheConfig::default().ll(4_194_304, 16, 64));
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cache_config<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalCacheConfig>,
+    {
+        self.0.cache_config = Some(config.into());
+        self
+    }
+
+    /// Run each benchmark once for each named [`crate::CacheConfig`] of a cache matrix
+    ///
+    /// This allows to see how a benchmark behaves with different cache geometries in a single
+    /// benchmark run. The cache matrix replaces the single run of a benchmark with one run for each
+    /// entry of the matrix. The cache levels which are not set in an entry are taken from the
+    /// [`LibraryBenchmarkConfig::cache_config`] or are the default ones. Each run is reported
+    /// separately with the name of the entry appended to the benchmark id (for example
+    /// `my_id.cache@small`) and has its own output files, so each run is compared with the old run
+    /// of the same entry.
+    ///
+    /// The names of the entries have to be unique and may only consist of alphanumeric characters,
+    /// `-` and `_`. An invalid name or cache geometry is an error. The cache matrix has no effect
+    /// if cachegrind is the default tool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{main, LibraryBenchmarkConfig, CacheConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .cache_matrix([
+    ///             ("small", CacheConfig::default().ll(1_048_576, 8, 64)),
+    ///             ("large", CacheConfig::default().ll(33_554_432, 16, 64)),
+    ///         ]);
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn cache_matrix<N, T, I>(&mut self, matrix: I) -> &mut Self
+    where
+        N: Into<String>,
+        T: Into<internal::InternalCacheConfig>,
+        I: IntoIterator<Item = (N, T)>,
+    {
+        self.0.cache_matrix = Some(
+            matrix
+                .into_iter()
+                .map(|(name, config)| (name.into(), config.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Measure only a set of functions and exclude others with a [`crate::CollectConfig`]
+    ///
+    /// The functions to collect are looked up within the benchmark function, so the costs of the
+    /// setup and teardown functions are never included. See [`crate::CollectConfig`] for the
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{main, CollectConfig, LibraryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default()
+    ///         .collect(CollectConfig::default().exclude(["my_crate::log::*"]));
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn collect<T>(&mut self, config: T) -> &mut Self
+    where
+        T: Into<internal::InternalCollectConfig>,
+    {
+        self.0.collect_config = Some(config.into());
+        self
+    }
+
+    /// Subtract the overhead of the benchmark harness measured by a calibration run
+    ///
+    /// The costs of a library benchmark include a small fixed overhead of the harness, for example
+    /// the call of the benchmark function and moving its arguments, which can hide differences in
+    /// very small functions. If enabled, each benchmark gets its own calibration run right before
+    /// the benchmark. It calls a function with the same generics and argument types as the
+    /// benchmark function but with an empty body with the arguments of the benchmark. The costs of
+    /// this calibration run are subtracted from the costs of the benchmark. The adjusted costs are
+    /// shown, checked for regressions and recorded as `events` in the summary and the raw costs are
+    /// recorded as `raw_events`.
+    ///
+    /// The calibration is only applied if callgrind is the default tool, the benchmark function is
+    /// the entry point (there are no functions to collect of a [`crate::CollectConfig`]) and the
+    /// callgrind output consists of a single dump. It is also ignored if the overhead is not
+    /// smaller than the costs of the benchmark. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use iai_callgrind::{library_benchmark, library_benchmark_group};
+    /// # #[library_benchmark]
+    /// # fn some_func() {}
+    /// # library_benchmark_group!(name = some_group; benchmarks = some_func);
+    /// use iai_callgrind::{main, LibraryBenchmarkConfig};
+    ///
+    /// # fn main() {
+    /// main!(
+    ///     config = LibraryBenchmarkConfig::default().harness_calibration(true);
+    ///     library_benchmark_groups = some_group
+    /// );
+    /// # }
+    /// ```
+    pub fn harness_calibration(&mut self, value: bool) -> &mut Self {
+        self.0.harness_calibration = Some(value);
+        self
+    }
+
     /// Enable performance regression checks with a [`crate::RegressionConfig`]
     ///
     /// # Examples
@@ -723,6 +723,23 @@ macro_rules! main {
                                 "teardown" => {
                                     $group::__run_teardown(true);
                                 }
+                                "calibration" => {
+                                    let group_index = std::hint::black_box(
+                                        args_iter
+                                            .next()
+                                            .expect("A group index should be present")
+                                            .parse::<usize>()
+                                            .expect("Expecting a valid group index")
+                                    );
+                                    let bench_index = std::hint::black_box(
+                                        args_iter
+                                            .next()
+                                            .expect("A bench index should be present")
+                                            .parse::<usize>()
+                                            .expect("Expecting a valid bench index")
+                                    );
+                                    $group::__run_calibration(group_index, bench_index);
+                                }
                                 value => {
                                     let group_index = std::hint::black_box(
                                         value
@@ -1443,6 +1460,12 @@ macro_rules! library_benchmark_group {
                 ),+
             ];
 
+            pub const __CALIBRATIONS: &[&[fn()]] = &[
+                $(
+                    super::$function::__CALIBRATIONS
+                ),+
+            ];
+
             #[inline(never)]
             pub fn __get_config() -> Option<$crate::internal::InternalLibraryBenchmarkConfig> {
                 let mut config: Option<$crate::internal::InternalLibraryBenchmarkConfig> = None;
@@ -1487,7 +1510,12 @@ macro_rules! library_benchmark_group {
 
             #[inline(never)]
             pub fn __run(group_index: usize, bench_index: usize) {
-                (__BENCHES[group_index].2[bench_index].func)();
+                $crate::internal::run_lib_bench(__BENCHES[group_index].2[bench_index].func);
+            }
+
+            #[inline(never)]
+            pub fn __run_calibration(group_index: usize, bench_index: usize) {
+                (__CALIBRATIONS[group_index][bench_index])();
             }
         }
     };